@@ -0,0 +1,13 @@
+create table idempotency_keys (
+  id varchar(255) not null,
+  client varchar(64) not null,
+  fingerprint varchar(64) not null,
+  status int not null,
+  headers text not null,
+  body mediumblob not null,
+  created_at datetime not null,
+  expires_at datetime not null,
+
+  primary key (client, id),
+  index idempotency_keys_expires_at (expires_at)
+);
@@ -0,0 +1 @@
+alter table todos add column due_at datetime null;
@@ -0,0 +1,7 @@
+create table calendar_tokens (
+  token varchar(64) not null,
+  owner varchar(255) not null,
+  created_at datetime not null,
+
+  primary key (token)
+);
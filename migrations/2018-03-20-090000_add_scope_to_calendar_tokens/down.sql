@@ -0,0 +1,2 @@
+alter table calendar_tokens
+  drop column scope;
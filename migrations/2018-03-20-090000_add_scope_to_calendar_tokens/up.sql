@@ -0,0 +1,2 @@
+alter table calendar_tokens
+  add column scope varchar(16) not null default 'read' after owner;
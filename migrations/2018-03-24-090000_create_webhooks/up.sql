@@ -0,0 +1,35 @@
+create table webhooks (
+  id bigint auto_increment not null,
+  url varchar(2048) not null,
+  secret varchar(64) not null,
+  events varchar(255) not null,
+  created_at datetime not null,
+
+  primary key (id)
+);
+
+create table webhook_events (
+  id bigint auto_increment not null,
+  event varchar(32) not null,
+  payload text not null,
+  created_at datetime not null,
+  dispatched_at datetime null,
+
+  primary key (id),
+  index (dispatched_at)
+);
+
+create table webhook_deliveries (
+  id bigint auto_increment not null,
+  webhook_id bigint not null,
+  event_id bigint not null,
+  status varchar(16) not null,
+  attempts integer not null,
+  next_attempt_at datetime not null,
+  last_error text null,
+  created_at datetime not null,
+  updated_at datetime not null,
+
+  primary key (id),
+  index (status, next_attempt_at)
+);
@@ -0,0 +1,3 @@
+drop table todo_tombstones;
+alter table todos drop column change_seq;
+drop table change_sequence;
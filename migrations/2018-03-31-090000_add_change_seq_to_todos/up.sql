@@ -0,0 +1,21 @@
+create table change_sequence (
+  id tinyint not null,
+  value bigint not null,
+
+  primary key (id)
+);
+
+alter table todos add column change_seq bigint not null default 0;
+update todos set change_seq = id;
+alter table todos add index (change_seq);
+
+insert into change_sequence (id, value) select 1, coalesce(max(id), 0) from todos;
+
+create table todo_tombstones (
+  todo_id bigint not null,
+  change_seq bigint not null,
+  deleted_at datetime not null,
+
+  primary key (todo_id),
+  index (change_seq)
+);
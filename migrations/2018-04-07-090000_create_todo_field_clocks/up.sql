@@ -0,0 +1,12 @@
+create table todo_field_clocks (
+  todo_id bigint not null,
+  field varchar(32) not null,
+  changed_at datetime(6) not null,
+
+  primary key (todo_id, field)
+);
+
+insert into todo_field_clocks (todo_id, field, changed_at) select id, 'text', updated_at from todos;
+insert into todo_field_clocks (todo_id, field, changed_at) select id, 'done', updated_at from todos;
+insert into todo_field_clocks (todo_id, field, changed_at)
+  select id, 'due_at', updated_at from todos where due_at is not null;
@@ -0,0 +1,16 @@
+use std::process;
+
+use config::Config;
+
+/// Print the effective configuration or the reason why it's invalid.
+pub fn run(cfg: Result<Config, String>) {
+  match cfg {
+    Ok(cfg) => for (name, value) in cfg.to_vars() {
+      println!("{}={}", name, value);
+    },
+    Err(err) => {
+      eprintln!("invalid config: {}", err);
+      process::exit(1);
+    }
+  }
+}
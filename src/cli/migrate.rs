@@ -0,0 +1,38 @@
+use clap::ArgMatches;
+
+use config::Config;
+use db;
+
+use super::exit_with;
+
+/// Apply, revert or show database migrations.
+pub fn run(cfg: &Config, args: &ArgMatches) {
+  let conn_pool = db::connection_pool(&cfg.database_url, 1);
+  let conn = conn_pool.get().expect("cannot get a database connection");
+
+  let result = match args.value_of("command") {
+    Some("up") => db::migrations::run_pending(&*conn).map(|applied| {
+      for migration in applied {
+        println!("applied {}", migration.name);
+      }
+    }),
+    Some("down") => db::migrations::revert_latest(&*conn).map(|reverted| match reverted {
+      Some(migration) => println!("reverted {}", migration.name),
+      None => println!("nothing to revert"),
+    }),
+    Some("redo") => db::migrations::redo(&*conn).map(|redone| match redone {
+      Some(migration) => println!("redone {}", migration.name),
+      None => println!("nothing to redo"),
+    }),
+    _ => db::migrations::status(&*conn).map(|status| {
+      for (migration, applied) in status {
+        let mark = if applied { "X" } else { " " };
+        println!("[{}] {}", mark, migration.name);
+      }
+    }),
+  };
+
+  if let Err(err) = result {
+    exit_with(&format!("migration failed: {}", err));
+  }
+}
@@ -0,0 +1,176 @@
+mod check_config;
+mod migrate;
+mod seed;
+mod serve;
+mod transfer;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::env;
+use std::process;
+
+use config::{Config, VARS};
+use logger;
+
+/// Parse command line arguments and run a subcommand, `serve` is the default one.
+pub fn run() {
+  let flags: Vec<String> = VARS.iter().map(|&(name, _, _)| flag_name(name)).collect();
+  let helps: Vec<String> = VARS
+    .iter()
+    .map(|&(name, default, help)| format!("{} [env: {}] [default: {}]", help, name, default))
+    .collect();
+
+  let global_args: Vec<Arg> = flags
+    .iter()
+    .zip(helps.iter())
+    .map(|(flag, help)| {
+      Arg::with_name(flag)
+        .long(flag)
+        .takes_value(true)
+        .global(true)
+        .help(help)
+    })
+    .collect();
+
+  let matches = App::new("todo-demo")
+    .version(crate_version!())
+    .about("Todo items http api")
+    .setting(AppSettings::VersionlessSubcommands)
+    .args(&global_args)
+    .subcommand(SubCommand::with_name("serve").about("Starts http server, the default command"))
+    .subcommand(
+      SubCommand::with_name("migrate")
+        .about("Manages database schema migrations")
+        .arg(
+          Arg::with_name("command")
+            .required(true)
+            .possible_values(&["up", "down", "status", "redo"]),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("seed")
+        .about("Generates fake todo items")
+        .arg(
+          Arg::with_name("count")
+            .long("count")
+            .short("n")
+            .takes_value(true)
+            .default_value("100")
+            .help("how many todo items to generate"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("import")
+        .about("Imports todo items from JSON lines, CSV or iCalendar")
+        .arg(format_arg())
+        .arg(
+          Arg::with_name("input")
+            .long("input")
+            .short("i")
+            .takes_value(true)
+            .help("a file to read from, stdin by default"),
+        )
+        .arg(
+          Arg::with_name("keep-ids")
+            .long("keep-ids")
+            .help("keep ids from the input, otherwise new ones are assigned"),
+        )
+        .arg(
+          Arg::with_name("dry-run")
+            .long("dry-run")
+            .help("only validate the input, nothing is written"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("export")
+        .about("Exports todo items as JSON lines, CSV or iCalendar")
+        .arg(format_arg())
+        .arg(
+          Arg::with_name("output")
+            .long("output")
+            .short("o")
+            .takes_value(true)
+            .help("a file to write to, stdout by default"),
+        )
+        .arg(
+          Arg::with_name("text")
+            .long("text")
+            .takes_value(true)
+            .help("export only todo items which text contains this value"),
+        ),
+    )
+    .subcommand(
+      SubCommand::with_name("check-config")
+        .about("Validates and prints the effective configuration, secrets are hidden"),
+    )
+    .get_matches();
+
+  let (name, sub_matches) = matches.subcommand();
+  let cfg = Config::from_vars(|var| {
+    let flag = flag_name(var);
+    sub_matches
+      .and_then(|it| it.value_of(&flag))
+      .or_else(|| matches.value_of(&flag))
+      .map(String::from)
+      .or_else(|| env::var(var).ok())
+  });
+
+  if name == "check-config" {
+    return check_config::run(cfg);
+  }
+
+  let cfg = cfg.unwrap_or_else(|err| exit_with(&err));
+  logger::init(&cfg);
+  let args = sub_matches.cloned().unwrap_or_default();
+
+  match name {
+    "migrate" => migrate::run(&cfg, &args),
+    "seed" => seed::run(&cfg, &args),
+    "import" => transfer::import(&cfg, &args),
+    "export" => transfer::export(&cfg, &args),
+    _ => serve::run(&cfg),
+  }
+}
+
+fn format_arg<'a, 'b>() -> Arg<'a, 'b> {
+  Arg::with_name("format")
+    .long("format")
+    .short("f")
+    .takes_value(true)
+    .possible_values(&["jsonl", "csv", "ics"])
+    .default_value("jsonl")
+}
+
+/// A command line flag name for an environment variable, `HTTP_PORT` becomes `http-port`.
+fn flag_name(var: &str) -> String {
+  var.to_lowercase().replace('_', "-")
+}
+
+/// Print an error and exit with a non zero code.
+fn exit_with(err: &str) -> ! {
+  eprintln!("error: {}", err);
+  process::exit(1);
+}
+
+/// Parse a subcommand argument or exit with an error.
+fn parse_arg<T>(args: &ArgMatches, name: &str) -> Option<T>
+where
+  T: ::std::str::FromStr,
+{
+  args.value_of(name).map(|value| {
+    value
+      .parse()
+      .unwrap_or_else(|_| exit_with(&format!("cannot parse {} {:?}", name, value)))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_build_flag_name() {
+    assert_that(&flag_name("HTTP_PORT").as_str()).is_equal_to("http-port");
+    assert_that(&flag_name("DATABASE_URL").as_str()).is_equal_to("database-url");
+  }
+}
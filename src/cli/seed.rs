@@ -0,0 +1,75 @@
+use chrono::Utc;
+use clap::ArgMatches;
+use futures::{future, stream, Future, Stream};
+
+use config::Config;
+use db::{self, NewTodo, TodosRepo, UpdateTodo};
+use metrics::Metrics;
+use result::Error;
+
+use super::{exit_with, parse_arg};
+
+const VERBS: &[&str] = &[
+  "buy", "call", "fix", "read", "write", "clean", "plan", "review", "book", "return"
+];
+const NOUNS: &[&str] = &[
+  "milk", "mom", "bike", "book", "report", "kitchen", "trip", "pull request", "tickets", "car"
+];
+
+/// Insert fake todo items, every third one is done.
+pub fn run(cfg: &Config, args: &ArgMatches) {
+  let count: usize = parse_arg(args, "count").unwrap_or(100);
+
+  let cpu_pool = cfg.create_cpu_pool();
+  let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
+  let repo = TodosRepo::new(conn_pool, cpu_pool, Metrics::new());
+  let seed = Utc::now().timestamp() as usize;
+
+  let result = stream::iter_ok::<_, Error>(0..count)
+    .map(move |n| {
+      let repo = repo.clone();
+      let new_todo = NewTodo {
+        text: fake_text(seed + n),
+      };
+
+      repo.insert(new_todo).and_then(move |todo| {
+        if n % 3 != 0 {
+          return future::Either::A(future::ok(todo));
+        }
+
+        let update = UpdateTodo {
+          id: todo.id,
+          text: None,
+          done: Some(true),
+          changed_at: None,
+        };
+        future::Either::B(repo.update(update).map(|it| it.todo))
+      })
+    })
+    .buffer_unordered(cfg.pool_size as usize)
+    .fold(0, |count, _| Ok::<_, Error>(count + 1))
+    .wait();
+
+  match result {
+    Ok(count) => println!("created {} todo items", count),
+    Err(err) => exit_with(&format!("cannot create todo items: {}", err)),
+  }
+}
+
+fn fake_text(n: usize) -> String {
+  let verb = VERBS[n % VERBS.len()];
+  let noun = NOUNS[(n / VERBS.len()) % NOUNS.len()];
+  format!("{} {} #{}", verb, noun, n)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_generate_fake_text() {
+    assert_that(&fake_text(0).as_str()).is_equal_to("buy milk #0");
+    assert_that(&fake_text(11).as_str()).is_equal_to("call mom #11");
+  }
+}
@@ -0,0 +1,43 @@
+use std::process;
+
+use config::Config;
+use db;
+use http;
+use metrics::Metrics;
+use trace;
+use webhooks;
+
+/// Check migrations and start the http and grpc servers.
+pub fn run(cfg: &Config) {
+  info!("using {:?}", cfg);
+
+  let cpu_pool = cfg.create_cpu_pool();
+  let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
+
+  {
+    let conn = conn_pool.get().expect("cannot get a database connection");
+
+    if cfg.run_migrations_on_start {
+      db::migrations::run_pending(&*conn).expect("cannot apply migrations");
+    } else {
+      let pending = db::migrations::pending(&*conn).expect("cannot check migrations");
+      if !pending.is_empty() {
+        for migration in pending {
+          error!("pending migration {}", migration.name);
+        }
+        error!("run `todo-demo migrate up` or set RUN_MIGRATIONS_ON_START=true");
+        process::exit(1);
+      }
+    }
+  }
+
+  webhooks::Worker::new(cfg, conn_pool.clone(), cpu_pool.clone()).spawn();
+  let metrics = Metrics::new();
+  let (tracer, exporter) = trace::Exporter::new(cfg);
+  if let Some(exporter) = exporter {
+    exporter.spawn();
+  }
+  let server = http::Server::new(cfg, conn_pool, cpu_pool, metrics, tracer);
+  let _grpc = server.listen_grpc(cfg.grpc_port);
+  server.listen(cfg.http_port);
+}
@@ -0,0 +1,102 @@
+use clap::ArgMatches;
+use futures::{Future, Stream};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+
+use config::Config;
+use db::{self, QueryTodos, TodosRepo};
+use metrics::Metrics;
+use transfer::{self, Format, ImportOptions};
+use validators::Validator;
+
+use super::exit_with;
+
+/// Write todo items matching `--text` filter as JSON lines, CSV or iCalendar.
+pub fn export(cfg: &Config, args: &ArgMatches) {
+  let repo = create_repo(cfg);
+  let format = format_arg(args);
+
+  let query = QueryTodos {
+    next: None,
+    limit: None,
+    text: args.value_of("text").map(String::from),
+  };
+  let query = query
+    .validated()
+    .unwrap_or_else(|err| exit_with(&err.to_string()));
+
+  let mut output: Box<Write> = match args.value_of("output") {
+    Some(path) => Box::new(BufWriter::new(
+      File::create(path).unwrap_or_else(|err| exit_with(&format!("cannot create {}: {}", path, err))),
+    )),
+    None => Box::new(BufWriter::new(io::stdout())),
+  };
+
+  for chunk in transfer::export(&repo, query, format).wait() {
+    let chunk = chunk.unwrap_or_else(|err| exit_with(&format!("cannot export todo items: {}", err)));
+    output
+      .write_all(&chunk)
+      .unwrap_or_else(|err| exit_with(&format!("cannot write todo items: {}", err)));
+  }
+
+  output
+    .flush()
+    .unwrap_or_else(|err| exit_with(&format!("cannot write todo items: {}", err)));
+}
+
+/// Create todo items from JSON lines, CSV or iCalendar, invalid rows are reported and skipped.
+pub fn import(cfg: &Config, args: &ArgMatches) {
+  let repo = create_repo(cfg);
+  let options = ImportOptions {
+    format: format_arg(args),
+    keep_ids: args.is_present("keep-ids"),
+    dry_run: args.is_present("dry-run"),
+  };
+
+  let mut input: Box<Read> = match args.value_of("input") {
+    Some(path) => Box::new(
+      File::open(path).unwrap_or_else(|err| exit_with(&format!("cannot open {}: {}", path, err))),
+    ),
+    None => Box::new(io::stdin()),
+  };
+
+  let mut buf = Vec::new();
+  input
+    .read_to_end(&mut buf)
+    .unwrap_or_else(|err| exit_with(&format!("cannot read input: {}", err)));
+
+  let report = transfer::import(&repo, &buf, options)
+    .wait()
+    .unwrap_or_else(|err| exit_with(&format!("cannot import todo items: {}", err)));
+
+  for row in &report.rows {
+    match (&row.error, row.source_id, row.id) {
+      (&Some(ref err), _, _) => eprintln!("row {}: {}", row.row, err),
+      (&None, Some(source_id), Some(id)) if source_id != id => {
+        eprintln!("row {}: id {} imported as {}", row.row, source_id, id)
+      }
+      _ => {}
+    }
+  }
+
+  let verb = if report.dry_run { "validated" } else { "imported" };
+  eprintln!("{} {} todo items, {} failed", verb, report.imported, report.failed);
+
+  if report.failed > 0 {
+    exit_with("some todo items were not imported");
+  }
+}
+
+fn format_arg(args: &ArgMatches) -> Format {
+  args
+    .value_of("format")
+    .unwrap_or("jsonl")
+    .parse()
+    .unwrap_or_else(|err: ::result::Error| exit_with(&err.to_string()))
+}
+
+fn create_repo(cfg: &Config) -> TodosRepo {
+  let cpu_pool = cfg.create_cpu_pool();
+  let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
+  TodosRepo::new(conn_pool, cpu_pool, Metrics::new())
+}
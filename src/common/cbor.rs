@@ -0,0 +1,266 @@
+use serde_json::{Map, Number, Value as JsonValue};
+
+use result::Error;
+
+use super::decoder::Decoder;
+
+const UNSIGNED: u8 = 0;
+const NEGATIVE: u8 = 1;
+const BYTES: u8 = 2;
+const TEXT: u8 = 3;
+const ARRAY: u8 = 4;
+const MAP: u8 = 5;
+const TAG: u8 = 6;
+const SIMPLE: u8 = 7;
+
+/// Additional info of items with an indefinite length which end with a `BREAK` byte.
+const INDEFINITE: u8 = 31;
+const BREAK: u8 = 0xff;
+
+/// Encode a JSON value as CBOR, lengths are always definite.
+pub fn encode(value: &JsonValue) -> Vec<u8> {
+  let mut out = Vec::new();
+  write_value(&mut out, value);
+  out
+}
+
+/// Decode a CBOR value, tags are ignored and byte strings have no JSON form and are rejected.
+pub fn decode(bytes: &[u8]) -> Result<JsonValue, Error> {
+  let mut decoder = Decoder::new("cbor", bytes);
+  let value = read_value(&mut decoder, 0)?;
+  decoder.finish()?;
+  Ok(value)
+}
+
+fn write_value(out: &mut Vec<u8>, value: &JsonValue) {
+  match *value {
+    JsonValue::Null => out.push(0xf6),
+    JsonValue::Bool(false) => out.push(0xf4),
+    JsonValue::Bool(true) => out.push(0xf5),
+    JsonValue::Number(ref n) => {
+      if let Some(n) = n.as_u64() {
+        write_head(out, UNSIGNED, n);
+      } else if let Some(n) = n.as_i64() {
+        write_head(out, NEGATIVE, (-1 - n) as u64);
+      } else {
+        out.push(0xfb);
+        let bits = n.as_f64().unwrap_or(0.0).to_bits();
+        for i in (0..8).rev() {
+          out.push((bits >> (i * 8)) as u8);
+        }
+      }
+    }
+    JsonValue::String(ref s) => {
+      write_head(out, TEXT, s.len() as u64);
+      out.extend_from_slice(s.as_bytes());
+    }
+    JsonValue::Array(ref items) => {
+      write_head(out, ARRAY, items.len() as u64);
+      for item in items {
+        write_value(out, item);
+      }
+    }
+    JsonValue::Object(ref map) => {
+      write_head(out, MAP, map.len() as u64);
+      for (key, item) in map {
+        write_head(out, TEXT, key.len() as u64);
+        out.extend_from_slice(key.as_bytes());
+        write_value(out, item);
+      }
+    }
+  }
+}
+
+/// Write a major type with an argument in its shortest form.
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+  let major = major << 5;
+  let size = if n < 24 {
+    out.push(major | n as u8);
+    return;
+  } else if n <= 0xff {
+    out.push(major | 24);
+    1
+  } else if n <= 0xffff {
+    out.push(major | 25);
+    2
+  } else if n <= 0xffff_ffff {
+    out.push(major | 26);
+    4
+  } else {
+    out.push(major | 27);
+    8
+  };
+  for i in (0..size).rev() {
+    out.push((n >> (i * 8)) as u8);
+  }
+}
+
+/// Read an item's argument, `None` is an indefinite length.
+fn read_argument(decoder: &mut Decoder, info: u8) -> Result<Option<u64>, Error> {
+  match info {
+    info if info < 24 => Ok(Some(u64::from(info))),
+    24 => decoder.uint(1).map(Some),
+    25 => decoder.uint(2).map(Some),
+    26 => decoder.uint(4).map(Some),
+    27 => decoder.uint(8).map(Some),
+    INDEFINITE => Ok(None),
+    _ => Err(decoder.error("invalid additional info")),
+  }
+}
+
+fn read_value(decoder: &mut Decoder, depth: usize) -> Result<JsonValue, Error> {
+  decoder.enter(depth)?;
+
+  let initial = decoder.byte()?;
+  let (major, info) = (initial >> 5, initial & 0x1f);
+  if major == SIMPLE {
+    return read_simple(decoder, info);
+  }
+
+  let argument = read_argument(decoder, info)?;
+  match (major, argument) {
+    (UNSIGNED, Some(n)) => Ok(JsonValue::from(n)),
+    (NEGATIVE, Some(n)) if n <= i64::max_value() as u64 => Ok(JsonValue::from(-1 - n as i64)),
+    (NEGATIVE, Some(_)) => Err(decoder.error("negative integer is out of range")),
+    (BYTES, _) => Err(decoder.error("byte strings aren't supported")),
+    (TEXT, Some(len)) => decoder.string(len as usize).map(JsonValue::String),
+    (TEXT, None) => {
+      let mut text = String::new();
+      while decoder.peek()? != BREAK {
+        match read_value(decoder, depth + 1)? {
+          JsonValue::String(chunk) => text.push_str(&chunk),
+          _ => return Err(decoder.error("text chunks must be strings")),
+        }
+      }
+      decoder.byte()?;
+      Ok(JsonValue::String(text))
+    }
+    (ARRAY, len) => {
+      let mut items = Vec::with_capacity(decoder.capacity(len.unwrap_or(0) as usize));
+      while has_next(decoder, len, items.len())? {
+        items.push(read_value(decoder, depth + 1)?);
+      }
+      Ok(JsonValue::Array(items))
+    }
+    (MAP, len) => {
+      let mut map = Map::new();
+      let mut read = 0;
+      while has_next(decoder, len, read)? {
+        let key = match read_value(decoder, depth + 1)? {
+          JsonValue::String(key) => key,
+          _ => return Err(decoder.error("map keys must be strings")),
+        };
+        let value = read_value(decoder, depth + 1)?;
+        map.insert(key, value);
+        read += 1;
+      }
+      Ok(JsonValue::Object(map))
+    }
+    (TAG, Some(_)) => read_value(decoder, depth + 1),
+    _ => Err(decoder.error("invalid indefinite length")),
+  }
+}
+
+/// Check if there's a next item of an array or a map, an indefinite one ends with a break.
+fn has_next(decoder: &mut Decoder, len: Option<u64>, read: usize) -> Result<bool, Error> {
+  match len {
+    Some(len) => Ok((read as u64) < len),
+    None if decoder.peek()? == BREAK => {
+      decoder.byte()?;
+      Ok(false)
+    }
+    None => Ok(true),
+  }
+}
+
+fn read_simple(decoder: &mut Decoder, info: u8) -> Result<JsonValue, Error> {
+  let value = match info {
+    20 => return Ok(JsonValue::Bool(false)),
+    21 => return Ok(JsonValue::Bool(true)),
+    // undefined has no JSON form, it's the closest one
+    22 | 23 => return Ok(JsonValue::Null),
+    25 => half(decoder.uint(2)? as u16),
+    26 => f64::from(f32::from_bits(decoder.uint(4)? as u32)),
+    27 => f64::from_bits(decoder.uint(8)?),
+    _ => return Err(decoder.error("unsupported simple value")),
+  };
+
+  Number::from_f64(value)
+    .map(JsonValue::Number)
+    .ok_or_else(|| decoder.error("numbers must be finite"))
+}
+
+/// Convert a half precision float.
+fn half(bits: u16) -> f64 {
+  let exponent = i32::from((bits >> 10) & 0x1f);
+  let mantissa = f64::from(bits & 0x3ff);
+  let value = match exponent {
+    0 => mantissa * 2f64.powi(-24),
+    31 if mantissa == 0.0 => ::std::f64::INFINITY,
+    31 => ::std::f64::NAN,
+    _ => (mantissa + 1024.0) * 2f64.powi(exponent - 25),
+  };
+
+  match bits & 0x8000 {
+    0 => value,
+    _ => -value,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_encode_values_in_shortest_form() {
+    assert_that(&encode(&json!(null))).is_equal_to(vec![0xf6]);
+    assert_that(&encode(&json!(23))).is_equal_to(vec![0x17]);
+    assert_that(&encode(&json!(24))).is_equal_to(vec![0x18, 0x18]);
+    assert_that(&encode(&json!(1000))).is_equal_to(vec![0x19, 0x03, 0xe8]);
+    assert_that(&encode(&json!(-1))).is_equal_to(vec![0x20]);
+    assert_that(&encode(&json!(-1000))).is_equal_to(vec![0x39, 0x03, 0xe7]);
+    assert_that(&encode(&json!(1.1)))
+      .is_equal_to(vec![0xfb, 0x3f, 0xf1, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a]);
+    assert_that(&encode(&json!({"a": [1, "b"]})))
+      .is_equal_to(vec![0xa1, 0x61, b'a', 0x82, 0x01, 0x61, b'b']);
+  }
+
+  #[test]
+  fn should_decode_what_is_encoded() {
+    let value = json!({
+      "text": "x".repeat(300),
+      "items": (0..30).collect::<Vec<i32>>(),
+      "n": [-25, -5_000_000_000i64, 18_000_000_000_000_000_000u64, 0.25],
+      "nested": {"ok": null, "done": false},
+    });
+    assert_that(&decode(&encode(&value)).ok()).is_equal_to(Some(value));
+  }
+
+  #[test]
+  fn should_decode_indefinite_lengths_floats_and_tags() {
+    // {_ "a": [_ 1, 2], "b": (_ "x", "y")}
+    let bytes = [
+      0xbf, 0x61, b'a', 0x9f, 0x01, 0x02, 0xff, 0x61, b'b', 0x7f, 0x61, b'x', 0x61, b'y', 0xff,
+      0xff,
+    ];
+    assert_that(&decode(&bytes).ok()).is_equal_to(Some(json!({"a": [1, 2], "b": "xy"})));
+
+    assert_that(&decode(&[0xf9, 0x3e, 0x00]).ok()).is_equal_to(Some(json!(1.5)));
+    assert_that(&decode(&[0xf9, 0x80, 0x01]).ok()).is_equal_to(Some(json!(-5.960464477539063e-8)));
+    assert_that(&decode(&[0xfa, 0x47, 0xc3, 0x50, 0x00]).ok()).is_equal_to(Some(json!(100000.0)));
+    assert_that(&decode(&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0]).ok())
+      .is_equal_to(Some(json!(1363896240)));
+  }
+
+  #[test]
+  fn should_reject_invalid_input() {
+    assert_that(&decode(&[0x41, 0x00])).is_err();
+    assert_that(&decode(&[0xa1, 0x01, 0x01])).is_err();
+    assert_that(&decode(&[0x9b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])).is_err();
+    assert_that(&decode(&[0xf9, 0x7c, 0x00])).is_err();
+    assert_that(&decode(&[0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff])).is_err();
+    assert_that(&decode(&[0x81; 1000])).is_err();
+    assert_that(&decode(&[0x1f])).is_err();
+  }
+}
@@ -0,0 +1,260 @@
+use brotli;
+use flate2::{self, Compression};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use result::Error;
+
+/// A quality of brotli, higher ones are too slow to compress responses on the fly.
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+const BUFFER_SIZE: usize = 4096;
+
+/// A coding of compressed bodies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentEncoding {
+  Brotli,
+  Gzip,
+  /// zlib format, raw deflate is accepted in requests too
+  Deflate,
+}
+
+/// Codings in the order of server's preference.
+pub const CONTENT_ENCODINGS: &[ContentEncoding] = &[
+  ContentEncoding::Brotli,
+  ContentEncoding::Gzip,
+  ContentEncoding::Deflate,
+];
+
+impl ContentEncoding {
+  pub fn as_str(&self) -> &'static str {
+    match *self {
+      ContentEncoding::Brotli => "br",
+      ContentEncoding::Gzip => "gzip",
+      ContentEncoding::Deflate => "deflate",
+    }
+  }
+
+  /// A coding of a request's body, `None` when it isn't compressed.
+  pub fn from_header(content_encoding: Option<&str>) -> Result<Option<Self>, Error> {
+    let coding = match content_encoding {
+      Some(coding) => coding.trim().to_lowercase(),
+      None => return Ok(None),
+    };
+
+    match coding.as_str() {
+      "" | "identity" => Ok(None),
+      "br" => Ok(Some(ContentEncoding::Brotli)),
+      "gzip" | "x-gzip" => Ok(Some(ContentEncoding::Gzip)),
+      "deflate" => Ok(Some(ContentEncoding::Deflate)),
+      _ => Err(Error::UnsupportedMediaType(format!(
+        "content encoding {}",
+        coding
+      ))),
+    }
+  }
+
+  /// Pick a coding from `Accept-Encoding`, `None` when a response should be sent as it is.
+  pub fn negotiate(accept_encoding: Option<&str>) -> Option<Self> {
+    let codings: Vec<(String, f32)> = accept_encoding?
+      .split(',')
+      .filter_map(parse_coding)
+      .collect();
+    let wildcard = codings.iter().find(|it| it.0 == "*").map(|it| it.1);
+
+    let mut best: Option<(ContentEncoding, f32)> = None;
+    for coding in CONTENT_ENCODINGS {
+      let q = codings
+        .iter()
+        .find(|it| {
+          it.0 == coding.as_str() || (*coding == ContentEncoding::Gzip && it.0 == "x-gzip")
+        })
+        .map(|it| it.1)
+        .or(wildcard)
+        .unwrap_or(0.0);
+      if q > 0.0 && best.map_or(true, |it| q > it.1) {
+        best = Some((*coding, q));
+      }
+    }
+
+    best.map(|it| it.0)
+  }
+
+  /// Create an encoder which writes compressed bytes to `sink`.
+  pub fn encoder(&self, sink: Sink) -> Encoder {
+    let writer: Box<Write + Send> = match *self {
+      ContentEncoding::Brotli => Box::new(brotli::CompressorWriter::new(
+        sink.clone(),
+        BUFFER_SIZE,
+        BROTLI_QUALITY,
+        BROTLI_WINDOW,
+      )),
+      ContentEncoding::Gzip => Box::new(flate2::write::GzEncoder::new(
+        sink.clone(),
+        Compression::default(),
+      )),
+      ContentEncoding::Deflate => Box::new(flate2::write::ZlibEncoder::new(
+        sink.clone(),
+        Compression::default(),
+      )),
+    };
+
+    Encoder { writer, sink }
+  }
+
+  /// Compress a whole body.
+  pub fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = self.encoder(Sink::new());
+    let mut out = encoder.write(bytes);
+    out.extend(encoder.finish());
+    out
+  }
+
+  /// Decompress a body, a body which is larger than `limit` when it's decompressed is rejected.
+  pub fn decode<'a>(&self, bytes: &'a [u8], limit: u64) -> Result<Vec<u8>, Error> {
+    let reader: Box<Read + 'a> = match *self {
+      ContentEncoding::Brotli => Box::new(brotli::Decompressor::new(bytes, BUFFER_SIZE)),
+      ContentEncoding::Gzip => Box::new(flate2::read::GzDecoder::new(bytes)),
+      ContentEncoding::Deflate if is_zlib(bytes) => Box::new(flate2::read::ZlibDecoder::new(bytes)),
+      ContentEncoding::Deflate => Box::new(flate2::read::DeflateDecoder::new(bytes)),
+    };
+
+    let mut out = Vec::new();
+    reader
+      .take(limit + 1)
+      .read_to_end(&mut out)
+      .map_err(|err| Error::BodyParse(format!("{} {}", self.as_str(), err)))?;
+    if out.len() as u64 > limit {
+      return Err(Error::PayloadTooLarge(limit));
+    }
+
+    Ok(out)
+  }
+}
+
+/// A buffer which an encoder writes to, compressed bytes are taken from it after every write.
+#[derive(Clone, Default)]
+pub struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl Sink {
+  pub fn new() -> Self {
+    Sink::default()
+  }
+
+  fn take(&self) -> Vec<u8> {
+    let mut buf = self.0.lock().expect("sink lock is poisoned");
+    mem::replace(&mut *buf, Vec::new())
+  }
+}
+
+impl Write for Sink {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let mut inner = self.0.lock().expect("sink lock is poisoned");
+    inner.extend_from_slice(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+/// Compresses a body chunk by chunk.
+pub struct Encoder {
+  writer: Box<Write + Send>,
+  sink: Sink,
+}
+
+impl Encoder {
+  /// Compress a chunk, returns bytes which are ready, the encoder may keep the rest for now.
+  pub fn write(&mut self, chunk: &[u8]) -> Vec<u8> {
+    self
+      .writer
+      .write_all(chunk)
+      .expect("writing to memory cannot fail");
+    self.sink.take()
+  }
+
+  /// End a compressed stream, returns the last bytes.
+  pub fn finish(self) -> Vec<u8> {
+    let Encoder { writer, sink } = self;
+    // encoders write their trailers when they are dropped
+    drop(writer);
+    sink.take()
+  }
+}
+
+/// Check a zlib header, some clients send raw deflate as `deflate`.
+fn is_zlib(bytes: &[u8]) -> bool {
+  bytes.len() >= 2
+    && bytes[0] & 0x0f == 8
+    && (u16::from(bytes[0]) << 8 | u16::from(bytes[1])) % 31 == 0
+}
+
+/// Parse a coding of `Accept-Encoding` with its quality.
+fn parse_coding(coding: &str) -> Option<(String, f32)> {
+  let mut parts = coding.split(';');
+  let name = parts.next()?.trim().to_lowercase();
+  if name.is_empty() {
+    return None;
+  }
+
+  let mut q = 1.0;
+  for param in parts {
+    let mut pair = param.splitn(2, '=');
+    if pair.next().unwrap_or("").trim().eq_ignore_ascii_case("q") {
+      q = pair.next()?.trim().parse().ok()?;
+    }
+  }
+
+  Some((name, q))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_negotiate_content_encoding() {
+    let negotiate = ContentEncoding::negotiate;
+    assert_that(&negotiate(None)).is_none();
+    assert_that(&negotiate(Some("gzip, deflate, br"))).is_equal_to(Some(ContentEncoding::Brotli));
+    assert_that(&negotiate(Some("gzip;q=1.0, br;q=0.5"))).is_equal_to(Some(ContentEncoding::Gzip));
+    assert_that(&negotiate(Some("deflate"))).is_equal_to(Some(ContentEncoding::Deflate));
+    assert_that(&negotiate(Some("*, br;q=0"))).is_equal_to(Some(ContentEncoding::Gzip));
+    assert_that(&negotiate(Some("identity"))).is_none();
+    assert_that(&negotiate(Some("gzip;q=0"))).is_none();
+  }
+
+  #[test]
+  fn should_parse_content_encoding() {
+    assert_that(&ContentEncoding::from_header(None).ok()).is_equal_to(Some(None));
+    assert_that(&ContentEncoding::from_header(Some("identity")).ok()).is_equal_to(Some(None));
+    assert_that(&ContentEncoding::from_header(Some("GZIP")).ok())
+      .is_equal_to(Some(Some(ContentEncoding::Gzip)));
+    assert_that(&ContentEncoding::from_header(Some("zstd"))).is_err();
+  }
+
+  #[test]
+  fn should_decode_what_is_encoded() {
+    let body = "{\"text\": \"buy milk\"}\n".repeat(1000);
+    for coding in CONTENT_ENCODINGS {
+      let encoded = coding.encode(body.as_bytes());
+      assert_that(&(encoded.len() < body.len() / 10)).is_true();
+      assert_that(&coding.decode(&encoded, 1 << 20).ok())
+        .is_equal_to(Some(body.clone().into_bytes()));
+      assert_that(&coding.decode(&encoded, 100)).is_err();
+    }
+    assert_that(&ContentEncoding::Gzip.decode(b"not compressed", 1 << 20)).is_err();
+
+    let raw = {
+      let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), Compression::default());
+      encoder.write_all(body.as_bytes()).unwrap();
+      encoder.finish().unwrap()
+    };
+    assert_that(&ContentEncoding::Deflate.decode(&raw, 1 << 20).ok())
+      .is_equal_to(Some(body.into_bytes()));
+  }
+}
@@ -0,0 +1,86 @@
+use result::Error;
+
+/// How deep arrays and maps could be nested in a binary body.
+const MAX_DEPTH: usize = 128;
+
+/// Reads a binary body, errors tell the format and the offset where decoding failed.
+pub struct Decoder<'a> {
+  format: &'static str,
+  bytes: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+  pub fn new(format: &'static str, bytes: &'a [u8]) -> Self {
+    Decoder {
+      format,
+      bytes,
+      pos: 0,
+    }
+  }
+
+  pub fn error(&self, message: &str) -> Error {
+    Error::BodyParse(format!("{} at {}: {}", self.format, self.pos, message))
+  }
+
+  /// Check a value at `depth` could be decoded.
+  pub fn enter(&self, depth: usize) -> Result<(), Error> {
+    if depth < MAX_DEPTH {
+      Ok(())
+    } else {
+      Err(self.error("too deeply nested"))
+    }
+  }
+
+  /// Check all bytes were decoded.
+  pub fn finish(&self) -> Result<(), Error> {
+    if self.pos == self.bytes.len() {
+      Ok(())
+    } else {
+      Err(self.error("trailing bytes"))
+    }
+  }
+
+  /// Look at the next byte without taking it.
+  pub fn peek(&self) -> Result<u8, Error> {
+    match self.bytes.get(self.pos) {
+      Some(byte) => Ok(*byte),
+      None => Err(self.error("unexpected end")),
+    }
+  }
+
+  pub fn byte(&mut self) -> Result<u8, Error> {
+    let byte = self.peek()?;
+    self.pos += 1;
+    Ok(byte)
+  }
+
+  pub fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+    if len > self.bytes.len() - self.pos {
+      return Err(self.error("unexpected end"));
+    }
+    let bytes = &self.bytes[self.pos..self.pos + len];
+    self.pos += len;
+    Ok(bytes)
+  }
+
+  /// Read a big endian unsigned integer of `size` bytes.
+  pub fn uint(&mut self, size: usize) -> Result<u64, Error> {
+    let bytes = self.take(size)?;
+    Ok(bytes.iter().fold(0, |n, it| n << 8 | u64::from(*it)))
+  }
+
+  pub fn string(&mut self, len: usize) -> Result<String, Error> {
+    let bytes = self.take(len)?;
+    match String::from_utf8(bytes.to_vec()) {
+      Ok(s) => Ok(s),
+      Err(_) => Err(self.error("strings must be utf-8")),
+    }
+  }
+
+  /// A capacity to reserve for `len` items, a claimed length can't make it allocate more than
+  /// the body's size.
+  pub fn capacity(&self, len: usize) -> usize {
+    len.min(self.bytes.len() - self.pos)
+  }
+}
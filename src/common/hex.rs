@@ -0,0 +1,15 @@
+/// Encode bytes as a lower case hex string.
+pub fn to_hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|it| format!("{:02x}", it)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_encode_hex() {
+    assert_that(&to_hex(&[0, 15, 255]).as_str()).is_equal_to("000fff");
+  }
+}
@@ -0,0 +1,174 @@
+use serde_json::{self, Value as JsonValue};
+
+use result::Error;
+
+use super::{cbor, msgpack};
+
+/// An encoding of api bodies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaType {
+  Json,
+  MsgPack,
+  Cbor,
+}
+
+/// Media types in the order of server's preference.
+pub const MEDIA_TYPES: &[MediaType] = &[MediaType::Json, MediaType::MsgPack, MediaType::Cbor];
+
+impl MediaType {
+  pub fn as_str(&self) -> &'static str {
+    match *self {
+      MediaType::Json => "application/json",
+      MediaType::MsgPack => "application/msgpack",
+      MediaType::Cbor => "application/cbor",
+    }
+  }
+
+  /// A type which a request's body is decoded from, JSON when there is no `Content-Type`.
+  pub fn from_content_type(content_type: Option<&str>) -> Result<Self, Error> {
+    let content_type = match content_type {
+      Some(content_type) => essence(content_type),
+      None => return Ok(MediaType::Json),
+    };
+
+    match content_type.as_str() {
+      "application/json" => Ok(MediaType::Json),
+      "application/msgpack" | "application/x-msgpack" => Ok(MediaType::MsgPack),
+      "application/cbor" => Ok(MediaType::Cbor),
+      _ => Err(Error::UnsupportedMediaType(content_type)),
+    }
+  }
+
+  /// Pick a type which a response is encoded with, `None` when a client accepts none of them.
+  ///
+  /// The highest quality wins, a type which is named explicitly wins over a wildcard and the
+  /// server's preference decides the rest.
+  pub fn negotiate(accept: Option<&str>) -> Option<Self> {
+    let accept = match accept {
+      Some(accept) if !accept.trim().is_empty() => accept,
+      _ => return Some(MediaType::Json),
+    };
+    let ranges: Vec<(String, f32)> = accept.split(',').filter_map(parse_range).collect();
+
+    let mut best: Option<(MediaType, f32, usize)> = None;
+    for media_type in MEDIA_TYPES {
+      let matched = ranges
+        .iter()
+        .filter_map(|&(ref range, q)| specificity(range, media_type.as_str()).map(|it| (it, q)))
+        .max_by_key(|&(specificity, _)| specificity);
+      let (specificity, q) = match matched {
+        Some((specificity, q)) if q > 0.0 => (specificity, q),
+        _ => continue,
+      };
+
+      let better = match best {
+        Some((_, best_q, best_specificity)) => {
+          q > best_q || (q == best_q && specificity > best_specificity)
+        }
+        None => true,
+      };
+      if better {
+        best = Some((*media_type, q, specificity));
+      }
+    }
+
+    best.map(|it| it.0)
+  }
+
+  pub fn encode(&self, value: &JsonValue) -> Vec<u8> {
+    match *self {
+      MediaType::Json => serde_json::to_vec(value).expect("json serialization cannot be fail"),
+      MediaType::MsgPack => msgpack::encode(value),
+      MediaType::Cbor => cbor::encode(value),
+    }
+  }
+
+  pub fn decode(&self, bytes: &[u8]) -> Result<JsonValue, Error> {
+    match *self {
+      MediaType::Json => serde_json::from_slice(bytes).map_err(Error::from),
+      MediaType::MsgPack => msgpack::decode(bytes),
+      MediaType::Cbor => cbor::decode(bytes),
+    }
+  }
+}
+
+/// A lowercase type without parameters like `charset`.
+pub fn essence(media_type: &str) -> String {
+  media_type
+    .split(';')
+    .next()
+    .unwrap_or("")
+    .trim()
+    .to_lowercase()
+}
+
+/// Parse a media range of `Accept` with its quality.
+fn parse_range(range: &str) -> Option<(String, f32)> {
+  let mut parts = range.split(';');
+  let media_range = essence(parts.next()?);
+  if media_range.is_empty() {
+    return None;
+  }
+
+  let mut q = 1.0;
+  for param in parts {
+    let mut pair = param.splitn(2, '=');
+    let name = pair.next().unwrap_or("").trim();
+    if name.eq_ignore_ascii_case("q") {
+      q = pair.next()?.trim().parse().ok()?;
+    }
+  }
+
+  Some((media_range, q))
+}
+
+/// How specific a media range which matches a type is, `None` when it doesn't match.
+fn specificity(range: &str, media_type: &str) -> Option<usize> {
+  if range == media_type {
+    return Some(2);
+  }
+  if range == "*/*" {
+    return Some(0);
+  }
+
+  let prefix = range.trim_right_matches('*');
+  if range.ends_with("/*") && media_type.starts_with(prefix) {
+    Some(1)
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_pick_a_type_of_request_body() {
+    assert_that(&MediaType::from_content_type(None).ok()).is_equal_to(Some(MediaType::Json));
+    assert_that(&MediaType::from_content_type(Some("application/json; charset=utf-8")).ok())
+      .is_equal_to(Some(MediaType::Json));
+    assert_that(&MediaType::from_content_type(Some("Application/MsgPack")).ok())
+      .is_equal_to(Some(MediaType::MsgPack));
+    assert_that(&MediaType::from_content_type(Some("application/cbor")).ok())
+      .is_equal_to(Some(MediaType::Cbor));
+    assert_that(&MediaType::from_content_type(Some("text/xml"))).is_err();
+  }
+
+  #[test]
+  fn should_negotiate_a_type_of_response() {
+    let negotiate = MediaType::negotiate;
+    assert_that(&negotiate(None)).is_equal_to(Some(MediaType::Json));
+    assert_that(&negotiate(Some("*/*"))).is_equal_to(Some(MediaType::Json));
+    assert_that(&negotiate(Some("application/msgpack, */*;q=0.5")))
+      .is_equal_to(Some(MediaType::MsgPack));
+    assert_that(&negotiate(Some("application/*, application/cbor")))
+      .is_equal_to(Some(MediaType::Cbor));
+    assert_that(&negotiate(Some("application/json;q=0.5, application/cbor;q=0.9")))
+      .is_equal_to(Some(MediaType::Cbor));
+    assert_that(&negotiate(Some("application/*;q=0.1, application/json;q=0")))
+      .is_equal_to(Some(MediaType::MsgPack));
+    assert_that(&negotiate(Some("text/html, application/xml;q=0.9"))).is_none();
+  }
+}
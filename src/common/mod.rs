@@ -1,7 +1,16 @@
 mod request_ext;
 mod response_ext;
 mod futures_ext;
+mod hex;
+mod decoder;
+mod msgpack;
+mod cbor;
+mod media_type;
+mod content_encoding;
 
 pub use self::request_ext::RequestExt;
 pub use self::response_ext::ResponseExt;
 pub use self::futures_ext::FuturesExt;
+pub use self::hex::to_hex;
+pub use self::media_type::{essence, MediaType, MEDIA_TYPES};
+pub use self::content_encoding::{ContentEncoding, Encoder, Sink};
@@ -0,0 +1,253 @@
+use serde_json::{Map, Number, Value as JsonValue};
+
+use result::Error;
+
+use super::decoder::Decoder;
+
+/// Encode a JSON value as MessagePack, numbers are encoded in their smallest form.
+pub fn encode(value: &JsonValue) -> Vec<u8> {
+  let mut out = Vec::new();
+  write_value(&mut out, value);
+  out
+}
+
+/// Decode a MessagePack value, binary and extension types have no JSON form and are rejected.
+pub fn decode(bytes: &[u8]) -> Result<JsonValue, Error> {
+  let mut decoder = Decoder::new("msgpack", bytes);
+  let value = read_value(&mut decoder, 0)?;
+  decoder.finish()?;
+  Ok(value)
+}
+
+fn write_value(out: &mut Vec<u8>, value: &JsonValue) {
+  match *value {
+    JsonValue::Null => out.push(0xc0),
+    JsonValue::Bool(false) => out.push(0xc2),
+    JsonValue::Bool(true) => out.push(0xc3),
+    JsonValue::Number(ref n) => write_number(out, n),
+    JsonValue::String(ref s) => {
+      let len = s.len();
+      if len < 32 {
+        out.push(0xa0 | len as u8);
+      } else if len <= 0xff {
+        out.push(0xd9);
+        out.push(len as u8);
+      } else if len <= 0xffff {
+        out.push(0xda);
+        write_be(out, len as u64, 2);
+      } else {
+        out.push(0xdb);
+        write_be(out, len as u64, 4);
+      }
+      out.extend_from_slice(s.as_bytes());
+    }
+    JsonValue::Array(ref items) => {
+      write_len(out, items.len(), 0x90, 0xdc);
+      for item in items {
+        write_value(out, item);
+      }
+    }
+    JsonValue::Object(ref map) => {
+      write_len(out, map.len(), 0x80, 0xde);
+      for (key, item) in map {
+        write_value(out, &JsonValue::String(key.clone()));
+        write_value(out, item);
+      }
+    }
+  }
+}
+
+fn write_number(out: &mut Vec<u8>, n: &Number) {
+  if let Some(n) = n.as_u64() {
+    if n < 0x80 {
+      out.push(n as u8);
+    } else if n <= 0xff {
+      out.push(0xcc);
+      out.push(n as u8);
+    } else if n <= 0xffff {
+      out.push(0xcd);
+      write_be(out, n, 2);
+    } else if n <= 0xffff_ffff {
+      out.push(0xce);
+      write_be(out, n, 4);
+    } else {
+      out.push(0xcf);
+      write_be(out, n, 8);
+    }
+  } else if let Some(n) = n.as_i64() {
+    if n >= -32 {
+      out.push(n as u8);
+    } else if n >= i64::from(i8::min_value()) {
+      out.push(0xd0);
+      out.push(n as u8);
+    } else if n >= i64::from(i16::min_value()) {
+      out.push(0xd1);
+      write_be(out, n as u64, 2);
+    } else if n >= i64::from(i32::min_value()) {
+      out.push(0xd2);
+      write_be(out, n as u64, 4);
+    } else {
+      out.push(0xd3);
+      write_be(out, n as u64, 8);
+    }
+  } else {
+    out.push(0xcb);
+    write_be(out, n.as_f64().unwrap_or(0.0).to_bits(), 8);
+  }
+}
+
+/// Write a length of an array or a map, `fix` is a marker of the short form.
+fn write_len(out: &mut Vec<u8>, len: usize, fix: u8, marker16: u8) {
+  if len < 16 {
+    out.push(fix | len as u8);
+  } else if len <= 0xffff {
+    out.push(marker16);
+    write_be(out, len as u64, 2);
+  } else {
+    out.push(marker16 + 1);
+    write_be(out, len as u64, 4);
+  }
+}
+
+/// Write `size` lowest bytes of `n` in big endian.
+fn write_be(out: &mut Vec<u8>, n: u64, size: usize) {
+  for i in (0..size).rev() {
+    out.push((n >> (i * 8)) as u8);
+  }
+}
+
+fn read_value(decoder: &mut Decoder, depth: usize) -> Result<JsonValue, Error> {
+  decoder.enter(depth)?;
+
+  let marker = decoder.byte()?;
+  let value = match marker {
+    m if m <= 0x7f => JsonValue::from(m),
+    m if m <= 0x8f => read_map(decoder, usize::from(m & 0x0f), depth)?,
+    m if m <= 0x9f => read_array(decoder, usize::from(m & 0x0f), depth)?,
+    m if m <= 0xbf => read_str(decoder, usize::from(m & 0x1f))?,
+    0xc0 => JsonValue::Null,
+    0xc2 => JsonValue::Bool(false),
+    0xc3 => JsonValue::Bool(true),
+    0xca => {
+      let bits = decoder.uint(4)? as u32;
+      float(decoder, f64::from(f32::from_bits(bits)))?
+    }
+    0xcb => {
+      let bits = decoder.uint(8)?;
+      float(decoder, f64::from_bits(bits))?
+    }
+    0xcc => JsonValue::from(decoder.uint(1)?),
+    0xcd => JsonValue::from(decoder.uint(2)?),
+    0xce => JsonValue::from(decoder.uint(4)?),
+    0xcf => JsonValue::from(decoder.uint(8)?),
+    0xd0 => JsonValue::from(i64::from(decoder.uint(1)? as u8 as i8)),
+    0xd1 => JsonValue::from(i64::from(decoder.uint(2)? as u16 as i16)),
+    0xd2 => JsonValue::from(i64::from(decoder.uint(4)? as u32 as i32)),
+    0xd3 => JsonValue::from(decoder.uint(8)? as i64),
+    0xd9 => {
+      let len = decoder.uint(1)? as usize;
+      read_str(decoder, len)?
+    }
+    0xda => {
+      let len = decoder.uint(2)? as usize;
+      read_str(decoder, len)?
+    }
+    0xdb => {
+      let len = decoder.uint(4)? as usize;
+      read_str(decoder, len)?
+    }
+    0xdc => {
+      let len = decoder.uint(2)? as usize;
+      read_array(decoder, len, depth)?
+    }
+    0xdd => {
+      let len = decoder.uint(4)? as usize;
+      read_array(decoder, len, depth)?
+    }
+    0xde => {
+      let len = decoder.uint(2)? as usize;
+      read_map(decoder, len, depth)?
+    }
+    0xdf => {
+      let len = decoder.uint(4)? as usize;
+      read_map(decoder, len, depth)?
+    }
+    m if m >= 0xe0 => JsonValue::from(i64::from(m as i8)),
+    _ => return Err(decoder.error(&format!("unsupported type 0x{:02x}", marker))),
+  };
+
+  Ok(value)
+}
+
+fn read_str(decoder: &mut Decoder, len: usize) -> Result<JsonValue, Error> {
+  decoder.string(len).map(JsonValue::String)
+}
+
+fn read_array(decoder: &mut Decoder, len: usize, depth: usize) -> Result<JsonValue, Error> {
+  let mut items = Vec::with_capacity(decoder.capacity(len));
+  for _ in 0..len {
+    items.push(read_value(decoder, depth + 1)?);
+  }
+  Ok(JsonValue::Array(items))
+}
+
+fn read_map(decoder: &mut Decoder, len: usize, depth: usize) -> Result<JsonValue, Error> {
+  let mut map = Map::new();
+  for _ in 0..len {
+    let key = match read_value(decoder, depth + 1)? {
+      JsonValue::String(key) => key,
+      _ => return Err(decoder.error("map keys must be strings")),
+    };
+    let value = read_value(decoder, depth + 1)?;
+    map.insert(key, value);
+  }
+  Ok(JsonValue::Object(map))
+}
+
+fn float(decoder: &Decoder, value: f64) -> Result<JsonValue, Error> {
+  Number::from_f64(value)
+    .map(JsonValue::Number)
+    .ok_or_else(|| decoder.error("numbers must be finite"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_encode_values_in_smallest_form() {
+    assert_that(&encode(&json!(null))).is_equal_to(vec![0xc0]);
+    assert_that(&encode(&json!(127))).is_equal_to(vec![0x7f]);
+    assert_that(&encode(&json!(128))).is_equal_to(vec![0xcc, 0x80]);
+    assert_that(&encode(&json!(-32))).is_equal_to(vec![0xe0]);
+    assert_that(&encode(&json!(-33))).is_equal_to(vec![0xd0, 0xdf]);
+    assert_that(&encode(&json!(70000))).is_equal_to(vec![0xce, 0x00, 0x01, 0x11, 0x70]);
+    assert_that(&encode(&json!(1.5))).is_equal_to(vec![0xcb, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0]);
+    assert_that(&encode(&json!({"id": 1, "done": true}))).is_equal_to(vec![
+      0x82, 0xa4, b'd', b'o', b'n', b'e', 0xc3, 0xa2, b'i', b'd', 0x01,
+    ]);
+  }
+
+  #[test]
+  fn should_decode_what_is_encoded() {
+    let value = json!({
+      "text": "x".repeat(300),
+      "items": (0..20).collect::<Vec<i32>>(),
+      "n": [-1, -200, -70000, -5_000_000_000i64, 18_000_000_000_000_000_000u64, 0.25],
+      "nested": {"ok": null},
+    });
+    assert_that(&decode(&encode(&value)).ok()).is_equal_to(Some(value));
+
+    assert_that(&decode(&[0xca, 0x3f, 0xc0, 0x00, 0x00]).ok()).is_equal_to(Some(json!(1.5)));
+  }
+
+  #[test]
+  fn should_reject_invalid_input() {
+    assert_that(&decode(&[0xc4, 0x01, 0x00])).is_err();
+    assert_that(&decode(&[0x81, 0x01, 0x01])).is_err();
+    assert_that(&decode(&[0xdd, 0xff, 0xff, 0xff, 0xff])).is_err();
+    assert_that(&decode(&[0xc0, 0xc0])).is_err();
+    assert_that(&decode(&[0x91; 1000])).is_err();
+  }
+}
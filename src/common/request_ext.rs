@@ -1,15 +1,25 @@
-use hyper::Request;
+use hyper::{Body, Request};
+use hyper::header::{ContentLength, ContentType};
 use serde::de::DeserializeOwned;
 use serde_json;
-use futures::{Future, Stream};
+use futures::{future, Future, Stream};
+use std::collections::HashMap;
+use url::form_urlencoded;
 
 use result::Error;
-use common::FuturesExt;
+use trace;
+use common::{FuturesExt, MediaType};
 
 pub trait RequestExt {
+  /// Parse a body as JSON, MessagePack or CBOR by its `Content-Type`.
   fn json<T>(self) -> Box<Future<Item = T, Error = Error>>
   where
     T: DeserializeOwned + 'static;
+
+  /// Read a whole body, a body larger than `limit` bytes is rejected without reading the rest.
+  fn bytes(self, limit: u64) -> Box<Future<Item = Vec<u8>, Error = Error>>;
+
+  fn query_pairs(&self) -> HashMap<String, String>;
 }
 
 impl RequestExt for Request {
@@ -17,11 +27,58 @@ impl RequestExt for Request {
   where
     T: DeserializeOwned + 'static,
   {
+    let content_type = self.headers().get::<ContentType>().map(|it| it.to_string());
+    let media_type = match MediaType::from_content_type(content_type.as_ref().map(String::as_str))
+    {
+      Ok(it) => it,
+      Err(err) => return future::err(err).into_boxed(),
+    };
+
     self
       .body()
       .concat2()
       .map_err(Error::from)
-      .and_then(|chunk| serde_json::from_slice(&chunk).map_err(Error::from))
+      .and_then(move |chunk| {
+        let span = match media_type {
+          MediaType::Json => "serde_json.from_slice",
+          MediaType::MsgPack => "msgpack.decode",
+          MediaType::Cbor => "cbor.decode",
+        };
+        trace::in_span_sync(span, || match media_type {
+          MediaType::Json => serde_json::from_slice(&chunk).map_err(Error::from),
+          _ => serde_json::from_value(media_type.decode(&chunk)?).map_err(Error::from),
+        })
+      })
       .into_boxed()
   }
+
+  fn bytes(self, limit: u64) -> Box<Future<Item = Vec<u8>, Error = Error>> {
+    match self.headers().get::<ContentLength>() {
+      Some(&ContentLength(len)) if len > limit => {
+        future::err(Error::PayloadTooLarge(limit)).into_boxed()
+      }
+      _ => read_body(self.body(), limit),
+    }
+  }
+
+  fn query_pairs(&self) -> HashMap<String, String> {
+    let query = self.query().unwrap_or("");
+    form_urlencoded::parse(query.as_bytes())
+      .into_owned()
+      .collect()
+  }
+}
+
+/// Read a body's chunks until they are over `limit` bytes.
+pub fn read_body(body: Body, limit: u64) -> Box<Future<Item = Vec<u8>, Error = Error>> {
+  body
+    .map_err(Error::from)
+    .fold(Vec::new(), move |mut bytes, chunk| {
+      if (bytes.len() + chunk.len()) as u64 > limit {
+        return Err(Error::PayloadTooLarge(limit));
+      }
+      bytes.extend_from_slice(&chunk);
+      Ok(bytes)
+    })
+    .into_boxed()
 }
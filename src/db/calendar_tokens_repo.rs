@@ -0,0 +1,173 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use futures_cpupool::{CpuFuture, CpuPool};
+use rand::{OsRng, Rng};
+
+use result::Error;
+use common::to_hex;
+
+use super::schema::calendar_tokens;
+use super::ConnectionPool;
+
+/// A secret token which gives access to the calendar feed and CalDAV items
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct CalendarToken {
+  pub token: String,
+  pub owner: String,
+  /// `read` or `read_write`
+  pub scope: String,
+  pub created_at: NaiveDateTime,
+}
+
+impl CalendarToken {
+  /// Whether the token may change CalDAV items, the feed only reads them.
+  pub fn can_write(&self) -> bool {
+    self.scope == CalendarTokenScope::ReadWrite.as_str()
+  }
+}
+
+/// What a calendar token gives access to
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarTokenScope {
+  Read,
+  ReadWrite,
+}
+
+impl CalendarTokenScope {
+  pub fn as_str(&self) -> &'static str {
+    match *self {
+      CalendarTokenScope::Read => "read",
+      CalendarTokenScope::ReadWrite => "read_write",
+    }
+  }
+}
+
+/// Calendar tokens repository
+#[derive(Clone)]
+pub struct CalendarTokensRepo {
+  conn_pool: ConnectionPool,
+  cpu_pool: CpuPool,
+}
+
+impl CalendarTokensRepo {
+  pub fn new(conn_pool: ConnectionPool, cpu_pool: CpuPool) -> Self {
+    CalendarTokensRepo {
+      conn_pool,
+      cpu_pool,
+    }
+  }
+
+  /// Create a new random token for an owner.
+  pub fn create(
+    &self,
+    owner: String,
+    scope: CalendarTokenScope,
+  ) -> CpuFuture<CalendarToken, Error> {
+    let CalendarTokensRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      let mut rng = OsRng::new().expect("cannot access os random number generator");
+      let bytes: [u8; 32] = rng.gen();
+
+      let token = CalendarToken {
+        token: to_hex(&bytes),
+        owner,
+        scope: scope.as_str().to_string(),
+        created_at: Utc::now().naive_utc(),
+      };
+
+      diesel::insert_into(calendar_tokens::table)
+        .values(&(
+          calendar_tokens::token.eq(token.token.as_str()),
+          calendar_tokens::owner.eq(token.owner.as_str()),
+          calendar_tokens::scope.eq(token.scope.as_str()),
+          calendar_tokens::created_at.eq(&token.created_at),
+        ))
+        .execute(&*conn)
+        .map_err(Error::from)
+        .map(|_| token)
+    })
+  }
+
+  /// Find a token, fails with `RecordNotFound` when it doesn't exist or was revoked.
+  pub fn find(&self, token: String) -> CpuFuture<CalendarToken, Error> {
+    let CalendarTokensRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      calendar_tokens::table
+        .filter(calendar_tokens::token.eq(token.as_str()))
+        .first::<CalendarToken>(&*conn)
+        .map_err(Error::from)
+    })
+  }
+
+  /// Revoke a token.
+  pub fn revoke(&self, token: String) -> CpuFuture<(), Error> {
+    let CalendarTokensRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      let deleted =
+        diesel::delete(calendar_tokens::table.filter(calendar_tokens::token.eq(token.as_str())))
+          .execute(&*conn)
+          .map_err(Error::from)?;
+
+      if deleted == 0 {
+        return Err(Error::RecordNotFound);
+      }
+
+      Ok(())
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::Future;
+
+  use config::Config;
+  use db::connection_pool;
+
+  #[test]
+  fn should_create_find_and_revoke_token() {
+    let repo = create_repo();
+
+    let token = repo
+      .create("alice".to_string(), CalendarTokenScope::Read)
+      .wait()
+      .unwrap();
+    assert_that(&token.token.len()).is_equal_to(64);
+
+    let found = repo.find(token.token.clone()).wait().unwrap();
+    assert_that(&found.owner).is_equal_to("alice".to_string());
+    assert_that(&found.can_write()).is_false();
+
+    repo.revoke(token.token.clone()).wait().unwrap();
+    assert_that(&repo.find(token.token).wait()).is_err();
+  }
+
+  fn create_repo() -> CalendarTokensRepo {
+    let cfg = Config::default();
+    let conn_pool = connection_pool(&cfg.database_url, cfg.pool_size);
+    let cpu_pool = cfg.create_cpu_pool();
+    CalendarTokensRepo::new(conn_pool, cpu_pool)
+  }
+}
@@ -0,0 +1,117 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+
+use result::Error;
+
+use super::functions::last_insert_id;
+use super::schema::{change_sequence, todo_tombstones, todos};
+use super::Todo;
+
+/// A deleted todo item, kept for clients which haven't synced the deletion yet.
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct Tombstone {
+  #[serde(rename = "id")]
+  pub todo_id: i64,
+  #[serde(skip_serializing)]
+  pub change_seq: i64,
+  pub deleted_at: NaiveDateTime,
+}
+
+/// Todo items and tombstones changed after a sequence number, ordered by it.
+#[derive(Debug, Clone)]
+pub struct Changes {
+  pub todos: Vec<Todo>,
+  pub deleted: Vec<Tombstone>,
+  /// the latest returned change's sequence number, a client continues from it
+  pub seq: i64,
+  /// whether there are more changes after `seq`
+  pub more: bool,
+}
+
+/// Take the next change sequence number.
+///
+/// The counter's row stays locked until the transaction ends, so changes are committed in the
+/// order of their numbers and a reader never skips a number which is committed later.
+pub fn next_seq(conn: &MysqlConnection) -> Result<i64, Error> {
+  diesel::sql_query("update change_sequence set value = last_insert_id(value + 1) where id = 1")
+    .execute(conn)
+    .map_err(Error::from)?;
+
+  diesel::select(last_insert_id)
+    .first::<i64>(conn)
+    .map_err(Error::from)
+}
+
+/// The latest taken change sequence number, the counter has a single row.
+pub fn latest_seq(conn: &MysqlConnection) -> Result<i64, Error> {
+  change_sequence::table
+    .select(change_sequence::value)
+    .first::<i64>(conn)
+    .map_err(Error::from)
+}
+
+/// Record a deletion of a todo item.
+pub fn bury(conn: &MysqlConnection, todo_id: i64) -> Result<(), Error> {
+  let seq = next_seq(conn)?;
+
+  diesel::replace_into(todo_tombstones::table)
+    .values(&(
+      todo_tombstones::todo_id.eq(todo_id),
+      todo_tombstones::change_seq.eq(seq),
+      todo_tombstones::deleted_at.eq(Utc::now().naive_utc()),
+    ))
+    .execute(conn)
+    .map_err(Error::from)
+    .map(|_| ())
+}
+
+/// Forget a deletion when an item with the same id is created again.
+pub fn revive(conn: &MysqlConnection, todo_id: i64) -> Result<(), Error> {
+  diesel::delete(todo_tombstones::table.filter(todo_tombstones::todo_id.eq(todo_id)))
+    .execute(conn)
+    .map_err(Error::from)
+    .map(|_| ())
+}
+
+/// Load up to `limit` changes after `seq`.
+pub fn since(conn: &MysqlConnection, seq: i64, limit: i64) -> Result<Changes, Error> {
+  let mut todos = todos::table
+    .filter(todos::change_seq.gt(seq))
+    .order(todos::change_seq.asc())
+    .limit(limit + 1)
+    .load::<Todo>(conn)
+    .map_err(Error::from)?;
+
+  let mut deleted = todo_tombstones::table
+    .filter(todo_tombstones::change_seq.gt(seq))
+    .order(todo_tombstones::change_seq.asc())
+    .limit(limit + 1)
+    .load::<Tombstone>(conn)
+    .map_err(Error::from)?;
+
+  let mut seqs = todos
+    .iter()
+    .map(|it| it.change_seq)
+    .chain(deleted.iter().map(|it| it.change_seq))
+    .collect::<Vec<_>>();
+  seqs.sort();
+
+  let more = seqs.len() as i64 > limit;
+  let last = if more {
+    seqs[limit as usize - 1]
+  } else {
+    seqs.last().cloned().unwrap_or(seq)
+  };
+
+  todos.retain(|it| it.change_seq <= last);
+  deleted.retain(|it| it.change_seq <= last);
+
+  Ok(Changes {
+    todos,
+    deleted,
+    seq: last,
+    more,
+  })
+}
@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use diesel;
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+use std::collections::HashMap;
+
+use result::Error;
+
+use super::schema::todo_field_clocks;
+
+/// Fields of todo items whose changes are merged separately.
+pub const TEXT: &str = "text";
+pub const DONE: &str = "done";
+pub const DUE_AT: &str = "due_at";
+
+/// When each field of a todo item was changed, missing fields weren't changed since creation or
+/// import, any change of them wins.
+pub fn load(conn: &MysqlConnection, todo_id: i64) -> Result<HashMap<String, NaiveDateTime>, Error> {
+  let clocks = todo_field_clocks::table
+    .filter(todo_field_clocks::todo_id.eq(todo_id))
+    .select((todo_field_clocks::field, todo_field_clocks::changed_at))
+    .load::<(String, NaiveDateTime)>(conn)
+    .map_err(Error::from)?;
+
+  Ok(clocks.into_iter().collect())
+}
+
+/// Record a change of `fields`.
+pub fn touch(
+  conn: &MysqlConnection,
+  todo_id: i64,
+  fields: &[&str],
+  changed_at: NaiveDateTime,
+) -> Result<(), Error> {
+  for field in fields {
+    diesel::replace_into(todo_field_clocks::table)
+      .values(&(
+        todo_field_clocks::todo_id.eq(todo_id),
+        todo_field_clocks::field.eq(field),
+        todo_field_clocks::changed_at.eq(&changed_at),
+      ))
+      .execute(conn)
+      .map_err(Error::from)?;
+  }
+
+  Ok(())
+}
+
+/// Remove clocks of a deleted todo item.
+pub fn forget(conn: &MysqlConnection, todo_id: i64) -> Result<(), Error> {
+  diesel::delete(todo_field_clocks::table.filter(todo_field_clocks::todo_id.eq(todo_id)))
+    .execute(conn)
+    .map_err(Error::from)
+    .map(|_| ())
+}
@@ -0,0 +1,237 @@
+use chrono::{Duration, Utc};
+use diesel;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use futures_cpupool::{CpuFuture, CpuPool};
+
+use result::Error;
+
+use super::schema::idempotency_keys;
+use super::ConnectionPool;
+
+/// A status of the key which response isn't stored yet.
+const IN_PROGRESS: i32 = 0;
+
+/// Stored idempotency key with a response, mapping to `idempotency_keys` table
+#[derive(Queryable, Debug, Clone)]
+pub struct IdempotencyKey {
+  pub fingerprint: String,
+  pub status: i32,
+  /// response's headers as a JSON array of name and value pairs
+  pub headers: String,
+  pub body: Vec<u8>,
+}
+
+impl IdempotencyKey {
+  /// Whether the first request with this key is still being processed.
+  pub fn is_in_progress(&self) -> bool {
+    self.status == IN_PROGRESS
+  }
+}
+
+/// Idempotency keys repository
+#[derive(Clone)]
+pub struct IdempotencyKeysRepo {
+  conn_pool: ConnectionPool,
+  cpu_pool: CpuPool,
+}
+
+impl IdempotencyKeysRepo {
+  pub fn new(conn_pool: ConnectionPool, cpu_pool: CpuPool) -> Self {
+    IdempotencyKeysRepo {
+      conn_pool,
+      cpu_pool,
+    }
+  }
+
+  /// Reserve a client's key for a request, returns a stored key when it was already reserved.
+  ///
+  /// Keys are scoped by a client, so one client's key never replays another one's response.
+  pub fn reserve(
+    &self,
+    client: String,
+    id: String,
+    fingerprint: String,
+    ttl: Duration,
+  ) -> CpuFuture<Option<IdempotencyKey>, Error> {
+    let IdempotencyKeysRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let time = Utc::now().naive_utc();
+
+      diesel::delete(idempotency_keys::table.filter(idempotency_keys::expires_at.lt(&time)))
+        .execute(&*conn)
+        .map_err(Error::from)?;
+
+      let inserted = diesel::insert_into(idempotency_keys::table)
+        .values(&(
+          idempotency_keys::id.eq(id.as_str()),
+          idempotency_keys::client.eq(client.as_str()),
+          idempotency_keys::fingerprint.eq(fingerprint.as_str()),
+          idempotency_keys::status.eq(IN_PROGRESS),
+          idempotency_keys::headers.eq("[]"),
+          idempotency_keys::body.eq(&[] as &[u8]),
+          idempotency_keys::created_at.eq(&time),
+          idempotency_keys::expires_at.eq(&(time + ttl)),
+        ))
+        .execute(&*conn);
+
+      match inserted {
+        Ok(_) => Ok(None),
+        Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+          idempotency_keys::table
+            .select((
+              idempotency_keys::fingerprint,
+              idempotency_keys::status,
+              idempotency_keys::headers,
+              idempotency_keys::body,
+            ))
+            .filter(idempotency_keys::client.eq(client.as_str()))
+            .filter(idempotency_keys::id.eq(id.as_str()))
+            .first::<IdempotencyKey>(&*conn)
+            .map(Some)
+            .map_err(Error::from)
+        }
+        Err(err) => Err(Error::from(err)),
+      }
+    })
+  }
+
+  /// Store a response for a reserved key.
+  pub fn complete(
+    &self,
+    client: String,
+    id: String,
+    status: i32,
+    headers: String,
+    body: Vec<u8>,
+  ) -> CpuFuture<(), Error> {
+    let IdempotencyKeysRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      let key = idempotency_keys::table
+        .filter(idempotency_keys::client.eq(client.as_str()))
+        .filter(idempotency_keys::id.eq(id.as_str()));
+
+      diesel::update(key)
+        .set((
+          idempotency_keys::status.eq(status),
+          idempotency_keys::headers.eq(headers.as_str()),
+          idempotency_keys::body.eq(body.as_slice()),
+        ))
+        .execute(&*conn)
+        .map_err(Error::from)
+        .map(|_| ())
+    })
+  }
+
+  /// Release a reserved key, so the request could be retried.
+  pub fn release(&self, client: String, id: String) -> CpuFuture<(), Error> {
+    let IdempotencyKeysRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      diesel::delete(
+        idempotency_keys::table
+          .filter(idempotency_keys::client.eq(client.as_str()))
+          .filter(idempotency_keys::id.eq(id.as_str()))
+          .filter(idempotency_keys::status.eq(IN_PROGRESS)),
+      ).execute(&*conn)
+        .map_err(Error::from)
+        .map(|_| ())
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::Future;
+
+  use config::Config;
+  use db::connection_pool;
+
+  #[test]
+  fn should_reserve_and_complete_key() {
+    let repo = create_repo();
+    let id = format!("key-{}", Utc::now().timestamp_nanos());
+    let ttl = Duration::minutes(1);
+
+    let existing = repo
+      .reserve(client(), id.clone(), "foo".to_string(), ttl)
+      .wait()
+      .unwrap();
+    assert_that(&existing).is_none();
+
+    let existing = repo
+      .reserve(client(), id.clone(), "foo".to_string(), ttl)
+      .wait()
+      .unwrap();
+    assert_that(&existing.map(|it| it.is_in_progress())).is_equal_to(Some(true));
+
+    let headers = "[[\"Location\",\"/todos/1\"]]".to_string();
+    repo
+      .complete(client(), id.clone(), 201, headers.clone(), vec![0x80, 0xff])
+      .wait()
+      .unwrap();
+
+    let other = repo
+      .reserve("ip:10.0.0.1".to_string(), id.clone(), "foo".to_string(), ttl)
+      .wait()
+      .unwrap();
+    assert_that(&other).is_none();
+
+    let existing = repo
+      .reserve(client(), id.clone(), "foo".to_string(), ttl)
+      .wait()
+      .unwrap()
+      .unwrap();
+    assert_that(&existing.status).is_equal_to(201);
+    assert_that(&existing.headers).is_equal_to(headers);
+    assert_that(&existing.body).is_equal_to(vec![0x80, 0xff]);
+  }
+
+  #[test]
+  fn should_release_key() {
+    let repo = create_repo();
+    let id = format!("key-{}", Utc::now().timestamp_nanos());
+    let ttl = Duration::minutes(1);
+
+    repo
+      .reserve(client(), id.clone(), "foo".to_string(), ttl)
+      .wait()
+      .unwrap();
+    repo.release(client(), id.clone()).wait().unwrap();
+
+    let existing = repo
+      .reserve(client(), id.clone(), "foo".to_string(), ttl)
+      .wait()
+      .unwrap();
+    assert_that(&existing).is_none();
+  }
+
+  fn client() -> String {
+    "ip:127.0.0.1".to_string()
+  }
+
+  fn create_repo() -> IdempotencyKeysRepo {
+    let cfg = Config::default();
+    let conn_pool = connection_pool(&cfg.database_url, cfg.pool_size);
+    let cpu_pool = cfg.create_cpu_pool();
+    IdempotencyKeysRepo::new(conn_pool, cpu_pool)
+  }
+}
@@ -0,0 +1,208 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use diesel::connection::SimpleConnection;
+use diesel::MysqlConnection;
+
+use result::Error;
+
+// Diesel's migrations table, the same one `diesel_cli` uses.
+table! {
+    __diesel_schema_migrations (version) {
+        version -> Varchar,
+        run_on -> Timestamp,
+    }
+}
+
+use self::__diesel_schema_migrations as schema_migrations;
+
+/// A migration embedded into the binary from `migrations/` directory.
+#[derive(Debug)]
+pub struct Migration {
+  /// a version, same as diesel's one, digits from the directory's name
+  pub version: &'static str,
+  /// a directory name
+  pub name: &'static str,
+  up: &'static str,
+  down: &'static str,
+}
+
+macro_rules! migration {
+  ($version:expr, $name:expr) => {
+    Migration {
+      version: $version,
+      name: $name,
+      up: include_str!(concat!("../../migrations/", $name, "/up.sql")),
+      down: include_str!(concat!("../../migrations/", $name, "/down.sql")),
+    }
+  };
+}
+
+/// All known migrations ordered by version, a test checks they match `migrations/` directory.
+pub const MIGRATIONS: &[Migration] = &[
+  migration!("20180217124539", "2018-02-17-124539_create_todos"),
+  migration!("20180310090000", "2018-03-10-090000_create_idempotency_keys"),
+  migration!("20180317100000", "2018-03-17-100000_add_due_at_to_todos"),
+  migration!("20180317100100", "2018-03-17-100100_create_calendar_tokens"),
+  migration!("20180320090000", "2018-03-20-090000_add_scope_to_calendar_tokens"),
+  migration!("20180324090000", "2018-03-24-090000_create_webhooks"),
+  migration!("20180331090000", "2018-03-31-090000_add_change_seq_to_todos"),
+  migration!("20180407090000", "2018-04-07-090000_create_todo_field_clocks"),
+];
+
+/// Create migrations table if it doesn't exist yet.
+fn setup(conn: &MysqlConnection) -> Result<(), Error> {
+  conn
+    .batch_execute(
+      "create table if not exists __diesel_schema_migrations (
+         version varchar(50) primary key not null,
+         run_on timestamp not null default current_timestamp
+       )",
+    )
+    .map_err(Error::from)
+}
+
+fn applied_versions(conn: &MysqlConnection) -> Result<Vec<String>, Error> {
+  setup(conn)?;
+
+  schema_migrations::table
+    .select(schema_migrations::version)
+    .order(schema_migrations::version.asc())
+    .load::<String>(conn)
+    .map_err(Error::from)
+}
+
+/// Returns every migration paired with whether it was applied.
+pub fn status(conn: &MysqlConnection) -> Result<Vec<(&'static Migration, bool)>, Error> {
+  let applied = applied_versions(conn)?;
+
+  Ok(
+    MIGRATIONS
+      .iter()
+      .map(|it| (it, applied.iter().any(|v| v == it.version)))
+      .collect(),
+  )
+}
+
+/// Returns migrations which aren't applied yet.
+pub fn pending(conn: &MysqlConnection) -> Result<Vec<&'static Migration>, Error> {
+  Ok(
+    status(conn)?
+      .into_iter()
+      .filter(|&(_, applied)| !applied)
+      .map(|(it, _)| it)
+      .collect(),
+  )
+}
+
+/// Apply all pending migrations, returns applied ones.
+///
+/// Migrations aren't run in transactions, MySQL commits DDL statements implicitly. A migration
+/// which fails halfway stays partially applied and has to be repaired by hand.
+pub fn run_pending(conn: &MysqlConnection) -> Result<Vec<&'static Migration>, Error> {
+  let pending = pending(conn)?;
+
+  for migration in &pending {
+    info!("applying migration {}", migration.name);
+    let run_on: NaiveDateTime = Utc::now().naive_utc();
+
+    conn.batch_execute(migration.up).map_err(Error::from)?;
+    diesel::insert_into(schema_migrations::table)
+      .values(&(
+        schema_migrations::version.eq(migration.version),
+        schema_migrations::run_on.eq(&run_on),
+      ))
+      .execute(conn)
+      .map_err(Error::from)?;
+  }
+
+  Ok(pending)
+}
+
+/// Revert the latest applied migration, returns `None` when nothing to revert.
+///
+/// Like `run_pending` it runs without a transaction.
+pub fn revert_latest(conn: &MysqlConnection) -> Result<Option<&'static Migration>, Error> {
+  let latest = match applied_versions(conn)?.pop() {
+    Some(version) => version,
+    None => return Ok(None),
+  };
+
+  let migration = MIGRATIONS
+    .iter()
+    .find(|it| it.version == latest)
+    .ok_or_else(|| Error::Validation(format!("unknown applied migration {}", latest)))?;
+
+  info!("reverting migration {}", migration.name);
+
+  conn.batch_execute(migration.down).map_err(Error::from)?;
+  diesel::delete(schema_migrations::table.filter(schema_migrations::version.eq(migration.version)))
+    .execute(conn)
+    .map_err(Error::from)?;
+
+  Ok(Some(migration))
+}
+
+/// Revert and apply again the latest migration.
+pub fn redo(conn: &MysqlConnection) -> Result<Option<&'static Migration>, Error> {
+  match revert_latest(conn)? {
+    Some(_) => Ok(run_pending(conn)?.pop()),
+    None => Ok(None),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use std::fs;
+  use std::ops::Deref;
+
+  use config::Config;
+  use db::connection_pool;
+
+  #[test]
+  fn should_be_ordered_by_version() {
+    let versions: Vec<_> = MIGRATIONS.iter().map(|it| it.version).collect();
+    let mut sorted = versions.clone();
+    sorted.sort();
+    sorted.dedup();
+
+    assert_that(&versions).is_equal_to(sorted);
+
+    for migration in MIGRATIONS {
+      let digits: String = migration
+        .name
+        .chars()
+        .take_while(|it| *it != '_')
+        .filter(|it| it.is_digit(10))
+        .collect();
+      assert_that(&digits.as_str()).is_equal_to(migration.version);
+    }
+  }
+
+  #[test]
+  fn should_embed_every_migration_directory() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+    let mut names: Vec<String> = fs::read_dir(dir)
+      .unwrap()
+      .map(|entry| entry.unwrap())
+      .filter(|entry| entry.path().is_dir())
+      .map(|entry| entry.file_name().to_string_lossy().into_owned())
+      .collect();
+    names.sort();
+
+    let embedded: Vec<String> = MIGRATIONS.iter().map(|it| it.name.to_string()).collect();
+    assert_that(&embedded).is_equal_to(names);
+  }
+
+  #[test]
+  fn should_have_no_pending_migrations() {
+    let cfg = Config::default();
+    let pool = connection_pool(&cfg.database_url, cfg.pool_size);
+    let conn = pool.get().unwrap();
+
+    run_pending(conn.deref()).unwrap();
+    assert_that(&pending(conn.deref()).unwrap()).is_empty();
+  }
+}
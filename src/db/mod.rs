@@ -1,9 +1,22 @@
 mod functions;
+mod calendar_tokens_repo;
+mod changes;
 mod connection_pool;
+mod field_clocks;
+mod idempotency_keys_repo;
+pub mod migrations;
+pub mod outbox;
 mod paginated;
 mod schema;
+mod sync_repo;
 mod todos_repo;
+mod webhooks_repo;
 
-pub use self::todos_repo::{NewTodo, QueryTodos, Todo, TodosRepo, UpdateTodo};
+pub use self::todos_repo::{ImportTodo, Merged, NewTodo, QueryTodos, Todo, TodosRepo, UpdateTodo};
+pub use self::calendar_tokens_repo::{CalendarTokenScope, CalendarTokensRepo};
+pub use self::idempotency_keys_repo::{IdempotencyKey, IdempotencyKeysRepo};
+pub use self::outbox::{StoredEvent, TodoEvent};
+pub use self::sync_repo::{parse_token, SyncRepo, SyncTodos};
+pub use self::webhooks_repo::{NewWebhook, PendingDelivery, Webhook, WebhooksRepo};
 pub use self::connection_pool::{connection_pool, ConnectionPool};
 pub use self::paginated::Paginated;
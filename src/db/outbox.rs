@@ -0,0 +1,140 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+use std::fmt;
+use std::str::FromStr;
+
+use result::Error;
+
+use super::schema::webhook_events;
+use super::Todo;
+
+/// A change of a todo item which webhooks are notified about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TodoEvent {
+  Created,
+  Updated,
+  Completed,
+  Deleted,
+}
+
+impl TodoEvent {
+  pub const ALL: &'static [TodoEvent] = &[
+    TodoEvent::Created,
+    TodoEvent::Updated,
+    TodoEvent::Completed,
+    TodoEvent::Deleted,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match *self {
+      TodoEvent::Created => "todo.created",
+      TodoEvent::Updated => "todo.updated",
+      TodoEvent::Completed => "todo.completed",
+      TodoEvent::Deleted => "todo.deleted",
+    }
+  }
+
+  /// An event for a changed item, it's `Completed` when the item became done.
+  pub fn of_update(before: &Todo, after: &Todo) -> Self {
+    if after.done && !before.done {
+      TodoEvent::Completed
+    } else {
+      TodoEvent::Updated
+    }
+  }
+}
+
+impl fmt::Display for TodoEvent {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl FromStr for TodoEvent {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Error> {
+    TodoEvent::ALL
+      .iter()
+      .find(|it| it.as_str() == s)
+      .cloned()
+      .ok_or_else(|| Error::Validation(format!("unknown event {:?}", s)))
+  }
+}
+
+/// An event stored in the outbox, mapping to `webhook_events` table
+#[derive(Queryable, Debug, Clone)]
+pub struct StoredEvent {
+  pub id: i64,
+  pub event: String,
+  /// a json object with an event name, a time and a todo item
+  pub payload: String,
+  pub created_at: NaiveDateTime,
+}
+
+/// Write an event to the outbox, must be called in the same transaction as the change.
+pub fn push(conn: &MysqlConnection, event: TodoEvent, todo: &Todo) -> Result<(), Error> {
+  let time = Utc::now().naive_utc();
+  let payload = json!({
+    "event": event.as_str(),
+    "created_at": time,
+    "todo": todo,
+  });
+
+  diesel::insert_into(webhook_events::table)
+    .values(&(
+      webhook_events::event.eq(event.as_str()),
+      webhook_events::payload.eq(payload.to_string()),
+      webhook_events::created_at.eq(&time),
+    ))
+    .execute(conn)
+    .map_err(Error::from)
+    .map(|_| ())
+}
+
+/// Events with ids greater than `after_id` ordered by id.
+pub fn load_after(
+  conn: &MysqlConnection,
+  after_id: i64,
+  limit: i64,
+) -> Result<Vec<StoredEvent>, Error> {
+  webhook_events::table
+    .select((
+      webhook_events::id,
+      webhook_events::event,
+      webhook_events::payload,
+      webhook_events::created_at,
+    ))
+    .filter(webhook_events::id.gt(after_id))
+    .order(webhook_events::id.asc())
+    .limit(limit)
+    .load::<StoredEvent>(conn)
+    .map_err(Error::from)
+}
+
+/// The latest event's id, zero when there are no events.
+pub fn latest_id(conn: &MysqlConnection) -> Result<i64, Error> {
+  webhook_events::table
+    .select(webhook_events::id)
+    .order(webhook_events::id.desc())
+    .first::<i64>(conn)
+    .optional()
+    .map(|it| it.unwrap_or(0))
+    .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_parse_events() {
+    for event in TodoEvent::ALL {
+      assert_that(&event.as_str().parse::<TodoEvent>()).is_ok().is_equal_to(event);
+    }
+    assert_that(&"todo.foo".parse::<TodoEvent>()).is_err();
+  }
+}
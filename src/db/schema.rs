@@ -5,5 +5,88 @@ table! {
         done -> Bool,
         created_at -> Datetime,
         updated_at -> Datetime,
+        due_at -> Nullable<Datetime>,
+        change_seq -> Bigint,
     }
 }
+
+table! {
+    change_sequence (id) {
+        id -> Tinyint,
+        value -> Bigint,
+    }
+}
+
+table! {
+    todo_field_clocks (todo_id, field) {
+        todo_id -> Bigint,
+        field -> Varchar,
+        changed_at -> Datetime,
+    }
+}
+
+table! {
+    todo_tombstones (todo_id) {
+        todo_id -> Bigint,
+        change_seq -> Bigint,
+        deleted_at -> Datetime,
+    }
+}
+
+table! {
+    idempotency_keys (client, id) {
+        id -> Varchar,
+        client -> Varchar,
+        fingerprint -> Varchar,
+        status -> Integer,
+        headers -> Text,
+        body -> Blob,
+        created_at -> Datetime,
+        expires_at -> Datetime,
+    }
+}
+
+table! {
+    calendar_tokens (token) {
+        token -> Varchar,
+        owner -> Varchar,
+        scope -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    webhooks (id) {
+        id -> Bigint,
+        url -> Varchar,
+        secret -> Varchar,
+        events -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    webhook_events (id) {
+        id -> Bigint,
+        event -> Varchar,
+        payload -> Text,
+        created_at -> Datetime,
+        dispatched_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    webhook_deliveries (id) {
+        id -> Bigint,
+        webhook_id -> Bigint,
+        event_id -> Bigint,
+        status -> Varchar,
+        attempts -> Integer,
+        next_attempt_at -> Datetime,
+        last_error -> Nullable<Text>,
+        created_at -> Datetime,
+        updated_at -> Datetime,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(webhook_deliveries, webhook_events);
@@ -0,0 +1,320 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::MysqlConnection;
+use futures_cpupool::{CpuFuture, CpuPool};
+use std::collections::HashSet;
+
+use result::Error;
+
+use super::changes::{self, Tombstone};
+use super::todos_repo::{self, Todo};
+use super::ConnectionPool;
+
+/// How many changes are returned when a request doesn't set a limit.
+const DEFAULT_LIMIT: u32 = 100;
+
+/// Changes made by a client while offline and a token of its latest sync.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncTodos {
+  pub token: Option<String>,
+  pub limit: Option<u32>,
+  #[serde(default)]
+  pub changes: Vec<LocalChange>,
+}
+
+/// A queued local change, a todo item is created when `id` is missing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalChange {
+  /// a client's own reference which is echoed back
+  pub client_id: Option<String>,
+  pub id: Option<i64>,
+  pub text: Option<String>,
+  pub done: Option<bool>,
+  /// when the change was made, updates which have it are merged by field instead of conflicting
+  pub changed_at: Option<NaiveDateTime>,
+  #[serde(default)]
+  pub deleted: bool,
+}
+
+/// A local change which was applied.
+#[derive(Debug, Clone, Serialize)]
+pub struct Applied {
+  pub client_id: Option<String>,
+  pub id: i64,
+  /// fields which kept server's values because they were changed later
+  pub overridden: Vec<&'static str>,
+}
+
+/// A local change which wasn't applied because the item was changed or deleted since the token.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+  pub client_id: Option<String>,
+  pub id: i64,
+  pub reason: &'static str,
+  /// the current item, `None` when it's deleted
+  pub todo: Option<Todo>,
+}
+
+/// A sync's result, `token` is sent with the next sync.
+#[derive(Debug, Clone, Serialize)]
+pub struct Synced {
+  pub token: String,
+  /// whether there are more changes, a client syncs again with the new token
+  pub more: bool,
+  pub todos: Vec<Todo>,
+  pub deleted: Vec<Tombstone>,
+  pub applied: Vec<Applied>,
+  pub conflicts: Vec<Conflict>,
+}
+
+/// Sync's repository, clients upload local changes and download changes after their token.
+///
+/// Changes to items which were changed or deleted by someone else since the token aren't
+/// applied, they are reported back as conflicts with the current item instead. Updates which
+/// tell when they were made are merged by field, the latest change of a field wins.
+#[derive(Clone)]
+pub struct SyncRepo {
+  conn_pool: ConnectionPool,
+  cpu_pool: CpuPool,
+}
+
+impl SyncRepo {
+  pub fn new(conn_pool: ConnectionPool, cpu_pool: CpuPool) -> Self {
+    SyncRepo {
+      conn_pool,
+      cpu_pool,
+    }
+  }
+
+  /// Apply local changes in a single transaction and load changes after the token.
+  pub fn sync(&self, sync: SyncTodos) -> CpuFuture<Synced, Error> {
+    let SyncRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let since = match sync.token {
+        Some(ref token) => parse_token(token)?,
+        None => 0,
+      };
+      let limit = i64::from(sync.limit.unwrap_or(DEFAULT_LIMIT));
+
+      let (applied, conflicts) = conn.transaction::<_, Error, _>(|| {
+        let mut applied = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut touched = HashSet::new();
+
+        for change in sync.changes {
+          match apply(&conn, since, &mut touched, change)? {
+            Ok(it) => applied.push(it),
+            Err(it) => conflicts.push(it),
+          }
+        }
+
+        Ok((applied, conflicts))
+      })?;
+
+      let changes = changes::since(&conn, since, limit)?;
+
+      Ok(Synced {
+        token: changes.seq.to_string(),
+        more: changes.more,
+        todos: changes.todos,
+        deleted: changes.deleted,
+        applied,
+        conflicts,
+      })
+    })
+  }
+}
+
+/// Parse a token returned by a previous sync.
+pub fn parse_token(token: &str) -> Result<i64, Error> {
+  match token.parse::<i64>() {
+    Ok(seq) if seq >= 0 => Ok(seq),
+    _ => Err(Error::Validation(format!("invalid sync token {:?}", token))),
+  }
+}
+
+/// Apply a local change unless it conflicts, `touched` are items changed by the same sync.
+fn apply(
+  conn: &MysqlConnection,
+  since: i64,
+  touched: &mut HashSet<i64>,
+  change: LocalChange,
+) -> Result<Result<Applied, Conflict>, Error> {
+  let LocalChange {
+    client_id,
+    id,
+    text,
+    done,
+    changed_at,
+    deleted,
+  } = change;
+
+  let id = match id {
+    Some(id) => id,
+    None => {
+      let text = text.ok_or_else(|| Error::Validation("new todo's text is missing".to_string()))?;
+      let mut todo = todos_repo::create(conn, &text)?;
+      if done == Some(true) {
+        todo = todos_repo::merge(conn, &todo, None, done, None)?.todo;
+      }
+      touched.insert(todo.id);
+      return Ok(Ok(Applied {
+        client_id,
+        id: todo.id,
+        overridden: Vec::new(),
+      }));
+    }
+  };
+
+  let current = match todos_repo::lock(conn, id) {
+    Ok(current) => current,
+    // deleting a deleted item is fine, there is nothing to update though
+    Err(Error::RecordNotFound) if deleted => {
+      return Ok(Ok(Applied {
+        client_id,
+        id,
+        overridden: Vec::new(),
+      }))
+    }
+    Err(Error::RecordNotFound) => {
+      return Ok(Err(Conflict {
+        client_id,
+        id,
+        reason: "deleted",
+        todo: None,
+      }))
+    }
+    Err(err) => return Err(err),
+  };
+
+  let merged_by_field = changed_at.is_some() && !deleted;
+  if current.change_seq > since && !touched.contains(&id) && !merged_by_field {
+    return Ok(Err(Conflict {
+      client_id,
+      id,
+      reason: "changed",
+      todo: Some(current),
+    }));
+  }
+
+  let overridden = if deleted {
+    todos_repo::remove(conn, &current)?;
+    Vec::new()
+  } else {
+    todos_repo::merge(conn, &current, text, done, changed_at)?.overridden
+  };
+  touched.insert(id);
+
+  Ok(Ok(Applied {
+    client_id,
+    id,
+    overridden,
+  }))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::Future;
+
+  use config::Config;
+  use db::{connection_pool, NewTodo, TodosRepo, UpdateTodo};
+  use metrics::Metrics;
+
+  #[test]
+  fn should_parse_token() {
+    assert_that(&parse_token("42").ok()).is_equal_to(Some(42));
+    assert_that(&parse_token("-1")).is_err();
+    assert_that(&parse_token("foo")).is_err();
+  }
+
+  #[test]
+  fn should_sync_changes_and_report_conflicts() {
+    let (todos_repo, sync_repo) = create_repos();
+    let initial = sync_repo.sync(sync(None, Vec::new())).wait().unwrap();
+
+    let todo = todos_repo
+      .insert(NewTodo {
+        text: "foo".to_string(),
+      })
+      .wait()
+      .unwrap();
+    let removed = todos_repo
+      .insert(NewTodo {
+        text: "bar".to_string(),
+      })
+      .wait()
+      .unwrap();
+    todos_repo.delete(removed.id, |_| Ok(())).wait().unwrap();
+
+    let synced = sync_repo
+      .sync(sync(Some(initial.token.clone()), Vec::new()))
+      .wait()
+      .unwrap();
+    let ids: Vec<_> = synced.todos.iter().map(|it| it.id).collect();
+    let deleted: Vec<_> = synced.deleted.iter().map(|it| it.todo_id).collect();
+    assert_that(&ids).is_equal_to(vec![todo.id]);
+    assert_that(&deleted).is_equal_to(vec![removed.id]);
+
+    // changed by someone else after the client's token
+    todos_repo
+      .update(UpdateTodo {
+        id: todo.id,
+        text: None,
+        done: Some(true),
+        changed_at: None,
+      })
+      .wait()
+      .unwrap();
+
+    let changes = vec![
+      local_change(Some(todo.id), "changed offline"),
+      local_change(Some(removed.id), "changed offline"),
+      local_change(None, "created offline"),
+    ];
+    let synced = sync_repo
+      .sync(sync(Some(synced.token), changes))
+      .wait()
+      .unwrap();
+
+    let reasons: Vec<_> = synced.conflicts.iter().map(|it| it.reason).collect();
+    assert_that(&reasons).is_equal_to(vec!["changed", "deleted"]);
+    assert_that(&synced.applied).has_length(1);
+    assert_that(&synced.todos.iter().any(|it| it.text == "created offline")).is_true();
+  }
+
+  fn sync(token: Option<String>, changes: Vec<LocalChange>) -> SyncTodos {
+    SyncTodos {
+      token,
+      limit: Some(100),
+      changes,
+    }
+  }
+
+  fn local_change(id: Option<i64>, text: &str) -> LocalChange {
+    LocalChange {
+      client_id: Some(text.to_string()),
+      id,
+      text: Some(text.to_string()),
+      done: None,
+      changed_at: None,
+      deleted: false,
+    }
+  }
+
+  fn create_repos() -> (TodosRepo, SyncRepo) {
+    let cfg = Config::default();
+    let conn_pool = connection_pool(&cfg.database_url, cfg.pool_size);
+    let cpu_pool = cfg.create_cpu_pool();
+    (
+      TodosRepo::new(conn_pool.clone(), cpu_pool.clone(), Metrics::new()),
+      SyncRepo::new(conn_pool, cpu_pool),
+    )
+  }
+}
@@ -1,141 +1,351 @@
 use chrono::{NaiveDateTime, Utc};
 use diesel;
+use futures::{stream, Stream};
 use futures_cpupool::{CpuFuture, CpuPool};
 use diesel::prelude::*;
+use diesel::mysql::Mysql;
+use diesel::MysqlConnection;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::time::{Instant, SystemTime};
 
 use result::Error;
+use metrics::{self, Metrics};
+use logger::Text;
+use trace::{self, Span, SpanKind};
 
+use super::changes::{self, Changes};
+use super::field_clocks;
 use super::functions::last_insert_id;
+use super::outbox::{self, TodoEvent};
 use super::schema::todos;
 use super::ConnectionPool;
 use super::Paginated;
 
-/// Todo item model, mapping to `todos` table
-#[derive(Queryable, Debug, Clone, Serialize)]
+/// Todo item model, mapping to `todos` table, `Debug` hides the text unless it's enabled for logs
+#[derive(Queryable, Clone, Serialize, Deserialize)]
 pub struct Todo {
   pub id: i64,
   pub text: String,
   pub done: bool,
   pub created_at: NaiveDateTime,
   pub updated_at: NaiveDateTime,
+  pub due_at: Option<NaiveDateTime>,
+  /// a sequence number of the latest change, used by sync
+  #[serde(skip_serializing, default)]
+  pub change_seq: i64,
+}
+
+impl fmt::Debug for Todo {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Todo")
+      .field("id", &self.id)
+      .field("text", &Text(&self.text))
+      .field("done", &self.done)
+      .field("created_at", &self.created_at)
+      .field("updated_at", &self.updated_at)
+      .field("due_at", &self.due_at)
+      .field("change_seq", &self.change_seq)
+      .finish()
+  }
 }
 
 /// Model for a new todo item that contains only fields required for todo item creation
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct NewTodo {
   pub text: String,
 }
 
+impl fmt::Debug for NewTodo {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("NewTodo")
+      .field("text", &Text(&self.text))
+      .finish()
+  }
+}
+
+/// Model for an imported todo item, missing fields are filled like for a new one
+#[derive(Clone, Deserialize)]
+pub struct ImportTodo {
+  pub id: Option<i64>,
+  pub text: String,
+  pub done: Option<bool>,
+  pub created_at: Option<NaiveDateTime>,
+  pub updated_at: Option<NaiveDateTime>,
+  pub due_at: Option<NaiveDateTime>,
+}
+
+impl fmt::Debug for ImportTodo {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("ImportTodo")
+      .field("id", &self.id)
+      .field("text", &Text(&self.text))
+      .field("done", &self.done)
+      .field("created_at", &self.created_at)
+      .field("updated_at", &self.updated_at)
+      .field("due_at", &self.due_at)
+      .finish()
+  }
+}
+
 /// Query parameters
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct QueryTodos {
   pub next: Option<i64>,
   pub limit: Option<u8>,
   pub text: Option<String>,
 }
 
+impl fmt::Debug for QueryTodos {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("QueryTodos")
+      .field("next", &self.next)
+      .field("limit", &self.limit)
+      .field("text", &self.text.as_ref().map(|it| Text(it)))
+      .finish()
+  }
+}
+
 /// Complete todo parameters
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct UpdateTodo {
   pub id: i64,
   pub text: Option<String>,
   pub done: Option<bool>,
+  /// when the change was made, e.g. offline, it's now when missing
+  pub changed_at: Option<NaiveDateTime>,
+}
+
+impl fmt::Debug for UpdateTodo {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("UpdateTodo")
+      .field("id", &self.id)
+      .field("text", &self.text.as_ref().map(|it| Text(it)))
+      .field("done", &self.done)
+      .field("changed_at", &self.changed_at)
+      .finish()
+  }
+}
+
+/// An update's result, fields are merged separately and the latest change of a field wins
+#[derive(Debug, Clone)]
+pub struct Merged {
+  pub todo: Todo,
+  /// fields which took the update's values
+  pub accepted: Vec<&'static str>,
+  /// fields which kept their values because they were changed after the update
+  pub overridden: Vec<&'static str>,
 }
 
-/// Todo's repository
+impl Serialize for Merged {
+  /// Serialized as the todo item with `accepted` and `overridden` fields.
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut value = json!(self.todo);
+    value["accepted"] = json!(self.accepted);
+    value["overridden"] = json!(self.overridden);
+    value.serialize(serializer)
+  }
+}
+
+/// How many items are loaded at once when scanning through todo items
+const SCAN_BATCH_SIZE: i64 = 500;
+
+/// Todo's repository, every write also takes a change sequence number and records a webhook event
+/// in the same transaction
 #[derive(Clone)]
 pub struct TodosRepo {
   conn_pool: ConnectionPool,
   cpu_pool: CpuPool,
+  metrics: Metrics,
+}
+
+/// Counts an operation waiting for a cpu pool's thread until it's dropped.
+struct Queued(Metrics);
+
+impl Queued {
+  fn new(metrics: Metrics) -> Self {
+    metrics.add(metrics::CPU_POOL_QUEUE_DEPTH, &[], 1.0);
+    Queued(metrics)
+  }
+}
+
+impl Drop for Queued {
+  fn drop(&mut self) {
+    self.0.add(metrics::CPU_POOL_QUEUE_DEPTH, &[], -1.0);
+  }
 }
 
 impl TodosRepo {
-  pub fn new(conn_pool: ConnectionPool, cpu_pool: CpuPool) -> Self {
+  pub fn new(conn_pool: ConnectionPool, cpu_pool: CpuPool, metrics: Metrics) -> Self {
     TodosRepo {
       conn_pool,
       cpu_pool,
+      metrics,
     }
   }
 
-  /// Create a new todo after that query and return it from db.
-  pub fn insert(&self, new_todo: NewTodo) -> CpuFuture<Todo, Error> {
+  /// Run an operation with a connection on the cpu pool, its latency and errors are measured.
+  ///
+  /// A traced operation gets a span with children for the queue, the pool and the query.
+  fn run<T, F>(&self, operation: &'static str, f: F) -> CpuFuture<T, Error>
+  where
+    T: Send + 'static,
+    F: FnOnce(&MysqlConnection) -> Result<T, Error> + Send + 'static,
+  {
     let TodosRepo {
       conn_pool,
       cpu_pool,
+      metrics,
     } = self.clone();
+    let started = Instant::now();
+    let queued = Queued::new(metrics.clone());
+    let traced = trace::current().map(|parent| {
+      let mut span = parent.child(&format!("TodosRepo.{}", operation));
+      span.set_attribute("db.operation", operation);
+      (parent.tracer, span)
+    });
 
     cpu_pool.spawn_fn(move || {
-      let conn = conn_pool.get().map_err(Error::from)?;
-      let time = Utc::now().naive_utc();
-
-      diesel::insert_into(todos::table)
-        .values(&(
-          todos::text.eq(new_todo.text.as_str()),
-          todos::done.eq(false),
-          todos::created_at.eq(&time),
-          todos::updated_at.eq(&time),
-        ))
-        .execute(&*conn)
-        .map_err(Error::from)?;
+      drop(queued);
+      let dequeued = SystemTime::now();
+
+      let waiting = Instant::now();
+      let conn = conn_pool.get().map_err(Error::from);
+      metrics.observe(metrics::DB_POOL_WAIT, &[], waiting.elapsed());
+      let checked_out = SystemTime::now();
+      let checkout_error = conn.as_ref().err().map(|it| it.variant());
+
+      let result = conn.and_then(|conn| f(&conn));
+      let error = result.as_ref().err().map(|it| it.variant());
+
+      let labels = &[("operation", operation)];
+      metrics.observe(metrics::TODOS_REPO_DURATION, labels, started.elapsed());
+      if let Some(error) = error {
+        let labels = &[("operation", operation), ("error", error)];
+        metrics.inc(metrics::TODOS_REPO_ERRORS, labels);
+      }
 
-      let todo_id = diesel::select(last_insert_id)
-        .first::<i64>(&*conn)
-        .map_err(Error::from)?;
+      if let Some((tracer, span)) = traced {
+        let queue = child_span(&span, "cpu_pool.queue", span.start, None);
+        tracer.export(queue.finish_at(dequeued));
+        let checkout = child_span(&span, "db_pool.checkout", dequeued, checkout_error);
+        tracer.export(checkout.finish_at(checked_out));
+        if checkout_error.is_none() {
+          let query = child_span(&span, "db.query", checked_out, error);
+          tracer.export(query.finish_at(SystemTime::now()));
+        }
+        tracer.export(span.finish(error.map(String::from)));
+      }
+      result
+    })
+  }
 
-      todos::table
-        .filter(todos::id.eq(todo_id))
-        .first::<Todo>(&*conn)
-        .map_err(Error::from)
+  /// Create a new todo after that query and return it from db.
+  pub fn insert(&self, new_todo: NewTodo) -> CpuFuture<Todo, Error> {
+    self.run("insert", move |conn| {
+      conn.transaction::<_, Error, _>(|| create(&conn, &new_todo.text))
     })
   }
 
   /// Query todo items, return paginated result
   pub fn query(&self, query: QueryTodos) -> CpuFuture<Paginated<Todo>, Error> {
-    let TodosRepo {
-      conn_pool,
-      cpu_pool,
-    } = self.clone();
-
-    cpu_pool.spawn_fn(move || {
-      let conn = conn_pool.get().map_err(Error::from)?;
-      let mut stmt = todos::table.order(todos::id.desc()).into_boxed();
-
-      match query.next {
-        Some(next) if next > 0 => stmt = stmt.filter(todos::id.lt(next)),
-        _ => {}
-      }
-
-      match query.limit {
-        Some(limit) if limit <= 10 && limit > 0 => stmt = stmt.limit(i64::from(limit)),
-        _ => stmt = stmt.limit(10),
-      }
-
-      match query.text {
-        Some(ref text) if !text.is_empty() => {
-          let pattern = format!("%{}%", text);
-          stmt = stmt.filter(todos::text.like(pattern));
-        }
-        _ => {}
-      }
+    self.run("query", move |conn| {
+      let limit = match query.limit {
+        Some(limit) if limit <= 10 && limit > 0 => i64::from(limit),
+        _ => 10,
+      };
 
-      let items = stmt.load::<Todo>(&*conn).map_err(Error::from)?;
+      let items = filtered(&query)
+        .limit(limit)
+        .load::<Todo>(&*conn)
+        .map_err(Error::from)?;
       let next = items.last().map(|it| it.id);
 
       Ok(Paginated { next, items })
     })
   }
 
-  /// Find a single todo item
-  #[allow(dead_code)]
-  pub fn find(&self, id: i64) -> CpuFuture<Todo, Error> {
-    let TodosRepo {
-      conn_pool,
-      cpu_pool,
-    } = self.clone();
+  /// Stream all todo items matching query's filters in batches, query's limit is ignored
+  pub fn scan(&self, query: QueryTodos) -> Box<Stream<Item = Vec<Todo>, Error = Error> + Send> {
+    let repo = self.clone();
+
+    let batches = stream::unfold(Some(query), move |query| {
+      let query = query?;
+      let batch = repo.run("scan", move |conn| {
+        let items = filtered(&query)
+          .limit(SCAN_BATCH_SIZE)
+          .load::<Todo>(&*conn)
+          .map_err(Error::from)?;
+
+        let next = match items.last() {
+          Some(last) if items.len() as i64 == SCAN_BATCH_SIZE => Some(QueryTodos {
+            next: Some(last.id),
+            ..query
+          }),
+          _ => None,
+        };
+
+        Ok((items, next))
+      });
+
+      Some(batch)
+    });
+
+    Box::new(batches.filter(|items| !items.is_empty()))
+  }
 
-    cpu_pool.spawn_fn(move || {
-      let conn = conn_pool.get().map_err(Error::from)?;
+  /// Insert an imported todo item, keep its id only when asked, otherwise a new one is assigned
+  pub fn import(&self, import: ImportTodo, keep_id: bool) -> CpuFuture<Todo, Error> {
+    self.run("import", move |conn| {
+      let time = Utc::now().naive_utc();
+      let created_at = import.created_at.unwrap_or(time);
+      let updated_at = import.updated_at.unwrap_or(created_at);
+
+      conn.transaction::<_, Error, _>(|| {
+        let values = (
+          todos::text.eq(import.text.as_str()),
+          todos::done.eq(import.done.unwrap_or(false)),
+          todos::created_at.eq(&created_at),
+          todos::updated_at.eq(&updated_at),
+          todos::due_at.eq(&import.due_at),
+          todos::change_seq.eq(changes::next_seq(&conn)?),
+        );
+
+        let todo_id = match import.id {
+          Some(id) if keep_id => {
+            diesel::insert_into(todos::table)
+              .values(&(todos::id.eq(id), values))
+              .execute(&*conn)
+              .map_err(Error::from)?;
+            id
+          }
+          _ => {
+            diesel::insert_into(todos::table)
+              .values(&values)
+              .execute(&*conn)
+              .map_err(Error::from)?;
+
+            diesel::select(last_insert_id)
+              .first::<i64>(&*conn)
+              .map_err(Error::from)?
+          }
+        };
+        changes::revive(&conn, todo_id)?;
+
+        let todo = todos::table
+          .filter(todos::id.eq(todo_id))
+          .first::<Todo>(&*conn)
+          .map_err(Error::from)?;
+
+        outbox::push(&conn, TodoEvent::Created, &todo)?;
+        Ok(todo)
+      })
+    })
+  }
 
+  /// Find a single todo item
+  pub fn find(&self, id: i64) -> CpuFuture<Todo, Error> {
+    self.run("find", move |conn| {
       todos::table
         .filter(todos::id.eq(id))
         .first::<Todo>(&*conn)
@@ -143,43 +353,89 @@ impl TodosRepo {
     })
   }
 
-  /// Update completion status and/or text for a single todo item
-  pub fn update(&self, update: UpdateTodo) -> CpuFuture<Todo, Error> {
-    let TodosRepo {
-      conn_pool,
-      cpu_pool,
-    } = self.clone();
+  /// Find todo items by their ids, missing ones are skipped.
+  pub fn find_all(&self, ids: Vec<i64>) -> CpuFuture<Vec<Todo>, Error> {
+    self.run("find_all", move |conn| {
+      todos::table
+        .filter(todos::id.eq_any(ids))
+        .load::<Todo>(&*conn)
+        .map_err(Error::from)
+    })
+  }
 
-    cpu_pool.spawn_fn(move || {
-      let conn = conn_pool.get().map_err(Error::from)?;
+  /// Load up to `limit` changed and deleted items after a change sequence number.
+  pub fn changes(&self, since: i64, limit: i64) -> CpuFuture<Changes, Error> {
+    self.run("changes", move |conn| changes::since(&conn, since, limit))
+  }
 
-      let mut todo = todos::table
-        .filter(todos::id.eq(update.id))
-        .first::<Todo>(&*conn)
-        .map_err(Error::from)?;
+  /// The latest change sequence number, it grows with every write.
+  pub fn latest_seq(&self) -> CpuFuture<i64, Error> {
+    self.run("latest_seq", move |conn| changes::latest_seq(&conn))
+  }
 
-      todo = Todo {
-        updated_at: Utc::now().naive_utc(),
-        ..todo
-      };
+  /// Update completion status and/or text for a single todo item, see `Merged`
+  pub fn update(&self, update: UpdateTodo) -> CpuFuture<Merged, Error> {
+    self.run("update", move |conn| {
+      conn.transaction::<_, Error, _>(|| {
+        let current = lock(&conn, update.id)?;
 
-      if let Some(text) = update.text {
-        todo.text = text;
-      }
+        merge(&conn, &current, update.text, update.done, update.changed_at)
+      })
+    })
+  }
 
-      if let Some(done) = update.done {
-        todo.done = done;
-      }
+  /// Replace text, completion status and due time of a todo item.
+  ///
+  /// `check` is called with the current item in the same transaction, an error aborts the update.
+  pub fn replace<F>(&self, id: i64, todo: ImportTodo, check: F) -> CpuFuture<Todo, Error>
+  where
+    F: FnOnce(&Todo) -> Result<(), Error> + Send + 'static,
+  {
+    self.run("replace", move |conn| {
+      conn.transaction::<_, Error, _>(|| {
+        let current = lock(&conn, id)?;
+        check(&current)?;
+
+        let todo = Todo {
+          text: todo.text,
+          done: todo.done.unwrap_or(false),
+          updated_at: Utc::now().naive_utc(),
+          due_at: todo.due_at,
+          change_seq: changes::next_seq(&conn)?,
+          ..current.clone()
+        };
+
+        diesel::update(todos::table.filter(todos::id.eq(id)))
+          .set((
+            todos::text.eq(todo.text.as_str()),
+            todos::done.eq(todo.done),
+            todos::updated_at.eq(&todo.updated_at),
+            todos::due_at.eq(&todo.due_at),
+            todos::change_seq.eq(todo.change_seq),
+          ))
+          .execute(&*conn)
+          .map_err(Error::from)?;
+        let fields = [field_clocks::TEXT, field_clocks::DONE, field_clocks::DUE_AT];
+        field_clocks::touch(&conn, id, &fields, todo.updated_at)?;
+
+        outbox::push(&conn, TodoEvent::of_update(&current, &todo), &todo)?;
+        Ok(todo)
+      })
+    })
+  }
 
-      diesel::update(todos::table.filter(todos::id.eq(todo.id)))
-        .set((
-          todos::text.eq(todo.text.as_str()),
-          todos::done.eq(todo.done),
-          todos::updated_at.eq(&todo.updated_at),
-        ))
-        .execute(&*conn)
-        .map_err(Error::from)
-        .map(|_| todo)
+  /// Delete a todo item, `check` is called with the current item in the same transaction.
+  pub fn delete<F>(&self, id: i64, check: F) -> CpuFuture<(), Error>
+  where
+    F: FnOnce(&Todo) -> Result<(), Error> + Send + 'static,
+  {
+    self.run("delete", move |conn| {
+      conn.transaction::<_, Error, _>(|| {
+        let current = lock(&conn, id)?;
+        check(&current)?;
+
+        remove(&conn, &current)
+      })
     })
   }
 
@@ -194,11 +450,169 @@ impl TodosRepo {
   }
 }
 
+/// A span of an operation's phase which started at `start`.
+fn child_span(parent: &Span, name: &str, start: SystemTime, error: Option<&str>) -> Span {
+  let mut span = Span::start(name, SpanKind::Internal, Some(&parent.context));
+  span.start = start;
+  span.error = error.map(String::from);
+  span
+}
+
+/// Load a todo item and lock it until the transaction ends, concurrent writes wait for it.
+pub fn lock(conn: &MysqlConnection, id: i64) -> Result<Todo, Error> {
+  todos::table
+    .filter(todos::id.eq(id))
+    .for_update()
+    .first::<Todo>(conn)
+    .map_err(Error::from)
+}
+
+/// Insert a new todo item and return it from db, should be called in a transaction.
+pub fn create(conn: &MysqlConnection, text: &str) -> Result<Todo, Error> {
+  let time = Utc::now().naive_utc();
+  let seq = changes::next_seq(conn)?;
+
+  diesel::insert_into(todos::table)
+    .values(&(
+      todos::text.eq(text),
+      todos::done.eq(false),
+      todos::created_at.eq(&time),
+      todos::updated_at.eq(&time),
+      todos::change_seq.eq(seq),
+    ))
+    .execute(conn)
+    .map_err(Error::from)?;
+
+  let todo_id = diesel::select(last_insert_id)
+    .first::<i64>(conn)
+    .map_err(Error::from)?;
+  // ids of deleted items can be reused after a restart
+  changes::revive(conn, todo_id)?;
+
+  let todo = todos::table
+    .filter(todos::id.eq(todo_id))
+    .first::<Todo>(conn)
+    .map_err(Error::from)?;
+
+  outbox::push(conn, TodoEvent::Created, &todo)?;
+  Ok(todo)
+}
+
+/// Merge text and/or completion status changed at `changed_at` into a todo item, a field takes a
+/// new value unless it was changed later. Should be called in a transaction with `current` loaded
+/// by `lock`.
+pub fn merge(
+  conn: &MysqlConnection,
+  current: &Todo,
+  text: Option<String>,
+  done: Option<bool>,
+  changed_at: Option<NaiveDateTime>,
+) -> Result<Merged, Error> {
+  let now = Utc::now().naive_utc();
+  // a client's clock can be ahead, a change from the future would always win
+  let changed_at = match changed_at {
+    Some(changed_at) if changed_at < now => changed_at,
+    _ => now,
+  };
+
+  let clocks = field_clocks::load(conn, current.id)?;
+  let wins = |field: &str| clocks.get(field).map_or(true, |it| changed_at >= *it);
+
+  let mut merged = Merged {
+    todo: current.clone(),
+    accepted: Vec::new(),
+    overridden: Vec::new(),
+  };
+
+  if let Some(text) = text {
+    if wins(field_clocks::TEXT) {
+      merged.todo.text = text;
+      merged.accepted.push(field_clocks::TEXT);
+    } else {
+      merged.overridden.push(field_clocks::TEXT);
+    }
+  }
+
+  if let Some(done) = done {
+    if wins(field_clocks::DONE) {
+      merged.todo.done = done;
+      merged.accepted.push(field_clocks::DONE);
+    } else {
+      merged.overridden.push(field_clocks::DONE);
+    }
+  }
+
+  if merged.accepted.is_empty() {
+    return Ok(merged);
+  }
+
+  merged.todo.updated_at = now;
+  merged.todo.change_seq = changes::next_seq(conn)?;
+
+  // only accepted fields are written, a concurrent change of another field is kept
+  let todo = &merged.todo;
+  let text = if merged.accepted.contains(&field_clocks::TEXT) {
+    Some(todos::text.eq(todo.text.as_str()))
+  } else {
+    None
+  };
+  let done = if merged.accepted.contains(&field_clocks::DONE) {
+    Some(todos::done.eq(todo.done))
+  } else {
+    None
+  };
+  diesel::update(todos::table.filter(todos::id.eq(todo.id)))
+    .set((
+      text,
+      done,
+      todos::updated_at.eq(&todo.updated_at),
+      todos::change_seq.eq(todo.change_seq),
+    ))
+    .execute(conn)
+    .map_err(Error::from)?;
+  field_clocks::touch(conn, todo.id, &merged.accepted, changed_at)?;
+
+  outbox::push(conn, TodoEvent::of_update(current, todo), todo)?;
+  Ok(merged)
+}
+
+/// Delete a todo item leaving a tombstone, should be called in a transaction.
+pub fn remove(conn: &MysqlConnection, current: &Todo) -> Result<(), Error> {
+  diesel::delete(todos::table.filter(todos::id.eq(current.id)))
+    .execute(conn)
+    .map_err(Error::from)?;
+  changes::bury(conn, current.id)?;
+  field_clocks::forget(conn, current.id)?;
+
+  outbox::push(conn, TodoEvent::Deleted, current)
+}
+
+/// Build a statement which applies query's filters and cursor, ordered by id descending
+fn filtered(query: &QueryTodos) -> todos::BoxedQuery<'static, Mysql> {
+  let mut stmt = todos::table.order(todos::id.desc()).into_boxed();
+
+  match query.next {
+    Some(next) if next > 0 => stmt = stmt.filter(todos::id.lt(next)),
+    _ => {}
+  }
+
+  match query.text {
+    Some(ref text) if !text.is_empty() => {
+      let pattern = format!("%{}%", text);
+      stmt = stmt.filter(todos::text.like(pattern));
+    }
+    _ => {}
+  }
+
+  stmt
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use spectral::prelude::*;
-  use futures::Future;
+  use futures::{Future, Stream};
+  use chrono::Duration;
 
   use config::Config;
   use db::connection_pool;
@@ -266,8 +680,9 @@ mod tests {
         id: todo.id,
         text: None,
         done: Some(true),
+        changed_at: None,
       };
-      let todo = todos_repo.update(update).wait().unwrap();
+      let todo = todos_repo.update(update).wait().unwrap().todo;
       assert_that(&todo.done).is_true();
       assert_that(&todo.text).is_equal_to("foo".to_string());
     }
@@ -277,17 +692,182 @@ mod tests {
         id: todo.id,
         text: Some("bar".to_string()),
         done: None,
+        changed_at: None,
       };
-      let todo = todos_repo.update(update).wait().unwrap();
+      let todo = todos_repo.update(update).wait().unwrap().todo;
       assert_that(&todo.done).is_true();
       assert_that(&todo.text).is_equal_to("bar".to_string());
     }
   }
 
+  #[test]
+  fn should_merge_concurrent_updates_by_field() {
+    let todos_repo = create_repo();
+    let todo = todos_repo
+      .insert(NewTodo {
+        text: "foo".to_string(),
+      })
+      .wait()
+      .unwrap();
+
+    // one device completes the item now
+    todos_repo
+      .update(UpdateTodo {
+        id: todo.id,
+        text: None,
+        done: Some(true),
+        changed_at: None,
+      })
+      .wait()
+      .unwrap();
+
+    // another one changed both fields offline an hour ago
+    let merged = todos_repo
+      .update(UpdateTodo {
+        id: todo.id,
+        text: Some("bar".to_string()),
+        done: Some(false),
+        changed_at: Some(Utc::now().naive_utc() - Duration::hours(1)),
+      })
+      .wait()
+      .unwrap();
+
+    assert_that(&merged.accepted).is_equal_to(vec!["text"]);
+    assert_that(&merged.overridden).is_equal_to(vec!["done"]);
+    assert_that(&merged.todo.text).is_equal_to("bar".to_string());
+    assert_that(&merged.todo.done).is_true();
+
+    let json = json!(merged);
+    assert_that(&json["id"].as_i64()).is_equal_to(Some(todo.id));
+    assert_that(&json["overridden"][0].as_str()).is_equal_to(Some("done"));
+  }
+
+  #[test]
+  fn should_keep_concurrent_updates_of_other_fields() {
+    let todos_repo = create_repo();
+    let todo = todos_repo
+      .insert(NewTodo {
+        text: "foo".to_string(),
+      })
+      .wait()
+      .unwrap();
+
+    // one writer reads the item, another one completes it before the first one writes
+    let read = todos_repo.find(todo.id).wait().unwrap();
+    todos_repo
+      .update(UpdateTodo {
+        id: todo.id,
+        text: None,
+        done: Some(true),
+        changed_at: None,
+      })
+      .wait()
+      .unwrap();
+
+    let conn = todos_repo.conn_pool.get().unwrap();
+    let merged = conn
+      .transaction::<_, Error, _>(|| merge(&conn, &read, Some("bar".to_string()), None, None))
+      .unwrap();
+    assert_that(&merged.accepted).is_equal_to(vec!["text"]);
+
+    let todo = todos_repo.find(todo.id).wait().unwrap();
+    assert_that(&todo.text).is_equal_to("bar".to_string());
+    assert_that(&todo.done).is_true();
+  }
+
+  #[test]
+  fn should_scan_todos() {
+    let todos_repo = create_repo();
+    todos_repo.truncate().unwrap();
+
+    for text in &["foo", "bar", "foo bar"] {
+      todos_repo
+        .insert(NewTodo {
+          text: text.to_string(),
+        })
+        .wait()
+        .unwrap();
+    }
+
+    let query = QueryTodos {
+      next: None,
+      limit: Some(1),
+      text: Some("foo".to_string()),
+    };
+    let batches = todos_repo.scan(query).collect().wait().unwrap();
+    let text: Vec<_> = batches
+      .iter()
+      .flat_map(|it| it.iter())
+      .map(|it| it.text.as_str())
+      .collect();
+
+    assert_that(&text).is_equal_to(vec!["foo bar", "foo"]);
+  }
+
+  #[test]
+  fn should_import_todo() {
+    let todos_repo = create_repo();
+    todos_repo.truncate().unwrap();
+
+    let import = ImportTodo {
+      id: Some(42),
+      text: "foo".to_string(),
+      done: Some(true),
+      created_at: None,
+      updated_at: None,
+      due_at: None,
+    };
+
+    let todo = todos_repo.import(import.clone(), true).wait().unwrap();
+    assert_that(&todo.id).is_equal_to(42);
+    assert_that(&todo.done).is_true();
+
+    assert_that(&todos_repo.import(import.clone(), true).wait()).is_err();
+
+    let todo = todos_repo.import(import, false).wait().unwrap();
+    assert_that(&todo.id).is_not_equal_to(42);
+  }
+
+  #[test]
+  fn should_replace_and_delete_todo() {
+    let todos_repo = create_repo();
+    let todo = todos_repo
+      .insert(NewTodo {
+        text: "foo".to_string(),
+      })
+      .wait()
+      .unwrap();
+
+    let replacement = ImportTodo {
+      id: None,
+      text: "bar".to_string(),
+      done: Some(true),
+      created_at: None,
+      updated_at: None,
+      due_at: Some(todo.created_at),
+    };
+
+    let rejected = todos_repo
+      .replace(todo.id, replacement.clone(), |_| Err(Error::PreconditionFailed))
+      .wait();
+    assert_that(&rejected).is_err();
+
+    let replaced = todos_repo
+      .replace(todo.id, replacement, |_| Ok(()))
+      .wait()
+      .unwrap();
+    assert_that(&replaced.text).is_equal_to("bar".to_string());
+    assert_that(&replaced.done).is_true();
+    assert_that(&replaced.due_at).is_equal_to(Some(todo.created_at));
+
+    todos_repo.delete(todo.id, |_| Ok(())).wait().unwrap();
+    assert_that(&todos_repo.find(todo.id).wait()).is_err();
+  }
+
   fn create_repo() -> TodosRepo {
     let cfg = Config::default();
     let conn_pool = connection_pool(&cfg.database_url, cfg.pool_size);
     let cpu_pool = cfg.create_cpu_pool();
-    TodosRepo::new(conn_pool, cpu_pool)
+    TodosRepo::new(conn_pool, cpu_pool, Metrics::new())
   }
 }
@@ -0,0 +1,548 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel;
+use diesel::dsl::not;
+use diesel::prelude::*;
+use futures_cpupool::{CpuFuture, CpuPool};
+use rand::{OsRng, Rng};
+
+use result::Error;
+use common::to_hex;
+
+use super::functions::last_insert_id;
+use super::outbox;
+use super::schema::{webhook_deliveries, webhook_events, webhooks};
+use super::ConnectionPool;
+
+/// Webhook's events value which subscribes to all events.
+const ALL_EVENTS: &str = "*";
+
+const PENDING: &str = "pending";
+const DELIVERED: &str = "delivered";
+const DEAD: &str = "dead";
+
+/// A registered webhook, mapping to `webhooks` table
+#[derive(Queryable, Debug, Clone)]
+pub struct Webhook {
+  pub id: i64,
+  pub url: String,
+  pub secret: String,
+  /// comma separated event names or `*`
+  pub events: String,
+  pub created_at: NaiveDateTime,
+}
+
+impl Webhook {
+  pub fn events(&self) -> Vec<&str> {
+    self.events.split(',').collect()
+  }
+
+  pub fn subscribes(&self, event: &str) -> bool {
+    self.events().iter().any(|it| *it == ALL_EVENTS || *it == event)
+  }
+}
+
+/// Parameters to register a webhook
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewWebhook {
+  pub url: String,
+  /// event names, all events when missing
+  pub events: Option<Vec<String>>,
+}
+
+/// A delivery of an event to a webhook, mapping to `webhook_deliveries` table
+#[derive(Queryable, Debug, Clone, Serialize)]
+pub struct WebhookDelivery {
+  pub id: i64,
+  pub webhook_id: i64,
+  pub event_id: i64,
+  pub status: String,
+  pub attempts: i32,
+  pub next_attempt_at: NaiveDateTime,
+  pub last_error: Option<String>,
+  pub created_at: NaiveDateTime,
+  pub updated_at: NaiveDateTime,
+}
+
+/// A claimed delivery with everything needed to send it
+#[derive(Debug, Clone)]
+pub struct PendingDelivery {
+  pub id: i64,
+  pub url: String,
+  pub secret: String,
+  pub event: String,
+  pub payload: String,
+  pub attempts: i32,
+}
+
+/// Webhooks and their deliveries repository
+#[derive(Clone)]
+pub struct WebhooksRepo {
+  conn_pool: ConnectionPool,
+  cpu_pool: CpuPool,
+}
+
+impl WebhooksRepo {
+  pub fn new(conn_pool: ConnectionPool, cpu_pool: CpuPool) -> Self {
+    WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    }
+  }
+
+  /// Register a webhook with a new random secret.
+  pub fn create(&self, new_webhook: NewWebhook) -> CpuFuture<Webhook, Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      let mut rng = OsRng::new().expect("cannot access os random number generator");
+      let bytes: [u8; 32] = rng.gen();
+      let events = match new_webhook.events {
+        Some(ref events) if !events.is_empty() => events.join(","),
+        _ => ALL_EVENTS.to_string(),
+      };
+
+      diesel::insert_into(webhooks::table)
+        .values(&(
+          webhooks::url.eq(new_webhook.url.as_str()),
+          webhooks::secret.eq(to_hex(&bytes)),
+          webhooks::events.eq(events),
+          webhooks::created_at.eq(&Utc::now().naive_utc()),
+        ))
+        .execute(&*conn)
+        .map_err(Error::from)?;
+
+      let id = diesel::select(last_insert_id)
+        .first::<i64>(&*conn)
+        .map_err(Error::from)?;
+
+      webhooks::table
+        .filter(webhooks::id.eq(id))
+        .first::<Webhook>(&*conn)
+        .map_err(Error::from)
+    })
+  }
+
+  /// All registered webhooks.
+  pub fn list(&self) -> CpuFuture<Vec<Webhook>, Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      webhooks::table
+        .order(webhooks::id.asc())
+        .load::<Webhook>(&*conn)
+        .map_err(Error::from)
+    })
+  }
+
+  /// Delete a webhook with its undelivered events.
+  pub fn delete(&self, id: i64) -> CpuFuture<(), Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      conn.transaction::<_, Error, _>(|| {
+        let deleted = diesel::delete(webhooks::table.filter(webhooks::id.eq(id)))
+          .execute(&*conn)
+          .map_err(Error::from)?;
+        if deleted == 0 {
+          return Err(Error::RecordNotFound);
+        }
+
+        diesel::delete(
+          webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(id))
+            .filter(webhook_deliveries::status.ne(DELIVERED)),
+        ).execute(&*conn)
+          .map_err(Error::from)
+          .map(|_| ())
+      })
+    })
+  }
+
+  /// Create deliveries for outbox events to subscribed webhooks, returns how many events.
+  pub fn dispatch(&self, limit: i64) -> CpuFuture<usize, Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let time = Utc::now().naive_utc();
+
+      conn.transaction::<_, Error, _>(|| {
+        let events = webhook_events::table
+          .select((webhook_events::id, webhook_events::event))
+          .filter(webhook_events::dispatched_at.is_null())
+          .order(webhook_events::id.asc())
+          .limit(limit)
+          .load::<(i64, String)>(&*conn)
+          .map_err(Error::from)?;
+        if events.is_empty() {
+          return Ok(0);
+        }
+
+        let hooks = webhooks::table
+          .load::<Webhook>(&*conn)
+          .map_err(Error::from)?;
+
+        for &(event_id, ref event) in &events {
+          for hook in hooks.iter().filter(|it| it.subscribes(event)) {
+            diesel::insert_into(webhook_deliveries::table)
+              .values(&(
+                webhook_deliveries::webhook_id.eq(hook.id),
+                webhook_deliveries::event_id.eq(event_id),
+                webhook_deliveries::status.eq(PENDING),
+                webhook_deliveries::attempts.eq(0),
+                webhook_deliveries::next_attempt_at.eq(&time),
+                webhook_deliveries::created_at.eq(&time),
+                webhook_deliveries::updated_at.eq(&time),
+              ))
+              .execute(&*conn)
+              .map_err(Error::from)?;
+          }
+        }
+
+        let ids: Vec<i64> = events.iter().map(|&(id, _)| id).collect();
+        diesel::update(webhook_events::table.filter(webhook_events::id.eq_any(ids)))
+          .set(webhook_events::dispatched_at.eq(&time))
+          .execute(&*conn)
+          .map_err(Error::from)?;
+
+        Ok(events.len())
+      })
+    })
+  }
+
+  /// Delete events dispatched `retention` ago which were delivered to every webhook, returns
+  /// how many. Their deliveries are deleted too, dead letters and their events are kept.
+  ///
+  /// The latest event is never deleted, the event bus resumes after its id.
+  pub fn prune(&self, retention: Duration, limit: i64) -> CpuFuture<usize, Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let before = Utc::now().naive_utc() - retention;
+
+      conn.transaction::<_, Error, _>(|| {
+        let latest = outbox::latest_id(&conn)?;
+        let undelivered = webhook_deliveries::table
+          .select(webhook_deliveries::event_id)
+          .filter(webhook_deliveries::status.ne(DELIVERED));
+
+        let ids = webhook_events::table
+          .select(webhook_events::id)
+          .filter(webhook_events::dispatched_at.lt(&before))
+          .filter(webhook_events::id.lt(latest))
+          .filter(not(webhook_events::id.eq_any(undelivered)))
+          .order(webhook_events::id.asc())
+          .limit(limit)
+          .load::<i64>(&*conn)
+          .map_err(Error::from)?;
+        if ids.is_empty() {
+          return Ok(0);
+        }
+
+        diesel::delete(webhook_deliveries::table.filter(webhook_deliveries::event_id.eq_any(&ids)))
+          .execute(&*conn)
+          .map_err(Error::from)?;
+        diesel::delete(webhook_events::table.filter(webhook_events::id.eq_any(&ids)))
+          .execute(&*conn)
+          .map_err(Error::from)
+      })
+    })
+  }
+
+  /// Take due deliveries, they aren't due again for `lease` so other workers skip them.
+  pub fn claim(&self, limit: i64, lease: Duration) -> CpuFuture<Vec<PendingDelivery>, Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let time = Utc::now().naive_utc();
+
+      conn.transaction::<_, Error, _>(|| {
+        let deliveries = webhook_deliveries::table
+          .filter(webhook_deliveries::status.eq(PENDING))
+          .filter(webhook_deliveries::next_attempt_at.le(&time))
+          .order(webhook_deliveries::next_attempt_at.asc())
+          .limit(limit)
+          .load::<WebhookDelivery>(&*conn)
+          .map_err(Error::from)?;
+        if deliveries.is_empty() {
+          return Ok(Vec::new());
+        }
+
+        let ids: Vec<i64> = deliveries.iter().map(|it| it.id).collect();
+        diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq_any(ids)))
+          .set(webhook_deliveries::next_attempt_at.eq(&(time + lease)))
+          .execute(&*conn)
+          .map_err(Error::from)?;
+
+        let hook_ids: Vec<i64> = deliveries.iter().map(|it| it.webhook_id).collect();
+        let hooks = webhooks::table
+          .filter(webhooks::id.eq_any(hook_ids))
+          .load::<Webhook>(&*conn)
+          .map_err(Error::from)?;
+
+        let event_ids: Vec<i64> = deliveries.iter().map(|it| it.event_id).collect();
+        let events = webhook_events::table
+          .select((
+            webhook_events::id,
+            webhook_events::event,
+            webhook_events::payload,
+          ))
+          .filter(webhook_events::id.eq_any(event_ids))
+          .load::<(i64, String, String)>(&*conn)
+          .map_err(Error::from)?;
+
+        let pending = deliveries
+          .into_iter()
+          .filter_map(|delivery| {
+            let hook = hooks.iter().find(|it| it.id == delivery.webhook_id)?;
+            let event = events.iter().find(|it| it.0 == delivery.event_id)?;
+
+            Some(PendingDelivery {
+              id: delivery.id,
+              url: hook.url.clone(),
+              secret: hook.secret.clone(),
+              event: event.1.clone(),
+              payload: event.2.clone(),
+              attempts: delivery.attempts,
+            })
+          })
+          .collect();
+
+        Ok(pending)
+      })
+    })
+  }
+
+  /// Mark a delivery as delivered.
+  pub fn succeed(&self, id: i64) -> CpuFuture<(), Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+        .set((
+          webhook_deliveries::status.eq(DELIVERED),
+          webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+          webhook_deliveries::last_error.eq(None::<String>),
+          webhook_deliveries::updated_at.eq(&Utc::now().naive_utc()),
+        ))
+        .execute(&*conn)
+        .map_err(Error::from)
+        .map(|_| ())
+    })
+  }
+
+  /// Record a failed attempt, a delivery goes to dead letters when there is no next attempt.
+  pub fn fail(
+    &self,
+    id: i64,
+    error: String,
+    next_attempt_at: Option<NaiveDateTime>,
+  ) -> CpuFuture<(), Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let time = Utc::now().naive_utc();
+      let status = if next_attempt_at.is_some() {
+        PENDING
+      } else {
+        DEAD
+      };
+
+      diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq(id)))
+        .set((
+          webhook_deliveries::status.eq(status),
+          webhook_deliveries::attempts.eq(webhook_deliveries::attempts + 1),
+          webhook_deliveries::next_attempt_at.eq(&next_attempt_at.unwrap_or(time)),
+          webhook_deliveries::last_error.eq(Some(error)),
+          webhook_deliveries::updated_at.eq(&time),
+        ))
+        .execute(&*conn)
+        .map_err(Error::from)
+        .map(|_| ())
+    })
+  }
+
+  /// Deliveries which ran out of attempts, the latest first.
+  pub fn dead_letters(&self) -> CpuFuture<Vec<WebhookDelivery>, Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+
+      webhook_deliveries::table
+        .filter(webhook_deliveries::status.eq(DEAD))
+        .order(webhook_deliveries::id.desc())
+        .limit(100)
+        .load::<WebhookDelivery>(&*conn)
+        .map_err(Error::from)
+    })
+  }
+
+  /// Move a dead letter back to pending deliveries with a fresh attempts counter.
+  pub fn retry(&self, id: i64) -> CpuFuture<(), Error> {
+    let WebhooksRepo {
+      conn_pool,
+      cpu_pool,
+    } = self.clone();
+
+    cpu_pool.spawn_fn(move || {
+      let conn = conn_pool.get().map_err(Error::from)?;
+      let time = Utc::now().naive_utc();
+
+      let updated = diesel::update(
+        webhook_deliveries::table
+          .filter(webhook_deliveries::id.eq(id))
+          .filter(webhook_deliveries::status.eq(DEAD)),
+      ).set((
+        webhook_deliveries::status.eq(PENDING),
+        webhook_deliveries::attempts.eq(0),
+        webhook_deliveries::next_attempt_at.eq(&time),
+        webhook_deliveries::updated_at.eq(&time),
+      ))
+        .execute(&*conn)
+        .map_err(Error::from)?;
+
+      match updated {
+        0 => Err(Error::RecordNotFound),
+        _ => Ok(()),
+      }
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::Future;
+
+  use config::Config;
+  use db::{connection_pool, NewTodo, TodosRepo};
+  use metrics::Metrics;
+
+  #[test]
+  fn should_match_events() {
+    let mut hook = Webhook {
+      id: 1,
+      url: "http://localhost/hook".to_string(),
+      secret: String::new(),
+      events: "todo.created,todo.deleted".to_string(),
+      created_at: Utc::now().naive_utc(),
+    };
+    assert_that(&hook.subscribes("todo.created")).is_true();
+    assert_that(&hook.subscribes("todo.updated")).is_false();
+
+    hook.events = ALL_EVENTS.to_string();
+    assert_that(&hook.subscribes("todo.updated")).is_true();
+  }
+
+  #[test]
+  fn should_deliver_outbox_events() {
+    let cfg = Config::default();
+    let conn_pool = connection_pool(&cfg.database_url, cfg.pool_size);
+    let cpu_pool = cfg.create_cpu_pool();
+    let repo = WebhooksRepo::new(conn_pool.clone(), cpu_pool.clone());
+    let todos_repo = TodosRepo::new(conn_pool.clone(), cpu_pool, Metrics::new());
+
+    // flush events written by other tests
+    while repo.dispatch(1000).wait().unwrap() > 0 {}
+
+    let hook = repo
+      .create(NewWebhook {
+        url: "http://localhost/hook".to_string(),
+        events: Some(vec!["todo.created".to_string()]),
+      })
+      .wait()
+      .unwrap();
+    todos_repo
+      .insert(NewTodo {
+        text: "foo".to_string(),
+      })
+      .wait()
+      .unwrap();
+
+    assert_that(&repo.dispatch(1000).wait().unwrap()).is_greater_than_or_equal_to(1);
+
+    let pending = repo.claim(1000, Duration::minutes(1)).wait().unwrap();
+    let delivery = pending.iter().find(|it| it.url == hook.url).unwrap();
+    assert_that(&delivery.event.as_str()).is_equal_to("todo.created");
+
+    repo
+      .fail(delivery.id, "timeout".to_string(), None)
+      .wait()
+      .unwrap();
+    let dead = repo.dead_letters().wait().unwrap();
+    assert_that(&dead.iter().any(|it| it.id == delivery.id)).is_true();
+
+    let event_id = webhook_deliveries::table
+      .select(webhook_deliveries::event_id)
+      .filter(webhook_deliveries::id.eq(delivery.id))
+      .first::<i64>(&*conn_pool.get().unwrap())
+      .unwrap();
+    let stored = || {
+      webhook_events::table
+        .filter(webhook_events::id.eq(event_id))
+        .count()
+        .get_result::<i64>(&*conn_pool.get().unwrap())
+        .unwrap()
+    };
+
+    // a dead letter keeps its event
+    repo.prune(Duration::seconds(-60), 1000).wait().unwrap();
+    assert_that(&stored()).is_equal_to(1);
+
+    repo.retry(delivery.id).wait().unwrap();
+    repo.succeed(delivery.id).wait().unwrap();
+
+    // a delivered event is pruned unless it is the latest one
+    todos_repo
+      .insert(NewTodo {
+        text: "bar".to_string(),
+      })
+      .wait()
+      .unwrap();
+    repo.dispatch(1000).wait().unwrap();
+    repo.prune(Duration::seconds(-60), 1000).wait().unwrap();
+    assert_that(&stored()).is_equal_to(0);
+
+    repo.delete(hook.id).wait().unwrap();
+  }
+}
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Utc};
+use futures::Future;
+use hyper::{Request, Response, StatusCode};
+use hyper::header::ContentLength;
+use rand::{self, Rng};
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use config::{AccessLogFormat, Config};
+use result::Error;
+use common::{to_hex, FuturesExt};
+use logger::{self, Context, Scoped};
+
+use super::middleware::{Endpoint, Middleware};
+use super::peer;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// A longest request id which is taken from a client.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+header! { (XRequestId, "X-Request-Id") => [String] }
+
+/// Gives every request an id and logs a line per response.
+///
+/// A client's `X-Request-Id` is used when it's sane, otherwise a random one is generated.
+/// Either way it's returned in the response and added to log records made while the request
+/// is handled.
+pub struct AccessLog {
+  format: AccessLogFormat,
+}
+
+/// What's logged about a request, a path is logged without the query which may carry tokens.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+  time: DateTime<Utc>,
+  request_id: String,
+  client: Option<SocketAddr>,
+  method: String,
+  path: String,
+  version: String,
+  route: Option<String>,
+  status: u16,
+  /// `None` for streamed bodies
+  size: Option<u64>,
+  /// until the response's head is ready
+  latency: Duration,
+}
+
+impl AccessLog {
+  pub fn new(cfg: &Config) -> Self {
+    AccessLog {
+      format: cfg.access_log_format,
+    }
+  }
+}
+
+impl Middleware for AccessLog {
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    let format = self.format;
+    let started = Instant::now();
+
+    let request_id = req
+      .headers()
+      .get::<XRequestId>()
+      .and_then(|it| {
+        if is_valid_request_id(&it.0) {
+          Some(it.0.clone())
+        } else {
+          None
+        }
+      })
+      .unwrap_or_else(generate_request_id);
+    let mut entry = Entry {
+      time: Utc::now(),
+      request_id: request_id.clone(),
+      client: peer::addr(),
+      method: req.method().to_string(),
+      path: req.path().to_string(),
+      version: req.version().to_string(),
+      route: None,
+      status: 0,
+      size: None,
+      latency: Duration::from_secs(0),
+    };
+
+    let context = Rc::new(RefCell::new(Context::new(request_id.clone())));
+    let resp = logger::scope(&context, || next.call(req));
+
+    Scoped::new(context.clone(), resp)
+      .then(move |result| {
+        entry.route = context.borrow().route.clone();
+        entry.latency = started.elapsed();
+        match result {
+          Ok(ref resp) => {
+            entry.status = resp.status().as_u16();
+            entry.size = resp.headers().get::<ContentLength>().map(|it| it.0);
+          }
+          Err(_) => entry.status = StatusCode::InternalServerError.as_u16(),
+        }
+
+        logger::scope(&context, || {
+          info!(target: logger::ACCESS_TARGET, "{}", entry.format(format))
+        });
+        result.map(|resp| resp.with_header(XRequestId(request_id)))
+      })
+      .into_boxed()
+  }
+}
+
+impl Entry {
+  fn format(&self, format: AccessLogFormat) -> String {
+    let latency =
+      self.latency.as_secs() as f64 + f64::from(self.latency.subsec_nanos()) / 1e9;
+
+    match format {
+      AccessLogFormat::Common => format!(
+        "{} - - [{}] \"{} {} {}\" {} {} \"{}\" {:.6} {}",
+        self
+          .client
+          .map_or_else(|| "-".to_string(), |it| it.ip().to_string()),
+        self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+        self.method,
+        self.path,
+        self.version,
+        self.status,
+        self.size.map_or_else(|| "-".to_string(), |it| it.to_string()),
+        self.route.as_ref().map_or("-", |it| it.as_str()),
+        latency,
+        self.request_id
+      ),
+      AccessLogFormat::Json => json!({
+        "time": self.time.to_rfc3339(),
+        "request_id": self.request_id,
+        "client": self.client.map(|it| it.ip().to_string()),
+        "method": self.method,
+        "path": self.path,
+        "route": self.route,
+        "status": self.status,
+        "size": self.size,
+        "latency": latency,
+      }).to_string(),
+    }
+  }
+}
+
+/// A sane id is short and printable so it can't break log lines.
+fn is_valid_request_id(id: &str) -> bool {
+  !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN
+    && id
+      .chars()
+      .all(|it| it.is_ascii_alphanumeric() || "-_.:".contains(it))
+}
+
+fn generate_request_id() -> String {
+  let bytes: [u8; 16] = rand::thread_rng().gen();
+  to_hex(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use chrono::TimeZone;
+
+  #[test]
+  fn should_validate_request_ids() {
+    assert_that(&is_valid_request_id("3f0c2a9e-1b7d-4c58")).is_true();
+    assert_that(&is_valid_request_id("")).is_false();
+    assert_that(&is_valid_request_id("a b")).is_false();
+    assert_that(&is_valid_request_id("a\nINFO forged")).is_false();
+    assert_that(&is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1))).is_false();
+
+    let id = generate_request_id();
+    assert_that(&id.len()).is_equal_to(32);
+    assert_that(&is_valid_request_id(&id)).is_true();
+    assert_that(&generate_request_id()).is_not_equal_to(id);
+  }
+
+  #[test]
+  fn should_format_entries() {
+    let entry = Entry {
+      time: Utc.ymd(2018, 3, 1).and_hms(12, 30, 0),
+      request_id: "abc".to_string(),
+      client: Some("127.0.0.1:5000".parse().unwrap()),
+      method: "GET".to_string(),
+      path: "/todos/1".to_string(),
+      version: "HTTP/1.1".to_string(),
+      route: Some("/todos/{id}".to_string()),
+      status: 200,
+      size: Some(42),
+      latency: Duration::from_millis(15),
+    };
+
+    assert_that(&entry.format(AccessLogFormat::Common)).is_equal_to(
+      "127.0.0.1 - - [01/Mar/2018:12:30:00 +0000] \"GET /todos/1 HTTP/1.1\" 200 42 \
+       \"/todos/{id}\" 0.015000 abc"
+        .to_string(),
+    );
+
+    let line: ::serde_json::Value =
+      ::serde_json::from_str(&entry.format(AccessLogFormat::Json)).unwrap();
+    assert_that(&line).is_equal_to(json!({
+      "time": "2018-03-01T12:30:00+00:00",
+      "request_id": "abc",
+      "client": "127.0.0.1",
+      "method": "GET",
+      "path": "/todos/1",
+      "route": "/todos/{id}",
+      "status": 200,
+      "size": 42,
+      "latency": 0.015,
+    }));
+
+    let entry = Entry {
+      client: None,
+      route: None,
+      size: None,
+      ..entry
+    };
+    assert_that(&entry.format(AccessLogFormat::Common)).is_equal_to(
+      "- - - [01/Mar/2018:12:30:00 +0000] \"GET /todos/1 HTTP/1.1\" 200 - \"-\" 0.015000 abc"
+        .to_string(),
+    );
+  }
+}
@@ -0,0 +1,94 @@
+use futures::{future, Future};
+use hyper::{Request, Response};
+use hyper::header::{Authorization, Bearer};
+
+use config::Config;
+use result::Error;
+use common::FuturesExt;
+
+use super::middleware::{Endpoint, Middleware};
+use super::server::error_response;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// Lets only requests with `Authorization: Bearer <ADMIN_TOKEN>` through, routes which manage
+/// secrets like calendar tokens or webhooks are wrapped with it.
+///
+/// When `ADMIN_TOKEN` is empty the wrapped routes are disabled.
+#[derive(Clone)]
+pub struct AdminAuth {
+  token: String,
+}
+
+impl AdminAuth {
+  pub fn new(cfg: &Config) -> Self {
+    AdminAuth {
+      token: cfg.admin_token.clone(),
+    }
+  }
+}
+
+impl Middleware for AdminAuth {
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    if is_admin(&req, &self.token) {
+      return next.call(req);
+    }
+
+    warn!("unauthorized {} {}", req.method(), req.path());
+    let mut resp = error_response(&Error::Unauthorized);
+    resp.headers_mut().set_raw("WWW-Authenticate", "Bearer");
+    future::ok(resp).into_boxed()
+  }
+}
+
+/// Whether a request has `Authorization: Bearer <token>`, an empty `token` never matches.
+pub fn is_admin(req: &Request, token: &str) -> bool {
+  match req.headers().get::<Authorization<Bearer>>() {
+    Some(auth) => !token.is_empty() && is_same(auth.token.as_bytes(), token.as_bytes()),
+    None => false,
+  }
+}
+
+/// Compare tokens in a time which doesn't tell how many first bytes match.
+fn is_same(a: &[u8], b: &[u8]) -> bool {
+  a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use hyper::{Get, StatusCode};
+  use std::rc::Rc;
+
+  #[test]
+  fn should_require_admin_token() {
+    let cfg = Config {
+      admin_token: "secret".to_string(),
+      ..Config::default()
+    };
+    let endpoint = Endpoint::new(|_| future::ok(Response::new()).into_boxed());
+    let admin = endpoint.clone().wrap(Rc::new(AdminAuth::new(&cfg)));
+
+    assert_that(&call(&admin, None)).is_equal_to(StatusCode::Unauthorized);
+    assert_that(&call(&admin, Some("secreT"))).is_equal_to(StatusCode::Unauthorized);
+    assert_that(&call(&admin, Some("secret"))).is_equal_to(StatusCode::Ok);
+
+    let cfg = Config {
+      admin_token: String::new(),
+      ..Config::default()
+    };
+    let disabled = endpoint.wrap(Rc::new(AdminAuth::new(&cfg)));
+    assert_that(&call(&disabled, Some(""))).is_equal_to(StatusCode::Unauthorized);
+  }
+
+  fn call(endpoint: &Endpoint, token: Option<&str>) -> StatusCode {
+    let mut req = Request::new(Get, "/webhooks".parse().unwrap());
+    if let Some(token) = token {
+      req
+        .headers_mut()
+        .set_raw("Authorization", format!("Bearer {}", token));
+    }
+    endpoint.call(req).wait().unwrap().status()
+  }
+}
@@ -0,0 +1,755 @@
+use futures::{future, Future, Stream};
+use hyper::{Request, Response, StatusCode};
+use hyper::header::{Authorization, Basic, ContentLength, ContentType, ETag, EntityTag, IfMatch,
+                    IfNoneMatch, Location};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+use result::Error;
+use db::{self, CalendarTokensRepo, ImportTodo, QueryTodos, Todo, TodosRepo};
+use common::{to_hex, FuturesExt, RequestExt};
+use transfer::{ical, Format};
+use validators::Validator;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// The only principal, it's also the calendar home.
+const PRINCIPAL_PATH: &str = "/dav/";
+/// The calendar collection with todo items.
+const COLLECTION_PATH: &str = "/dav/todos/";
+/// Methods supported by every dav resource.
+const ALLOW: &str = "OPTIONS, PROPFIND, REPORT, GET, PUT, DELETE";
+const ITEM_CONTENT_TYPE: &str = "text/calendar; charset=utf-8; component=vtodo";
+const SYNC_TOKEN_PREFIX: &str = "http://todo-demo/sync/";
+/// How many changes a sync-collection report returns, the rest is left for the next one.
+const SYNC_LIMIT: i64 = 500;
+
+header! { (Depth, "Depth") => [String] }
+
+/// A resource addressed by a request's path.
+#[derive(Debug, Clone, PartialEq)]
+enum Resource {
+  Principal,
+  Collection,
+  /// a todo item by its file name without `.ics`
+  Item(String),
+}
+
+impl Resource {
+  fn of(path: &str) -> Option<Self> {
+    match path {
+      "/dav" | PRINCIPAL_PATH => Some(Resource::Principal),
+      "/dav/todos" | COLLECTION_PATH => Some(Resource::Collection),
+      _ => {
+        let name = path.trim_left_matches(COLLECTION_PATH);
+        if name.len() == path.len() || name.contains('/') || !name.ends_with(".ics") {
+          return None;
+        }
+        Some(Resource::Item(name.trim_right_matches(".ics").to_string()))
+      }
+    }
+  }
+}
+
+/// A minimal CalDAV server for the todo items collection.
+///
+/// Clients authenticate with basic auth where a password is a calendar token, a user name is
+/// ignored. Only `read_write` tokens may put or delete items, feed tokens are read only. Items
+/// put under unknown names get a new id and a `Location`, a name is never taken as an id. Sync
+/// tokens are change sequence numbers, so only changed items are queried.
+#[derive(Clone)]
+pub struct CalDavController {
+  todos_repo: TodosRepo,
+  calendar_tokens_repo: CalendarTokensRepo,
+  max_body_size: u64,
+}
+
+impl CalDavController {
+  pub fn new(
+    todos_repo: TodosRepo,
+    calendar_tokens_repo: CalendarTokensRepo,
+    max_body_size: u64,
+  ) -> Self {
+    CalDavController {
+      todos_repo,
+      calendar_tokens_repo,
+      max_body_size,
+    }
+  }
+
+  pub fn call(&self, req: Request) -> BoxFuture<Response> {
+    let token = match req.headers().get::<Authorization<Basic>>() {
+      Some(auth) => auth.password.clone().unwrap_or_default(),
+      None => return future::ok(unauthorized()).into_boxed(),
+    };
+
+    let controller = self.clone();
+    self
+      .calendar_tokens_repo
+      .find(token)
+      .then(move |result| match result {
+        Ok(ref it) if !it.can_write() && is_write(&req) => {
+          future::ok(forbidden("need-privileges")).into_boxed()
+        }
+        Ok(_) => controller.route(req),
+        Err(Error::RecordNotFound) => future::ok(unauthorized()).into_boxed(),
+        Err(err) => future::err(err).into_boxed(),
+      })
+      .into_boxed()
+  }
+
+  fn route(&self, req: Request) -> BoxFuture<Response> {
+    let resource = match Resource::of(req.path()) {
+      Some(it) => it,
+      None => return future::err(Error::RecordNotFound).into_boxed(),
+    };
+    let method = req.method().clone();
+
+    match (method.as_ref(), resource) {
+      ("OPTIONS", _) => future::ok(options()).into_boxed(),
+      ("PROPFIND", Resource::Principal) => self.call_propfind_principal(&req),
+      ("PROPFIND", Resource::Collection) => self.call_propfind_collection(&req),
+      ("PROPFIND", Resource::Item(name)) => self.call_propfind_item(&name),
+      ("REPORT", Resource::Collection) => self.call_report(req),
+      ("GET", Resource::Item(name)) => self.call_get(&name),
+      ("PUT", Resource::Item(name)) => self.call_put(&name, req),
+      ("DELETE", Resource::Item(name)) => self.call_delete(&name, req),
+      _ => future::ok(method_not_allowed()).into_boxed(),
+    }
+  }
+
+  fn call_propfind_principal(&self, req: &Request) -> BoxFuture<Response> {
+    let mut out = String::new();
+    write_response(&mut out, PRINCIPAL_PATH, &principal_props());
+
+    if !is_depth_zero(req) {
+      return self
+        .todos_repo
+        .latest_seq()
+        .map(move |seq| {
+          write_response(&mut out, COLLECTION_PATH, &collection_props(SyncToken(seq)));
+          multistatus(&out)
+        })
+        .into_boxed();
+    }
+
+    future::ok(multistatus(&out)).into_boxed()
+  }
+
+  fn call_propfind_collection(&self, req: &Request) -> BoxFuture<Response> {
+    let depth_zero = is_depth_zero(req);
+    let repo = self.clone();
+
+    // the token is taken first, items changed while they are loaded are synced again
+    self
+      .todos_repo
+      .latest_seq()
+      .and_then(move |seq| -> BoxFuture<(i64, Vec<Todo>)> {
+        if depth_zero {
+          return future::ok((seq, Vec::new())).into_boxed();
+        }
+        repo.load_all().map(move |todos| (seq, todos)).into_boxed()
+      })
+      .map(|(seq, todos)| {
+        let mut out = String::new();
+        write_response(&mut out, COLLECTION_PATH, &collection_props(SyncToken(seq)));
+        for todo in &todos {
+          write_response(&mut out, &item_href(todo.id), &item_props(todo, false));
+        }
+        multistatus(&out)
+      })
+      .into_boxed()
+  }
+
+  fn call_propfind_item(&self, name: &str) -> BoxFuture<Response> {
+    self
+      .find(name)
+      .map(|todo| {
+        let mut out = String::new();
+        write_response(&mut out, &item_href(todo.id), &item_props(&todo, false));
+        multistatus(&out)
+      })
+      .into_boxed()
+  }
+
+  fn call_report(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.clone();
+
+    req
+      .bytes(self.max_body_size)
+      .and_then(move |chunk| -> BoxFuture<Response> {
+        let body = String::from_utf8_lossy(&chunk).into_owned();
+
+        match root_element(&body) {
+          Some("calendar-query") => repo.report_calendar_query(),
+          Some("calendar-multiget") => repo.report_multiget(&body),
+          Some("sync-collection") => repo.report_sync_collection(&body),
+          other => {
+            warn!("unsupported report {:?}", other);
+            future::ok(forbidden("supported-report")).into_boxed()
+          }
+        }
+      })
+      .into_boxed()
+  }
+
+  /// All items are returned, filters are ignored since the collection has todo items only.
+  fn report_calendar_query(&self) -> BoxFuture<Response> {
+    self
+      .load_all()
+      .map(|todos| {
+        let mut out = String::new();
+        for todo in &todos {
+          write_response(&mut out, &item_href(todo.id), &item_props(todo, true));
+        }
+        multistatus(&out)
+      })
+      .into_boxed()
+  }
+
+  fn report_multiget(&self, body: &str) -> BoxFuture<Response> {
+    let hrefs: Vec<String> = element_texts(body, "href")
+      .into_iter()
+      .map(|it| it.to_string())
+      .collect();
+    let ids = hrefs
+      .iter()
+      .filter_map(|href| match Resource::of(href) {
+        Some(Resource::Item(name)) => parse_id(&name),
+        _ => None,
+      })
+      .collect();
+
+    self
+      .todos_repo
+      .find_all(ids)
+      .map(move |todos| {
+        let mut out = String::new();
+        for href in &hrefs {
+          let todo = match Resource::of(href) {
+            Some(Resource::Item(name)) => parse_id(&name)
+              .and_then(|id| todos.iter().find(|it| it.id == id)),
+            _ => None,
+          };
+          match todo {
+            Some(todo) => write_response(&mut out, href, &item_props(todo, true)),
+            None => write_not_found(&mut out, href),
+          }
+        }
+        multistatus(&out)
+      })
+      .into_boxed()
+  }
+
+  fn report_sync_collection(&self, body: &str) -> BoxFuture<Response> {
+    let token = element_texts(body, "sync-token")
+      .into_iter()
+      .next()
+      .unwrap_or("");
+    let initial = token.is_empty();
+
+    let since = match token.parse::<SyncToken>() {
+      Ok(SyncToken(seq)) => seq,
+      Err(_) if initial => 0,
+      Err(_) => return future::ok(forbidden("valid-sync-token")).into_boxed(),
+    };
+
+    let repo = self.todos_repo.clone();
+    self
+      .todos_repo
+      .latest_seq()
+      .and_then(move |latest| -> BoxFuture<Response> {
+        if since > latest {
+          return future::ok(forbidden("valid-sync-token")).into_boxed();
+        }
+
+        repo
+          .changes(since, SYNC_LIMIT)
+          .map(move |changes| {
+            let mut out = String::new();
+            for todo in &changes.todos {
+              write_response(&mut out, &item_href(todo.id), &item_props(todo, false));
+            }
+            // an initial sync has nothing to forget
+            if !initial {
+              for tombstone in &changes.deleted {
+                write_not_found(&mut out, &item_href(tombstone.todo_id));
+              }
+            }
+            if changes.more {
+              write_truncated(&mut out);
+            }
+            let token = SyncToken(changes.seq);
+            out.push_str(&format!("<d:sync-token>{}</d:sync-token>", token));
+            multistatus(&out)
+          })
+          .into_boxed()
+      })
+      .into_boxed()
+  }
+
+  fn call_get(&self, name: &str) -> BoxFuture<Response> {
+    self
+      .find(name)
+      .map(|todo| {
+        let body = ical::document(&todo);
+        let len = body.len();
+
+        Response::new()
+          .with_header(content_type(ITEM_CONTENT_TYPE))
+          .with_header(ETag(etag(&todo)))
+          .with_header(ContentLength(len as u64))
+          .with_body(body)
+      })
+      .into_boxed()
+  }
+
+  fn call_put(&self, name: &str, req: Request) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+    let preconditions = Preconditions::of(&req);
+    let id = parse_id(name);
+
+    req
+      .bytes(self.max_body_size)
+      .and_then(|chunk| parse_vtodo(&chunk))
+      .and_then(move |todo| -> BoxFuture<Response> {
+        let id = match id {
+          Some(id) => id,
+          None => return create(&repo, todo, &preconditions),
+        };
+
+        let check = preconditions.clone();
+        repo
+          .replace(id, todo.clone(), move |current| check.check(Some(current)))
+          .map(|todo| {
+            Response::new()
+              .with_status(StatusCode::NoContent)
+              .with_header(ETag(etag(&todo)))
+          })
+          .or_else(move |err| match err {
+            Error::RecordNotFound => create(&repo, todo, &preconditions),
+            err => future::err(err).into_boxed(),
+          })
+          .into_boxed()
+      })
+      .into_boxed()
+  }
+
+  fn call_delete(&self, name: &str, req: Request) -> BoxFuture<Response> {
+    let id = match parse_id(name) {
+      Some(id) => id,
+      None => return future::err(Error::RecordNotFound).into_boxed(),
+    };
+    let preconditions = Preconditions::of(&req);
+
+    self
+      .todos_repo
+      .delete(id, move |current| preconditions.check(Some(current)))
+      .inspect(move |_| info!("deleted {} with caldav", id))
+      .map(|_| Response::new().with_status(StatusCode::NoContent))
+      .into_boxed()
+  }
+
+  fn find(&self, name: &str) -> BoxFuture<Todo> {
+    match parse_id(name) {
+      Some(id) => self.todos_repo.find(id).into_boxed(),
+      None => future::err(Error::RecordNotFound).into_boxed(),
+    }
+  }
+
+  /// Only a calendar-query and a listing of the collection need every item.
+  fn load_all(&self) -> BoxFuture<Vec<Todo>> {
+    let query = QueryTodos {
+      next: None,
+      limit: None,
+      text: None,
+    };
+    self.todos_repo.scan(query).concat2().into_boxed()
+  }
+}
+
+/// Insert an item put under an unknown name, it gets a new id and the name isn't kept.
+///
+/// There is no `ETag` since it would describe the `Location` rather than the requested resource.
+fn create(
+  repo: &TodosRepo,
+  todo: ImportTodo,
+  preconditions: &Preconditions,
+) -> BoxFuture<Response> {
+  if let Err(err) = preconditions.check(None) {
+    return future::err(err).into_boxed();
+  }
+
+  repo
+    .import(todo, false)
+    .inspect(|it| info!("created {:?} with caldav", it))
+    .map(|todo| {
+      Response::new()
+        .with_status(StatusCode::Created)
+        .with_header(Location::new(item_href(todo.id)))
+    })
+    .into_boxed()
+}
+
+/// Conditional request headers of a write.
+#[derive(Debug, Clone)]
+struct Preconditions {
+  if_match: Option<IfMatch>,
+  if_none_match: Option<IfNoneMatch>,
+}
+
+impl Preconditions {
+  fn of(req: &Request) -> Self {
+    Preconditions {
+      if_match: req.headers().get::<IfMatch>().cloned(),
+      if_none_match: req.headers().get::<IfNoneMatch>().cloned(),
+    }
+  }
+
+  /// Check the headers against the current item, `None` when it doesn't exist.
+  fn check(&self, current: Option<&Todo>) -> Result<(), Error> {
+    let current = current.map(etag);
+    let matches = |tags: &[EntityTag]| {
+      current
+        .as_ref()
+        .map_or(false, |it| tags.iter().any(|tag| tag.strong_eq(it)))
+    };
+
+    let if_match = match self.if_match {
+      Some(IfMatch::Any) => current.is_some(),
+      Some(IfMatch::Items(ref tags)) => matches(tags),
+      None => true,
+    };
+    let if_none_match = match self.if_none_match {
+      Some(IfNoneMatch::Any) => current.is_none(),
+      Some(IfNoneMatch::Items(ref tags)) => !matches(tags),
+      None => true,
+    };
+
+    if if_match && if_none_match {
+      Ok(())
+    } else {
+      Err(Error::PreconditionFailed)
+    }
+  }
+}
+
+/// A collection's state, the latest change sequence number.
+///
+/// Items changed after it and tombstones of deleted ones are found by their sequence numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SyncToken(i64);
+
+impl fmt::Display for SyncToken {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}{}", SYNC_TOKEN_PREFIX, self.0)
+  }
+}
+
+impl FromStr for SyncToken {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Error> {
+    if !s.starts_with(SYNC_TOKEN_PREFIX) {
+      return Err(Error::Validation(format!("invalid sync token {:?}", s)));
+    }
+
+    db::parse_token(&s[SYNC_TOKEN_PREFIX.len()..]).map(SyncToken)
+  }
+}
+
+/// A strong etag which changes whenever an item's calendar data changes.
+fn etag(todo: &Todo) -> EntityTag {
+  let mut hasher = Sha256::default();
+  hasher.input(ical::document(todo).as_bytes());
+
+  EntityTag::strong(to_hex(&hasher.result()[..16]))
+}
+
+fn parse_id(name: &str) -> Option<i64> {
+  match name.parse() {
+    Ok(id) if id > 0 => Some(id),
+    _ => None,
+  }
+}
+
+/// Decode the first `VTODO` of a calendar object, an id is always taken from the path.
+fn parse_vtodo(body: &[u8]) -> Result<ImportTodo, Error> {
+  match Format::ICalendar.decode(body).into_iter().next() {
+    Some((_, todo)) => todo.and_then(|it| ImportTodo { id: None, ..it }.validated()),
+    None => Err(Error::Validation("calendar object has no VTODO".to_string())),
+  }
+}
+
+fn item_href(id: i64) -> String {
+  format!("{}{}.ics", COLLECTION_PATH, id)
+}
+
+fn is_depth_zero(req: &Request) -> bool {
+  req
+    .headers()
+    .get::<Depth>()
+    .map_or(false, |it| it.0.trim() == "0")
+}
+
+fn principal_props() -> String {
+  format!(
+    "<d:resourcetype><d:collection/><d:principal/></d:resourcetype>\
+     <d:current-user-principal><d:href>{0}</d:href></d:current-user-principal>\
+     <c:calendar-home-set><d:href>{0}</d:href></c:calendar-home-set>",
+    PRINCIPAL_PATH
+  )
+}
+
+fn collection_props(token: SyncToken) -> String {
+  format!(
+    "<d:resourcetype><d:collection/><c:calendar/></d:resourcetype>\
+     <d:displayname>Todos</d:displayname>\
+     <d:current-user-principal><d:href>{}</d:href></d:current-user-principal>\
+     <c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set>\
+     <d:supported-report-set>\
+     <d:supported-report><d:report><c:calendar-query/></d:report></d:supported-report>\
+     <d:supported-report><d:report><c:calendar-multiget/></d:report></d:supported-report>\
+     <d:supported-report><d:report><d:sync-collection/></d:report></d:supported-report>\
+     </d:supported-report-set>\
+     <cs:getctag>{}</cs:getctag>\
+     <d:sync-token>{}</d:sync-token>",
+    PRINCIPAL_PATH, token, token
+  )
+}
+
+fn item_props(todo: &Todo, with_data: bool) -> String {
+  let mut props = format!(
+    "<d:getetag>{}</d:getetag><d:getcontenttype>{}</d:getcontenttype>",
+    xml_escape(&etag(todo).to_string()),
+    ITEM_CONTENT_TYPE
+  );
+  if with_data {
+    props.push_str(&format!(
+      "<c:calendar-data>{}</c:calendar-data>",
+      xml_escape(&ical::document(todo))
+    ));
+  }
+  props
+}
+
+fn write_response(out: &mut String, href: &str, props: &str) {
+  out.push_str(&format!(
+    "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop>\
+     <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+    xml_escape(href),
+    props
+  ));
+}
+
+fn write_not_found(out: &mut String, href: &str) {
+  out.push_str(&format!(
+    "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+    xml_escape(href)
+  ));
+}
+
+/// Tell that a sync-collection report is truncated, a client syncs again with the new token.
+fn write_truncated(out: &mut String) {
+  out.push_str(&format!(
+    "<d:response><d:href>{}</d:href>\
+     <d:status>HTTP/1.1 507 Insufficient Storage</d:status></d:response>",
+    COLLECTION_PATH
+  ));
+}
+
+fn multistatus(responses: &str) -> Response {
+  xml_response(
+    StatusCode::MultiStatus,
+    format!(
+      "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+       <d:multistatus xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\" \
+       xmlns:cs=\"http://calendarserver.org/ns/\">{}</d:multistatus>",
+      responses
+    ),
+  )
+}
+
+/// A `403 Forbidden` with a failed precondition, like `valid-sync-token`.
+fn forbidden(precondition: &str) -> Response {
+  xml_response(
+    StatusCode::Forbidden,
+    format!(
+      "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+       <d:error xmlns:d=\"DAV:\"><d:{}/></d:error>",
+      precondition
+    ),
+  )
+}
+
+fn xml_response(status: StatusCode, body: String) -> Response {
+  let len = body.len();
+
+  Response::new()
+    .with_status(status)
+    .with_header(content_type("application/xml; charset=utf-8"))
+    .with_header(ContentLength(len as u64))
+    .with_body(body)
+}
+
+fn options() -> Response {
+  let mut resp = Response::new().with_header(ContentLength(0));
+  resp.headers_mut().set_raw("DAV", "1, 3, calendar-access");
+  resp.headers_mut().set_raw("Allow", ALLOW);
+  resp
+}
+
+fn method_not_allowed() -> Response {
+  let mut resp = Response::new()
+    .with_status(StatusCode::MethodNotAllowed)
+    .with_header(ContentLength(0));
+  resp.headers_mut().set_raw("Allow", ALLOW);
+  resp
+}
+
+fn is_write(req: &Request) -> bool {
+  match req.method().as_ref() {
+    "PUT" | "DELETE" => true,
+    _ => false,
+  }
+}
+
+fn unauthorized() -> Response {
+  let mut resp = Response::new()
+    .with_status(StatusCode::Unauthorized)
+    .with_header(ContentLength(0));
+  resp
+    .headers_mut()
+    .set_raw("WWW-Authenticate", "Basic realm=\"todos\"");
+  resp
+}
+
+fn content_type(value: &str) -> ContentType {
+  ContentType(value.parse().expect("content type cannot be invalid"))
+}
+
+/// The local name of a document's root element.
+fn root_element(xml: &str) -> Option<&str> {
+  tags(xml)
+    .find(|&(_, closing, _)| !closing)
+    .map(|(name, _, _)| name)
+}
+
+/// Texts of all elements with a given local name, namespace prefixes are ignored.
+fn element_texts<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+  tags(xml)
+    .filter(|&(tag, closing, _)| tag == name && !closing)
+    .map(|(_, _, text)| text.trim())
+    .collect()
+}
+
+/// Iterate over tags as their local names, whether a tag is closing and a text after it.
+///
+/// Good enough for small report bodies, there are no CDATA sections or comments there.
+fn tags<'a>(xml: &'a str) -> Box<Iterator<Item = (&'a str, bool, &'a str)> + 'a> {
+  let tags = xml.split('<').skip(1).filter_map(|part| {
+    let end = part.find('>')?;
+    let (tag, text) = (&part[..end], &part[end + 1..]);
+    if tag.starts_with('?') || tag.starts_with('!') {
+      return None;
+    }
+
+    let closing = tag.starts_with('/');
+    let self_closing = tag.ends_with('/');
+    let name = tag
+      .trim_left_matches('/')
+      .split(|ch: char| ch.is_whitespace() || ch == '/')
+      .next()?;
+    let name = name.rsplit(':').next()?;
+
+    Some((name, closing, if self_closing { "" } else { text }))
+  });
+
+  Box::new(tags)
+}
+
+fn xml_escape(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_resolve_resources() {
+    assert_that(&Resource::of("/dav/")).is_equal_to(Some(Resource::Principal));
+    assert_that(&Resource::of("/dav/todos")).is_equal_to(Some(Resource::Collection));
+    assert_that(&Resource::of("/dav/todos/42.ics"))
+      .is_equal_to(Some(Resource::Item("42".to_string())));
+    assert_that(&Resource::of("/dav/todos/42")).is_none();
+    assert_that(&Resource::of("/dav/other/42.ics")).is_none();
+  }
+
+  #[test]
+  fn should_parse_report_body() {
+    let body = r#"<?xml version="1.0" encoding="utf-8" ?>
+      <D:sync-collection xmlns:D="DAV:">
+        <D:sync-token>http://todo-demo/sync/10</D:sync-token>
+        <D:prop><D:getetag/></D:prop>
+      </D:sync-collection>"#;
+
+    assert_that(&root_element(body)).is_equal_to(Some("sync-collection"));
+    assert_that(&element_texts(body, "sync-token"))
+      .is_equal_to(vec!["http://todo-demo/sync/10"]);
+    assert_that(&element_texts("<d:sync-token/>", "sync-token")).is_equal_to(vec![""]);
+  }
+
+  #[test]
+  fn should_parse_sync_token() {
+    let token: SyncToken = "http://todo-demo/sync/15".parse().unwrap();
+    assert_that(&token).is_equal_to(SyncToken(15));
+    assert_that(&token.to_string().as_str()).is_equal_to("http://todo-demo/sync/15");
+
+    assert_that(&"15".parse::<SyncToken>()).is_err();
+    assert_that(&"http://todo-demo/sync/15-2-2".parse::<SyncToken>()).is_err();
+    assert_that(&"http://todo-demo/sync/-1".parse::<SyncToken>()).is_err();
+  }
+
+  #[test]
+  fn should_check_preconditions() {
+    let todo = create_todo(1, 10, 10);
+    let tag = etag(&todo);
+    let other = EntityTag::strong("other".to_string());
+
+    let check = |if_match, if_none_match, current| {
+      Preconditions {
+        if_match,
+        if_none_match,
+      }.check(current)
+        .is_ok()
+    };
+
+    assert_that(&check(None, None, Some(&todo))).is_true();
+    assert_that(&check(Some(IfMatch::Items(vec![tag.clone()])), None, Some(&todo))).is_true();
+    assert_that(&check(Some(IfMatch::Items(vec![other])), None, Some(&todo))).is_false();
+    assert_that(&check(Some(IfMatch::Any), None, None)).is_false();
+    assert_that(&check(None, Some(IfNoneMatch::Any), Some(&todo))).is_false();
+    assert_that(&check(None, Some(IfNoneMatch::Any), None)).is_true();
+  }
+
+  fn create_todo(id: i64, created_at: u32, updated_at: u32) -> Todo {
+    let time = |secs| NaiveDate::from_ymd(1970, 1, 1).and_hms(0, 0, secs);
+    Todo {
+      id,
+      text: "foo".to_string(),
+      done: false,
+      created_at: time(created_at),
+      updated_at: time(updated_at),
+      due_at: None,
+      change_seq: 0,
+    }
+  }
+}
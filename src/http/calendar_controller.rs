@@ -0,0 +1,120 @@
+use futures::Future;
+use futures_cpupool::CpuPool;
+use hyper::{Request, Response};
+use hyper::header::Host;
+
+use result::Error;
+use db::{CalendarTokenScope, CalendarTokensRepo, QueryTodos, TodosRepo};
+use common::{FuturesExt, RequestExt, ResponseExt};
+use transfer::{self, Format};
+
+use super::transfer_controller::stream_response;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// Parameters to create a calendar token
+#[derive(Debug, Deserialize)]
+struct NewCalendarToken {
+  owner: String,
+  /// `read` for the feed only when missing, `read_write` to also change CalDAV items
+  scope: Option<CalendarTokenScope>,
+}
+
+/// Parameters to revoke a calendar token
+#[derive(Debug, Deserialize)]
+struct RevokeCalendarToken {
+  token: String,
+}
+
+pub struct CalendarController {
+  todos_repo: TodosRepo,
+  calendar_tokens_repo: CalendarTokensRepo,
+  cpu_pool: CpuPool,
+}
+
+impl CalendarController {
+  pub fn new(
+    todos_repo: TodosRepo,
+    calendar_tokens_repo: CalendarTokensRepo,
+    cpu_pool: CpuPool,
+  ) -> Self {
+    CalendarController {
+      todos_repo,
+      calendar_tokens_repo,
+      cpu_pool,
+    }
+  }
+
+  /// A read only iCalendar feed with all todo items, requires a `token` query parameter.
+  pub fn call_feed(&self, req: Request) -> BoxFuture<Response> {
+    let todos_repo = self.todos_repo.clone();
+    let cpu_pool = self.cpu_pool.clone();
+    let token = req.query_pairs().remove("token").unwrap_or_default();
+
+    self
+      .calendar_tokens_repo
+      .find(token)
+      .map(move |_| {
+        let query = QueryTodos {
+          next: None,
+          limit: None,
+          text: None,
+        };
+        let chunks = transfer::export(&todos_repo, query, Format::ICalendar);
+        stream_response(&cpu_pool, chunks, Format::ICalendar)
+      })
+      .into_boxed()
+  }
+
+  /// Create a token, responds with the token and a feed url.
+  pub fn call_create_token(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.calendar_tokens_repo.clone();
+    let host = req
+      .headers()
+      .get::<Host>()
+      .map(|it| it.to_string())
+      .unwrap_or_else(|| "localhost".to_string());
+
+    req
+      .json::<NewCalendarToken>()
+      .and_then(|it| {
+        let scope = it.scope.unwrap_or(CalendarTokenScope::Read);
+        validate_owner(it.owner).map(|owner| (owner, scope))
+      })
+      .and_then(move |(owner, scope)| repo.create(owner, scope))
+      .inspect(|it| info!("created calendar token for {}", it.owner))
+      .map(move |it| {
+        let url = format!("webcal://{}/calendar.ics?token={}", host, it.token);
+        Response::new().json(&json!({
+          "token": it.token,
+          "owner": it.owner,
+          "scope": it.scope,
+          "created_at": it.created_at,
+          "url": url,
+        }))
+      })
+      .into_boxed()
+  }
+
+  /// Revoke a token, the feed stops responding to it.
+  pub fn call_revoke_token(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.calendar_tokens_repo.clone();
+
+    req
+      .json::<RevokeCalendarToken>()
+      .and_then(move |it| repo.revoke(it.token))
+      .map(|_| Response::new().json(&json!({"ok": true})))
+      .into_boxed()
+  }
+}
+
+fn validate_owner(owner: String) -> Result<String, Error> {
+  if owner.is_empty() || owner.len() > 255 {
+    return Err(Error::Validation(format!(
+      "calendar token's owner length must be between 1 and 255, got {}",
+      owner.len()
+    )));
+  }
+
+  Ok(owner)
+}
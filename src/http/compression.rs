@@ -0,0 +1,369 @@
+use futures::{future, Async, Future, Poll, Stream};
+use futures::sink::Sink as FuturesSink;
+use futures_cpupool::CpuPool;
+use hyper::{self, Body, Chunk, Headers, Method, Request, Response, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+
+use config::Config;
+use result::Error;
+use common::{essence, ContentEncoding, Encoder, FuturesExt, RequestExt, Sink};
+
+use super::middleware::{Endpoint, Middleware};
+use super::server::error_response;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// Compresses responses with a coding from `Accept-Encoding` and decompresses request bodies
+/// with `Content-Encoding`.
+///
+/// Responses smaller than `COMPRESSION_MIN_SIZE` are sent as they are, streamed ones have no
+/// size and are compressed chunk by chunk. Event streams are never compressed, an encoder would
+/// hold events back.
+#[derive(Clone)]
+pub struct Compression {
+  min_size: u64,
+  /// the largest request body before and after it's decompressed, bytes
+  max_body_size: u64,
+  cpu_pool: CpuPool,
+}
+
+impl Compression {
+  pub fn new(cfg: &Config, cpu_pool: CpuPool) -> Self {
+    Compression {
+      min_size: cfg.compression_min_size,
+      max_body_size: cfg.max_body_size,
+      cpu_pool,
+    }
+  }
+}
+
+impl Middleware for Compression {
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    let coding = ContentEncoding::negotiate(
+      header(req.headers(), "Accept-Encoding")
+        .as_ref()
+        .map(String::as_str),
+    );
+    let head = *req.method() == Method::Head;
+    let Compression {
+      min_size,
+      max_body_size,
+      cpu_pool,
+    } = self.clone();
+
+    decompress(req, max_body_size, &cpu_pool)
+      .then(move |result| match result {
+        Ok(req) => next.call(req),
+        Err(err) => future::ok(error_response(&err)).into_boxed(),
+      })
+      .and_then(move |resp| compress(resp, coding, min_size, head, &cpu_pool))
+      .into_boxed()
+  }
+}
+
+/// Decompress a request's body on the cpu pool, requests without `Content-Encoding` are passed
+/// as they are.
+///
+/// A compressed body is read up to `max_body_size` bytes and decompressed up to as many, a larger
+/// one is rejected with `413 Payload Too Large` before it's buffered.
+fn decompress(req: Request, max_body_size: u64, cpu_pool: &CpuPool) -> BoxFuture<Request> {
+  let coding = match ContentEncoding::from_header(
+    header(req.headers(), "Content-Encoding")
+      .as_ref()
+      .map(String::as_str),
+  ) {
+    Ok(Some(coding)) => coding,
+    Ok(None) => return future::ok(req).into_boxed(),
+    Err(err) => return future::err(err).into_boxed(),
+  };
+
+  let cpu_pool = cpu_pool.clone();
+  let (method, uri, version) = (req.method().clone(), req.uri().clone(), req.version());
+  let mut headers = req.headers().clone();
+  headers.remove_raw("Content-Encoding");
+
+  req
+    .bytes(max_body_size)
+    .and_then(move |bytes| cpu_pool.spawn_fn(move || coding.decode(&bytes, max_body_size)))
+    .map(move |body| {
+      headers.set(ContentLength(body.len() as u64));
+
+      let mut req = Request::new(method, uri);
+      req.set_version(version);
+      *req.headers_mut() = headers;
+      req.set_body(body);
+      req
+    })
+    .into_boxed()
+}
+
+/// Compress a response's body, a response with a known size is compressed as a whole.
+fn compress(
+  mut resp: Response,
+  coding: Option<ContentEncoding>,
+  min_size: u64,
+  head: bool,
+  cpu_pool: &CpuPool,
+) -> BoxFuture<Response> {
+  if !is_compressible(&resp) {
+    return future::ok(resp).into_boxed();
+  }
+  resp.headers_mut().append_raw("Vary", "Accept-Encoding");
+
+  let size = resp.headers().get::<ContentLength>().map(|it| it.0);
+  let coding = match coding {
+    Some(coding) if size.map_or(true, |size| size >= min_size) => coding,
+    _ => return future::ok(resp).into_boxed(),
+  };
+
+  let status = resp.status();
+  let mut headers = resp.headers().clone();
+  headers.set_raw("Content-Encoding", coding.as_str());
+  headers.remove::<ContentLength>();
+
+  if head {
+    // a GET's body isn't compressed only to tell its size
+    return future::ok(Response::new().with_status(status).with_headers(headers)).into_boxed();
+  }
+
+  if size.is_none() {
+    let body = stream(resp.body(), coding.encoder(Sink::new()), cpu_pool);
+    return future::ok(
+      Response::new()
+        .with_status(status)
+        .with_headers(headers)
+        .with_body(body),
+    ).into_boxed();
+  }
+
+  let cpu_pool = cpu_pool.clone();
+  resp
+    .body()
+    .concat2()
+    .map_err(Error::from)
+    .and_then(move |chunk| cpu_pool.spawn_fn(move || Ok(coding.encode(&chunk))))
+    .map(move |body| {
+      headers.set(ContentLength(body.len() as u64));
+      Response::new()
+        .with_status(status)
+        .with_headers(headers)
+        .with_body(body)
+    })
+    .into_boxed()
+}
+
+/// Check if a response has a body which could be compressed.
+fn is_compressible(resp: &Response) -> bool {
+  let status = resp.status();
+  if status == StatusCode::NoContent
+    || status == StatusCode::NotModified
+    || status.is_informational()
+  {
+    return false;
+  }
+  if resp.headers().get_raw("Content-Encoding").is_some() {
+    return false;
+  }
+
+  match resp.headers().get::<ContentType>() {
+    Some(content_type) => essence(&content_type.to_string()) != "text/event-stream",
+    None => true,
+  }
+}
+
+/// Compress a streamed body on the cpu pool like exports are streamed.
+fn stream(body: Body, encoder: Encoder, cpu_pool: &CpuPool) -> Body {
+  let (sender, compressed) = Body::pair();
+  let chunks = Compressed {
+    body,
+    encoder: Some(encoder),
+  }.then(|result| Ok::<_, ()>(result));
+
+  cpu_pool
+    .spawn(sender.sink_map_err(|_| ()).send_all(chunks))
+    .forget();
+
+  compressed
+}
+
+/// A body which is compressed chunk by chunk, it ends with the encoder's trailer.
+struct Compressed {
+  body: Body,
+  encoder: Option<Encoder>,
+}
+
+impl Stream for Compressed {
+  type Item = Chunk;
+  type Error = hyper::Error;
+
+  fn poll(&mut self) -> Poll<Option<Chunk>, hyper::Error> {
+    loop {
+      let chunk = match self.body.poll()? {
+        Async::Ready(Some(chunk)) => chunk,
+        Async::Ready(None) => {
+          let last = self
+            .encoder
+            .take()
+            .map(|encoder| Chunk::from(encoder.finish()));
+          return Ok(Async::Ready(last));
+        }
+        Async::NotReady => return Ok(Async::NotReady),
+      };
+
+      let bytes = match self.encoder {
+        Some(ref mut encoder) => encoder.write(&chunk),
+        None => return Ok(Async::Ready(None)),
+      };
+      // an encoder keeps small chunks until it has a block to emit
+      if !bytes.is_empty() {
+        return Ok(Async::Ready(Some(Chunk::from(bytes))));
+      }
+    }
+  }
+}
+
+/// A header's value, lines of a repeated header are joined with commas.
+fn header(headers: &Headers, name: &str) -> Option<String> {
+  headers.get_raw(name).map(|raw| {
+    raw
+      .iter()
+      .map(|line| String::from_utf8_lossy(line).into_owned())
+      .collect::<Vec<_>>()
+      .join(",")
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use hyper::{Get, Post};
+  use serde_json;
+  use std::rc::Rc;
+
+  use common::ResponseExt;
+
+  #[test]
+  fn should_compress_responses_above_min_size() {
+    let cfg = Config::default();
+    let endpoint = Endpoint::new(|req| {
+      let resp = match req.path() {
+        "/small" => Response::new().json(&json!({"id": 1})),
+        "/events" => Response::new()
+          .with_header(ContentType("text/event-stream; charset=utf-8".parse().unwrap()))
+          .with_body("retry: 1000\n\n"),
+        // a body without a size is streamed
+        "/stream" => Response::new().with_body("{\"text\": \"buy milk\"}\n".repeat(100)),
+        _ => Response::new().json(&json!({"items": vec!["buy milk"; 200]})),
+      };
+      future::ok(resp).into_boxed()
+    }).wrap(Rc::new(Compression::new(&cfg, cfg.create_cpu_pool())));
+
+    let resp = call(&endpoint, Get, "/todos", "gzip", None).unwrap();
+    assert_that(&header(resp.headers(), "Content-Encoding")).is_equal_to(Some("gzip".to_string()));
+    assert_that(&header(resp.headers(), "Vary")).is_equal_to(Some("Accept-Encoding".to_string()));
+    let size = resp.headers().get::<ContentLength>().map(|it| it.0);
+    let body = resp.body().concat2().wait().unwrap();
+    assert_that(&size).is_equal_to(Some(body.len() as u64));
+    let decoded = ContentEncoding::Gzip.decode(&body, 1 << 20).unwrap();
+    assert_that(&decoded)
+      .is_equal_to(serde_json::to_vec(&json!({"items": vec!["buy milk"; 200]})).unwrap());
+
+    let resp = call(&endpoint, Get, "/small", "gzip", None).unwrap();
+    assert_that(&header(resp.headers(), "Content-Encoding")).is_none();
+    assert_that(&header(resp.headers(), "Vary")).is_equal_to(Some("Accept-Encoding".to_string()));
+
+    let resp = call(&endpoint, Get, "/todos", "identity", None).unwrap();
+    assert_that(&header(resp.headers(), "Content-Encoding")).is_none();
+
+    let resp = call(&endpoint, Get, "/events", "gzip", None).unwrap();
+    assert_that(&header(resp.headers(), "Content-Encoding")).is_none();
+
+    let resp = call(&endpoint, Get, "/stream", "deflate", None).unwrap();
+    assert_that(&header(resp.headers(), "Content-Encoding"))
+      .is_equal_to(Some("deflate".to_string()));
+    let body = resp.body().concat2().wait().unwrap();
+    let decoded = ContentEncoding::Deflate.decode(&body, 1 << 20).unwrap();
+    assert_that(&decoded).is_equal_to("{\"text\": \"buy milk\"}\n".repeat(100).into_bytes());
+  }
+
+  #[test]
+  fn should_decompress_request_bodies() {
+    let cfg = Config::default();
+    let endpoint = Endpoint::new(|req| {
+      req
+        .body()
+        .concat2()
+        .map_err(Error::from)
+        .map(|chunk| Response::new().with_body(chunk))
+        .into_boxed()
+    }).wrap(Rc::new(Compression::new(&cfg, cfg.create_cpu_pool())));
+
+    let body = ContentEncoding::Gzip.encode(b"[{\"text\": \"buy milk\"}]");
+    let resp = call(&endpoint, Post, "/todos/import", "", Some(("gzip", body))).unwrap();
+    let body = resp.body().concat2().wait().unwrap();
+    assert_that(&body.to_vec()).is_equal_to(b"[{\"text\": \"buy milk\"}]".to_vec());
+
+    let resp = call(
+      &endpoint,
+      Post,
+      "/todos/import",
+      "",
+      Some(("zstd", vec![1])),
+    )
+    .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::UnsupportedMediaType);
+
+    let resp = call(
+      &endpoint,
+      Post,
+      "/todos/import",
+      "",
+      Some(("gzip", vec![1, 2])),
+    )
+    .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::BadRequest);
+
+    let cfg = Config::from_vars(|name| match name {
+      "MAX_BODY_SIZE" => Some("16".to_string()),
+      _ => None,
+    }).unwrap();
+    let endpoint = Endpoint::new(|_| future::ok(Response::new()).into_boxed())
+      .wrap(Rc::new(Compression::new(&cfg, cfg.create_cpu_pool())));
+
+    let body = ContentEncoding::Gzip.encode(b"[{\"text\": \"buy milk\"}]");
+    let resp = call(&endpoint, Post, "/todos/import", "", Some(("gzip", body))).unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::PayloadTooLarge);
+
+    // a small compressed body could be decompressed into a large one
+    let body = ContentEncoding::Gzip.encode(&[b' '; 1000]);
+    let cfg = Config {
+      max_body_size: body.len() as u64,
+      ..Config::default()
+    };
+    let endpoint = Endpoint::new(|_| future::ok(Response::new()).into_boxed())
+      .wrap(Rc::new(Compression::new(&cfg, cfg.create_cpu_pool())));
+    let resp = call(&endpoint, Post, "/todos/import", "", Some(("gzip", body))).unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::PayloadTooLarge);
+  }
+
+  fn call(
+    endpoint: &Endpoint,
+    method: Method,
+    path: &str,
+    accept_encoding: &str,
+    body: Option<(&str, Vec<u8>)>,
+  ) -> Result<Response, Error> {
+    let mut req = Request::new(method, path.parse().unwrap());
+    req
+      .headers_mut()
+      .set_raw("Accept-Encoding", accept_encoding.to_string());
+    if let Some((coding, body)) = body {
+      req
+        .headers_mut()
+        .set_raw("Content-Encoding", coding.to_string());
+      req.set_body(body);
+    }
+    endpoint.call(req).wait()
+  }
+}
@@ -0,0 +1,125 @@
+use futures::{future, Future, Stream};
+use hyper::{Method, Request, Response};
+use hyper::header::{ContentLength, ContentType};
+use serde_json::{self, Value as JsonValue};
+
+use result::Error;
+use common::{FuturesExt, MediaType};
+
+use super::middleware::Endpoint;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// A middleware which encodes JSON responses with a media type from `Accept`.
+///
+/// A client which accepts none of api's media types gets `406 Not Acceptable` before the handler
+/// runs, so a write isn't made for a response the client can't read. Routes which respond with
+/// their own media types like event streams or calendars aren't wrapped with it.
+pub fn negotiate_content(req: Request, next: Endpoint) -> BoxFuture<Response> {
+  let accept = req.headers().get_raw("Accept").map(|raw| {
+    raw
+      .iter()
+      .map(|line| String::from_utf8_lossy(line).into_owned())
+      .collect::<Vec<_>>()
+      .join(",")
+  });
+  let media_type = match MediaType::negotiate(accept.as_ref().map(String::as_str)) {
+    Some(media_type) => media_type,
+    None => return future::err(Error::NotAcceptable).into_boxed(),
+  };
+  let head = *req.method() == Method::Head;
+
+  next
+    .call(req)
+    .and_then(move |mut resp| -> BoxFuture<Response> {
+      if resp.headers().get::<ContentType>() != Some(&ContentType::json()) {
+        return future::ok(resp).into_boxed();
+      }
+      resp.headers_mut().set_raw("Vary", "Accept");
+
+      match media_type {
+        MediaType::Json => future::ok(resp).into_boxed(),
+        media_type => transcode(resp, media_type, head),
+      }
+    })
+    .into_boxed()
+}
+
+/// Encode a JSON response's body with another media type.
+fn transcode(resp: Response, media_type: MediaType, head: bool) -> BoxFuture<Response> {
+  let status = resp.status();
+  let mut headers = resp.headers().clone();
+  headers.set(ContentType(media_type.as_str().parse().unwrap()));
+
+  if head {
+    // a GET's body isn't encoded only to tell its size
+    headers.remove::<ContentLength>();
+    return future::ok(Response::new().with_status(status).with_headers(headers)).into_boxed();
+  }
+
+  resp
+    .body()
+    .concat2()
+    .map_err(Error::from)
+    .and_then(move |chunk| {
+      let value: JsonValue = serde_json::from_slice(&chunk)?;
+      let body = media_type.encode(&value);
+      headers.set(ContentLength(body.len() as u64));
+
+      Ok(
+        Response::new()
+          .with_status(status)
+          .with_headers(headers)
+          .with_body(body),
+      )
+    })
+    .into_boxed()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use hyper::Get;
+  use std::cell::Cell;
+  use std::rc::Rc;
+
+  use common::ResponseExt;
+
+  #[test]
+  fn should_encode_json_responses_with_accepted_type() {
+    let calls = Rc::new(Cell::new(0));
+    let handled = calls.clone();
+    let endpoint = Endpoint::new(move |req| {
+      handled.set(handled.get() + 1);
+      let resp = match req.path() {
+        "/text" => Response::new().with_body("hi"),
+        _ => Response::new().json(&json!({"id": 1})),
+      };
+      future::ok(resp).into_boxed()
+    }).wrap(Rc::new(negotiate_content));
+
+    let resp = call(&endpoint, "/todos", "application/cbor").unwrap();
+    assert_that(&resp.headers().get::<ContentType>().map(|it| it.to_string()))
+      .is_equal_to(Some("application/cbor".to_string()));
+    assert_that(&resp.headers().get::<ContentLength>()).is_equal_to(Some(&ContentLength(5)));
+    let body = resp.body().concat2().wait().unwrap();
+    assert_that(&body.to_vec()).is_equal_to(vec![0xa1, 0x62, b'i', b'd', 0x01]);
+
+    let resp = call(&endpoint, "/text", "application/cbor").unwrap();
+    assert_that(&resp.headers().get::<ContentType>()).is_none();
+    assert_that(&calls.get()).is_equal_to(2);
+
+    match call(&endpoint, "/todos", "text/html") {
+      Err(Error::NotAcceptable) => {}
+      result => panic!("unexpected result {:?}", result.map(|it| it.status())),
+    }
+    assert_that(&calls.get()).is_equal_to(2);
+  }
+
+  fn call(endpoint: &Endpoint, path: &str, accept: &str) -> Result<Response, Error> {
+    let mut req = Request::new(Get, path.parse().unwrap());
+    req.headers_mut().set_raw("Accept", accept.to_string());
+    endpoint.call(req).wait()
+  }
+}
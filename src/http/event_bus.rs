@@ -0,0 +1,241 @@
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use futures::sync::mpsc::{self, Receiver, Sender};
+use std::collections::VecDeque;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use db::{outbox, ConnectionPool, StoredEvent};
+use result::Error;
+
+/// How many latest events are kept to resume subscriptions.
+const BUFFER_SIZE: usize = 1000;
+/// How many messages a subscriber can fall behind before it's dropped.
+const SUBSCRIBER_BUFFER: usize = 100;
+/// How often the outbox is checked for new events.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How often subscribers get a heartbeat.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// How long to wait for a missing id, it could belong to a transaction which isn't committed yet.
+const GAP_TIMEOUT_SECS: i64 = 5;
+
+/// A message which is sent to subscribers.
+#[derive(Debug, Clone)]
+pub enum Message {
+  Event(Arc<StoredEvent>),
+  /// events since a requested id aren't buffered anymore, a client should reload items
+  Reset,
+  Heartbeat,
+}
+
+struct Inner {
+  /// the latest events, ordered by id
+  buffer: VecDeque<Arc<StoredEvent>>,
+  /// all events after this id are in the buffer
+  floor: i64,
+  /// the latest seen event's id, `None` until the poller is started
+  last_id: Option<i64>,
+  subscribers: Vec<Subscriber>,
+}
+
+impl Inner {
+  /// Send a message to every subscriber, closed and lagging ones are dropped.
+  fn broadcast(&mut self, message: Message) {
+    let subscribers = mem::replace(&mut self.subscribers, Vec::new());
+    self.subscribers = subscribers
+      .into_iter()
+      .filter_map(|mut it| if it.send(message.clone()) { Some(it) } else { None })
+      .collect();
+  }
+}
+
+/// A subscriber's bounded queue, one which falls behind is dropped instead of buffering events.
+///
+/// Its stream ends, so a client reconnects and resumes from the buffer or gets a `Reset`.
+struct Subscriber(Sender<Message>);
+
+impl Subscriber {
+  /// Queue a message, `false` when the subscriber is closed or lagging and should be dropped.
+  fn send(&mut self, message: Message) -> bool {
+    match self.0.try_send(message) {
+      Ok(()) => true,
+      Err(ref err) if err.is_full() => {
+        warn!("dropped a lagging event subscriber");
+        false
+      }
+      Err(_) => false,
+    }
+  }
+}
+
+/// Broadcasts todo events from the outbox to subscribers.
+///
+/// A single thread polls the outbox once it's needed, so connections don't query the db.
+#[derive(Clone)]
+pub struct EventBus {
+  conn_pool: ConnectionPool,
+  inner: Arc<Mutex<Inner>>,
+}
+
+impl EventBus {
+  pub fn new(conn_pool: ConnectionPool) -> Self {
+    EventBus {
+      conn_pool,
+      inner: Arc::new(Mutex::new(Inner {
+        buffer: VecDeque::new(),
+        floor: 0,
+        last_id: None,
+        subscribers: Vec::new(),
+      })),
+    }
+  }
+
+  /// Subscribe to events after `last_event_id` or to new ones only when it's missing.
+  ///
+  /// It's blocking when the poller isn't started yet, since the latest event's id is queried.
+  pub fn subscribe(&self, last_event_id: Option<i64>) -> Result<Receiver<Message>, Error> {
+    let mut inner = self.inner.lock().expect("event bus lock is poisoned");
+
+    if inner.last_id.is_none() {
+      let conn = self.conn_pool.get().map_err(Error::from)?;
+      let latest = outbox::latest_id(&*conn)?;
+      inner.floor = latest;
+      inner.last_id = Some(latest);
+
+      let bus = self.clone();
+      thread::Builder::new()
+        .name("event-bus".to_string())
+        .spawn(move || bus.run())
+        .expect("cannot spawn event bus");
+    }
+
+    let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+    let mut subscriber = Subscriber(sender);
+
+    if let Some(id) = last_event_id {
+      let missed: Vec<_> = inner.buffer.iter().filter(|it| it.id > id).cloned().collect();
+      // a subscriber which missed more than it can queue reloads items instead
+      if id < inner.floor || missed.len() > SUBSCRIBER_BUFFER {
+        subscriber.send(Message::Reset);
+      } else {
+        for event in missed {
+          subscriber.send(Message::Event(event));
+        }
+      }
+    }
+
+    inner.subscribers.push(subscriber);
+    Ok(receiver)
+  }
+
+  fn run(self) {
+    let mut heartbeat_at = Instant::now() + HEARTBEAT_INTERVAL;
+
+    loop {
+      thread::sleep(POLL_INTERVAL);
+
+      if let Err(err) = self.poll() {
+        error!("cannot load todo events {}", err);
+      }
+
+      if Instant::now() >= heartbeat_at {
+        heartbeat_at = Instant::now() + HEARTBEAT_INTERVAL;
+        self.broadcast(Message::Heartbeat);
+      }
+    }
+  }
+
+  fn poll(&self) -> Result<(), Error> {
+    let last_id = {
+      let inner = self.inner.lock().expect("event bus lock is poisoned");
+      inner.last_id.unwrap_or(0)
+    };
+
+    let conn = self.conn_pool.get().map_err(Error::from)?;
+    let events = outbox::load_after(&*conn, last_id, BUFFER_SIZE as i64)?;
+    let events = without_gaps(last_id, events, Utc::now().naive_utc());
+
+    // a subscriber takes the buffer under the same lock, so it gets an event only once
+    let mut inner = self.inner.lock().expect("event bus lock is poisoned");
+    for event in events {
+      let event = Arc::new(event);
+      inner.last_id = Some(event.id);
+      inner.buffer.push_back(event.clone());
+      if inner.buffer.len() > BUFFER_SIZE {
+        if let Some(evicted) = inner.buffer.pop_front() {
+          inner.floor = evicted.id;
+        }
+      }
+      inner.broadcast(Message::Event(event));
+    }
+
+    Ok(())
+  }
+
+  fn broadcast(&self, message: Message) {
+    let mut inner = self.inner.lock().expect("event bus lock is poisoned");
+    inner.broadcast(message);
+  }
+}
+
+/// Take events until a missing id which may be committed later, recent gaps are waited for.
+fn without_gaps(
+  last_id: i64,
+  events: Vec<StoredEvent>,
+  now: NaiveDateTime,
+) -> Vec<StoredEvent> {
+  let mut expected = last_id + 1;
+
+  events
+    .into_iter()
+    .take_while(|it| {
+      let timed_out = now - it.created_at >= ChronoDuration::seconds(GAP_TIMEOUT_SECS);
+      let ready = it.id == expected || timed_out;
+      expected = it.id + 1;
+      ready
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::Stream;
+
+  #[test]
+  fn should_wait_for_recent_gaps() {
+    let now = Utc::now().naive_utc();
+    let event = |id, secs_ago| StoredEvent {
+      id,
+      event: "todo.created".to_string(),
+      payload: "{}".to_string(),
+      created_at: now - ChronoDuration::seconds(secs_ago),
+    };
+
+    let ids = |events: Vec<StoredEvent>| events.iter().map(|it| it.id).collect::<Vec<_>>();
+
+    let events = vec![event(11, 0), event(12, 0), event(14, 0), event(15, 0)];
+    assert_that(&ids(without_gaps(10, events, now))).is_equal_to(vec![11, 12]);
+
+    let events = vec![event(11, 0), event(13, 60), event(14, 0)];
+    assert_that(&ids(without_gaps(10, events, now))).is_equal_to(vec![11, 13, 14]);
+  }
+
+  #[test]
+  fn should_drop_lagging_subscriber() {
+    let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+    let mut subscriber = Subscriber(sender);
+
+    let sent = (0..SUBSCRIBER_BUFFER * 2)
+      .take_while(|_| subscriber.send(Message::Heartbeat))
+      .count();
+    assert_that(&sent).is_less_than(SUBSCRIBER_BUFFER * 2);
+
+    // the stream ends after queued messages once the subscriber is dropped
+    drop(subscriber);
+    let received = receiver.wait().count();
+    assert_that(&received).is_equal_to(sent);
+  }
+}
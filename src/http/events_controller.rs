@@ -0,0 +1,111 @@
+use futures::{future, stream, Future, Sink, Stream};
+use futures_cpupool::CpuPool;
+use hyper::{Body, Chunk, Request, Response};
+use hyper::header::{CacheControl, CacheDirective, ContentType};
+
+use result::Error;
+use common::{FuturesExt, RequestExt};
+
+use super::event_bus::{EventBus, Message};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// How long a client waits before reconnecting, milliseconds.
+const RETRY_MS: u32 = 3000;
+
+header! { (LastEventId, "Last-Event-ID") => [String] }
+
+pub struct EventsController {
+  event_bus: EventBus,
+  cpu_pool: CpuPool,
+}
+
+impl EventsController {
+  pub fn new(event_bus: EventBus, cpu_pool: CpuPool) -> Self {
+    EventsController {
+      event_bus,
+      cpu_pool,
+    }
+  }
+
+  /// A server sent events stream of todo items' changes.
+  ///
+  /// A client resumes with `Last-Event-ID` header or `last_event_id` query parameter, when
+  /// events since it aren't buffered anymore a `reset` event is sent instead. The stream of a
+  /// client which doesn't keep up ends, it reconnects and resumes the same way.
+  pub fn call_events(&self, req: Request) -> BoxFuture<Response> {
+    let last_event_id = req
+      .headers()
+      .get::<LastEventId>()
+      .map(|it| it.0.clone())
+      .or_else(|| req.query_pairs().remove("last_event_id"));
+    let last_event_id = match last_event_id.map(|it| it.parse::<i64>()) {
+      Some(Ok(id)) => Some(id),
+      Some(Err(_)) => {
+        return future::err(Error::Validation("Last-Event-ID must be a number".to_string()))
+          .into_boxed()
+      }
+      None => None,
+    };
+
+    let event_bus = self.event_bus.clone();
+    let cpu_pool = self.cpu_pool.clone();
+
+    // missed events are loaded from the database
+    self
+      .cpu_pool
+      .spawn_fn(move || event_bus.subscribe(last_event_id))
+      .map(move |messages| {
+        let (sender, body) = Body::pair();
+        let chunks = stream::once(Ok(format!("retry: {}\n\n", RETRY_MS)))
+          .chain(messages.map(|it| encode(&it)))
+          .map(|it| Ok(Chunk::from(it)));
+
+        cpu_pool
+          .spawn(sender.sink_map_err(|_| ()).send_all(chunks))
+          .forget();
+
+        Response::new()
+          .with_header(ContentType("text/event-stream".parse().unwrap()))
+          .with_header(CacheControl(vec![CacheDirective::NoCache]))
+          .with_body(body)
+      })
+      .into_boxed()
+  }
+}
+
+/// Encode a message as an event stream's block.
+fn encode(message: &Message) -> String {
+  match *message {
+    Message::Event(ref event) => format!(
+      "id: {}\nevent: {}\ndata: {}\n\n",
+      event.id, event.event, event.payload
+    ),
+    Message::Reset => "event: reset\ndata: {}\n\n".to_string(),
+    Message::Heartbeat => ": heartbeat\n\n".to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::Utc;
+  use spectral::prelude::*;
+  use std::sync::Arc;
+
+  use db::StoredEvent;
+
+  #[test]
+  fn should_encode_messages() {
+    let event = StoredEvent {
+      id: 42,
+      event: "todo.created".to_string(),
+      payload: "{\"todo\":{}}".to_string(),
+      created_at: Utc::now().naive_utc(),
+    };
+
+    assert_that(&encode(&Message::Event(Arc::new(event))).as_str())
+      .is_equal_to("id: 42\nevent: todo.created\ndata: {\"todo\":{}}\n\n");
+    assert_that(&encode(&Message::Heartbeat).starts_with(':')).is_true();
+  }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+/// A parsed document with its operations and named fragments.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Document {
+  pub operations: Vec<Operation>,
+  pub fragments: HashMap<String, Fragment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperationKind {
+  Query,
+  Mutation,
+  Subscription,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Operation {
+  pub kind: OperationKind,
+  pub name: Option<String>,
+  pub variables: Vec<VariableDefinition>,
+  pub selection_set: Vec<Selection>,
+}
+
+/// A variable's declaration, only whether it's required matters for execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDefinition {
+  pub name: String,
+  pub required: bool,
+  pub default: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+  pub type_condition: String,
+  pub selection_set: Vec<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection {
+  Field(Field),
+  FragmentSpread {
+    name: String,
+    directives: Vec<Directive>,
+  },
+  InlineFragment {
+    type_condition: Option<String>,
+    directives: Vec<Directive>,
+    selection_set: Vec<Selection>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+  pub alias: Option<String>,
+  pub name: String,
+  pub arguments: Vec<(String, Value)>,
+  pub directives: Vec<Directive>,
+  pub selection_set: Vec<Selection>,
+}
+
+impl Field {
+  /// A key of the field in a response, its alias or name.
+  pub fn response_key(&self) -> &str {
+    self.alias.as_ref().unwrap_or(&self.name)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive {
+  pub name: String,
+  pub arguments: Vec<(String, Value)>,
+}
+
+/// An input value, variables are substituted before it's used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Variable(String),
+  Int(i64),
+  Float(f64),
+  String(String),
+  Boolean(bool),
+  Null,
+  Enum(String),
+  List(Vec<Value>),
+  Object(Vec<(String, Value)>),
+}
@@ -0,0 +1,497 @@
+use serde_json::{Map, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+
+use super::ast::*;
+
+/// A field's type, nullability isn't checked because resolvers return what they can.
+#[derive(Debug, Clone, Copy)]
+pub enum FieldType {
+  /// a string, a number or a boolean as it's serialized
+  Scalar,
+  /// serialized as a string
+  Id,
+  Object(&'static ObjectType),
+  List(&'static ObjectType),
+  ScalarList,
+}
+
+#[derive(Debug)]
+pub struct FieldDef {
+  pub name: &'static str,
+  /// a key of the resolved json value
+  pub key: &'static str,
+  pub ty: FieldType,
+  pub args: &'static [&'static str],
+}
+
+#[derive(Debug)]
+pub struct ObjectType {
+  pub name: &'static str,
+  pub fields: &'static [FieldDef],
+}
+
+impl ObjectType {
+  pub fn field(&self, name: &str) -> Option<&FieldDef> {
+    self.fields.iter().find(|it| it.name == name)
+  }
+}
+
+/// Limits which protect the database from expensive queries.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+  pub max_depth: usize,
+  pub max_complexity: u64,
+  /// how many items a list field is expected to have when its root field doesn't say
+  pub default_list_size: u64,
+  /// an additional cost of every root field, it's a database query
+  pub root_field_cost: u64,
+  /// how many fields and fragments an operation selects, a fragment counts every time it's spread
+  pub max_selections: usize,
+}
+
+/// An operation ready to execute, its fields are checked against the schema and limits.
+pub struct Prepared {
+  pub kind: OperationKind,
+  pub fields: Vec<Field>,
+  variables: Map<String, JsonValue>,
+  fragments: HashMap<String, Fragment>,
+  max_selections: usize,
+}
+
+impl Prepared {
+  /// Select an operation and check it against the schema and limits.
+  pub fn new(
+    document: Document,
+    operation_name: Option<&str>,
+    variables: Map<String, JsonValue>,
+    query: &'static ObjectType,
+    mutation: &'static ObjectType,
+    limits: &Limits,
+  ) -> Result<Self, String> {
+    let Document {
+      operations,
+      fragments,
+    } = document;
+
+    let operation = match operation_name {
+      Some(name) => operations
+        .into_iter()
+        .find(|it| it.name.as_ref().map(|it| it.as_str()) == Some(name))
+        .ok_or_else(|| format!("unknown operation {}", name))?,
+      None if operations.len() == 1 => operations.into_iter().next().expect("an operation"),
+      None => {
+        return Err("operationName is required for a document with many operations".to_string())
+      }
+    };
+
+    let root = match operation.kind {
+      OperationKind::Query => query,
+      OperationKind::Mutation => mutation,
+      OperationKind::Subscription => return Err("subscriptions aren't supported".to_string()),
+    };
+
+    let mut values = Map::new();
+    for definition in &operation.variables {
+      let value = match variables.get(&definition.name) {
+        Some(value) => value.clone(),
+        None => match definition.default {
+          Some(ref default) => to_json(default, &Map::new())?,
+          None if definition.required => {
+            return Err(format!("variable ${} is required", definition.name))
+          }
+          None => JsonValue::Null,
+        },
+      };
+      values.insert(definition.name.clone(), value);
+    }
+
+    check_fragments(&fragments, &operation.selection_set)?;
+
+    let mut prepared = Prepared {
+      kind: operation.kind,
+      fields: Vec::new(),
+      variables: values,
+      fragments,
+      max_selections: limits.max_selections,
+    };
+
+    // selections are counted across the operation, nested ones are collected by `check`
+    let mut selected = 0;
+    let fields: Vec<Field> = prepared
+      .collect_counted(root, &operation.selection_set, &mut selected)?
+      .into_iter()
+      .cloned()
+      .collect();
+    let complexity = {
+      let fields: Vec<&Field> = fields.iter().collect();
+      prepared.check(root, &fields, 1, limits.default_list_size, limits, &mut selected)?
+    };
+    if complexity > limits.max_complexity {
+      return Err(format!(
+        "query's complexity {} is more than {}",
+        complexity, limits.max_complexity
+      ));
+    }
+
+    prepared.fields = fields;
+    Ok(prepared)
+  }
+
+  /// Field's arguments as a json object.
+  pub fn arguments(&self, field: &Field) -> Result<JsonValue, String> {
+    let mut arguments = Map::new();
+    for &(ref name, ref value) in &field.arguments {
+      arguments.insert(name.clone(), to_json(value, &self.variables)?);
+    }
+    Ok(JsonValue::Object(arguments))
+  }
+
+  /// Select fields of a resolved value.
+  pub fn project(
+    &self,
+    value: &JsonValue,
+    ty: &FieldType,
+    field: &Field,
+  ) -> Result<JsonValue, String> {
+    if value.is_null() {
+      return Ok(JsonValue::Null);
+    }
+
+    match *ty {
+      FieldType::Scalar | FieldType::ScalarList => Ok(value.clone()),
+      FieldType::Id => Ok(match *value {
+        JsonValue::Number(ref id) => JsonValue::String(id.to_string()),
+        ref value => value.clone(),
+      }),
+      FieldType::Object(object) => self.project_object(value, object, &field.selection_set),
+      FieldType::List(object) => {
+        let items = value
+          .as_array()
+          .ok_or_else(|| format!("{} must be a list", field.name))?;
+        items
+          .iter()
+          .map(|it| self.project_object(it, object, &field.selection_set))
+          .collect::<Result<Vec<_>, _>>()
+          .map(JsonValue::Array)
+      }
+    }
+  }
+
+  fn project_object(
+    &self,
+    value: &JsonValue,
+    ty: &ObjectType,
+    selection_set: &[Selection],
+  ) -> Result<JsonValue, String> {
+    if value.is_null() {
+      return Ok(JsonValue::Null);
+    }
+
+    let mut object = Map::new();
+    for field in self.collect(ty, selection_set)? {
+      let projected = if field.name == "__typename" {
+        JsonValue::String(ty.name.to_string())
+      } else {
+        let def = ty
+          .field(&field.name)
+          .ok_or_else(|| format!("unknown field {}", field.name))?;
+        self.project(&value[def.key], &def.ty, field)?
+      };
+      object.insert(field.response_key().to_string(), projected);
+    }
+
+    Ok(JsonValue::Object(object))
+  }
+
+  /// Flatten fragments and skip fields excluded by `@skip` and `@include`.
+  fn collect<'a>(
+    &'a self,
+    ty: &ObjectType,
+    selection_set: &'a [Selection],
+  ) -> Result<Vec<&'a Field>, String> {
+    self.collect_counted(ty, selection_set, &mut 0)
+  }
+
+  /// Flatten fragments adding the number of visited selections to `selected`.
+  fn collect_counted<'a>(
+    &'a self,
+    ty: &ObjectType,
+    selection_set: &'a [Selection],
+    selected: &mut usize,
+  ) -> Result<Vec<&'a Field>, String> {
+    let mut fields = Vec::new();
+    self.collect_into(ty, selection_set, &mut fields, selected)?;
+    Ok(fields)
+  }
+
+  fn collect_into<'a>(
+    &'a self,
+    ty: &ObjectType,
+    selection_set: &'a [Selection],
+    fields: &mut Vec<&'a Field>,
+    selected: &mut usize,
+  ) -> Result<(), String> {
+    for selection in selection_set {
+      // fragments spread in many places multiply, the query stops before it grows any further
+      *selected += 1;
+      if *selected > self.max_selections {
+        return Err(format!(
+          "query selects more than {} fields and fragments",
+          self.max_selections
+        ));
+      }
+
+
+      match *selection {
+        Selection::Field(ref field) => {
+          if self.included(&field.directives)? {
+            fields.push(field);
+          }
+        }
+        Selection::FragmentSpread {
+          ref name,
+          ref directives,
+        } => {
+          let fragment = self
+            .fragments
+            .get(name)
+            .ok_or_else(|| format!("unknown fragment {}", name))?;
+          if self.included(directives)? && fragment.type_condition == ty.name {
+            self.collect_into(ty, &fragment.selection_set, fields, selected)?;
+          }
+        }
+        Selection::InlineFragment {
+          ref type_condition,
+          ref directives,
+          ref selection_set,
+        } => {
+          let applies = type_condition.as_ref().map_or(true, |it| it == ty.name);
+          if self.included(directives)? && applies {
+            self.collect_into(ty, selection_set, fields, selected)?;
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  fn included(&self, directives: &[Directive]) -> Result<bool, String> {
+    for directive in directives {
+      let condition = directive
+        .arguments
+        .iter()
+        .find(|it| it.0 == "if")
+        .map(|it| to_json(&it.1, &self.variables))
+        .unwrap_or(Ok(JsonValue::Null))?;
+      let condition = condition
+        .as_bool()
+        .ok_or_else(|| format!("@{} requires a boolean `if` argument", directive.name))?;
+
+      match directive.name.as_str() {
+        "skip" if condition => return Ok(false),
+        "include" if !condition => return Ok(false),
+        "skip" | "include" => {}
+        name => return Err(format!("unknown directive @{}", name)),
+      }
+    }
+
+    Ok(true)
+  }
+
+  /// Check fields exist and limits aren't exceeded, returns the fields' complexity.
+  fn check(
+    &self,
+    ty: &ObjectType,
+    fields: &[&Field],
+    depth: usize,
+    list_size: u64,
+    limits: &Limits,
+    selected: &mut usize,
+  ) -> Result<u64, String> {
+    if depth > limits.max_depth {
+      return Err(format!("query is deeper than {}", limits.max_depth));
+    }
+
+    let mut complexity = 0u64;
+    for field in fields {
+      if field.name == "__typename" {
+        complexity += 1;
+        continue;
+      }
+      if field.name.starts_with("__") {
+        return Err(format!(
+          "introspection field {} isn't supported",
+          field.name
+        ));
+      }
+
+      let def = ty
+        .field(&field.name)
+        .ok_or_else(|| format!("{} doesn't have field {}", ty.name, field.name))?;
+
+      for &(ref name, _) in &field.arguments {
+        if !def.args.contains(&name.as_str()) {
+          return Err(format!(
+            "{}.{} doesn't have argument {}",
+            ty.name, def.name, name
+          ));
+        }
+      }
+
+      // a root field's `first` argument tells how long its lists are
+      let list_size = if depth == 1 {
+        let arguments = self.arguments(field)?;
+        arguments["first"]
+          .as_u64()
+          .unwrap_or(limits.default_list_size)
+      } else {
+        list_size
+      };
+
+      let cost = match def.ty {
+        FieldType::Scalar | FieldType::Id | FieldType::ScalarList => {
+          if !field.selection_set.is_empty() {
+            return Err(format!("{}.{} cannot have a selection", ty.name, def.name));
+          }
+          1
+        }
+        FieldType::Object(object) | FieldType::List(object) => {
+          if field.selection_set.is_empty() {
+            return Err(format!("{}.{} must have a selection", ty.name, def.name));
+          }
+          let nested = self.collect_counted(object, &field.selection_set, selected)?;
+          let nested = self.check(object, &nested, depth + 1, list_size, limits, selected)?;
+          match def.ty {
+            FieldType::List(_) => 1 + nested.saturating_mul(list_size),
+            _ => 1 + nested,
+          }
+        }
+      };
+
+      complexity = complexity.saturating_add(cost);
+      if depth == 1 {
+        complexity = complexity.saturating_add(limits.root_field_cost);
+      }
+    }
+
+    Ok(complexity)
+  }
+}
+
+/// Check fragments before they're flattened, a fragment mustn't spread itself and a selection
+/// mustn't spread a fragment twice.
+fn check_fragments(
+  fragments: &HashMap<String, Fragment>,
+  selection_set: &[Selection],
+) -> Result<(), String> {
+  let mut spread = HashSet::new();
+  spreads(selection_set, &mut spread)?;
+
+  let mut graph = HashMap::new();
+  for (name, fragment) in fragments {
+    let mut edges = HashSet::new();
+    spreads(&fragment.selection_set, &mut edges)?;
+    spread.extend(edges.iter().cloned());
+    graph.insert(name.as_str(), edges);
+  }
+
+  if let Some(name) = spread.iter().find(|it| !fragments.contains_key(**it)) {
+    return Err(format!("unknown fragment {}", name));
+  }
+
+  // fragments are checked by name, so a cycle is reported from the same fragment every time
+  let mut names: Vec<_> = graph.keys().cloned().collect();
+  names.sort();
+
+  let mut checked = HashSet::new();
+  for name in names {
+    check_cycle(&graph, name, &mut Vec::new(), &mut checked)?;
+  }
+  Ok(())
+}
+
+/// Add names of fragments which a selection set and its fields spread to `spread`.
+fn spreads<'a>(
+  selection_set: &'a [Selection],
+  spread: &mut HashSet<&'a str>,
+) -> Result<(), String> {
+  spreads_into(selection_set, &mut HashSet::new(), spread)
+}
+
+/// Inline fragments are selected at the same level as their parent's fields.
+fn spreads_into<'a>(
+  selection_set: &'a [Selection],
+  level: &mut HashSet<&'a str>,
+  spread: &mut HashSet<&'a str>,
+) -> Result<(), String> {
+  for selection in selection_set {
+    match *selection {
+      Selection::Field(ref field) => spreads(&field.selection_set, spread)?,
+      Selection::FragmentSpread { ref name, .. } => {
+        if !level.insert(name) {
+          return Err(format!("fragment {} is spread twice in a selection", name));
+        }
+        spread.insert(name);
+      }
+      Selection::InlineFragment {
+        ref selection_set,
+        ..
+      } => spreads_into(selection_set, level, spread)?,
+    }
+  }
+  Ok(())
+}
+
+/// Follow spreads of a fragment, `path` are fragments which are being checked.
+fn check_cycle<'a>(
+  graph: &HashMap<&'a str, HashSet<&'a str>>,
+  name: &'a str,
+  path: &mut Vec<&'a str>,
+  checked: &mut HashSet<&'a str>,
+) -> Result<(), String> {
+  if checked.contains(name) {
+    return Ok(());
+  }
+  if path.contains(&name) {
+    return Err(format!("fragment {} spreads itself", name));
+  }
+
+  path.push(name);
+  for next in &graph[name] {
+    check_cycle(graph, next, path, checked)?;
+  }
+  path.pop();
+  checked.insert(name);
+  Ok(())
+}
+
+/// Convert an input value to json substituting variables.
+fn to_json(value: &Value, variables: &Map<String, JsonValue>) -> Result<JsonValue, String> {
+  let value = match *value {
+    Value::Variable(ref name) => variables
+      .get(name)
+      .cloned()
+      .ok_or_else(|| format!("variable ${} isn't defined", name))?,
+    Value::Int(value) => json!(value),
+    Value::Float(value) => json!(value),
+    Value::String(ref value) | Value::Enum(ref value) => json!(value),
+    Value::Boolean(value) => json!(value),
+    Value::Null => JsonValue::Null,
+    Value::List(ref items) => JsonValue::Array(
+      items
+        .iter()
+        .map(|it| to_json(it, variables))
+        .collect::<Result<_, _>>()?,
+    ),
+    Value::Object(ref fields) => {
+      let mut object = Map::new();
+      for &(ref name, ref value) in fields {
+        object.insert(name.clone(), to_json(value, variables)?);
+      }
+      JsonValue::Object(object)
+    }
+  };
+
+  Ok(value)
+}
@@ -0,0 +1,465 @@
+mod ast;
+mod executor;
+mod parser;
+
+use futures::{future, stream, Future, IntoFuture, Stream};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value as JsonValue};
+use std::error::Error as StdError;
+use std::rc::Rc;
+
+use db::{NewTodo, Paginated, QueryTodos, Todo, TodosRepo, UpdateTodo};
+use result::Error;
+use common::FuturesExt;
+use validators::Validator;
+
+use http::server::error_status;
+
+use self::ast::OperationKind;
+use self::executor::{FieldDef, FieldType, Limits, ObjectType, Prepared};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// How many items a page has when `first` is missing, the same as `TodosRepo::query`'s limit.
+const PAGE_SIZE: u8 = 10;
+
+/// A field without arguments.
+macro_rules! field {
+  ($name:expr, $key:expr, $ty:expr) => {
+    FieldDef {
+      name: $name,
+      key: $key,
+      ty: $ty,
+      args: &[],
+    }
+  };
+}
+
+const LIMITS: Limits = Limits {
+  max_depth: 6,
+  max_complexity: 300,
+  default_list_size: PAGE_SIZE as u64,
+  root_field_cost: 10,
+  max_selections: 500,
+};
+
+static TODO: ObjectType = ObjectType {
+  name: "Todo",
+  fields: &[
+    field!("id", "id", FieldType::Id),
+    field!("text", "text", FieldType::Scalar),
+    field!("done", "done", FieldType::Scalar),
+    field!("createdAt", "created_at", FieldType::Scalar),
+    field!("updatedAt", "updated_at", FieldType::Scalar),
+    field!("dueAt", "due_at", FieldType::Scalar),
+  ],
+};
+
+static TODO_EDGE: ObjectType = ObjectType {
+  name: "TodoEdge",
+  fields: &[
+    field!("cursor", "cursor", FieldType::Scalar),
+    field!("node", "node", FieldType::Object(&TODO)),
+  ],
+};
+
+static PAGE_INFO: ObjectType = ObjectType {
+  name: "PageInfo",
+  fields: &[
+    field!("hasNextPage", "has_next_page", FieldType::Scalar),
+    field!("endCursor", "end_cursor", FieldType::Scalar),
+  ],
+};
+
+static TODO_CONNECTION: ObjectType = ObjectType {
+  name: "TodoConnection",
+  fields: &[
+    field!("edges", "edges", FieldType::List(&TODO_EDGE)),
+    field!("nodes", "nodes", FieldType::List(&TODO)),
+    field!("pageInfo", "page_info", FieldType::Object(&PAGE_INFO)),
+  ],
+};
+
+static UPDATE_TODO_PAYLOAD: ObjectType = ObjectType {
+  name: "UpdateTodoPayload",
+  fields: &[
+    field!("todo", "todo", FieldType::Object(&TODO)),
+    field!("accepted", "accepted", FieldType::ScalarList),
+    field!("overridden", "overridden", FieldType::ScalarList),
+  ],
+};
+
+static QUERY: ObjectType = ObjectType {
+  name: "Query",
+  fields: &[
+    FieldDef {
+      name: "todo",
+      key: "todo",
+      ty: FieldType::Object(&TODO),
+      args: &["id"],
+    },
+    FieldDef {
+      name: "todos",
+      key: "todos",
+      ty: FieldType::Object(&TODO_CONNECTION),
+      args: &["first", "after", "text"],
+    },
+  ],
+};
+
+static MUTATION: ObjectType = ObjectType {
+  name: "Mutation",
+  fields: &[
+    FieldDef {
+      name: "createTodo",
+      key: "createTodo",
+      ty: FieldType::Object(&TODO),
+      args: &["text"],
+    },
+    FieldDef {
+      name: "updateTodo",
+      key: "updateTodo",
+      ty: FieldType::Object(&UPDATE_TODO_PAYLOAD),
+      args: &["id", "text", "done", "changedAt"],
+    },
+  ],
+};
+
+/// A request as it's sent by GraphQL clients.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GraphQLRequest {
+  pub query: String,
+  #[serde(rename = "operationName")]
+  pub operation_name: Option<String>,
+  pub variables: Option<Map<String, JsonValue>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TodoArgs {
+  id: JsonValue,
+}
+
+#[derive(Debug, Deserialize)]
+struct TodosArgs {
+  first: Option<u8>,
+  after: Option<String>,
+  text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateTodoArgs {
+  id: JsonValue,
+  text: Option<String>,
+  done: Option<bool>,
+  #[serde(rename = "changedAt")]
+  changed_at: Option<String>,
+}
+
+/// Todos' GraphQL schema with `todo` and `todos` queries and `createTodo` and `updateTodo`
+/// mutations.
+///
+/// Queries are checked against depth and complexity limits before anything is resolved, list
+/// fields cost as much as the page size. Introspection isn't supported.
+pub struct Schema {
+  todos_repo: TodosRepo,
+}
+
+impl Schema {
+  pub fn new(todos_repo: TodosRepo) -> Self {
+    Schema { todos_repo }
+  }
+
+  /// Execute a request, errors are reported in the response's `errors`.
+  pub fn execute(&self, request: GraphQLRequest) -> BoxFuture<JsonValue> {
+    let prepared = parser::parse(&request.query).and_then(|document| {
+      Prepared::new(
+        document,
+        request.operation_name.as_ref().map(|it| it.as_str()),
+        request.variables.unwrap_or_default(),
+        &QUERY,
+        &MUTATION,
+        &LIMITS,
+      )
+    });
+
+    let prepared = match prepared {
+      Ok(prepared) => Rc::new(prepared),
+      Err(message) => return future::ok(json!({"errors": [{"message": message}]})).into_boxed(),
+    };
+
+    let root = match prepared.kind {
+      OperationKind::Mutation => &MUTATION,
+      _ => &QUERY,
+    };
+    let repo = self.todos_repo.clone();
+    let resolve_field = {
+      let prepared = prepared.clone();
+      move |index: usize| {
+        let field = &prepared.fields[index];
+        let key = field.response_key().to_string();
+
+        let resolved: BoxFuture<JsonValue> = match root.field(&field.name) {
+          Some(def) => {
+            let prepared = prepared.clone();
+            let repo = repo.clone();
+            prepared
+              .arguments(field)
+              .map_err(Error::Validation)
+              .into_future()
+              .and_then(move |arguments| resolve(&repo, def.name, arguments))
+              .and_then(move |value| {
+                let field = &prepared.fields[index];
+                prepared
+                  .project(&value, &def.ty, field)
+                  .map_err(Error::Validation)
+              })
+              .into_boxed()
+          }
+          // the only field which isn't in the schema after the check
+          None => future::ok(json!(root.name)).into_boxed(),
+        };
+
+        resolved.then(move |result| Ok::<_, Error>((key, result)))
+      }
+    };
+
+    let indexes = 0..prepared.fields.len();
+    let resolved: BoxFuture<Vec<_>> = match prepared.kind {
+      // mutations run one after another, queries run concurrently
+      OperationKind::Mutation => stream::iter_ok(indexes)
+        .and_then(resolve_field)
+        .collect()
+        .into_boxed(),
+      _ => future::join_all(indexes.map(resolve_field)).into_boxed(),
+    };
+
+    resolved
+      .map(|results| {
+        let mut data = Map::new();
+        let mut errors = Vec::new();
+
+        for (key, result) in results {
+          match result {
+            Ok(value) => {
+              data.insert(key, value);
+            }
+            Err(err) => {
+              errors.push(json!({
+                "message": err.to_string(),
+                "path": [key],
+                "extensions": {
+                  "status": error_status(&err).as_u16(),
+                  "description": err.description(),
+                },
+              }));
+              data.insert(key, JsonValue::Null);
+            }
+          }
+        }
+
+        if errors.is_empty() {
+          json!({ "data": data })
+        } else {
+          json!({ "data": data, "errors": errors })
+        }
+      })
+      .into_boxed()
+  }
+}
+
+/// Resolve a root field to a json value which has keys of `FieldDef`s.
+fn resolve(repo: &TodosRepo, name: &str, arguments: JsonValue) -> BoxFuture<JsonValue> {
+  let repo = repo.clone();
+
+  match name {
+    "todo" => parse::<TodoArgs>(arguments)
+      .and_then(|it| parse_id(&it.id))
+      .into_future()
+      .and_then(move |id| repo.find(id))
+      .then(|result| match result {
+        Ok(todo) => Ok(json!(todo)),
+        Err(Error::RecordNotFound) => Ok(JsonValue::Null),
+        Err(err) => Err(err),
+      })
+      .into_boxed(),
+    "todos" => parse::<TodosArgs>(arguments)
+      .and_then(|it| {
+        let next = match it.after {
+          Some(ref cursor) => Some(parse_id(&json!(cursor))?),
+          None => None,
+        };
+        let query = QueryTodos {
+          next,
+          limit: it.first,
+          text: it.text,
+        };
+        query.validated()
+      })
+      .into_future()
+      .and_then(move |query| {
+        let limit = match query.limit {
+          Some(limit) if limit > 0 && limit <= PAGE_SIZE => limit,
+          _ => PAGE_SIZE,
+        };
+        repo.query(query).map(move |page| connection(&page, limit))
+      })
+      .into_boxed(),
+    "createTodo" => parse::<NewTodo>(arguments)
+      .and_then(|it| it.validated())
+      .into_future()
+      .and_then(move |it| repo.insert(it))
+      .inspect(|it| info!("created {:?}", it))
+      .map(|it| json!(it))
+      .into_boxed(),
+    "updateTodo" => parse::<UpdateTodoArgs>(arguments)
+      .and_then(|it| {
+        let changed_at = match it.changed_at {
+          Some(ref changed_at) => Some(serde_json::from_value(json!(changed_at))?),
+          None => None,
+        };
+        let update = UpdateTodo {
+          id: parse_id(&it.id)?,
+          text: it.text,
+          done: it.done,
+          changed_at,
+        };
+        update.validated()
+      })
+      .into_future()
+      .and_then(move |it| repo.update(it))
+      .inspect(|it| info!("updated {:?}", it))
+      .map(|it| {
+        json!({
+          "todo": it.todo,
+          "accepted": it.accepted,
+          "overridden": it.overridden,
+        })
+      })
+      .into_boxed(),
+    name => future::err(Error::Validation(format!("unknown field {}", name))).into_boxed(),
+  }
+}
+
+/// A connection with an edge per item, items' ids are cursors.
+///
+/// `hasNextPage` is true for every full page, the next one can be empty.
+fn connection(page: &Paginated<Todo>, limit: u8) -> JsonValue {
+  let edges: Vec<_> = page
+    .items
+    .iter()
+    .map(|it| json!({"cursor": it.id.to_string(), "node": it}))
+    .collect();
+
+  json!({
+    "edges": edges,
+    "nodes": page.items,
+    "page_info": {
+      "has_next_page": page.items.len() == limit as usize,
+      "end_cursor": page.next.map(|it| it.to_string()),
+    },
+  })
+}
+
+fn parse<T: DeserializeOwned>(arguments: JsonValue) -> Result<T, Error> {
+  serde_json::from_value(arguments).map_err(Error::from)
+}
+
+/// Ids are strings in GraphQL, numbers are accepted as well.
+fn parse_id(id: &JsonValue) -> Result<i64, Error> {
+  let parsed = match *id {
+    JsonValue::Number(ref id) => id.as_i64(),
+    JsonValue::String(ref id) => id.parse().ok(),
+    _ => None,
+  };
+
+  parsed.ok_or_else(|| Error::Validation(format!("invalid id {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_check_queries_against_schema_and_limits() {
+    let prepare = |query: &str| {
+      parser::parse(query)
+        .and_then(|it| Prepared::new(it, None, Map::new(), &QUERY, &MUTATION, &LIMITS))
+        .map(|it| it.fields.len())
+    };
+
+    assert_that(&prepare("{ todos { edges { cursor node { id text } } } }")).is_ok();
+    assert_that(&prepare(
+      "query { todo(id: 1) { ...todo } } fragment todo on Todo { id }",
+    ))
+    .is_ok_containing(1);
+    assert_that(&prepare(
+      "{ todo(id: 1) { id @skip(if: true) } todos { nodes { id } } }",
+    ))
+    .is_ok_containing(2);
+
+    assert_that(&prepare("{ todo(id: 1) { foo } }")).is_err();
+    assert_that(&prepare("{ todo(key: 1) { id } }")).is_err();
+    assert_that(&prepare("{ todo(id: 1) }")).is_err();
+    assert_that(&prepare("{ __schema { types { name } } }")).is_err();
+    assert_that(&prepare("subscription { todos { nodes { id } } }")).is_err();
+    assert_that(&prepare(
+      "{ todos(first: 100) { nodes { id text done createdAt } } }",
+    ))
+    .is_err_containing("query's complexity 412 is more than 300".to_string());
+  }
+
+  #[test]
+  fn should_reject_fragments_which_multiply() {
+    let prepare = |query: &str| {
+      parser::parse(query)
+        .and_then(|it| Prepared::new(it, None, Map::new(), &QUERY, &MUTATION, &LIMITS))
+        .map(|it| it.fields.len())
+    };
+
+    assert_that(&prepare(
+      "{ todo(id: 1) { ...a } } fragment a on Todo { id ...b } fragment b on Todo { ...a }",
+    ))
+    .is_err_containing("fragment a spreads itself".to_string());
+    assert_that(&prepare(
+      "{ todo(id: 1) { ...a ... on Todo { ...a } } } fragment a on Todo { id }",
+    ))
+    .is_err_containing("fragment a is spread twice in a selection".to_string());
+    assert_that(&prepare("{ todo(id: 1) { ...a } }")).is_err();
+
+    // both fragments of a level spread both of the next one, every level doubles the query
+    let mut query = "{ todo(id: 1) { ...f0 } }".to_string();
+    for i in 0..30 {
+      for name in &["f", "g"] {
+        query.push_str(&format!(
+          " fragment {}{} on Todo {{ id ...f{next} ...g{next} }}",
+          name,
+          i,
+          next = i + 1
+        ));
+      }
+    }
+    query.push_str(" fragment f30 on Todo { id } fragment g30 on Todo { id }");
+    assert_that(&prepare(&query))
+      .is_err_containing("query selects more than 500 fields and fragments".to_string());
+  }
+
+  #[test]
+  fn should_build_connections() {
+    let page = Paginated {
+      next: Some(1),
+      items: Vec::new(),
+    };
+    let connection = connection(&page, 10);
+
+    assert_that(&connection["page_info"]["has_next_page"]).is_equal_to(&json!(false));
+    assert_that(&connection["page_info"]["end_cursor"]).is_equal_to(&json!("1"));
+  }
+
+  #[test]
+  fn should_parse_ids() {
+    assert_that(&parse_id(&json!("42")).ok()).is_equal_to(Some(42));
+    assert_that(&parse_id(&json!(42)).ok()).is_equal_to(Some(42));
+    assert_that(&parse_id(&json!("foo"))).is_err();
+  }
+}
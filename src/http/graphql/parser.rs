@@ -0,0 +1,525 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::ast::*;
+
+/// Deeper documents are rejected before the parser's stack runs out.
+const MAX_NESTING: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Punct(char),
+  Spread,
+  Name(String),
+  Int(i64),
+  Float(f64),
+  Str(String),
+  End,
+}
+
+/// Splits a document into tokens, ignored tokens like commas and comments are skipped.
+struct Lexer<'a> {
+  chars: Peekable<Chars<'a>>,
+  line: usize,
+}
+
+impl<'a> Lexer<'a> {
+  fn new(source: &'a str) -> Self {
+    Lexer {
+      chars: source.chars().peekable(),
+      line: 1,
+    }
+  }
+
+  fn next_token(&mut self) -> Result<Token, String> {
+    self.skip_ignored();
+
+    let c = match self.chars.next() {
+      Some(c) => c,
+      None => return Ok(Token::End),
+    };
+
+    match c {
+      '!' | '$' | '(' | ')' | ':' | '=' | '@' | '[' | ']' | '{' | '|' | '}' => Ok(Token::Punct(c)),
+      '.' => {
+        if self.chars.next() == Some('.') && self.chars.next() == Some('.') {
+          Ok(Token::Spread)
+        } else {
+          Err(self.error("expected `...`"))
+        }
+      }
+      '"' => self.string(),
+      c if c == '-' || c.is_ascii_digit() => self.number(c),
+      c if c == '_' || c.is_ascii_alphabetic() => {
+        let mut name = c.to_string();
+        while let Some(&c) = self.chars.peek() {
+          if c == '_' || c.is_ascii_alphanumeric() {
+            name.push(c);
+            self.chars.next();
+          } else {
+            break;
+          }
+        }
+        Ok(Token::Name(name))
+      }
+      c => Err(self.error(&format!("unexpected character {:?}", c))),
+    }
+  }
+
+  fn skip_ignored(&mut self) {
+    while let Some(&c) = self.chars.peek() {
+      match c {
+        '\n' => {
+          self.line += 1;
+          self.chars.next();
+        }
+        ' ' | '\t' | '\r' | ',' | '\u{feff}' => {
+          self.chars.next();
+        }
+        '#' => {
+          while let Some(&c) = self.chars.peek() {
+            if c == '\n' {
+              break;
+            }
+            self.chars.next();
+          }
+        }
+        _ => break,
+      }
+    }
+  }
+
+  fn string(&mut self) -> Result<Token, String> {
+    let mut value = String::new();
+
+    loop {
+      match self.chars.next() {
+        Some('"') => return Ok(Token::Str(value)),
+        Some('\\') => match self.chars.next() {
+          Some('"') => value.push('"'),
+          Some('\\') => value.push('\\'),
+          Some('/') => value.push('/'),
+          Some('b') => value.push('\u{8}'),
+          Some('f') => value.push('\u{c}'),
+          Some('n') => value.push('\n'),
+          Some('r') => value.push('\r'),
+          Some('t') => value.push('\t'),
+          Some('u') => {
+            let code: String = self.chars.by_ref().take(4).collect();
+            let c = u32::from_str_radix(&code, 16)
+              .ok()
+              .and_then(::std::char::from_u32)
+              .ok_or_else(|| self.error("invalid unicode escape"))?;
+            value.push(c);
+          }
+          _ => return Err(self.error("invalid escape sequence")),
+        },
+        Some('\n') | None => return Err(self.error("unterminated string")),
+        Some(c) => value.push(c),
+      }
+    }
+  }
+
+  fn number(&mut self, first: char) -> Result<Token, String> {
+    let mut number = first.to_string();
+    let mut float = false;
+
+    while let Some(&c) = self.chars.peek() {
+      match c {
+        '.' | 'e' | 'E' | '+' | '-' => float = true,
+        c if c.is_ascii_digit() => {}
+        _ => break,
+      }
+      number.push(c);
+      self.chars.next();
+    }
+
+    let token = if float {
+      number.parse().ok().map(Token::Float)
+    } else {
+      number.parse().ok().map(Token::Int)
+    };
+    token.ok_or_else(|| self.error(&format!("invalid number {}", number)))
+  }
+
+  fn error(&self, message: &str) -> String {
+    format!("syntax error at line {}: {}", self.line, message)
+  }
+}
+
+/// A recursive descent parser of executable documents.
+struct Parser<'a> {
+  lexer: Lexer<'a>,
+  token: Token,
+  nesting: usize,
+}
+
+/// Parse a document with operations and fragments, type system definitions aren't supported.
+pub fn parse(source: &str) -> Result<Document, String> {
+  let mut lexer = Lexer::new(source);
+  let token = lexer.next_token()?;
+  let mut parser = Parser {
+    lexer,
+    token,
+    nesting: 0,
+  };
+
+  parser.document()
+}
+
+impl<'a> Parser<'a> {
+  fn document(&mut self) -> Result<Document, String> {
+    let mut document = Document {
+      operations: Vec::new(),
+      fragments: HashMap::new(),
+    };
+
+    while self.token != Token::End {
+      if self.token == Token::Punct('{') {
+        let selection_set = self.selection_set()?;
+        document.operations.push(Operation {
+          kind: OperationKind::Query,
+          name: None,
+          variables: Vec::new(),
+          selection_set,
+        });
+        continue;
+      }
+
+      match self.name()?.as_str() {
+        "query" => document
+          .operations
+          .push(self.operation(OperationKind::Query)?),
+        "mutation" => document
+          .operations
+          .push(self.operation(OperationKind::Mutation)?),
+        "subscription" => document
+          .operations
+          .push(self.operation(OperationKind::Subscription)?),
+        "fragment" => {
+          let name = self.name()?;
+          if self.name()? != "on" {
+            return Err(self.error("expected `on`"));
+          }
+          let type_condition = self.name()?;
+          self.directives()?;
+          let selection_set = self.selection_set()?;
+          let fragment = Fragment {
+            type_condition,
+            selection_set,
+          };
+          if document.fragments.insert(name.clone(), fragment).is_some() {
+            return Err(format!("fragment {} is defined twice", name));
+          }
+        }
+        name => return Err(self.error(&format!("unexpected definition {}", name))),
+      }
+    }
+
+    if document.operations.is_empty() {
+      return Err("document doesn't have operations".to_string());
+    }
+
+    Ok(document)
+  }
+
+  fn operation(&mut self, kind: OperationKind) -> Result<Operation, String> {
+    let name = match self.token {
+      Token::Name(_) => Some(self.name()?),
+      _ => None,
+    };
+
+    let mut variables = Vec::new();
+    if self.skip(Token::Punct('('))? {
+      while !self.skip(Token::Punct(')'))? {
+        self.expect(Token::Punct('$'))?;
+        let name = self.name()?;
+        self.expect(Token::Punct(':'))?;
+        let required = self.type_reference()?;
+        let default = if self.skip(Token::Punct('='))? {
+          Some(self.value(true)?)
+        } else {
+          None
+        };
+        variables.push(VariableDefinition {
+          name,
+          required,
+          default,
+        });
+      }
+    }
+
+    self.directives()?;
+    let selection_set = self.selection_set()?;
+
+    Ok(Operation {
+      kind,
+      name,
+      variables,
+      selection_set,
+    })
+  }
+
+  /// Skip a type, returns whether it's non null.
+  fn type_reference(&mut self) -> Result<bool, String> {
+    if self.skip(Token::Punct('['))? {
+      self.nest()?;
+      self.type_reference()?;
+      self.expect(Token::Punct(']'))?;
+      self.nesting -= 1;
+    } else {
+      self.name()?;
+    }
+
+    self.skip(Token::Punct('!'))
+  }
+
+  fn selection_set(&mut self) -> Result<Vec<Selection>, String> {
+    self.expect(Token::Punct('{'))?;
+    self.nesting += 1;
+    if self.nesting > MAX_NESTING {
+      return Err(self.error("document is nested too deep"));
+    }
+
+    let mut selections = Vec::new();
+    while !self.skip(Token::Punct('}'))? {
+      selections.push(self.selection()?);
+    }
+
+    self.nesting -= 1;
+    if selections.is_empty() {
+      return Err(self.error("selection set is empty"));
+    }
+    Ok(selections)
+  }
+
+  fn selection(&mut self) -> Result<Selection, String> {
+    if self.skip(Token::Spread)? {
+      let is_spread = match self.token {
+        Token::Name(ref name) => name != "on",
+        _ => false,
+      };
+
+      if is_spread {
+        let name = self.name()?;
+        let directives = self.directives()?;
+        return Ok(Selection::FragmentSpread { name, directives });
+      }
+
+      let type_condition = match self.token {
+        Token::Name(_) => {
+          self.name()?;
+          Some(self.name()?)
+        }
+        _ => None,
+      };
+      let directives = self.directives()?;
+      let selection_set = self.selection_set()?;
+      return Ok(Selection::InlineFragment {
+        type_condition,
+        directives,
+        selection_set,
+      });
+    }
+
+    let mut name = self.name()?;
+    let mut alias = None;
+    if self.skip(Token::Punct(':'))? {
+      alias = Some(name);
+      name = self.name()?;
+    }
+
+    let arguments = self.arguments(false)?;
+    let directives = self.directives()?;
+    let selection_set = if self.token == Token::Punct('{') {
+      self.selection_set()?
+    } else {
+      Vec::new()
+    };
+
+    Ok(Selection::Field(Field {
+      alias,
+      name,
+      arguments,
+      directives,
+      selection_set,
+    }))
+  }
+
+  fn arguments(&mut self, constant: bool) -> Result<Vec<(String, Value)>, String> {
+    let mut arguments = Vec::new();
+
+    if self.skip(Token::Punct('('))? {
+      while !self.skip(Token::Punct(')'))? {
+        let name = self.name()?;
+        self.expect(Token::Punct(':'))?;
+        arguments.push((name, self.value(constant)?));
+      }
+    }
+
+    Ok(arguments)
+  }
+
+  fn directives(&mut self) -> Result<Vec<Directive>, String> {
+    let mut directives = Vec::new();
+
+    while self.skip(Token::Punct('@'))? {
+      let name = self.name()?;
+      let arguments = self.arguments(false)?;
+      directives.push(Directive { name, arguments });
+    }
+
+    Ok(directives)
+  }
+
+  /// Parse a value, `constant` ones like defaults cannot have variables.
+  fn value(&mut self, constant: bool) -> Result<Value, String> {
+    let token = self.advance()?;
+
+    let value = match token {
+      Token::Punct('$') if !constant => Value::Variable(self.name()?),
+      Token::Int(value) => Value::Int(value),
+      Token::Float(value) => Value::Float(value),
+      Token::Str(value) => Value::String(value),
+      Token::Name(name) => match name.as_str() {
+        "true" => Value::Boolean(true),
+        "false" => Value::Boolean(false),
+        "null" => Value::Null,
+        _ => Value::Enum(name),
+      },
+      Token::Punct('[') => {
+        self.nest()?;
+        let mut items = Vec::new();
+        while !self.skip(Token::Punct(']'))? {
+          items.push(self.value(constant)?);
+        }
+        self.nesting -= 1;
+        Value::List(items)
+      }
+      Token::Punct('{') => {
+        self.nest()?;
+        let mut fields = Vec::new();
+        while !self.skip(Token::Punct('}'))? {
+          let name = self.name()?;
+          self.expect(Token::Punct(':'))?;
+          fields.push((name, self.value(constant)?));
+        }
+        self.nesting -= 1;
+        Value::Object(fields)
+      }
+      token => return Err(self.error(&format!("unexpected {:?}", token))),
+    };
+
+    Ok(value)
+  }
+
+  fn nest(&mut self) -> Result<(), String> {
+    self.nesting += 1;
+    if self.nesting > MAX_NESTING {
+      return Err(self.error("document is nested too deep"));
+    }
+    Ok(())
+  }
+
+  fn name(&mut self) -> Result<String, String> {
+    match self.advance()? {
+      Token::Name(name) => Ok(name),
+      token => Err(self.error(&format!("expected a name, got {:?}", token))),
+    }
+  }
+
+  fn expect(&mut self, token: Token) -> Result<(), String> {
+    if self.skip(token.clone())? {
+      Ok(())
+    } else {
+      Err(self.error(&format!("expected {:?}, got {:?}", token, self.token)))
+    }
+  }
+
+  /// Take the current token if it's the same as `token`.
+  fn skip(&mut self, token: Token) -> Result<bool, String> {
+    if self.token != token {
+      return Ok(false);
+    }
+
+    self.token = self.lexer.next_token()?;
+    Ok(true)
+  }
+
+  /// Take the current token.
+  fn advance(&mut self) -> Result<Token, String> {
+    if self.token == Token::End {
+      return Err(self.error("unexpected end of document"));
+    }
+
+    let next = self.lexer.next_token()?;
+    Ok(::std::mem::replace(&mut self.token, next))
+  }
+
+  fn error(&self, message: &str) -> String {
+    self.lexer.error(message)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_parse_operations() {
+    let document = parse(
+      r#"
+      # a comment
+      query Todos($first: Int = 5, $text: String!) {
+        recent: todos(first: $first, text: $text) {
+          edges { cursor node { ...todo } }
+          pageInfo { hasNextPage @include(if: true) }
+        }
+      }
+
+      fragment todo on Todo { id text, done }
+
+      mutation { createTodo(text: "buy \"milk\"\n") { ... on Todo { id } } }
+      "#,
+    )
+    .unwrap();
+
+    assert_that(&document.operations).has_length(2);
+    assert_that(&document.fragments.contains_key("todo")).is_true();
+
+    let query = &document.operations[0];
+    assert_that(&query.name).is_equal_to(Some("Todos".to_string()));
+    assert_that(&query.variables[0].default).is_equal_to(Some(Value::Int(5)));
+    assert_that(&query.variables[1].required).is_true();
+
+    let field = match query.selection_set[0] {
+      Selection::Field(ref field) => field.clone(),
+      ref it => panic!("unexpected selection {:?}", it),
+    };
+    assert_that(&field.response_key()).is_equal_to("recent");
+    assert_that(&field.arguments[0])
+      .is_equal_to(("first".to_string(), Value::Variable("first".to_string())));
+
+    let mutation = &document.operations[1];
+    assert_that(&mutation.kind).is_equal_to(OperationKind::Mutation);
+    match mutation.selection_set[0] {
+      Selection::Field(ref field) => {
+        assert_that(&field.arguments[0].1).is_equal_to(Value::String("buy \"milk\"\n".to_string()))
+      }
+      ref it => panic!("unexpected selection {:?}", it),
+    }
+  }
+
+  #[test]
+  fn should_reject_invalid_documents() {
+    assert_that(&parse("{ todos { }")).is_err();
+    assert_that(&parse("query { todo(id: ) { id } }")).is_err();
+    assert_that(&parse("fragment todo on Todo { id }")).is_err();
+    assert_that(&parse("type Todo { id: ID }")).is_err();
+
+    let deep = format!("{}{}", "{ a ".repeat(100), "}".repeat(100));
+    assert_that(&parse(&deep)).is_err();
+  }
+}
@@ -0,0 +1,71 @@
+use futures::{future, stream, Future, Stream};
+use hyper::{Request, Response};
+use serde_json::{self, Value as JsonValue};
+use std::rc::Rc;
+
+use result::Error;
+use db::TodosRepo;
+use common::{FuturesExt, RequestExt, ResponseExt};
+
+use super::graphql::{GraphQLRequest, Schema};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// How many operations a batch can have.
+const MAX_BATCH: usize = 10;
+
+pub struct GraphQLController {
+  schema: Rc<Schema>,
+}
+
+impl GraphQLController {
+  pub fn new(todos_repo: TodosRepo) -> Self {
+    GraphQLController {
+      schema: Rc::new(Schema::new(todos_repo)),
+    }
+  }
+
+  /// Execute a request or a batch of them, operations of a batch run one after another.
+  pub fn call_graphql(&self, req: Request) -> BoxFuture<Response> {
+    let schema = self.schema.clone();
+
+    req
+      .json::<JsonValue>()
+      .and_then(move |body| -> BoxFuture<JsonValue> {
+        match body {
+          JsonValue::Array(requests) => {
+            if requests.len() > MAX_BATCH {
+              let err = Error::Validation(format!(
+                "a batch cannot have more than {} operations",
+                MAX_BATCH
+              ));
+              return future::err(err).into_boxed();
+            }
+
+            let requests = match parse_requests(requests) {
+              Ok(requests) => requests,
+              Err(err) => return future::err(err).into_boxed(),
+            };
+            stream::iter_ok(requests)
+              .and_then(move |it| schema.execute(it))
+              .collect()
+              .map(JsonValue::Array)
+              .into_boxed()
+          }
+          body => match serde_json::from_value(body) {
+            Ok(request) => schema.execute(request),
+            Err(err) => future::err(Error::from(err)).into_boxed(),
+          },
+        }
+      })
+      .map(|it| Response::new().json(&it))
+      .into_boxed()
+  }
+}
+
+fn parse_requests(requests: Vec<JsonValue>) -> Result<Vec<GraphQLRequest>, Error> {
+  requests
+    .into_iter()
+    .map(|it| serde_json::from_value(it).map_err(Error::from))
+    .collect()
+}
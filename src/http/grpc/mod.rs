@@ -0,0 +1,435 @@
+mod todos;
+mod todos_grpc;
+
+use chrono::NaiveDateTime;
+use futures::{Future, IntoFuture, Stream};
+use futures_cpupool::CpuPool;
+use grpc::{self, GrpcMessageError, GrpcStatus, RequestOptions, SingleResponse, StreamingResponse};
+use protobuf::RepeatedField;
+use protobuf::well_known_types::Timestamp;
+use serde_json;
+
+use db::{self, Merged, NewTodo, Paginated, QueryTodos, TodosRepo, UpdateTodo};
+use result::Error;
+use validators::Validator;
+
+use super::event_bus::{EventBus, Message};
+use super::rate_limiter::{ip_key, RateLimiter};
+
+use self::todos::{CreateTodoRequest, GetTodoRequest, QueryTodosRequest, QueryTodosResponse, Todo,
+                  TodoChange, UpdateTodoRequest, UpdateTodoResponse, WatchTodosRequest};
+use self::todos_grpc::{TodoService, TodoServiceServer};
+
+/// Serves `proto/todos.proto`'s `TodoService` with the same repository and validators as
+/// `TodosController`.
+///
+/// `todos.rs` and `todos_grpc.rs` are generated from the proto file by rust-protobuf 1.6 and
+/// grpc-compiler 0.4, they are regenerated rather than edited.
+///
+/// Writes are rate limited like http ones, but grpc 0.4 doesn't give a peer's address, so all
+/// grpc clients share the bucket of an unknown address.
+#[derive(Clone)]
+pub struct GrpcTodos {
+  todos_repo: TodosRepo,
+  event_bus: EventBus,
+  cpu_pool: CpuPool,
+  rate_limiter: RateLimiter,
+}
+
+/// A watched change of a todo item, the outbox's event payload.
+#[derive(Deserialize)]
+struct Payload {
+  todo: db::Todo,
+}
+
+impl GrpcTodos {
+  pub fn new(
+    todos_repo: TodosRepo,
+    event_bus: EventBus,
+    cpu_pool: CpuPool,
+    rate_limiter: RateLimiter,
+  ) -> Self {
+    GrpcTodos {
+      todos_repo,
+      event_bus,
+      cpu_pool,
+      rate_limiter,
+    }
+  }
+
+  /// Start serving on `port` in a separate thread, the server stops when it's dropped.
+  pub fn listen(&self, port: u16) -> grpc::Server {
+    let mut builder = grpc::ServerBuilder::new_plain();
+    builder
+      .http
+      .set_addr(("0.0.0.0", port))
+      .expect("cannot parse grpc address");
+    builder.add_service(TodoServiceServer::new_service_def(self.clone()));
+
+    let server = builder.build().expect("cannot start grpc server");
+    info!("listen on grpc://{}", server.local_addr());
+    server
+  }
+}
+
+impl TodoService for GrpcTodos {
+  fn create(&self, _: RequestOptions, req: CreateTodoRequest) -> SingleResponse<Todo> {
+    let repo = self.todos_repo.clone();
+    let new_todo = NewTodo {
+      text: req.get_text().to_string(),
+    };
+
+    let todo = self
+      .rate_limiter
+      .limit_write(&ip_key(None))
+      .and_then(|_| new_todo.validated())
+      .into_future()
+      .and_then(move |it| repo.insert(it))
+      .inspect(|it| info!("created {:?}", it))
+      .map(|it| todo_message(&it))
+      .map_err(grpc_error);
+    SingleResponse::no_metadata(todo)
+  }
+
+  fn update(&self, _: RequestOptions, req: UpdateTodoRequest) -> SingleResponse<UpdateTodoResponse> {
+    let repo = self.todos_repo.clone();
+
+    let merged = self
+      .rate_limiter
+      .limit_write(&ip_key(None))
+      .and_then(|_| update_todo(&req))
+      .and_then(|it| it.validated())
+      .into_future()
+      .and_then(move |it| repo.update(it))
+      .inspect(|it| info!("updated {:?}", it))
+      .map(|it| merged_message(&it))
+      .map_err(grpc_error);
+    SingleResponse::no_metadata(merged)
+  }
+
+  fn get(&self, _: RequestOptions, req: GetTodoRequest) -> SingleResponse<Todo> {
+    let todo = self
+      .todos_repo
+      .find(req.get_id())
+      .map(|it| todo_message(&it))
+      .map_err(grpc_error);
+    SingleResponse::no_metadata(todo)
+  }
+
+  fn query(&self, _: RequestOptions, req: QueryTodosRequest) -> SingleResponse<QueryTodosResponse> {
+    let repo = self.todos_repo.clone();
+
+    let page = query_todos(&req)
+      .and_then(|it| it.validated())
+      .into_future()
+      .and_then(move |it| repo.query(it))
+      .map(|it| page_message(&it))
+      .map_err(grpc_error);
+    SingleResponse::no_metadata(page)
+  }
+
+  /// Changes after the call or after `last_event_id` when it isn't zero.
+  ///
+  /// A watch which missed events or fell behind ends with `ABORTED`, a client reloads items and
+  /// watches again. Heartbeats aren't sent, HTTP/2 keeps the connection alive.
+  fn watch(&self, _: RequestOptions, req: WatchTodosRequest) -> StreamingResponse<TodoChange> {
+    let last_event_id = match req.get_last_event_id() {
+      0 => None,
+      id => Some(id),
+    };
+    let event_bus = self.event_bus.clone();
+
+    let changes = self
+      .cpu_pool
+      .spawn_fn(move || event_bus.subscribe(last_event_id))
+      .map_err(grpc_error)
+      .map(|messages| {
+        messages
+          .map_err(|_| grpc::Error::Other("event bus is closed"))
+          .and_then(|it| change_message(&it))
+          .filter_map(|it| it)
+      })
+      .flatten_stream()
+      // the bus ends a lagging subscriber's stream
+      .chain(Err(missed_events()).into_future().into_stream());
+    StreamingResponse::no_metadata(changes)
+  }
+}
+
+/// A status which an api error is reported with.
+pub fn grpc_status(err: &Error) -> GrpcStatus {
+  match *err {
+    Error::Validation(_) | Error::JsonParse(_) => GrpcStatus::Argument,
+    Error::RecordNotFound => GrpcStatus::NotFound,
+    Error::PreconditionFailed => GrpcStatus::FailedPrecondition,
+    Error::MySqlConnection(_) => GrpcStatus::Unavailable,
+    Error::TooManyRequests(_) => GrpcStatus::ResourceExhausted,
+    _ => GrpcStatus::Internal,
+  }
+}
+
+fn grpc_error(err: Error) -> grpc::Error {
+  warn!("grpc call failed with {}", err);
+
+  grpc::Error::GrpcMessage(GrpcMessageError {
+    grpc_status: grpc_status(&err) as i32,
+    grpc_message: err.to_string(),
+  })
+}
+
+fn missed_events() -> grpc::Error {
+  grpc::Error::GrpcMessage(GrpcMessageError {
+    grpc_status: GrpcStatus::Aborted as i32,
+    grpc_message: "events were missed, reload items and watch again".to_string(),
+  })
+}
+
+fn update_todo(req: &UpdateTodoRequest) -> Result<UpdateTodo, Error> {
+  let changed_at = if req.has_changed_at() {
+    Some(naive_time(req.get_changed_at())?)
+  } else {
+    None
+  };
+
+  Ok(UpdateTodo {
+    id: req.get_id(),
+    text: if req.has_text() {
+      Some(req.get_text().get_value().to_string())
+    } else {
+      None
+    },
+    done: if req.has_done() {
+      Some(req.get_done().get_value())
+    } else {
+      None
+    },
+    changed_at,
+  })
+}
+
+fn query_todos(req: &QueryTodosRequest) -> Result<QueryTodos, Error> {
+  let limit = match req.get_limit() {
+    0 => None,
+    limit if limit > u32::from(u8::max_value()) => {
+      return Err(Error::Validation(format!("limit is too large, got {}", limit)))
+    }
+    limit => Some(limit as u8),
+  };
+
+  Ok(QueryTodos {
+    next: match req.get_next() {
+      0 => None,
+      next => Some(next),
+    },
+    limit,
+    text: match req.get_text() {
+      "" => None,
+      text => Some(text.to_string()),
+    },
+  })
+}
+
+fn todo_message(todo: &db::Todo) -> Todo {
+  let mut message = Todo::new();
+  message.set_id(todo.id);
+  message.set_text(todo.text.clone());
+  message.set_done(todo.done);
+  message.set_created_at(timestamp(&todo.created_at));
+  message.set_updated_at(timestamp(&todo.updated_at));
+  if let Some(ref due_at) = todo.due_at {
+    message.set_due_at(timestamp(due_at));
+  }
+  message
+}
+
+fn merged_message(merged: &Merged) -> UpdateTodoResponse {
+  let fields = |it: &[&str]| RepeatedField::from_vec(it.iter().map(|it| it.to_string()).collect());
+
+  let mut message = UpdateTodoResponse::new();
+  message.set_todo(todo_message(&merged.todo));
+  message.set_accepted(fields(&merged.accepted));
+  message.set_overridden(fields(&merged.overridden));
+  message
+}
+
+fn page_message(page: &Paginated<db::Todo>) -> QueryTodosResponse {
+  let mut message = QueryTodosResponse::new();
+  message.set_items(page.items.iter().map(todo_message).collect());
+  message.set_next(page.next.unwrap_or(0));
+  message
+}
+
+/// A change for a bus message, `None` for heartbeats.
+fn change_message(message: &Message) -> Result<Option<TodoChange>, grpc::Error> {
+  match *message {
+    Message::Event(ref event) => {
+      let payload = serde_json::from_str::<Payload>(&event.payload)
+        .map_err(|_| grpc::Error::Other("cannot parse a todo event"))?;
+
+      let mut change = TodoChange::new();
+      change.set_event_id(event.id);
+      change.set_event(event.event.clone());
+      change.set_todo(todo_message(&payload.todo));
+      Ok(Some(change))
+    }
+    Message::Reset => Err(missed_events()),
+    Message::Heartbeat => Ok(None),
+  }
+}
+
+fn timestamp(time: &NaiveDateTime) -> Timestamp {
+  let mut timestamp = Timestamp::new();
+  timestamp.set_seconds(time.timestamp());
+  timestamp.set_nanos(time.timestamp_subsec_nanos() as i32);
+  timestamp
+}
+
+fn naive_time(timestamp: &Timestamp) -> Result<NaiveDateTime, Error> {
+  let nanos = timestamp.get_nanos();
+  let time = if nanos >= 0 {
+    NaiveDateTime::from_timestamp_opt(timestamp.get_seconds(), nanos as u32)
+  } else {
+    None
+  };
+  time.ok_or_else(|| Error::Validation("changed_at is out of range".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use chrono::Utc;
+  use protobuf::well_known_types::{BoolValue, StringValue};
+  use std::sync::Arc;
+
+  use config::Config;
+  use db::{self, StoredEvent};
+  use metrics::Metrics;
+
+  use super::todos_grpc::TodoServiceClient;
+
+  #[test]
+  fn should_map_errors_to_statuses() {
+    let status = |err: Error| grpc_status(&err) as i32;
+
+    assert_that(&status(Error::Validation("text is empty".to_string())))
+      .is_equal_to(GrpcStatus::Argument as i32);
+    assert_that(&status(Error::RecordNotFound)).is_equal_to(GrpcStatus::NotFound as i32);
+    assert_that(&status(Error::PreconditionFailed))
+      .is_equal_to(GrpcStatus::FailedPrecondition as i32);
+    assert_that(&status(Error::TooManyRequests(1)))
+      .is_equal_to(GrpcStatus::ResourceExhausted as i32);
+    assert_that(&status(Error::Unauthorized)).is_equal_to(GrpcStatus::Internal as i32);
+
+    match grpc_error(Error::Validation("text is empty".to_string())) {
+      grpc::Error::GrpcMessage(err) => {
+        assert_that(&err.grpc_status).is_equal_to(GrpcStatus::Argument as i32);
+        assert_that(&err.grpc_message.contains("text is empty")).is_true();
+      }
+      err => panic!("unexpected error {:?}", err),
+    }
+  }
+
+  #[test]
+  fn should_convert_requests() {
+    let mut req = UpdateTodoRequest::new();
+    req.set_id(1);
+    let mut done = BoolValue::new();
+    done.set_value(true);
+    req.set_done(done);
+    let changed_at = NaiveDateTime::from_timestamp(1_500_000_000, 5);
+    req.set_changed_at(timestamp(&changed_at));
+
+    let update = update_todo(&req).unwrap();
+    assert_that(&update.text).is_none();
+    assert_that(&update.done).is_equal_to(Some(true));
+    assert_that(&update.changed_at).is_equal_to(Some(changed_at));
+
+    let mut text = StringValue::new();
+    text.set_value(String::new());
+    req.set_text(text);
+    assert_that(&update_todo(&req).unwrap().validated()).is_err();
+
+    let mut req = QueryTodosRequest::new();
+    req.set_limit(5);
+    let query = query_todos(&req).unwrap();
+    assert_that(&query.next).is_none();
+    assert_that(&query.limit).is_equal_to(Some(5));
+    assert_that(&query.text).is_none();
+
+    req.set_limit(1000);
+    assert_that(&query_todos(&req)).is_err();
+  }
+
+  #[test]
+  fn should_convert_events() {
+    let now = Utc::now().naive_utc();
+    let event = StoredEvent {
+      id: 7,
+      event: "todo.completed".to_string(),
+      payload: json!({
+        "event": "todo.completed",
+        "todo": {"id": 1, "text": "foo", "done": true, "created_at": now, "updated_at": now},
+      }).to_string(),
+      created_at: now,
+    };
+
+    let change = change_message(&Message::Event(Arc::new(event)))
+      .unwrap()
+      .unwrap();
+    assert_that(&change.get_event_id()).is_equal_to(7);
+    assert_that(&change.get_event()).is_equal_to("todo.completed");
+    assert_that(&change.get_todo().get_done()).is_true();
+    assert_that(&change.get_todo().has_due_at()).is_false();
+
+    assert_that(&change_message(&Message::Heartbeat).unwrap()).is_none();
+    assert_that(&change_message(&Message::Reset)).is_err();
+  }
+
+  #[test]
+  fn should_serve_todos() {
+    let cfg = Config::default();
+    let conn_pool = db::connection_pool(&cfg.database_url, 1);
+    let cpu_pool = cfg.create_cpu_pool();
+    let todos_repo = TodosRepo::new(conn_pool.clone(), cpu_pool.clone(), Metrics::new());
+    let rate_limiter = RateLimiter::new(&cfg);
+    let server = GrpcTodos::new(todos_repo, EventBus::new(conn_pool), cpu_pool, rate_limiter);
+    let server = server.listen(0);
+    let port = server.local_addr().port().unwrap();
+    let client = TodoServiceClient::new_plain("127.0.0.1", port, Default::default()).unwrap();
+
+    let mut req = CreateTodoRequest::new();
+    req.set_text("foo".to_string());
+    let created = client
+      .create(RequestOptions::new(), req)
+      .wait_drop_metadata()
+      .unwrap();
+    assert_that(&created.get_text()).is_equal_to("foo");
+
+    let mut req = GetTodoRequest::new();
+    req.set_id(created.get_id());
+    let found = client
+      .get(RequestOptions::new(), req)
+      .wait_drop_metadata()
+      .unwrap();
+    assert_that(&found).is_equal_to(&created);
+
+    let mut req = GetTodoRequest::new();
+    req.set_id(i64::max_value());
+    match client.get(RequestOptions::new(), req).wait_drop_metadata() {
+      Err(grpc::Error::GrpcMessage(err)) => {
+        assert_that(&err.grpc_status).is_equal_to(GrpcStatus::NotFound as i32)
+      }
+      result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+
+    let mut req = CreateTodoRequest::new();
+    req.set_text(String::new());
+    match client.create(RequestOptions::new(), req).wait_drop_metadata() {
+      Err(grpc::Error::GrpcMessage(err)) => {
+        assert_that(&err.grpc_status).is_equal_to(GrpcStatus::Argument as i32)
+      }
+      result => panic!("unexpected result {:?}", result.map(|_| ())),
+    }
+  }
+}
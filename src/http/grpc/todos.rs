@@ -0,0 +1,2346 @@
+// This file is generated by rust-protobuf 1.6.0. Do not edit
+// @generated
+
+// https://github.com/Manishearth/rust-clippy/issues/702
+#![allow(unknown_lints)]
+#![allow(clippy)]
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+
+#![allow(box_pointers)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(trivial_casts)]
+#![allow(unsafe_code)]
+#![allow(unused_imports)]
+#![allow(unused_results)]
+
+use protobuf::Message as Message_imported_for_functions;
+use protobuf::ProtobufEnum as ProtobufEnum_imported_for_functions;
+
+#[derive(PartialEq,Clone,Default)]
+pub struct Todo {
+    // message fields
+    pub id: i64,
+    pub text: ::std::string::String,
+    pub done: bool,
+    pub created_at: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
+    pub updated_at: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
+    pub due_at: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl Todo {
+    pub fn new() -> Todo {
+        ::std::default::Default::default()
+    }
+
+    // int64 id = 1;
+
+    pub fn clear_id(&mut self) {
+        self.id = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_id(&mut self, v: i64) {
+        self.id = v;
+    }
+
+    pub fn get_id(&self) -> i64 {
+        self.id
+    }
+
+    // string text = 2;
+
+    pub fn clear_text(&mut self) {
+        self.text.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_text(&mut self, v: ::std::string::String) {
+        self.text = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_text(&mut self) -> &mut ::std::string::String {
+        &mut self.text
+    }
+
+    // Take field
+    pub fn take_text(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.text, ::std::string::String::new())
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    // bool done = 3;
+
+    pub fn clear_done(&mut self) {
+        self.done = false;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_done(&mut self, v: bool) {
+        self.done = v;
+    }
+
+    pub fn get_done(&self) -> bool {
+        self.done
+    }
+
+    // .google.protobuf.Timestamp created_at = 4;
+
+    pub fn clear_created_at(&mut self) {
+        self.created_at.clear();
+    }
+
+    pub fn has_created_at(&self) -> bool {
+        self.created_at.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_created_at(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.created_at = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_created_at(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.created_at.is_none() {
+            self.created_at.set_default();
+        }
+        self.created_at.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_created_at(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.created_at.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_created_at(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.created_at.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
+
+    // .google.protobuf.Timestamp updated_at = 5;
+
+    pub fn clear_updated_at(&mut self) {
+        self.updated_at.clear();
+    }
+
+    pub fn has_updated_at(&self) -> bool {
+        self.updated_at.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_updated_at(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.updated_at = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_updated_at(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.updated_at.is_none() {
+            self.updated_at.set_default();
+        }
+        self.updated_at.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_updated_at(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.updated_at.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_updated_at(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.updated_at.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
+
+    // .google.protobuf.Timestamp due_at = 6;
+
+    pub fn clear_due_at(&mut self) {
+        self.due_at.clear();
+    }
+
+    pub fn has_due_at(&self) -> bool {
+        self.due_at.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_due_at(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.due_at = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_due_at(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.due_at.is_none() {
+            self.due_at.set_default();
+        }
+        self.due_at.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_due_at(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.due_at.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_due_at(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.due_at.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
+}
+
+impl ::protobuf::Message for Todo {
+    fn is_initialized(&self) -> bool {
+        for v in &self.created_at {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.updated_at {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.due_at {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.id = tmp;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.text)?;
+                },
+                3 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_bool()?;
+                    self.done = tmp;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.created_at)?;
+                },
+                5 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.updated_at)?;
+                },
+                6 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.due_at)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.id != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.id, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.text.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.text);
+        }
+        if self.done != false {
+            my_size += 2;
+        }
+        if let Some(ref v) = self.created_at.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(ref v) = self.updated_at.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(ref v) = self.due_at.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.id != 0 {
+            os.write_int64(1, self.id)?;
+        }
+        if !self.text.is_empty() {
+            os.write_string(2, &self.text)?;
+        }
+        if self.done != false {
+            os.write_bool(3, self.done)?;
+        }
+        if let Some(ref v) = self.created_at.as_ref() {
+            os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(ref v) = self.updated_at.as_ref() {
+            os.write_tag(5, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(ref v) = self.due_at.as_ref() {
+            os.write_tag(6, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> Todo {
+        Todo::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "id",
+                    |m: &Todo| { &m.id },
+                    |m: &mut Todo| { &mut m.id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "text",
+                    |m: &Todo| { &m.text },
+                    |m: &mut Todo| { &mut m.text },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeBool>(
+                    "done",
+                    |m: &Todo| { &m.done },
+                    |m: &mut Todo| { &mut m.done },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "created_at",
+                    |m: &Todo| { &m.created_at },
+                    |m: &mut Todo| { &mut m.created_at },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "updated_at",
+                    |m: &Todo| { &m.updated_at },
+                    |m: &mut Todo| { &mut m.updated_at },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "due_at",
+                    |m: &Todo| { &m.due_at },
+                    |m: &mut Todo| { &mut m.due_at },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<Todo>(
+                    "Todo",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static Todo {
+        static mut instance: ::protobuf::lazy::Lazy<Todo> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const Todo,
+        };
+        unsafe {
+            instance.get(Todo::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for Todo {
+    fn clear(&mut self) {
+        self.clear_id();
+        self.clear_text();
+        self.clear_done();
+        self.clear_created_at();
+        self.clear_updated_at();
+        self.clear_due_at();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for Todo {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for Todo {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct CreateTodoRequest {
+    // message fields
+    pub text: ::std::string::String,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl CreateTodoRequest {
+    pub fn new() -> CreateTodoRequest {
+        ::std::default::Default::default()
+    }
+
+    // string text = 1;
+
+    pub fn clear_text(&mut self) {
+        self.text.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_text(&mut self, v: ::std::string::String) {
+        self.text = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_text(&mut self) -> &mut ::std::string::String {
+        &mut self.text
+    }
+
+    // Take field
+    pub fn take_text(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.text, ::std::string::String::new())
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl ::protobuf::Message for CreateTodoRequest {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.text)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if !self.text.is_empty() {
+            my_size += ::protobuf::rt::string_size(1, &self.text);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if !self.text.is_empty() {
+            os.write_string(1, &self.text)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> CreateTodoRequest {
+        CreateTodoRequest::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "text",
+                    |m: &CreateTodoRequest| { &m.text },
+                    |m: &mut CreateTodoRequest| { &mut m.text },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<CreateTodoRequest>(
+                    "CreateTodoRequest",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static CreateTodoRequest {
+        static mut instance: ::protobuf::lazy::Lazy<CreateTodoRequest> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const CreateTodoRequest,
+        };
+        unsafe {
+            instance.get(CreateTodoRequest::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for CreateTodoRequest {
+    fn clear(&mut self) {
+        self.clear_text();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for CreateTodoRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for CreateTodoRequest {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct UpdateTodoRequest {
+    // message fields
+    pub id: i64,
+    pub text: ::protobuf::SingularPtrField<::protobuf::well_known_types::StringValue>,
+    pub done: ::protobuf::SingularPtrField<::protobuf::well_known_types::BoolValue>,
+    pub changed_at: ::protobuf::SingularPtrField<::protobuf::well_known_types::Timestamp>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl UpdateTodoRequest {
+    pub fn new() -> UpdateTodoRequest {
+        ::std::default::Default::default()
+    }
+
+    // int64 id = 1;
+
+    pub fn clear_id(&mut self) {
+        self.id = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_id(&mut self, v: i64) {
+        self.id = v;
+    }
+
+    pub fn get_id(&self) -> i64 {
+        self.id
+    }
+
+    // .google.protobuf.StringValue text = 2;
+
+    pub fn clear_text(&mut self) {
+        self.text.clear();
+    }
+
+    pub fn has_text(&self) -> bool {
+        self.text.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_text(&mut self, v: ::protobuf::well_known_types::StringValue) {
+        self.text = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_text(&mut self) -> &mut ::protobuf::well_known_types::StringValue {
+        if self.text.is_none() {
+            self.text.set_default();
+        }
+        self.text.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_text(&mut self) -> ::protobuf::well_known_types::StringValue {
+        self.text.take().unwrap_or_else(|| ::protobuf::well_known_types::StringValue::new())
+    }
+
+    pub fn get_text(&self) -> &::protobuf::well_known_types::StringValue {
+        self.text.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::StringValue::default_instance())
+    }
+
+    // .google.protobuf.BoolValue done = 3;
+
+    pub fn clear_done(&mut self) {
+        self.done.clear();
+    }
+
+    pub fn has_done(&self) -> bool {
+        self.done.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_done(&mut self, v: ::protobuf::well_known_types::BoolValue) {
+        self.done = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_done(&mut self) -> &mut ::protobuf::well_known_types::BoolValue {
+        if self.done.is_none() {
+            self.done.set_default();
+        }
+        self.done.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_done(&mut self) -> ::protobuf::well_known_types::BoolValue {
+        self.done.take().unwrap_or_else(|| ::protobuf::well_known_types::BoolValue::new())
+    }
+
+    pub fn get_done(&self) -> &::protobuf::well_known_types::BoolValue {
+        self.done.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::BoolValue::default_instance())
+    }
+
+    // .google.protobuf.Timestamp changed_at = 4;
+
+    pub fn clear_changed_at(&mut self) {
+        self.changed_at.clear();
+    }
+
+    pub fn has_changed_at(&self) -> bool {
+        self.changed_at.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_changed_at(&mut self, v: ::protobuf::well_known_types::Timestamp) {
+        self.changed_at = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_changed_at(&mut self) -> &mut ::protobuf::well_known_types::Timestamp {
+        if self.changed_at.is_none() {
+            self.changed_at.set_default();
+        }
+        self.changed_at.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_changed_at(&mut self) -> ::protobuf::well_known_types::Timestamp {
+        self.changed_at.take().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::new())
+    }
+
+    pub fn get_changed_at(&self) -> &::protobuf::well_known_types::Timestamp {
+        self.changed_at.as_ref().unwrap_or_else(|| ::protobuf::well_known_types::Timestamp::default_instance())
+    }
+}
+
+impl ::protobuf::Message for UpdateTodoRequest {
+    fn is_initialized(&self) -> bool {
+        for v in &self.text {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.done {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        for v in &self.changed_at {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.id = tmp;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.text)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.done)?;
+                },
+                4 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.changed_at)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.id != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.id, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if let Some(ref v) = self.text.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(ref v) = self.done.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        if let Some(ref v) = self.changed_at.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.id != 0 {
+            os.write_int64(1, self.id)?;
+        }
+        if let Some(ref v) = self.text.as_ref() {
+            os.write_tag(2, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(ref v) = self.done.as_ref() {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        if let Some(ref v) = self.changed_at.as_ref() {
+            os.write_tag(4, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> UpdateTodoRequest {
+        UpdateTodoRequest::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "id",
+                    |m: &UpdateTodoRequest| { &m.id },
+                    |m: &mut UpdateTodoRequest| { &mut m.id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::StringValue>>(
+                    "text",
+                    |m: &UpdateTodoRequest| { &m.text },
+                    |m: &mut UpdateTodoRequest| { &mut m.text },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::BoolValue>>(
+                    "done",
+                    |m: &UpdateTodoRequest| { &m.done },
+                    |m: &mut UpdateTodoRequest| { &mut m.done },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<::protobuf::well_known_types::Timestamp>>(
+                    "changed_at",
+                    |m: &UpdateTodoRequest| { &m.changed_at },
+                    |m: &mut UpdateTodoRequest| { &mut m.changed_at },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<UpdateTodoRequest>(
+                    "UpdateTodoRequest",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static UpdateTodoRequest {
+        static mut instance: ::protobuf::lazy::Lazy<UpdateTodoRequest> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const UpdateTodoRequest,
+        };
+        unsafe {
+            instance.get(UpdateTodoRequest::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for UpdateTodoRequest {
+    fn clear(&mut self) {
+        self.clear_id();
+        self.clear_text();
+        self.clear_done();
+        self.clear_changed_at();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for UpdateTodoRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UpdateTodoRequest {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct UpdateTodoResponse {
+    // message fields
+    pub todo: ::protobuf::SingularPtrField<Todo>,
+    pub accepted: ::protobuf::RepeatedField<::std::string::String>,
+    pub overridden: ::protobuf::RepeatedField<::std::string::String>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl UpdateTodoResponse {
+    pub fn new() -> UpdateTodoResponse {
+        ::std::default::Default::default()
+    }
+
+    // .todos.Todo todo = 1;
+
+    pub fn clear_todo(&mut self) {
+        self.todo.clear();
+    }
+
+    pub fn has_todo(&self) -> bool {
+        self.todo.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_todo(&mut self, v: Todo) {
+        self.todo = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_todo(&mut self) -> &mut Todo {
+        if self.todo.is_none() {
+            self.todo.set_default();
+        }
+        self.todo.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_todo(&mut self) -> Todo {
+        self.todo.take().unwrap_or_else(|| Todo::new())
+    }
+
+    pub fn get_todo(&self) -> &Todo {
+        self.todo.as_ref().unwrap_or_else(|| Todo::default_instance())
+    }
+
+    // repeated string accepted = 2;
+
+    pub fn clear_accepted(&mut self) {
+        self.accepted.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_accepted(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.accepted = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_accepted(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.accepted
+    }
+
+    // Take field
+    pub fn take_accepted(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.accepted, ::protobuf::RepeatedField::new())
+    }
+
+    pub fn get_accepted(&self) -> &[::std::string::String] {
+        &self.accepted
+    }
+
+    // repeated string overridden = 3;
+
+    pub fn clear_overridden(&mut self) {
+        self.overridden.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_overridden(&mut self, v: ::protobuf::RepeatedField<::std::string::String>) {
+        self.overridden = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_overridden(&mut self) -> &mut ::protobuf::RepeatedField<::std::string::String> {
+        &mut self.overridden
+    }
+
+    // Take field
+    pub fn take_overridden(&mut self) -> ::protobuf::RepeatedField<::std::string::String> {
+        ::std::mem::replace(&mut self.overridden, ::protobuf::RepeatedField::new())
+    }
+
+    pub fn get_overridden(&self) -> &[::std::string::String] {
+        &self.overridden
+    }
+}
+
+impl ::protobuf::Message for UpdateTodoResponse {
+    fn is_initialized(&self) -> bool {
+        for v in &self.todo {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.todo)?;
+                },
+                2 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.accepted)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_repeated_string_into(wire_type, is, &mut self.overridden)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if let Some(ref v) = self.todo.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        for value in &self.accepted {
+            my_size += ::protobuf::rt::string_size(2, &value);
+        };
+        for value in &self.overridden {
+            my_size += ::protobuf::rt::string_size(3, &value);
+        };
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if let Some(ref v) = self.todo.as_ref() {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        for v in &self.accepted {
+            os.write_string(2, &v)?;
+        };
+        for v in &self.overridden {
+            os.write_string(3, &v)?;
+        };
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> UpdateTodoResponse {
+        UpdateTodoResponse::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<Todo>>(
+                    "todo",
+                    |m: &UpdateTodoResponse| { &m.todo },
+                    |m: &mut UpdateTodoResponse| { &mut m.todo },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "accepted",
+                    |m: &UpdateTodoResponse| { &m.accepted },
+                    |m: &mut UpdateTodoResponse| { &mut m.accepted },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "overridden",
+                    |m: &UpdateTodoResponse| { &m.overridden },
+                    |m: &mut UpdateTodoResponse| { &mut m.overridden },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<UpdateTodoResponse>(
+                    "UpdateTodoResponse",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static UpdateTodoResponse {
+        static mut instance: ::protobuf::lazy::Lazy<UpdateTodoResponse> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const UpdateTodoResponse,
+        };
+        unsafe {
+            instance.get(UpdateTodoResponse::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for UpdateTodoResponse {
+    fn clear(&mut self) {
+        self.clear_todo();
+        self.clear_accepted();
+        self.clear_overridden();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for UpdateTodoResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for UpdateTodoResponse {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct GetTodoRequest {
+    // message fields
+    pub id: i64,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl GetTodoRequest {
+    pub fn new() -> GetTodoRequest {
+        ::std::default::Default::default()
+    }
+
+    // int64 id = 1;
+
+    pub fn clear_id(&mut self) {
+        self.id = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_id(&mut self, v: i64) {
+        self.id = v;
+    }
+
+    pub fn get_id(&self) -> i64 {
+        self.id
+    }
+}
+
+impl ::protobuf::Message for GetTodoRequest {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.id = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.id != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.id, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.id != 0 {
+            os.write_int64(1, self.id)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> GetTodoRequest {
+        GetTodoRequest::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "id",
+                    |m: &GetTodoRequest| { &m.id },
+                    |m: &mut GetTodoRequest| { &mut m.id },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<GetTodoRequest>(
+                    "GetTodoRequest",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static GetTodoRequest {
+        static mut instance: ::protobuf::lazy::Lazy<GetTodoRequest> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const GetTodoRequest,
+        };
+        unsafe {
+            instance.get(GetTodoRequest::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for GetTodoRequest {
+    fn clear(&mut self) {
+        self.clear_id();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for GetTodoRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for GetTodoRequest {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct QueryTodosRequest {
+    // message fields
+    pub next: i64,
+    pub limit: u32,
+    pub text: ::std::string::String,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl QueryTodosRequest {
+    pub fn new() -> QueryTodosRequest {
+        ::std::default::Default::default()
+    }
+
+    // int64 next = 1;
+
+    pub fn clear_next(&mut self) {
+        self.next = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_next(&mut self, v: i64) {
+        self.next = v;
+    }
+
+    pub fn get_next(&self) -> i64 {
+        self.next
+    }
+
+    // uint32 limit = 2;
+
+    pub fn clear_limit(&mut self) {
+        self.limit = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_limit(&mut self, v: u32) {
+        self.limit = v;
+    }
+
+    pub fn get_limit(&self) -> u32 {
+        self.limit
+    }
+
+    // string text = 3;
+
+    pub fn clear_text(&mut self) {
+        self.text.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_text(&mut self, v: ::std::string::String) {
+        self.text = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_text(&mut self) -> &mut ::std::string::String {
+        &mut self.text
+    }
+
+    // Take field
+    pub fn take_text(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.text, ::std::string::String::new())
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl ::protobuf::Message for QueryTodosRequest {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.next = tmp;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_uint32()?;
+                    self.limit = tmp;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.text)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.next != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.next, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if self.limit != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.limit, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.text.is_empty() {
+            my_size += ::protobuf::rt::string_size(3, &self.text);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.next != 0 {
+            os.write_int64(1, self.next)?;
+        }
+        if self.limit != 0 {
+            os.write_uint32(2, self.limit)?;
+        }
+        if !self.text.is_empty() {
+            os.write_string(3, &self.text)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> QueryTodosRequest {
+        QueryTodosRequest::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "next",
+                    |m: &QueryTodosRequest| { &m.next },
+                    |m: &mut QueryTodosRequest| { &mut m.next },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeUint32>(
+                    "limit",
+                    |m: &QueryTodosRequest| { &m.limit },
+                    |m: &mut QueryTodosRequest| { &mut m.limit },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "text",
+                    |m: &QueryTodosRequest| { &m.text },
+                    |m: &mut QueryTodosRequest| { &mut m.text },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<QueryTodosRequest>(
+                    "QueryTodosRequest",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static QueryTodosRequest {
+        static mut instance: ::protobuf::lazy::Lazy<QueryTodosRequest> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const QueryTodosRequest,
+        };
+        unsafe {
+            instance.get(QueryTodosRequest::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for QueryTodosRequest {
+    fn clear(&mut self) {
+        self.clear_next();
+        self.clear_limit();
+        self.clear_text();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for QueryTodosRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for QueryTodosRequest {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct QueryTodosResponse {
+    // message fields
+    pub items: ::protobuf::RepeatedField<Todo>,
+    pub next: i64,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl QueryTodosResponse {
+    pub fn new() -> QueryTodosResponse {
+        ::std::default::Default::default()
+    }
+
+    // repeated .todos.Todo items = 1;
+
+    pub fn clear_items(&mut self) {
+        self.items.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_items(&mut self, v: ::protobuf::RepeatedField<Todo>) {
+        self.items = v;
+    }
+
+    // Mutable pointer to the field.
+    pub fn mut_items(&mut self) -> &mut ::protobuf::RepeatedField<Todo> {
+        &mut self.items
+    }
+
+    // Take field
+    pub fn take_items(&mut self) -> ::protobuf::RepeatedField<Todo> {
+        ::std::mem::replace(&mut self.items, ::protobuf::RepeatedField::new())
+    }
+
+    pub fn get_items(&self) -> &[Todo] {
+        &self.items
+    }
+
+    // int64 next = 2;
+
+    pub fn clear_next(&mut self) {
+        self.next = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_next(&mut self, v: i64) {
+        self.next = v;
+    }
+
+    pub fn get_next(&self) -> i64 {
+        self.next
+    }
+}
+
+impl ::protobuf::Message for QueryTodosResponse {
+    fn is_initialized(&self) -> bool {
+        for v in &self.items {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    ::protobuf::rt::read_repeated_message_into(wire_type, is, &mut self.items)?;
+                },
+                2 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.next = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        for value in &self.items {
+            let len = value.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        };
+        if self.next != 0 {
+            my_size += ::protobuf::rt::value_size(2, self.next, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        for v in &self.items {
+            os.write_tag(1, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        };
+        if self.next != 0 {
+            os.write_int64(2, self.next)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> QueryTodosResponse {
+        QueryTodosResponse::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_repeated_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<Todo>>(
+                    "items",
+                    |m: &QueryTodosResponse| { &m.items },
+                    |m: &mut QueryTodosResponse| { &mut m.items },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "next",
+                    |m: &QueryTodosResponse| { &m.next },
+                    |m: &mut QueryTodosResponse| { &mut m.next },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<QueryTodosResponse>(
+                    "QueryTodosResponse",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static QueryTodosResponse {
+        static mut instance: ::protobuf::lazy::Lazy<QueryTodosResponse> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const QueryTodosResponse,
+        };
+        unsafe {
+            instance.get(QueryTodosResponse::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for QueryTodosResponse {
+    fn clear(&mut self) {
+        self.clear_items();
+        self.clear_next();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for QueryTodosResponse {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for QueryTodosResponse {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct WatchTodosRequest {
+    // message fields
+    pub last_event_id: i64,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl WatchTodosRequest {
+    pub fn new() -> WatchTodosRequest {
+        ::std::default::Default::default()
+    }
+
+    // int64 last_event_id = 1;
+
+    pub fn clear_last_event_id(&mut self) {
+        self.last_event_id = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_last_event_id(&mut self, v: i64) {
+        self.last_event_id = v;
+    }
+
+    pub fn get_last_event_id(&self) -> i64 {
+        self.last_event_id
+    }
+}
+
+impl ::protobuf::Message for WatchTodosRequest {
+    fn is_initialized(&self) -> bool {
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.last_event_id = tmp;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.last_event_id != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.last_event_id, ::protobuf::wire_format::WireTypeVarint);
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.last_event_id != 0 {
+            os.write_int64(1, self.last_event_id)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> WatchTodosRequest {
+        WatchTodosRequest::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "last_event_id",
+                    |m: &WatchTodosRequest| { &m.last_event_id },
+                    |m: &mut WatchTodosRequest| { &mut m.last_event_id },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<WatchTodosRequest>(
+                    "WatchTodosRequest",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static WatchTodosRequest {
+        static mut instance: ::protobuf::lazy::Lazy<WatchTodosRequest> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const WatchTodosRequest,
+        };
+        unsafe {
+            instance.get(WatchTodosRequest::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for WatchTodosRequest {
+    fn clear(&mut self) {
+        self.clear_last_event_id();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for WatchTodosRequest {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for WatchTodosRequest {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+#[derive(PartialEq,Clone,Default)]
+pub struct TodoChange {
+    // message fields
+    pub event_id: i64,
+    pub event: ::std::string::String,
+    pub todo: ::protobuf::SingularPtrField<Todo>,
+    // special fields
+    unknown_fields: ::protobuf::UnknownFields,
+    cached_size: ::protobuf::CachedSize,
+}
+
+impl TodoChange {
+    pub fn new() -> TodoChange {
+        ::std::default::Default::default()
+    }
+
+    // int64 event_id = 1;
+
+    pub fn clear_event_id(&mut self) {
+        self.event_id = 0;
+    }
+
+    // Param is passed by value, moved
+    pub fn set_event_id(&mut self, v: i64) {
+        self.event_id = v;
+    }
+
+    pub fn get_event_id(&self) -> i64 {
+        self.event_id
+    }
+
+    // string event = 2;
+
+    pub fn clear_event(&mut self) {
+        self.event.clear();
+    }
+
+    // Param is passed by value, moved
+    pub fn set_event(&mut self, v: ::std::string::String) {
+        self.event = v;
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_event(&mut self) -> &mut ::std::string::String {
+        &mut self.event
+    }
+
+    // Take field
+    pub fn take_event(&mut self) -> ::std::string::String {
+        ::std::mem::replace(&mut self.event, ::std::string::String::new())
+    }
+
+    pub fn get_event(&self) -> &str {
+        &self.event
+    }
+
+    // .todos.Todo todo = 3;
+
+    pub fn clear_todo(&mut self) {
+        self.todo.clear();
+    }
+
+    pub fn has_todo(&self) -> bool {
+        self.todo.is_some()
+    }
+
+    // Param is passed by value, moved
+    pub fn set_todo(&mut self, v: Todo) {
+        self.todo = ::protobuf::SingularPtrField::some(v);
+    }
+
+    // Mutable pointer to the field.
+    // If field is not initialized, it is initialized with default value first.
+    pub fn mut_todo(&mut self) -> &mut Todo {
+        if self.todo.is_none() {
+            self.todo.set_default();
+        }
+        self.todo.as_mut().unwrap()
+    }
+
+    // Take field
+    pub fn take_todo(&mut self) -> Todo {
+        self.todo.take().unwrap_or_else(|| Todo::new())
+    }
+
+    pub fn get_todo(&self) -> &Todo {
+        self.todo.as_ref().unwrap_or_else(|| Todo::default_instance())
+    }
+}
+
+impl ::protobuf::Message for TodoChange {
+    fn is_initialized(&self) -> bool {
+        for v in &self.todo {
+            if !v.is_initialized() {
+                return false;
+            }
+        };
+        true
+    }
+
+    fn merge_from(&mut self, is: &mut ::protobuf::CodedInputStream) -> ::protobuf::ProtobufResult<()> {
+        while !is.eof()? {
+            let (field_number, wire_type) = is.read_tag_unpack()?;
+            match field_number {
+                1 => {
+                    if wire_type != ::protobuf::wire_format::WireTypeVarint {
+                        return ::std::result::Result::Err(::protobuf::rt::unexpected_wire_type(wire_type));
+                    }
+                    let tmp = is.read_int64()?;
+                    self.event_id = tmp;
+                },
+                2 => {
+                    ::protobuf::rt::read_singular_proto3_string_into(wire_type, is, &mut self.event)?;
+                },
+                3 => {
+                    ::protobuf::rt::read_singular_message_into(wire_type, is, &mut self.todo)?;
+                },
+                _ => {
+                    ::protobuf::rt::read_unknown_or_skip_group(field_number, wire_type, is, self.mut_unknown_fields())?;
+                },
+            };
+        }
+        ::std::result::Result::Ok(())
+    }
+
+    // Compute sizes of nested messages
+    #[allow(unused_variables)]
+    fn compute_size(&self) -> u32 {
+        let mut my_size = 0;
+        if self.event_id != 0 {
+            my_size += ::protobuf::rt::value_size(1, self.event_id, ::protobuf::wire_format::WireTypeVarint);
+        }
+        if !self.event.is_empty() {
+            my_size += ::protobuf::rt::string_size(2, &self.event);
+        }
+        if let Some(ref v) = self.todo.as_ref() {
+            let len = v.compute_size();
+            my_size += 1 + ::protobuf::rt::compute_raw_varint32_size(len) + len;
+        }
+        my_size += ::protobuf::rt::unknown_fields_size(self.get_unknown_fields());
+        self.cached_size.set(my_size);
+        my_size
+    }
+
+    fn write_to_with_cached_sizes(&self, os: &mut ::protobuf::CodedOutputStream) -> ::protobuf::ProtobufResult<()> {
+        if self.event_id != 0 {
+            os.write_int64(1, self.event_id)?;
+        }
+        if !self.event.is_empty() {
+            os.write_string(2, &self.event)?;
+        }
+        if let Some(ref v) = self.todo.as_ref() {
+            os.write_tag(3, ::protobuf::wire_format::WireTypeLengthDelimited)?;
+            os.write_raw_varint32(v.get_cached_size())?;
+            v.write_to_with_cached_sizes(os)?;
+        }
+        os.write_unknown_fields(self.get_unknown_fields())?;
+        ::std::result::Result::Ok(())
+    }
+
+    fn get_cached_size(&self) -> u32 {
+        self.cached_size.get()
+    }
+
+    fn get_unknown_fields(&self) -> &::protobuf::UnknownFields {
+        &self.unknown_fields
+    }
+
+    fn mut_unknown_fields(&mut self) -> &mut ::protobuf::UnknownFields {
+        &mut self.unknown_fields
+    }
+
+    fn as_any(&self) -> &::std::any::Any {
+        self as &::std::any::Any
+    }
+    fn as_any_mut(&mut self) -> &mut ::std::any::Any {
+        self as &mut ::std::any::Any
+    }
+    fn into_any(self: Box<Self>) -> ::std::boxed::Box<::std::any::Any> {
+        self
+    }
+
+    fn descriptor(&self) -> &'static ::protobuf::reflect::MessageDescriptor {
+        Self::descriptor_static()
+    }
+
+    fn new() -> TodoChange {
+        TodoChange::new()
+    }
+
+    fn descriptor_static() -> &'static ::protobuf::reflect::MessageDescriptor {
+        static mut descriptor: ::protobuf::lazy::Lazy<::protobuf::reflect::MessageDescriptor> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const ::protobuf::reflect::MessageDescriptor,
+        };
+        unsafe {
+            descriptor.get(|| {
+                let mut fields = ::std::vec::Vec::new();
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeInt64>(
+                    "event_id",
+                    |m: &TodoChange| { &m.event_id },
+                    |m: &mut TodoChange| { &mut m.event_id },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_simple_field_accessor::<_, ::protobuf::types::ProtobufTypeString>(
+                    "event",
+                    |m: &TodoChange| { &m.event },
+                    |m: &mut TodoChange| { &mut m.event },
+                ));
+                fields.push(::protobuf::reflect::accessor::make_singular_ptr_field_accessor::<_, ::protobuf::types::ProtobufTypeMessage<Todo>>(
+                    "todo",
+                    |m: &TodoChange| { &m.todo },
+                    |m: &mut TodoChange| { &mut m.todo },
+                ));
+                ::protobuf::reflect::MessageDescriptor::new::<TodoChange>(
+                    "TodoChange",
+                    fields,
+                    file_descriptor_proto()
+                )
+            })
+        }
+    }
+
+    fn default_instance() -> &'static TodoChange {
+        static mut instance: ::protobuf::lazy::Lazy<TodoChange> = ::protobuf::lazy::Lazy {
+            lock: ::protobuf::lazy::ONCE_INIT,
+            ptr: 0 as *const TodoChange,
+        };
+        unsafe {
+            instance.get(TodoChange::new)
+        }
+    }
+}
+
+impl ::protobuf::Clear for TodoChange {
+    fn clear(&mut self) {
+        self.clear_event_id();
+        self.clear_event();
+        self.clear_todo();
+        self.unknown_fields.clear();
+    }
+}
+
+impl ::std::fmt::Debug for TodoChange {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        ::protobuf::text_format::fmt(self, f)
+    }
+}
+
+impl ::protobuf::reflect::ProtobufValue for TodoChange {
+    fn as_ref(&self) -> ::protobuf::reflect::ProtobufValueRef {
+        ::protobuf::reflect::ProtobufValueRef::Message(self)
+    }
+}
+
+static file_descriptor_proto_data: &'static [u8] = b"\
+    \n\x0btodos.proto\x12\x05todos\x1a\x1fgoogle/protobuf/timestamp.proto\
+    \x1a\x1egoogle/protobuf/wrappers.proto\"\xe7\x01\n\x04Todo\x12\x0e\n\x02\
+    id\x18\x01\x20\x01(\x03R\x02id\x12\x12\n\x04text\x18\x02\x20\x01(\tR\x04\
+    text\x12\x12\n\x04done\x18\x03\x20\x01(\x08R\x04done\x129\n\ncreated_at\
+    \x18\x04\x20\x01(\x0b2\x1a.google.protobuf.TimestampR\tcreatedAt\x129\n\
+    \nupdated_at\x18\x05\x20\x01(\x0b2\x1a.google.protobuf.TimestampR\tupdat\
+    edAt\x121\n\x06due_at\x18\x06\x20\x01(\x0b2\x1a.google.protobuf.Timestam\
+    pR\x05dueAt\"'\n\x11CreateTodoRequest\x12\x12\n\x04text\x18\x01\x20\x01(\
+    \tR\x04text\"\xc0\x01\n\x11UpdateTodoRequest\x12\x0e\n\x02id\x18\x01\x20\
+    \x01(\x03R\x02id\x120\n\x04text\x18\x02\x20\x01(\x0b2\x1c.google.protobu\
+    f.StringValueR\x04text\x12.\n\x04done\x18\x03\x20\x01(\x0b2\x1a.google.p\
+    rotobuf.BoolValueR\x04done\x129\n\nchanged_at\x18\x04\x20\x01(\x0b2\x1a.\
+    google.protobuf.TimestampR\tchangedAt\"q\n\x12UpdateTodoResponse\x12\x1f\
+    \n\x04todo\x18\x01\x20\x01(\x0b2\x0b.todos.TodoR\x04todo\x12\x1a\n\x08ac\
+    cepted\x18\x02\x20\x03(\tR\x08accepted\x12\x1e\n\noverridden\x18\x03\x20\
+    \x03(\tR\noverridden\"\x20\n\x0eGetTodoRequest\x12\x0e\n\x02id\x18\x01\
+    \x20\x01(\x03R\x02id\"Q\n\x11QueryTodosRequest\x12\x12\n\x04next\x18\x01\
+    \x20\x01(\x03R\x04next\x12\x14\n\x05limit\x18\x02\x20\x01(\rR\x05limit\
+    \x12\x12\n\x04text\x18\x03\x20\x01(\tR\x04text\"K\n\x12QueryTodosRespons\
+    e\x12!\n\x05items\x18\x01\x20\x03(\x0b2\x0b.todos.TodoR\x05items\x12\x12\
+    \n\x04next\x18\x02\x20\x01(\x03R\x04next\"7\n\x11WatchTodosRequest\x12\"\
+    \n\rlast_event_id\x18\x01\x20\x01(\x03R\x0blastEventId\"^\n\nTodoChange\
+    \x12\x19\n\x08event_id\x18\x01\x20\x01(\x03R\x07eventId\x12\x14\n\x05eve\
+    nt\x18\x02\x20\x01(\tR\x05event\x12\x1f\n\x04todo\x18\x03\x20\x01(\x0b2\
+    \x0b.todos.TodoR\x04todo2\x9e\x02\n\x0bTodoService\x12/\n\x06Create\x12\
+    \x18.todos.CreateTodoRequest\x1a\x0b.todos.Todo\x12=\n\x06Update\x12\x18\
+    .todos.UpdateTodoRequest\x1a\x19.todos.UpdateTodoResponse\x12)\n\x03Get\
+    \x12\x15.todos.GetTodoRequest\x1a\x0b.todos.Todo\x12<\n\x05Query\x12\x18\
+    .todos.QueryTodosRequest\x1a\x19.todos.QueryTodosResponse\x126\n\x05Watc\
+    h\x12\x18.todos.WatchTodosRequest\x1a\x11.todos.TodoChange0\x01J\xa0\x17\
+    \n\x06\x12\x04\x0e\0U\x01\n\x91\x05\n\x01\x0c\x12\x03\x0e\0\x12\x1a\x86\
+    \x05\x20Todos'\x20gRPC\x20contract.\n\n\x20Only\x20the\x20contract\x20is\
+    \x20defined\x20so\x20far,\x20there\x20is\x20no\x20server\x20for\x20it\
+    \x20yet:\x20hyper\x200.11\x20serves\n\x20HTTP/1.1\x20only\x20and\x20the\
+    \x20build\x20doesn't\x20have\x20HTTP/2\x20or\x20protobuf\x20crates\x20to\
+    \x20serve\x20it\x20with.\n\x20The\x20same\x20operations\x20are\x20availa\
+    ble\x20over\x20`/rpc`\x20(JSON-RPC\x202.0)\x20and\x20`/todos/ws`\x20mean\
+    while.\n\n\x20A\x20server\x20is\x20expected\x20to\x20share\x20`TodosRepo\
+    `\x20and\x20the\x20validators\x20with\x20`TodosController`\x20and\x20to\
+    \n\x20map\x20`result::Error`\x20to\x20status\x20codes\x20like\x20this:\n\
+    \n\x20\x20\x20Validation,\x20JsonParse\x20\x20\x20\x20\x20\x20INVALID_AR\
+    GUMENT\n\x20\x20\x20RecordNotFound\x20\x20\x20\x20\x20\x20\x20\x20\x20\
+    \x20\x20\x20\x20NOT_FOUND\n\x20\x20\x20PreconditionFailed\x20\x20\x20\
+    \x20\x20\x20\x20\x20\x20FAILED_PRECONDITION\n\x20\x20\x20MySqlConnection\
+    \x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20UNAVAILABLE\n\x20\x20\
+    \x20anything\x20else\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\x20\
+    \x20INTERNAL\n\n\x08\n\x01\x02\x12\x03\x10\0\x0e\n\t\n\x02\x03\0\x12\x03\
+    \x12\0)\n\t\n\x02\x03\x01\x12\x03\x13\0(\n\n\n\x02\x06\0\x12\x04\x15\0\
+    \x1c\x01\n\n\n\x03\x06\0\x01\x12\x03\x15\x08\x13\n\x0b\n\x04\x06\0\x02\0\
+    \x12\x03\x16\x02/\n\x0c\n\x05\x06\0\x02\0\x01\x12\x03\x16\x06\x0c\n\x0c\
+    \n\x05\x06\0\x02\0\x02\x12\x03\x16\r\x1e\n\x0c\n\x05\x06\0\x02\0\x03\x12\
+    \x03\x16)-\n\x0b\n\x04\x06\0\x02\x01\x12\x03\x17\x02=\n\x0c\n\x05\x06\0\
+    \x02\x01\x01\x12\x03\x17\x06\x0c\n\x0c\n\x05\x06\0\x02\x01\x02\x12\x03\
+    \x17\r\x1e\n\x0c\n\x05\x06\0\x02\x01\x03\x12\x03\x17);\n\x0b\n\x04\x06\0\
+    \x02\x02\x12\x03\x18\x02)\n\x0c\n\x05\x06\0\x02\x02\x01\x12\x03\x18\x06\
+    \t\n\x0c\n\x05\x06\0\x02\x02\x02\x12\x03\x18\n\x18\n\x0c\n\x05\x06\0\x02\
+    \x02\x03\x12\x03\x18#'\n\x0b\n\x04\x06\0\x02\x03\x12\x03\x19\x02<\n\x0c\
+    \n\x05\x06\0\x02\x03\x01\x12\x03\x19\x06\x0b\n\x0c\n\x05\x06\0\x02\x03\
+    \x02\x12\x03\x19\x0c\x1d\n\x0c\n\x05\x06\0\x02\x03\x03\x12\x03\x19(:\nR\
+    \n\x04\x06\0\x02\x04\x12\x03\x1b\x02;\x1aE\x20Changes\x20after\x20the\
+    \x20call,\x20the\x20stream\x20ends\x20when\x20the\x20server\x20shuts\x20\
+    down.\n\n\x0c\n\x05\x06\0\x02\x04\x01\x12\x03\x1b\x06\x0b\n\x0c\n\x05\
+    \x06\0\x02\x04\x02\x12\x03\x1b\x0c\x1d\n\x0c\n\x05\x06\0\x02\x04\x06\x12\
+    \x03\x1b(.\n\x0c\n\x05\x06\0\x02\x04\x03\x12\x03\x1b/9\n\n\n\x02\x04\0\
+    \x12\x04\x1e\0%\x01\n\n\n\x03\x04\0\x01\x12\x03\x1e\x08\x0c\n\x0b\n\x04\
+    \x04\0\x02\0\x12\x03\x1f\x02\x0f\n\x0c\n\x05\x04\0\x02\0\x05\x12\x03\x1f\
+    \x02\x07\n\x0c\n\x05\x04\0\x02\0\x01\x12\x03\x1f\x08\n\n\x0c\n\x05\x04\0\
+    \x02\0\x03\x12\x03\x1f\r\x0e\n\x0b\n\x04\x04\0\x02\x01\x12\x03\x20\x02\
+    \x12\n\x0c\n\x05\x04\0\x02\x01\x05\x12\x03\x20\x02\x08\n\x0c\n\x05\x04\0\
+    \x02\x01\x01\x12\x03\x20\t\r\n\x0c\n\x05\x04\0\x02\x01\x03\x12\x03\x20\
+    \x10\x11\n\x0b\n\x04\x04\0\x02\x02\x12\x03!\x02\x10\n\x0c\n\x05\x04\0\
+    \x02\x02\x05\x12\x03!\x02\x06\n\x0c\n\x05\x04\0\x02\x02\x01\x12\x03!\x07\
+    \x0b\n\x0c\n\x05\x04\0\x02\x02\x03\x12\x03!\x0e\x0f\n\x0b\n\x04\x04\0\
+    \x02\x03\x12\x03\"\x02+\n\x0c\n\x05\x04\0\x02\x03\x06\x12\x03\"\x02\x1b\
+    \n\x0c\n\x05\x04\0\x02\x03\x01\x12\x03\"\x1c&\n\x0c\n\x05\x04\0\x02\x03\
+    \x03\x12\x03\")*\n\x0b\n\x04\x04\0\x02\x04\x12\x03#\x02+\n\x0c\n\x05\x04\
+    \0\x02\x04\x06\x12\x03#\x02\x1b\n\x0c\n\x05\x04\0\x02\x04\x01\x12\x03#\
+    \x1c&\n\x0c\n\x05\x04\0\x02\x04\x03\x12\x03#)*\n\x0b\n\x04\x04\0\x02\x05\
+    \x12\x03$\x02'\n\x0c\n\x05\x04\0\x02\x05\x06\x12\x03$\x02\x1b\n\x0c\n\
+    \x05\x04\0\x02\x05\x01\x12\x03$\x1c\"\n\x0c\n\x05\x04\0\x02\x05\x03\x12\
+    \x03$%&\n\n\n\x02\x04\x01\x12\x04'\0)\x01\n\n\n\x03\x04\x01\x01\x12\x03'\
+    \x08\x19\n\x0b\n\x04\x04\x01\x02\0\x12\x03(\x02\x12\n\x0c\n\x05\x04\x01\
+    \x02\0\x05\x12\x03(\x02\x08\n\x0c\n\x05\x04\x01\x02\0\x01\x12\x03(\t\r\n\
+    \x0c\n\x05\x04\x01\x02\0\x03\x12\x03(\x10\x11\n2\n\x02\x04\x02\x12\x04,\
+    \02\x01\x1a&\x20Missing\x20fields\x20are\x20left\x20as\x20they\x20are.\n\
+    \n\n\n\x03\x04\x02\x01\x12\x03,\x08\x19\n\x0b\n\x04\x04\x02\x02\0\x12\
+    \x03-\x02\x0f\n\x0c\n\x05\x04\x02\x02\0\x05\x12\x03-\x02\x07\n\x0c\n\x05\
+    \x04\x02\x02\0\x01\x12\x03-\x08\n\n\x0c\n\x05\x04\x02\x02\0\x03\x12\x03-\
+    \r\x0e\n\x0b\n\x04\x04\x02\x02\x01\x12\x03.\x02'\n\x0c\n\x05\x04\x02\x02\
+    \x01\x06\x12\x03.\x02\x1d\n\x0c\n\x05\x04\x02\x02\x01\x01\x12\x03.\x1e\"\
+    \n\x0c\n\x05\x04\x02\x02\x01\x03\x12\x03.%&\n\x0b\n\x04\x04\x02\x02\x02\
+    \x12\x03/\x02%\n\x0c\n\x05\x04\x02\x02\x02\x06\x12\x03/\x02\x1b\n\x0c\n\
+    \x05\x04\x02\x02\x02\x01\x12\x03/\x1c\x20\n\x0c\n\x05\x04\x02\x02\x02\
+    \x03\x12\x03/#$\nQ\n\x04\x04\x02\x02\x03\x12\x031\x02+\x1aD\x20when\x20t\
+    he\x20change\x20was\x20made,\x20fields\x20are\x20merged\x20by\x20their\
+    \x20latest\x20change\n\n\x0c\n\x05\x04\x02\x02\x03\x06\x12\x031\x02\x1b\
+    \n\x0c\n\x05\x04\x02\x02\x03\x01\x12\x031\x1c&\n\x0c\n\x05\x04\x02\x02\
+    \x03\x03\x12\x031)*\n\n\n\x02\x04\x03\x12\x044\08\x01\n\n\n\x03\x04\x03\
+    \x01\x12\x034\x08\x1a\n\x0b\n\x04\x04\x03\x02\0\x12\x035\x02\x10\n\x0c\n\
+    \x05\x04\x03\x02\0\x06\x12\x035\x02\x06\n\x0c\n\x05\x04\x03\x02\0\x01\
+    \x12\x035\x07\x0b\n\x0c\n\x05\x04\x03\x02\0\x03\x12\x035\x0e\x0f\n\x0b\n\
+    \x04\x04\x03\x02\x01\x12\x036\x02\x1f\n\x0c\n\x05\x04\x03\x02\x01\x04\
+    \x12\x036\x02\n\n\x0c\n\x05\x04\x03\x02\x01\x05\x12\x036\x0b\x11\n\x0c\n\
+    \x05\x04\x03\x02\x01\x01\x12\x036\x12\x1a\n\x0c\n\x05\x04\x03\x02\x01\
+    \x03\x12\x036\x1d\x1e\n\x0b\n\x04\x04\x03\x02\x02\x12\x037\x02!\n\x0c\n\
+    \x05\x04\x03\x02\x02\x04\x12\x037\x02\n\n\x0c\n\x05\x04\x03\x02\x02\x05\
+    \x12\x037\x0b\x11\n\x0c\n\x05\x04\x03\x02\x02\x01\x12\x037\x12\x1c\n\x0c\
+    \n\x05\x04\x03\x02\x02\x03\x12\x037\x1f\x20\n\n\n\x02\x04\x04\x12\x04:\0\
+    <\x01\n\n\n\x03\x04\x04\x01\x12\x03:\x08\x16\n\x0b\n\x04\x04\x04\x02\0\
+    \x12\x03;\x02\x0f\n\x0c\n\x05\x04\x04\x02\0\x05\x12\x03;\x02\x07\n\x0c\n\
+    \x05\x04\x04\x02\0\x01\x12\x03;\x08\n\n\x0c\n\x05\x04\x04\x02\0\x03\x12\
+    \x03;\r\x0e\n\n\n\x02\x04\x05\x12\x04>\0D\x01\n\n\n\x03\x04\x05\x01\x12\
+    \x03>\x08\x19\n4\n\x04\x04\x05\x02\0\x12\x03@\x02\x11\x1a'\x20a\x20next\
+    \x20item's\x20id\x20from\x20a\x20previous\x20page\n\n\x0c\n\x05\x04\x05\
+    \x02\0\x05\x12\x03@\x02\x07\n\x0c\n\x05\x04\x05\x02\0\x01\x12\x03@\x08\
+    \x0c\n\x0c\n\x05\x04\x05\x02\0\x03\x12\x03@\x0f\x10\n&\n\x04\x04\x05\x02\
+    \x01\x12\x03B\x02\x13\x1a\x19\x20at\x20most\x2010,\x20the\x20default\n\n\
+    \x0c\n\x05\x04\x05\x02\x01\x05\x12\x03B\x02\x08\n\x0c\n\x05\x04\x05\x02\
+    \x01\x01\x12\x03B\t\x0e\n\x0c\n\x05\x04\x05\x02\x01\x03\x12\x03B\x11\x12\
+    \n\x0b\n\x04\x04\x05\x02\x02\x12\x03C\x02\x12\n\x0c\n\x05\x04\x05\x02\
+    \x02\x05\x12\x03C\x02\x08\n\x0c\n\x05\x04\x05\x02\x02\x01\x12\x03C\t\r\n\
+    \x0c\n\x05\x04\x05\x02\x02\x03\x12\x03C\x10\x11\n\n\n\x02\x04\x06\x12\
+    \x04F\0I\x01\n\n\n\x03\x04\x06\x01\x12\x03F\x08\x1a\n\x0b\n\x04\x04\x06\
+    \x02\0\x12\x03G\x02\x1a\n\x0c\n\x05\x04\x06\x02\0\x04\x12\x03G\x02\n\n\
+    \x0c\n\x05\x04\x06\x02\0\x06\x12\x03G\x0b\x0f\n\x0c\n\x05\x04\x06\x02\0\
+    \x01\x12\x03G\x10\x15\n\x0c\n\x05\x04\x06\x02\0\x03\x12\x03G\x18\x19\n\
+    \x0b\n\x04\x04\x06\x02\x01\x12\x03H\x02\x11\n\x0c\n\x05\x04\x06\x02\x01\
+    \x05\x12\x03H\x02\x07\n\x0c\n\x05\x04\x06\x02\x01\x01\x12\x03H\x08\x0c\n\
+    \x0c\n\x05\x04\x06\x02\x01\x03\x12\x03H\x0f\x10\n\n\n\x02\x04\x07\x12\
+    \x04K\0N\x01\n\n\n\x03\x04\x07\x01\x12\x03K\x08\x19\nM\n\x04\x04\x07\x02\
+    \0\x12\x03M\x02\x1a\x1a@\x20the\x20last\x20seen\x20change's\x20id,\x20ch\
+    anges\x20after\x20it\x20are\x20replayed\x20first\n\n\x0c\n\x05\x04\x07\
+    \x02\0\x05\x12\x03M\x02\x07\n\x0c\n\x05\x04\x07\x02\0\x01\x12\x03M\x08\
+    \x15\n\x0c\n\x05\x04\x07\x02\0\x03\x12\x03M\x18\x19\n\n\n\x02\x04\x08\
+    \x12\x04P\0U\x01\n\n\n\x03\x04\x08\x01\x12\x03P\x08\x12\n\x0b\n\x04\x04\
+    \x08\x02\0\x12\x03Q\x02\x15\n\x0c\n\x05\x04\x08\x02\0\x05\x12\x03Q\x02\
+    \x07\n\x0c\n\x05\x04\x08\x02\0\x01\x12\x03Q\x08\x10\n\x0c\n\x05\x04\x08\
+    \x02\0\x03\x12\x03Q\x13\x14\nQ\n\x04\x04\x08\x02\x01\x12\x03S\x02\x13\
+    \x1aD\x20`todo.created`,\x20`todo.updated`,\x20`todo.completed`\x20or\
+    \x20`todo.deleted`\n\n\x0c\n\x05\x04\x08\x02\x01\x05\x12\x03S\x02\x08\n\
+    \x0c\n\x05\x04\x08\x02\x01\x01\x12\x03S\t\x0e\n\x0c\n\x05\x04\x08\x02\
+    \x01\x03\x12\x03S\x11\x12\n\x0b\n\x04\x04\x08\x02\x02\x12\x03T\x02\x10\n\
+    \x0c\n\x05\x04\x08\x02\x02\x06\x12\x03T\x02\x06\n\x0c\n\x05\x04\x08\x02\
+    \x02\x01\x12\x03T\x07\x0b\n\x0c\n\x05\x04\x08\x02\x02\x03\x12\x03T\x0e\
+    \x0fb\x06proto3\
+";
+
+static mut file_descriptor_proto_lazy: ::protobuf::lazy::Lazy<::protobuf::descriptor::FileDescriptorProto> = ::protobuf::lazy::Lazy {
+    lock: ::protobuf::lazy::ONCE_INIT,
+    ptr: 0 as *const ::protobuf::descriptor::FileDescriptorProto,
+};
+
+fn parse_descriptor_proto() -> ::protobuf::descriptor::FileDescriptorProto {
+    ::protobuf::parse_from_bytes(file_descriptor_proto_data).unwrap()
+}
+
+pub fn file_descriptor_proto() -> &'static ::protobuf::descriptor::FileDescriptorProto {
+    unsafe {
+        file_descriptor_proto_lazy.get(|| {
+            parse_descriptor_proto()
+        })
+    }
+}
@@ -0,0 +1,191 @@
+// This file is generated. Do not edit
+// @generated
+
+// https://github.com/Manishearth/rust-clippy/issues/702
+#![allow(unknown_lints)]
+#![allow(clippy)]
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+
+#![allow(box_pointers)]
+#![allow(dead_code)]
+#![allow(missing_docs)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+#![allow(non_upper_case_globals)]
+#![allow(trivial_casts)]
+#![allow(unsafe_code)]
+#![allow(unused_imports)]
+#![allow(unused_results)]
+
+
+// interface
+
+pub trait TodoService {
+    fn create(&self, o: ::grpc::RequestOptions, p: super::todos::CreateTodoRequest) -> ::grpc::SingleResponse<super::todos::Todo>;
+
+    fn update(&self, o: ::grpc::RequestOptions, p: super::todos::UpdateTodoRequest) -> ::grpc::SingleResponse<super::todos::UpdateTodoResponse>;
+
+    fn get(&self, o: ::grpc::RequestOptions, p: super::todos::GetTodoRequest) -> ::grpc::SingleResponse<super::todos::Todo>;
+
+    fn query(&self, o: ::grpc::RequestOptions, p: super::todos::QueryTodosRequest) -> ::grpc::SingleResponse<super::todos::QueryTodosResponse>;
+
+    fn watch(&self, o: ::grpc::RequestOptions, p: super::todos::WatchTodosRequest) -> ::grpc::StreamingResponse<super::todos::TodoChange>;
+}
+
+// client
+
+pub struct TodoServiceClient {
+    grpc_client: ::grpc::Client,
+    method_Create: ::std::sync::Arc<::grpc::rt::MethodDescriptor<super::todos::CreateTodoRequest, super::todos::Todo>>,
+    method_Update: ::std::sync::Arc<::grpc::rt::MethodDescriptor<super::todos::UpdateTodoRequest, super::todos::UpdateTodoResponse>>,
+    method_Get: ::std::sync::Arc<::grpc::rt::MethodDescriptor<super::todos::GetTodoRequest, super::todos::Todo>>,
+    method_Query: ::std::sync::Arc<::grpc::rt::MethodDescriptor<super::todos::QueryTodosRequest, super::todos::QueryTodosResponse>>,
+    method_Watch: ::std::sync::Arc<::grpc::rt::MethodDescriptor<super::todos::WatchTodosRequest, super::todos::TodoChange>>,
+}
+
+impl TodoServiceClient {
+    pub fn with_client(grpc_client: ::grpc::Client) -> Self {
+        TodoServiceClient {
+            grpc_client: grpc_client,
+            method_Create: ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                name: "/todos.TodoService/Create".to_string(),
+                streaming: ::grpc::rt::GrpcStreaming::Unary,
+                req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+            }),
+            method_Update: ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                name: "/todos.TodoService/Update".to_string(),
+                streaming: ::grpc::rt::GrpcStreaming::Unary,
+                req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+            }),
+            method_Get: ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                name: "/todos.TodoService/Get".to_string(),
+                streaming: ::grpc::rt::GrpcStreaming::Unary,
+                req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+            }),
+            method_Query: ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                name: "/todos.TodoService/Query".to_string(),
+                streaming: ::grpc::rt::GrpcStreaming::Unary,
+                req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+            }),
+            method_Watch: ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                name: "/todos.TodoService/Watch".to_string(),
+                streaming: ::grpc::rt::GrpcStreaming::ServerStreaming,
+                req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+            }),
+        }
+    }
+
+    pub fn new_plain(host: &str, port: u16, conf: ::grpc::ClientConf) -> ::grpc::Result<Self> {
+        ::grpc::Client::new_plain(host, port, conf).map(|c| {
+            TodoServiceClient::with_client(c)
+        })
+    }
+    pub fn new_tls<C : ::tls_api::TlsConnector>(host: &str, port: u16, conf: ::grpc::ClientConf) -> ::grpc::Result<Self> {
+        ::grpc::Client::new_tls::<C>(host, port, conf).map(|c| {
+            TodoServiceClient::with_client(c)
+        })
+    }
+}
+
+impl TodoService for TodoServiceClient {
+    fn create(&self, o: ::grpc::RequestOptions, p: super::todos::CreateTodoRequest) -> ::grpc::SingleResponse<super::todos::Todo> {
+        self.grpc_client.call_unary(o, p, self.method_Create.clone())
+    }
+
+    fn update(&self, o: ::grpc::RequestOptions, p: super::todos::UpdateTodoRequest) -> ::grpc::SingleResponse<super::todos::UpdateTodoResponse> {
+        self.grpc_client.call_unary(o, p, self.method_Update.clone())
+    }
+
+    fn get(&self, o: ::grpc::RequestOptions, p: super::todos::GetTodoRequest) -> ::grpc::SingleResponse<super::todos::Todo> {
+        self.grpc_client.call_unary(o, p, self.method_Get.clone())
+    }
+
+    fn query(&self, o: ::grpc::RequestOptions, p: super::todos::QueryTodosRequest) -> ::grpc::SingleResponse<super::todos::QueryTodosResponse> {
+        self.grpc_client.call_unary(o, p, self.method_Query.clone())
+    }
+
+    fn watch(&self, o: ::grpc::RequestOptions, p: super::todos::WatchTodosRequest) -> ::grpc::StreamingResponse<super::todos::TodoChange> {
+        self.grpc_client.call_server_streaming(o, p, self.method_Watch.clone())
+    }
+}
+
+// server
+
+pub struct TodoServiceServer;
+
+
+impl TodoServiceServer {
+    pub fn new_service_def<H : TodoService + 'static + Sync + Send + 'static>(handler: H) -> ::grpc::rt::ServerServiceDefinition {
+        let handler_arc = ::std::sync::Arc::new(handler);
+        ::grpc::rt::ServerServiceDefinition::new("/todos.TodoService",
+            vec![
+                ::grpc::rt::ServerMethod::new(
+                    ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                        name: "/todos.TodoService/Create".to_string(),
+                        streaming: ::grpc::rt::GrpcStreaming::Unary,
+                        req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                        resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                    }),
+                    {
+                        let handler_copy = handler_arc.clone();
+                        ::grpc::rt::MethodHandlerUnary::new(move |o, p| handler_copy.create(o, p))
+                    },
+                ),
+                ::grpc::rt::ServerMethod::new(
+                    ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                        name: "/todos.TodoService/Update".to_string(),
+                        streaming: ::grpc::rt::GrpcStreaming::Unary,
+                        req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                        resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                    }),
+                    {
+                        let handler_copy = handler_arc.clone();
+                        ::grpc::rt::MethodHandlerUnary::new(move |o, p| handler_copy.update(o, p))
+                    },
+                ),
+                ::grpc::rt::ServerMethod::new(
+                    ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                        name: "/todos.TodoService/Get".to_string(),
+                        streaming: ::grpc::rt::GrpcStreaming::Unary,
+                        req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                        resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                    }),
+                    {
+                        let handler_copy = handler_arc.clone();
+                        ::grpc::rt::MethodHandlerUnary::new(move |o, p| handler_copy.get(o, p))
+                    },
+                ),
+                ::grpc::rt::ServerMethod::new(
+                    ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                        name: "/todos.TodoService/Query".to_string(),
+                        streaming: ::grpc::rt::GrpcStreaming::Unary,
+                        req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                        resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                    }),
+                    {
+                        let handler_copy = handler_arc.clone();
+                        ::grpc::rt::MethodHandlerUnary::new(move |o, p| handler_copy.query(o, p))
+                    },
+                ),
+                ::grpc::rt::ServerMethod::new(
+                    ::std::sync::Arc::new(::grpc::rt::MethodDescriptor {
+                        name: "/todos.TodoService/Watch".to_string(),
+                        streaming: ::grpc::rt::GrpcStreaming::ServerStreaming,
+                        req_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                        resp_marshaller: Box::new(::grpc::protobuf::MarshallerProtobuf),
+                    }),
+                    {
+                        let handler_copy = handler_arc.clone();
+                        ::grpc::rt::MethodHandlerServerStreaming::new(move |o, p| handler_copy.watch(o, p))
+                    },
+                ),
+            ],
+        )
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::Duration;
+use futures::{future, Future, Stream};
+use hyper::{Headers, Method, Request, Response, StatusCode};
+use hyper::header::ContentLength;
+use serde_json;
+use sha2::{Digest, Sha256};
+
+use config::Config;
+use db::{IdempotencyKey, IdempotencyKeysRepo};
+use result::Error;
+use common::{to_hex, FuturesExt, RequestExt};
+
+use super::middleware::{Endpoint, Middleware};
+use super::rate_limiter::client_key;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+header! { (IdempotencyKeyHeader, "Idempotency-Key") => [String] }
+header! { (IdempotentReplayed, "Idempotent-Replayed") => [bool] }
+
+/// Replays stored responses for requests retried with the same `Idempotency-Key`.
+///
+/// Keys are scoped by a client like rate limits are.
+#[derive(Clone)]
+pub struct Idempotency {
+  repo: IdempotencyKeysRepo,
+  ttl: Duration,
+  max_body_size: u64,
+  admin_token: String,
+}
+
+impl Idempotency {
+  pub fn new(cfg: &Config, repo: IdempotencyKeysRepo) -> Self {
+    let ttl = Duration::from_std(cfg.idempotency_ttl).expect("idempotency ttl is out of range");
+    Idempotency {
+      repo,
+      ttl,
+      max_body_size: cfg.max_body_size,
+      admin_token: cfg.admin_token.clone(),
+    }
+  }
+}
+
+impl Middleware for Idempotency {
+  /// Call the next endpoint once per idempotency key, requests without the key are passed as is.
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    let key = match req.headers().get::<IdempotencyKeyHeader>() {
+      Some(key) => key.0.clone(),
+      None => return next.call(req),
+    };
+
+    if key.is_empty() || key.len() > 255 {
+      return future::err(Error::Validation(format!(
+        "idempotency key length must be between 1 and 255, got {}",
+        key.len()
+      ))).into_boxed();
+    }
+
+    let client = client_key(&req, &self.admin_token);
+    let (repo, ttl) = (self.repo.clone(), self.ttl);
+    let (method, uri, version) = (req.method().clone(), req.uri().clone(), req.version());
+    let headers = req.headers().clone();
+
+    req
+      .bytes(self.max_body_size)
+      .and_then(move |body| {
+        let fingerprint = fingerprint(&method, uri.path(), &body);
+
+        repo
+          .reserve(client.clone(), key.clone(), fingerprint.clone(), ttl)
+          .and_then(move |existing| -> BoxFuture<Response> {
+            match existing {
+              Some(ref it) if it.fingerprint != fingerprint => {
+                future::err(Error::IdempotencyKeyReused).into_boxed()
+              }
+              Some(ref it) if it.is_in_progress() => {
+                future::err(Error::IdempotencyKeyInProgress).into_boxed()
+              }
+              Some(it) => future::ok(replay(it)).into_boxed(),
+              None => {
+                let mut req = Request::new(method, uri);
+                req.set_version(version);
+                *req.headers_mut() = headers;
+                req.set_body(body);
+
+                next.call(req)
+                  .then(move |result| -> BoxFuture<Response> {
+                    match result {
+                      Ok(resp) => store(repo, client, key, resp),
+                      Err(err) => {
+                        repo.release(client, key).then(move |_| Err(err)).into_boxed()
+                      }
+                    }
+                  })
+                  .into_boxed()
+              }
+            }
+          })
+      })
+      .into_boxed()
+  }
+}
+
+/// Store a response's status, headers and body as they are, return the same response back.
+fn store(
+  repo: IdempotencyKeysRepo,
+  client: String,
+  key: String,
+  resp: Response,
+) -> BoxFuture<Response> {
+  let status = resp.status();
+  let headers = resp.headers().clone();
+  let stored = encode_headers(&headers);
+
+  resp
+    .body()
+    .concat2()
+    .map_err(Error::from)
+    .and_then(move |chunk| {
+      repo
+        .complete(client, key, i32::from(status.as_u16()), stored, chunk.to_vec())
+        .map(move |_| {
+          Response::new()
+            .with_status(status)
+            .with_headers(headers)
+            .with_body(chunk)
+        })
+    })
+    .into_boxed()
+}
+
+/// Respond with a stored response like the first request was responded.
+fn replay(key: IdempotencyKey) -> Response {
+  let status = StatusCode::try_from(key.status as u16).unwrap_or(StatusCode::InternalServerError);
+  let mut headers = decode_headers(&key.headers);
+  headers.set(ContentLength(key.body.len() as u64));
+  headers.set(IdempotentReplayed(true));
+
+  Response::new()
+    .with_status(status)
+    .with_headers(headers)
+    .with_body(key.body)
+}
+
+/// Headers as a JSON array of name and value pairs, a repeated header has a pair per line.
+fn encode_headers(headers: &Headers) -> String {
+  let mut pairs: Vec<(String, String)> = Vec::new();
+  for header in headers.iter() {
+    for line in header.raw().iter() {
+      pairs.push((
+        header.name().to_string(),
+        String::from_utf8_lossy(line).into_owned(),
+      ));
+    }
+  }
+
+  serde_json::to_string(&pairs).expect("json serialization cannot be fail")
+}
+
+fn decode_headers(stored: &str) -> Headers {
+  let pairs: Vec<(String, String)> = serde_json::from_str(stored).unwrap_or_default();
+  let mut headers = Headers::new();
+  for (name, value) in pairs {
+    headers.append_raw(name, value);
+  }
+  headers
+}
+
+/// A hex encoded sha256 of a request's method, path and body.
+fn fingerprint(method: &Method, path: &str, body: &[u8]) -> String {
+  let mut hasher = Sha256::default();
+  hasher.input(method.as_ref().as_bytes());
+  hasher.input(b" ");
+  hasher.input(path.as_bytes());
+  hasher.input(b"\n");
+  hasher.input(body);
+
+  to_hex(&hasher.result())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_replay_stored_response() {
+    let mut headers = Headers::new();
+    headers.set_raw("Content-Type", "application/cbor");
+    headers.set_raw("Location", "/todos/1");
+    headers.append_raw("Vary", "Accept");
+    headers.append_raw("Vary", "Accept-Encoding");
+
+    let resp = replay(IdempotencyKey {
+      fingerprint: "foo".to_string(),
+      status: 201,
+      headers: encode_headers(&headers),
+      body: vec![0xa1, 0x62, b'i', b'd', 0x01],
+    });
+
+    assert_that(&resp.status()).is_equal_to(StatusCode::Created);
+    assert_that(&resp.headers().get_raw("Location").map(|it| it.one()))
+      .is_equal_to(Some(Some(&b"/todos/1"[..])));
+    assert_that(&resp.headers().get_raw("Vary").map(|it| it.len())).is_equal_to(Some(2));
+    assert_that(&resp.headers().get::<ContentLength>()).is_equal_to(Some(&ContentLength(5)));
+    assert_that(&resp.headers().get::<IdempotentReplayed>()).is_some();
+    let body = resp.body().concat2().wait().unwrap();
+    assert_that(&body.to_vec()).is_equal_to(vec![0xa1, 0x62, b'i', b'd', 0x01]);
+  }
+
+  #[test]
+  fn should_calculate_fingerprint() {
+    let foo = fingerprint(&Method::Post, "/todos/create", b"foo");
+    let bar = fingerprint(&Method::Post, "/todos/create", b"bar");
+
+    assert_that(&foo.len()).is_equal_to(64);
+    assert_that(&foo).is_not_equal_to(&bar);
+    assert_that(&foo).is_equal_to(fingerprint(&Method::Post, "/todos/create", b"foo"));
+  }
+}
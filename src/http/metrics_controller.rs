@@ -0,0 +1,153 @@
+use futures::{future, Future};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper::header::{ContentLength, ContentType};
+use std::time::Instant;
+
+use db::ConnectionPool;
+use metrics::{self, Metrics};
+use result::Error;
+use common::FuturesExt;
+use logger;
+
+use super::middleware::{Endpoint, Middleware};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// A route label of requests which matched no route.
+const UNMATCHED: &str = "unmatched";
+
+pub struct MetricsController {
+  metrics: Metrics,
+  conn_pool: ConnectionPool,
+}
+
+impl MetricsController {
+  pub fn new(metrics: Metrics, conn_pool: ConnectionPool) -> Self {
+    MetricsController { metrics, conn_pool }
+  }
+
+  /// Render metrics in Prometheus text format, the db pool's state is sampled on every call.
+  pub fn call_metrics(&self) -> BoxFuture<Response> {
+    let state = self.conn_pool.state();
+    self.metrics.set(
+      metrics::DB_POOL_CONNECTIONS,
+      &[],
+      f64::from(state.connections),
+    );
+    self.metrics.set(
+      metrics::DB_POOL_IDLE_CONNECTIONS,
+      &[],
+      f64::from(state.idle_connections),
+    );
+
+    let body = self.metrics.render();
+    let resp = Response::new()
+      .with_header(ContentType(
+        "text/plain; version=0.0.4; charset=utf-8".parse().unwrap(),
+      ))
+      .with_header(ContentLength(body.len() as u64))
+      .with_body(body);
+
+    future::ok(resp).into_boxed()
+  }
+}
+
+/// Counts requests and measures their latency by method, route and status.
+///
+/// It must run inside `AccessLog` which keeps the matched route.
+pub struct RequestMetrics {
+  metrics: Metrics,
+}
+
+impl RequestMetrics {
+  pub fn new(metrics: Metrics) -> Self {
+    RequestMetrics { metrics }
+  }
+}
+
+impl Middleware for RequestMetrics {
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    let metrics = self.metrics.clone();
+    let method = method_label(req.method());
+    let started = Instant::now();
+
+    next
+      .call(req)
+      .then(move |result| {
+        let route = logger::current()
+          .and_then(|it| it.route)
+          .unwrap_or_else(|| UNMATCHED.to_string());
+        let status = match result {
+          Ok(ref resp) => resp.status(),
+          Err(_) => StatusCode::InternalServerError,
+        };
+        let status = status.as_u16().to_string();
+
+        let labels = &[("method", method), ("route", &route), ("status", &status)];
+        metrics.inc(metrics::HTTP_REQUESTS, labels);
+        metrics.observe(metrics::HTTP_REQUEST_DURATION, labels, started.elapsed());
+
+        result
+      })
+      .into_boxed()
+  }
+}
+
+/// Methods which clients make up are counted together.
+fn method_label(method: &Method) -> &'static str {
+  match *method {
+    Method::Options => "OPTIONS",
+    Method::Get => "GET",
+    Method::Post => "POST",
+    Method::Put => "PUT",
+    Method::Delete => "DELETE",
+    Method::Head => "HEAD",
+    Method::Trace => "TRACE",
+    Method::Connect => "CONNECT",
+    Method::Patch => "PATCH",
+    Method::Extension(ref method) => match method.as_str() {
+      "PROPFIND" => "PROPFIND",
+      "REPORT" => "REPORT",
+      _ => "other",
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use hyper::Get;
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  use logger::{Context, Scoped};
+
+  #[test]
+  fn should_count_requests_by_route() {
+    let metrics = Metrics::new();
+    let endpoint = Endpoint::new(|_| {
+      logger::set_route("/todos/{id}");
+      future::ok(Response::new().with_status(StatusCode::Created)).into_boxed()
+    }).wrap(Rc::new(RequestMetrics::new(metrics.clone())));
+
+    let context = Rc::new(RefCell::new(Context::new("abc".to_string())));
+    let req = Request::new(Get, "/todos/1".parse().unwrap());
+    let resp = logger::scope(&context, || endpoint.call(req));
+    Scoped::new(context, resp).wait().unwrap();
+
+    let req = Request::new(Method::Extension("BREW".to_string()), "/".parse().unwrap());
+    endpoint.call(req).wait().unwrap();
+
+    let text = metrics.render();
+    assert_that(&text.contains(
+      "http_requests_total{method=\"GET\",route=\"/todos/{id}\",status=\"201\"} 1",
+    )).is_true();
+    assert_that(&text.contains(
+      "http_requests_total{method=\"other\",route=\"unmatched\",status=\"201\"} 1",
+    )).is_true();
+    assert_that(&text.contains(
+      "http_request_duration_seconds_count{method=\"GET\",route=\"/todos/{id}\",status=\"201\"} 1",
+    )).is_true();
+  }
+}
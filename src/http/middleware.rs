@@ -0,0 +1,87 @@
+use futures::Future;
+use hyper::{Request, Response};
+use std::rc::Rc;
+
+use result::Error;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// A request handler which middlewares wrap, it's cheap to clone.
+#[derive(Clone)]
+pub struct Endpoint(Rc<Fn(Request) -> BoxFuture<Response>>);
+
+impl Endpoint {
+  pub fn new<F>(handler: F) -> Self
+  where
+    F: Fn(Request) -> BoxFuture<Response> + 'static,
+  {
+    Endpoint(Rc::new(handler))
+  }
+
+  pub fn call(&self, req: Request) -> BoxFuture<Response> {
+    (self.0)(req)
+  }
+
+  /// Wrap the endpoint with a middleware.
+  pub fn wrap(self, middleware: Rc<Middleware>) -> Self {
+    Endpoint::new(move |req| middleware.call(req, self.clone()))
+  }
+}
+
+/// A cross-cutting concern which runs around an endpoint.
+///
+/// A middleware either answers a request itself or passes it to `next`, it can change both the
+/// request and the response on the way.
+pub trait Middleware: 'static {
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response>;
+}
+
+impl<F> Middleware for F
+where
+  F: Fn(Request, Endpoint) -> BoxFuture<Response> + 'static,
+{
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    self(req, next)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::future;
+  use hyper::{Get, StatusCode};
+
+  use common::FuturesExt;
+
+  #[test]
+  fn should_run_middlewares_around_endpoint() {
+    let endpoint = Endpoint::new(|_| future::ok(Response::new()).into_boxed());
+
+    let teapot = |req: Request, next: Endpoint| -> BoxFuture<Response> {
+      if req.path() == "/teapot" {
+        return future::ok(Response::new().with_status(StatusCode::ImATeapot)).into_boxed();
+      }
+      next.call(req)
+    };
+    let created = |req: Request, next: Endpoint| -> BoxFuture<Response> {
+      next
+        .call(req)
+        .map(|resp| resp.with_status(StatusCode::Created))
+        .into_boxed()
+    };
+    let endpoint = endpoint.wrap(Rc::new(created)).wrap(Rc::new(teapot));
+
+    let resp = endpoint
+      .call(Request::new(Get, "/teapot".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::ImATeapot);
+
+    let resp = endpoint
+      .call(Request::new(Get, "/".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::Created);
+  }
+}
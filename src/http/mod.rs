@@ -1,5 +1,28 @@
+mod access_log;
+mod admin_auth;
+mod calendar_controller;
+mod caldav_controller;
+mod compression;
+mod content_negotiation;
+mod event_bus;
+mod events_controller;
+mod graphql;
+mod grpc;
+mod graphql_controller;
+mod idempotency;
+mod metrics_controller;
+mod middleware;
+mod openapi;
+mod peer;
+mod rate_limiter;
+mod router;
+mod rpc_controller;
 mod server;
+mod sync_controller;
 mod todos_controller;
+mod transfer_controller;
+mod webhooks_controller;
+mod websocket;
 
 #[cfg(test)]
 mod assertions;
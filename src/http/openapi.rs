@@ -0,0 +1,626 @@
+use futures::{future, Future};
+use hyper::{Method, Response};
+use serde_json::Value as JsonValue;
+
+use db::{NewTodo, Paginated, QueryTodos, Todo, UpdateTodo};
+use validators::MAX_TEXT_LEN;
+use result::Error;
+use common::{FuturesExt, ResponseExt, MEDIA_TYPES};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// A JSON schema of a request's or a response's body.
+pub trait Schema {
+  /// a name of the schema in `components`
+  fn name() -> String;
+  fn schema() -> JsonValue;
+
+  /// A reference to the schema in `components`.
+  fn reference() -> JsonValue {
+    json!({"$ref": format!("#/components/schemas/{}", Self::name())})
+  }
+}
+
+impl Schema for NewTodo {
+  fn name() -> String {
+    "NewTodo".to_string()
+  }
+
+  fn schema() -> JsonValue {
+    json!({
+      "type": "object",
+      "required": ["text"],
+      "properties": {"text": text()},
+    })
+  }
+}
+
+impl Schema for UpdateTodo {
+  fn name() -> String {
+    "UpdateTodo".to_string()
+  }
+
+  fn schema() -> JsonValue {
+    json!({
+      "type": "object",
+      "required": ["id"],
+      "properties": {
+        "id": id(),
+        "text": text(),
+        "done": {"type": "boolean"},
+        "changed_at": date_time("when the change was made, e.g. offline, it's now when missing"),
+      },
+    })
+  }
+}
+
+impl Schema for QueryTodos {
+  fn name() -> String {
+    "QueryTodos".to_string()
+  }
+
+  fn schema() -> JsonValue {
+    json!({
+      "type": "object",
+      "properties": {
+        "next": {"type": "integer", "format": "int64", "description": "a previous page's `next`"},
+        "limit": limit(),
+        "text": text(),
+      },
+    })
+  }
+}
+
+impl Schema for Todo {
+  fn name() -> String {
+    "Todo".to_string()
+  }
+
+  fn schema() -> JsonValue {
+    json!({
+      "type": "object",
+      "required": ["id", "text", "done", "created_at", "updated_at", "due_at"],
+      "properties": {
+        "id": id(),
+        "text": text(),
+        "done": {"type": "boolean"},
+        "created_at": date_time("when the item was created"),
+        "updated_at": date_time("when the item was changed last time"),
+        "due_at": {
+          "type": "string",
+          "format": "date-time",
+          "nullable": true,
+          "description": "when the item is due",
+        },
+      },
+    })
+  }
+}
+
+impl<T: Schema> Schema for Paginated<T> {
+  fn name() -> String {
+    format!("Paginated{}", T::name())
+  }
+
+  fn schema() -> JsonValue {
+    json!({
+      "type": "object",
+      "required": ["next", "items"],
+      "properties": {
+        "next": {
+          "type": "integer",
+          "format": "int64",
+          "nullable": true,
+          "description": "a next item's id",
+        },
+        "items": {"type": "array", "items": T::reference()},
+      },
+    })
+  }
+}
+
+/// A body of api errors which `handle_api_err` responds with.
+pub struct ApiError;
+
+impl Schema for ApiError {
+  fn name() -> String {
+    "Error".to_string()
+  }
+
+  fn schema() -> JsonValue {
+    json!({
+      "type": "object",
+      "required": ["error", "description"],
+      "properties": {
+        "error": {"type": "string", "description": "a message of the error"},
+        "description": {"type": "string", "description": "a kind of the error"},
+      },
+    })
+  }
+}
+
+/// A body of a documented operation's response.
+enum Body {
+  Empty,
+  Json(JsonValue),
+  /// a content type of a body which isn't JSON
+  Other(&'static str),
+}
+
+/// A documented route.
+struct Operation {
+  method: Method,
+  path: &'static str,
+  summary: &'static str,
+  /// names of query parameters with their description
+  query: &'static [(&'static str, &'static str)],
+  /// a JSON schema of the request's body
+  request: Option<JsonValue>,
+  /// a status of a successful response
+  status: u16,
+  response: Body,
+  /// statuses of expected errors, unexpected ones are documented as a default response
+  errors: &'static [u16],
+  /// an `Idempotency-Key` header is accepted
+  idempotent: bool,
+  /// `ADMIN_TOKEN` is required
+  admin: bool,
+}
+
+fn operations() -> Vec<Operation> {
+  let object = || json!({"type": "object"});
+  let list = |schema: JsonValue| json!({"type": "array", "items": schema});
+  let todos_query: &[(&str, &str)] = &[
+    ("next", "a previous page's `next`"),
+    ("limit", "how many items are returned, 1 to 10"),
+    ("text", "a text which items contain"),
+  ];
+
+  vec![
+    Operation {
+      summary: "Check the server is up",
+      response: Body::Json(json!({"type": "object", "properties": {"ok": {"type": "boolean"}}})),
+      ..Operation::new(Method::Get, "/health")
+    },
+    Operation {
+      summary: "Metrics in Prometheus text format",
+      response: Body::Other("text/plain"),
+      ..Operation::new(Method::Get, "/metrics")
+    },
+    Operation {
+      summary: "This document",
+      response: Body::Json(object()),
+      ..Operation::new(Method::Get, "/openapi.json")
+    },
+    Operation {
+      summary: "Create a todo item",
+      request: Some(NewTodo::reference()),
+      response: Body::Json(Todo::reference()),
+      errors: &[400, 412],
+      idempotent: true,
+      ..Operation::new(Method::Post, "/todos/create")
+    },
+    Operation {
+      summary: "Update a todo item",
+      request: Some(UpdateTodo::reference()),
+      response: Body::Json(Todo::reference()),
+      errors: &[400, 404, 412],
+      idempotent: true,
+      ..Operation::new(Method::Post, "/todos/update")
+    },
+    Operation {
+      summary: "Create a todo item, its location is in `Location` header",
+      request: Some(NewTodo::reference()),
+      status: 201,
+      response: Body::Json(Todo::reference()),
+      errors: &[400, 412],
+      idempotent: true,
+      ..Operation::new(Method::Post, "/todos")
+    },
+    Operation {
+      summary: "Change a todo item, missing fields are left as they are",
+      request: Some(todo_changes()),
+      response: Body::Json(Todo::reference()),
+      errors: &[400, 404, 412],
+      idempotent: true,
+      ..Operation::new(Method::Patch, "/todos/{id}")
+    },
+    Operation {
+      summary: "Import todo items in JSON lines, CSV or iCalendar",
+      query: &[
+        ("format", "`jsonl`, `csv` or `ics`, `jsonl` when missing"),
+        ("ids", "`keep` to keep ids of the input"),
+        ("dry_run", "`true` to only validate items"),
+      ],
+      response: Body::Json(object()),
+      errors: &[400, 412, 413],
+      idempotent: true,
+      ..Operation::new(Method::Post, "/todos/import")
+    },
+    Operation {
+      summary: "Upload local changes and download changes since a sync token",
+      request: Some(object()),
+      response: Body::Json(object()),
+      errors: &[400, 412],
+      idempotent: true,
+      ..Operation::new(Method::Post, "/sync")
+    },
+    Operation {
+      summary: "Query todo items",
+      request: Some(QueryTodos::reference()),
+      response: Body::Json(Paginated::<Todo>::reference()),
+      errors: &[400, 412],
+      ..Operation::new(Method::Post, "/todos/query")
+    },
+    Operation {
+      summary: "List todo items",
+      query: todos_query,
+      response: Body::Json(Paginated::<Todo>::reference()),
+      errors: &[412],
+      ..Operation::new(Method::Get, "/todos")
+    },
+    Operation {
+      summary: "Find a todo item",
+      response: Body::Json(Todo::reference()),
+      errors: &[404],
+      ..Operation::new(Method::Get, "/todos/{id}")
+    },
+    Operation {
+      summary: "Delete a todo item",
+      status: 204,
+      errors: &[404],
+      ..Operation::new(Method::Delete, "/todos/{id}")
+    },
+    Operation {
+      summary: "A server sent events stream of todo items' changes",
+      query: &[("last_event_id", "an event to resume after, like `Last-Event-ID`")],
+      response: Body::Other("text/event-stream"),
+      ..Operation::new(Method::Get, "/todos/events")
+    },
+    Operation {
+      summary: "Upgrade to a websocket which creates, updates and queries todo items",
+      status: 101,
+      errors: &[403, 426],
+      ..Operation::new(Method::Get, "/todos/ws")
+    },
+    Operation {
+      summary: "Export todo items in JSON lines, CSV or iCalendar",
+      query: &[
+        ("format", "`jsonl`, `csv` or `ics`, `jsonl` when missing"),
+        ("next", "an item to start after"),
+        ("text", "a text which items contain"),
+      ],
+      response: Body::Other("application/x-ndjson"),
+      errors: &[412],
+      ..Operation::new(Method::Get, "/todos/export")
+    },
+    Operation {
+      summary: "Execute a GraphQL request or a batch of them",
+      request: Some(object()),
+      response: Body::Json(object()),
+      errors: &[400],
+      ..Operation::new(Method::Post, "/graphql")
+    },
+    Operation {
+      summary: "Execute a JSON-RPC 2.0 call or a batch of them",
+      request: Some(object()),
+      response: Body::Json(object()),
+      errors: &[400],
+      ..Operation::new(Method::Post, "/rpc")
+    },
+    Operation {
+      summary: "An iCalendar feed of todo items",
+      query: &[("token", "a calendar token")],
+      response: Body::Other("text/calendar"),
+      errors: &[404],
+      ..Operation::new(Method::Get, "/calendar.ics")
+    },
+    Operation {
+      summary: "Create a calendar token",
+      request: Some(json!({
+        "type": "object",
+        "required": ["owner"],
+        "properties": {
+          "owner": {"type": "string"},
+          "scope": {"type": "string", "enum": ["read", "read_write"]},
+        },
+      })),
+      response: Body::Json(object()),
+      errors: &[400, 412],
+      admin: true,
+      ..Operation::new(Method::Post, "/calendar/tokens")
+    },
+    Operation {
+      summary: "Revoke a calendar token",
+      request: Some(json!({
+        "type": "object",
+        "required": ["token"],
+        "properties": {"token": {"type": "string"}},
+      })),
+      response: Body::Json(object()),
+      errors: &[400, 404],
+      admin: true,
+      ..Operation::new(Method::Post, "/calendar/tokens/revoke")
+    },
+    Operation {
+      summary: "List webhooks",
+      response: Body::Json(list(object())),
+      admin: true,
+      ..Operation::new(Method::Get, "/webhooks")
+    },
+    Operation {
+      summary: "Register a webhook, its secret is returned only here, hosts must be public",
+      request: Some(json!({
+        "type": "object",
+        "required": ["url"],
+        "properties": {
+          "url": {"type": "string", "format": "uri"},
+          "events": {"type": "array", "items": {"type": "string"}},
+        },
+      })),
+      response: Body::Json(object()),
+      errors: &[400, 412],
+      admin: true,
+      ..Operation::new(Method::Post, "/webhooks/create")
+    },
+    Operation {
+      summary: "Delete a webhook",
+      request: Some(by_id()),
+      response: Body::Json(object()),
+      errors: &[400, 404],
+      admin: true,
+      ..Operation::new(Method::Post, "/webhooks/delete")
+    },
+    Operation {
+      summary: "List deliveries which failed for good",
+      response: Body::Json(list(object())),
+      admin: true,
+      ..Operation::new(Method::Get, "/webhooks/dead_letters")
+    },
+    Operation {
+      summary: "Schedule a dead letter's delivery again",
+      request: Some(by_id()),
+      response: Body::Json(object()),
+      errors: &[400, 404],
+      admin: true,
+      ..Operation::new(Method::Post, "/webhooks/dead_letters/retry")
+    },
+  ]
+}
+
+impl Operation {
+  fn new(method: Method, path: &'static str) -> Self {
+    Operation {
+      method,
+      path,
+      summary: "",
+      query: &[],
+      request: None,
+      status: 200,
+      response: Body::Empty,
+      errors: &[],
+      idempotent: false,
+      admin: false,
+    }
+  }
+
+  fn to_json(&self) -> JsonValue {
+    let mut parameters: Vec<JsonValue> = path_params(self.path)
+      .into_iter()
+      .map(|name| json!({"name": name, "in": "path", "required": true, "schema": id()}))
+      .collect();
+    for &(name, description) in self.query {
+      parameters.push(json!({
+        "name": name,
+        "in": "query",
+        "description": description,
+        "schema": {"type": "string"},
+      }));
+    }
+    if self.idempotent {
+      parameters.push(json!({
+        "name": "Idempotency-Key",
+        "in": "header",
+        "description": "a retried request with the same key is answered with the first response",
+        "schema": {"type": "string"},
+      }));
+    }
+
+    let mut success = json!({"description": "success"});
+    match self.response {
+      Body::Empty => (),
+      Body::Json(ref schema) => success["content"] = content(schema),
+      Body::Other(content_type) => {
+        success["content"][content_type] = json!({"schema": {"type": "string"}});
+      }
+    }
+
+    let mut responses = json!({});
+    responses[self.status.to_string()] = success;
+    let mut errors = self.errors.to_vec();
+    if self.request.is_some() {
+      errors.push(415);
+    }
+    if let Body::Json(_) = self.response {
+      errors.push(406);
+    }
+    if self.idempotent {
+      errors.extend(&[409, 422]);
+    }
+    if self.admin {
+      errors.push(401);
+    }
+    for status in errors {
+      responses[status.to_string()] = error_response(error_description(status));
+    }
+    responses["default"] = error_response("an unexpected error");
+
+    let mut operation = json!({
+      "summary": self.summary,
+      "parameters": parameters,
+      "responses": responses,
+    });
+    if self.admin {
+      operation["security"] = json!([{"admin": []}]);
+    }
+    if let Some(ref schema) = self.request {
+      operation["requestBody"] = json!({
+        "required": true,
+        "content": content(schema),
+      });
+    }
+
+    operation
+  }
+}
+
+/// Generate an OpenAPI 3 document of the api.
+pub fn spec() -> JsonValue {
+  let mut paths = json!({});
+  for operation in operations() {
+    let method = operation.method.to_string().to_lowercase();
+    paths[operation.path][method] = operation.to_json();
+  }
+
+  let schemas: Vec<(String, JsonValue)> = vec![
+    (NewTodo::name(), NewTodo::schema()),
+    (UpdateTodo::name(), UpdateTodo::schema()),
+    (QueryTodos::name(), QueryTodos::schema()),
+    (Todo::name(), Todo::schema()),
+    (Paginated::<Todo>::name(), Paginated::<Todo>::schema()),
+    (ApiError::name(), ApiError::schema()),
+  ];
+  let mut components = json!({});
+  for (name, schema) in schemas {
+    components[name] = schema;
+  }
+
+  json!({
+    "openapi": "3.0.3",
+    "info": {"title": "todo-demo", "version": env!("CARGO_PKG_VERSION")},
+    "paths": paths,
+    "components": {
+      "schemas": components,
+      "securitySchemes": {"admin": {"type": "http", "scheme": "bearer"}},
+    },
+  })
+}
+
+/// `GET /openapi.json`
+pub fn call_spec() -> BoxFuture<Response> {
+  future::ok(Response::new().json(&spec())).into_boxed()
+}
+
+fn text() -> JsonValue {
+  json!({"type": "string", "minLength": 1, "maxLength": MAX_TEXT_LEN})
+}
+
+fn id() -> JsonValue {
+  json!({"type": "integer", "format": "int64", "minimum": 1})
+}
+
+fn limit() -> JsonValue {
+  json!({
+    "type": "integer",
+    "minimum": 1,
+    "maximum": 10,
+    "default": 10,
+    "description": "how many items are returned, it's 10 when out of range",
+  })
+}
+
+fn date_time(description: &str) -> JsonValue {
+  json!({"type": "string", "format": "date-time", "description": description})
+}
+
+/// `PATCH /todos/{id}` takes `UpdateTodo` without an id.
+fn todo_changes() -> JsonValue {
+  let mut schema = UpdateTodo::schema();
+  if let Some(properties) = schema["properties"].as_object_mut() {
+    properties.remove("id");
+  }
+  schema["required"] = json!([]);
+  schema
+}
+
+fn by_id() -> JsonValue {
+  json!({"type": "object", "required": ["id"], "properties": {"id": id()}})
+}
+
+/// Api bodies could be encoded with any of `MEDIA_TYPES`.
+fn content(schema: &JsonValue) -> JsonValue {
+  let mut content = json!({});
+  for media_type in MEDIA_TYPES {
+    content[media_type.as_str()] = json!({ "schema": schema });
+  }
+  content
+}
+
+fn error_response(description: &str) -> JsonValue {
+  json!({
+    "description": description,
+    "content": content(&ApiError::reference()),
+  })
+}
+
+/// Describe statuses which `error_status` maps errors to.
+fn error_description(status: u16) -> &'static str {
+  match status {
+    400 => "the body cannot be parsed",
+    401 => "`ADMIN_TOKEN` is missing or wrong",
+    403 => "the page's origin isn't allowed by `WS_ALLOWED_ORIGINS`",
+    404 => "the item doesn't exist",
+    406 => "none of accepted media types is supported",
+    409 => "a request with the same idempotency key is in progress",
+    412 => "the request is invalid",
+    413 => "the body is larger than `MAX_BODY_SIZE`",
+    415 => "the body's media type isn't supported",
+    422 => "the idempotency key was used with another request",
+    426 => "the request isn't a websocket upgrade",
+    _ => "an error",
+  }
+}
+
+/// Names of parameters like `{id}` in a path.
+fn path_params(path: &str) -> Vec<&str> {
+  path
+    .split('/')
+    .filter(|it| it.starts_with('{') && it.ends_with('}'))
+    .map(|it| &it[1..it.len() - 1])
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_describe_todos_with_constraints() {
+    let spec = spec();
+    let schemas = &spec["components"]["schemas"];
+    assert_that(&schemas["NewTodo"]["properties"]["text"]["maxLength"])
+      .is_equal_to(&json!(MAX_TEXT_LEN));
+    assert_that(&schemas["UpdateTodo"]["properties"]["id"]["minimum"]).is_equal_to(&json!(1));
+    assert_that(&schemas["PaginatedTodo"]["properties"]["items"]["items"]["$ref"])
+      .is_equal_to(&json!("#/components/schemas/Todo"));
+
+    let patch = &spec["paths"]["/todos/{id}"]["patch"];
+    assert_that(&patch["parameters"][0]["name"]).is_equal_to(&json!("id"));
+    assert_that(&patch["requestBody"]["content"]["application/json"]["schema"]["properties"]
+      .get("id"))
+      .is_none();
+    assert_that(&patch["responses"]["412"]["content"]["application/json"]["schema"]["$ref"])
+      .is_equal_to(&json!("#/components/schemas/Error"));
+  }
+
+  #[test]
+  fn should_document_path_params() {
+    let spec = spec();
+    let find = &spec["paths"]["/todos/{id}"]["get"];
+    assert_that(&find["parameters"][0]["in"]).is_equal_to(&json!("path"));
+    assert_that(&spec["paths"]["/todos"]["get"]["parameters"][0]["in"])
+      .is_equal_to(&json!("query"));
+    assert_that(&path_params("/todos/{id}/x")).is_equal_to(vec!["id"]);
+  }
+}
@@ -0,0 +1,25 @@
+use std::cell::Cell;
+use std::net::SocketAddr;
+
+thread_local! {
+  static PEER: Cell<Option<SocketAddr>> = Cell::new(None);
+}
+
+/// The address of the client which the current request came from.
+///
+/// The server serves connections itself to upgrade them, so hyper doesn't put addresses to
+/// requests. A connection's address is current while the connection is polled.
+pub fn addr() -> Option<SocketAddr> {
+  PEER.with(|it| it.get())
+}
+
+/// Call `f` with `peer` as the current client's address.
+pub fn scope<F, R>(peer: Option<SocketAddr>, f: F) -> R
+where
+  F: FnOnce() -> R,
+{
+  let previous = PEER.with(|it| it.replace(peer));
+  let result = f();
+  PEER.with(|it| it.set(previous));
+  result
+}
@@ -0,0 +1,364 @@
+use futures::{future, Future};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper::header::{ContentLength, RetryAfter};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use config::{Config, RateLimit};
+use result::Error;
+use common::FuturesExt;
+
+use super::admin_auth::is_admin;
+use super::middleware::{Endpoint, Middleware};
+use super::peer;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// How many buckets could be kept, the least recently used one is dropped for a new client.
+const MAX_BUCKETS: usize = 10_000;
+
+header! { (RateLimitLimit, "RateLimit-Limit") => [u32] }
+header! { (RateLimitRemaining, "RateLimit-Remaining") => [u32] }
+header! { (RateLimitReset, "RateLimit-Reset") => [u64] }
+
+/// A class of routes which share the same limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+  Read,
+  Write,
+}
+
+impl RouteClass {
+  /// Classify a request, returns `None` for routes which are never limited.
+  pub fn of(method: &Method, path: &str) -> Option<Self> {
+    match (method, path) {
+      (_, "/health") | (_, "/metrics") => None,
+      (&Method::Post, "/todos/query") => Some(RouteClass::Read),
+      (&Method::Get, _) | (&Method::Head, _) | (&Method::Options, _) => Some(RouteClass::Read),
+      (&Method::Extension(ref method), _) if method == "PROPFIND" || method == "REPORT" => {
+        Some(RouteClass::Read)
+      }
+      _ => Some(RouteClass::Write),
+    }
+  }
+}
+
+/// A result of an attempt to take a token from the bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitStatus {
+  /// bucket's capacity
+  pub limit: u32,
+  /// tokens left in the bucket
+  pub remaining: u32,
+  /// seconds until the bucket is completely refilled
+  pub reset: u64,
+  /// seconds until the next token, zero when the request was allowed
+  pub retry_after: u64,
+}
+
+impl RateLimitStatus {
+  pub fn is_allowed(&self) -> bool {
+    self.retry_after == 0
+  }
+
+  /// Add `RateLimit-*` headers to a response.
+  pub fn apply(&self, mut resp: Response) -> Response {
+    {
+      let headers = resp.headers_mut();
+      headers.set(RateLimitLimit(self.limit));
+      headers.set(RateLimitRemaining(self.remaining));
+      headers.set(RateLimitReset(self.reset));
+    }
+    resp
+  }
+
+  /// Create a `429 Too Many Requests` response.
+  pub fn too_many_requests(&self) -> Response {
+    let resp = Response::new()
+      .with_status(StatusCode::TooManyRequests)
+      .with_header(RetryAfter::Delay(Duration::from_secs(self.retry_after)))
+      .with_header(ContentLength(0));
+
+    self.apply(resp)
+  }
+}
+
+type BucketKey = (RouteClass, String);
+
+#[derive(Debug, Clone)]
+struct Bucket {
+  tokens: f64,
+  updated_at: Instant,
+  /// a position in `Buckets::lru`
+  used: u64,
+}
+
+/// Buckets of clients with their order of use, so a new client evicts only one of them.
+#[derive(Default)]
+struct Buckets {
+  map: HashMap<BucketKey, Bucket>,
+  lru: BTreeMap<u64, BucketKey>,
+  uses: u64,
+}
+
+impl Buckets {
+  /// Get a client's bucket and mark it as the most recently used one.
+  fn touch(&mut self, key: BucketKey, capacity: f64, now: Instant) -> &mut Bucket {
+    self.uses += 1;
+    let used = self.uses;
+
+    match self.map.get(&key).map(|it| it.used) {
+      Some(previous) => {
+        self.lru.remove(&previous);
+      }
+      None if self.map.len() >= MAX_BUCKETS => {
+        let oldest = self.lru.keys().next().cloned();
+        if let Some(evicted) = oldest.and_then(|it| self.lru.remove(&it)) {
+          self.map.remove(&evicted);
+        }
+      }
+      None => {}
+    }
+    self.lru.insert(used, key.clone());
+
+    let bucket = self.map.entry(key).or_insert_with(|| Bucket {
+      tokens: capacity,
+      updated_at: now,
+      used,
+    });
+    bucket.used = used;
+    bucket
+  }
+}
+
+/// Per client token bucket rate limiter.
+#[derive(Clone)]
+pub struct RateLimiter {
+  read: RateLimit,
+  write: RateLimit,
+  admin_token: String,
+  buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+  pub fn new(cfg: &Config) -> Self {
+    RateLimiter {
+      read: cfg.rate_limit_read,
+      write: cfg.rate_limit_write,
+      admin_token: cfg.admin_token.clone(),
+      buckets: Arc::new(Mutex::new(Buckets::default())),
+    }
+  }
+
+  /// Take a token for a given request, returns `None` when route isn't limited.
+  pub fn check(&self, req: &Request) -> Option<RateLimitStatus> {
+    RouteClass::of(req.method(), req.path())
+      .map(|class| self.acquire(class, &client_key(req, &self.admin_token), Instant::now()))
+  }
+
+  /// Take a token from a client's bucket at a given time.
+  pub fn acquire(&self, class: RouteClass, key: &str, now: Instant) -> RateLimitStatus {
+    let limit = self.limit(class);
+    let capacity = f64::from(limit.requests);
+    let rate = capacity / as_secs_f64(limit.period);
+
+    let mut buckets = self.buckets.lock().expect("rate limiter lock is poisoned");
+    let bucket = buckets.touch((class, key.to_string()), capacity, now);
+
+    let elapsed = as_secs_f64(now.duration_since(bucket.updated_at));
+    bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+    bucket.updated_at = now;
+
+    let retry_after = if bucket.tokens >= 1.0 {
+      bucket.tokens -= 1.0;
+      0
+    } else {
+      ((1.0 - bucket.tokens) / rate).ceil().max(1.0) as u64
+    };
+
+    RateLimitStatus {
+      limit: limit.requests,
+      remaining: bucket.tokens.floor() as u32,
+      reset: ((capacity - bucket.tokens) / rate).ceil() as u64,
+      retry_after,
+    }
+  }
+
+  /// Take a write token for a client's command which isn't an http request.
+  pub fn limit_write(&self, key: &str) -> Result<(), Error> {
+    let status = self.acquire(RouteClass::Write, key, Instant::now());
+    if status.is_allowed() {
+      Ok(())
+    } else {
+      Err(Error::TooManyRequests(status.retry_after))
+    }
+  }
+
+  fn limit(&self, class: RouteClass) -> RateLimit {
+    match class {
+      RouteClass::Read => self.read,
+      RouteClass::Write => self.write,
+    }
+  }
+}
+
+impl Middleware for RateLimiter {
+  /// Reject requests over the limit with `429 Too Many Requests`, add limit's headers otherwise.
+  fn call(&self, req: Request, next: Endpoint) -> BoxFuture<Response> {
+    let limit = self.check(&req);
+
+    match limit {
+      Some(ref limit) if !limit.is_allowed() => {
+        warn!("rate limited {} {}", req.method(), req.path());
+        return future::ok(limit.too_many_requests()).into_boxed();
+      }
+      _ => {}
+    }
+
+    next
+      .call(req)
+      .map(move |resp| match limit {
+        Some(limit) => limit.apply(resp),
+        None => resp,
+      })
+      .into_boxed()
+  }
+}
+
+/// Identify a client by the token it's authenticated with, by its ip address otherwise.
+///
+/// Only `ADMIN_TOKEN` is checked, an unchecked token would be the client's own choice and a new
+/// one would give it a new bucket. Calendar tokens are looked up in the database by their routes,
+/// so their requests are keyed by ip and a lookup doesn't run before the limit.
+pub fn client_key(req: &Request, admin_token: &str) -> String {
+  if is_admin(req, admin_token) {
+    return "token:admin".to_string();
+  }
+  ip_key(peer::addr())
+}
+
+/// Identify a client by its ip address.
+pub fn ip_key(addr: Option<SocketAddr>) -> String {
+  match addr {
+    Some(addr) => format!("ip:{}", addr.ip()),
+    None => "ip:unknown".to_string(),
+  }
+}
+
+fn as_secs_f64(duration: Duration) -> f64 {
+  duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_classify_routes() {
+    assert_that(&RouteClass::of(&Method::Get, "/health")).is_none();
+    assert_that(&RouteClass::of(&Method::Get, "/metrics")).is_none();
+    assert_that(&RouteClass::of(&Method::Post, "/todos/query")).is_equal_to(Some(RouteClass::Read));
+    assert_that(&RouteClass::of(&Method::Post, "/todos/create"))
+      .is_equal_to(Some(RouteClass::Write));
+    assert_that(&RouteClass::of(&Method::Extension("PROPFIND".to_string()), "/dav/"))
+      .is_equal_to(Some(RouteClass::Read));
+  }
+
+  #[test]
+  fn should_limit_requests() {
+    let limiter = create_limiter();
+    let now = Instant::now();
+
+    let status = limiter.acquire(RouteClass::Write, "foo", now);
+    assert_that(&status.is_allowed()).is_true();
+    assert_that(&status.remaining).is_equal_to(1);
+
+    let status = limiter.acquire(RouteClass::Write, "foo", now);
+    assert_that(&status.is_allowed()).is_true();
+    assert_that(&status.remaining).is_equal_to(0);
+
+    let status = limiter.acquire(RouteClass::Write, "foo", now);
+    assert_that(&status.is_allowed()).is_false();
+    assert_that(&status.retry_after).is_equal_to(5);
+    assert_that(&status.reset).is_equal_to(10);
+
+    let status = limiter.acquire(RouteClass::Write, "bar", now);
+    assert_that(&status.is_allowed()).is_true();
+
+    let status = limiter.acquire(RouteClass::Read, "foo", now);
+    assert_that(&status.is_allowed()).is_true();
+  }
+
+  #[test]
+  fn should_limit_writes_of_commands() {
+    let limiter = create_limiter();
+    let status = limiter.acquire(RouteClass::Write, "ip:10.0.0.1", Instant::now());
+    assert_that(&status.is_allowed()).is_true();
+
+    assert_that(&limiter.limit_write("ip:10.0.0.1")).is_ok();
+    match limiter.limit_write("ip:10.0.0.1") {
+      Err(Error::TooManyRequests(retry_after)) => assert_that(&retry_after).is_equal_to(5),
+      other => panic!("unexpected result {:?}", other),
+    }
+    assert_that(&limiter.limit_write("ip:10.0.0.2")).is_ok();
+  }
+
+  #[test]
+  fn should_refill_bucket() {
+    let limiter = create_limiter();
+    let now = Instant::now();
+
+    limiter.acquire(RouteClass::Write, "foo", now);
+    limiter.acquire(RouteClass::Write, "foo", now);
+
+    let status = limiter.acquire(RouteClass::Write, "foo", now + Duration::from_secs(5));
+    assert_that(&status.is_allowed()).is_true();
+    assert_that(&status.remaining).is_equal_to(0);
+  }
+
+  #[test]
+  fn should_evict_least_recently_used_bucket() {
+    let limiter = create_limiter();
+    let now = Instant::now();
+
+    limiter.acquire(RouteClass::Write, "foo", now);
+    limiter.acquire(RouteClass::Write, "foo", now);
+    for i in 0..MAX_BUCKETS {
+      limiter.acquire(RouteClass::Read, &i.to_string(), now);
+    }
+    let len = limiter.buckets.lock().unwrap().map.len();
+    assert_that(&len).is_equal_to(MAX_BUCKETS);
+
+    let status = limiter.acquire(RouteClass::Write, "foo", now);
+    assert_that(&status.is_allowed()).is_true();
+    let status = limiter.acquire(RouteClass::Read, &(MAX_BUCKETS - 1).to_string(), now);
+    assert_that(&status.remaining).is_equal_to(limiter.read.requests - 2);
+  }
+
+  #[test]
+  fn should_key_clients_by_token() {
+    let addr: SocketAddr = "10.0.0.1:4000".parse().unwrap();
+    let mut req = Request::new(Method::Get, "/todos".parse().unwrap());
+    let key = peer::scope(Some(addr), || client_key(&req, "secret"));
+    assert_that(&key.as_str()).is_equal_to("ip:10.0.0.1");
+
+    req.headers_mut().set_raw("Authorization", "Bearer secret");
+    let key = peer::scope(Some(addr), || client_key(&req, "secret"));
+    assert_that(&key.as_str()).is_equal_to("token:admin");
+
+    req.headers_mut().set_raw("Authorization", "Bearer other");
+    let key = peer::scope(Some(addr), || client_key(&req, "secret"));
+    assert_that(&key.as_str()).is_equal_to("ip:10.0.0.1");
+  }
+
+  fn create_limiter() -> RateLimiter {
+    let cfg = Config {
+      rate_limit_write: "2/10".parse().unwrap(),
+      ..Config::default()
+    };
+    RateLimiter::new(&cfg)
+  }
+}
@@ -0,0 +1,493 @@
+use futures::{future, Future};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use hyper::header::{Allow, ContentLength};
+use std::rc::Rc;
+
+use result::Error;
+use common::{FuturesExt, ResponseExt};
+use logger;
+
+use super::middleware::{Endpoint, Middleware};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+type Handler<S> = Rc<Fn(&S, Request, Params) -> BoxFuture<Response>>;
+
+/// Parameters of a matched route's path.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Params(Vec<(String, String)>);
+
+impl Params {
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self
+      .0
+      .iter()
+      .find(|it| it.0 == name)
+      .map(|it| it.1.as_str())
+  }
+
+  /// Parse an id, a malformed id is reported like a missing item.
+  pub fn id(&self, name: &str) -> Result<i64, Error> {
+    match self.get(name).map(|it| it.parse::<i64>()) {
+      Some(Ok(id)) if id > 0 => Ok(id),
+      _ => Err(Error::RecordNotFound),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+  Literal(String),
+  Param(String),
+  /// matches the rest of a path, even when it's empty
+  Rest,
+}
+
+#[derive(Debug, Clone)]
+struct Pattern(Vec<Segment>);
+
+impl Pattern {
+  /// Parse a pattern like `/todos/{id}`, a `*` segment matches the rest of a path.
+  fn parse(pattern: &str) -> Self {
+    let segments = split(pattern)
+      .map(|it| {
+        if it == "*" {
+          Segment::Rest
+        } else if it.len() > 2 && it.starts_with('{') && it.ends_with('}') {
+          Segment::Param(it[1..it.len() - 1].to_string())
+        } else {
+          Segment::Literal(it.to_string())
+        }
+      })
+      .collect();
+
+    Pattern(segments)
+  }
+
+  fn matches(&self, path: &str) -> Option<Params> {
+    let mut params = Vec::new();
+    let mut parts = split(path);
+
+    for segment in &self.0 {
+      match *segment {
+        Segment::Rest => return Some(Params(params)),
+        Segment::Literal(ref literal) => {
+          if parts.next() != Some(literal.as_str()) {
+            return None;
+          }
+        }
+        Segment::Param(ref name) => match parts.next() {
+          Some(part) if !part.is_empty() => params.push((name.clone(), part.to_string())),
+          _ => return None,
+        },
+      }
+    }
+
+    match parts.next() {
+      Some(_) => None,
+      None => Some(Params(params)),
+    }
+  }
+
+  /// Literal segments are more specific than parameters and those are more specific than `*`.
+  fn specificity(&self) -> Vec<u8> {
+    self
+      .0
+      .iter()
+      .map(|it| match *it {
+        Segment::Literal(_) => 2,
+        Segment::Param(_) => 1,
+        Segment::Rest => 0,
+      })
+      .collect()
+  }
+}
+
+struct Route<S> {
+  /// `None` matches any method
+  method: Option<Method>,
+  path: String,
+  pattern: Pattern,
+  handler: Handler<S>,
+}
+
+/// Routes requests by their method and path to handlers which get a shared state.
+///
+/// The most specific route wins when a few of them match a path, `/todos/events` wins over
+/// `/todos/{id}` for example. Groups share a path prefix and middlewares.
+///
+/// A path which matches with another method is answered with `405 Method Not Allowed`,
+/// `OPTIONS` is answered with the allowed methods and `HEAD` is handled by `GET` routes.
+pub struct Router<S> {
+  routes: Vec<Route<S>>,
+  middlewares: Vec<Rc<Middleware>>,
+}
+
+impl<S> Default for Router<S> {
+  fn default() -> Self {
+    Router {
+      routes: Vec::new(),
+      middlewares: Vec::new(),
+    }
+  }
+}
+
+impl<S: Clone + 'static> Router<S> {
+  pub fn new() -> Self {
+    Router::default()
+  }
+
+  /// Wrap router's routes with a middleware, the first one added runs first.
+  pub fn wrap<M: Middleware>(&mut self, middleware: M) -> &mut Self {
+    self.middlewares.push(Rc::new(middleware));
+    self
+  }
+
+  pub fn get<H>(&mut self, path: &str, handler: H) -> &mut Self
+  where
+    H: Fn(&S, Request, Params) -> BoxFuture<Response> + 'static,
+  {
+    self.route(Some(Method::Get), path, handler)
+  }
+
+  pub fn post<H>(&mut self, path: &str, handler: H) -> &mut Self
+  where
+    H: Fn(&S, Request, Params) -> BoxFuture<Response> + 'static,
+  {
+    self.route(Some(Method::Post), path, handler)
+  }
+
+  pub fn patch<H>(&mut self, path: &str, handler: H) -> &mut Self
+  where
+    H: Fn(&S, Request, Params) -> BoxFuture<Response> + 'static,
+  {
+    self.route(Some(Method::Patch), path, handler)
+  }
+
+  pub fn delete<H>(&mut self, path: &str, handler: H) -> &mut Self
+  where
+    H: Fn(&S, Request, Params) -> BoxFuture<Response> + 'static,
+  {
+    self.route(Some(Method::Delete), path, handler)
+  }
+
+  /// A route for any method.
+  pub fn any<H>(&mut self, path: &str, handler: H) -> &mut Self
+  where
+    H: Fn(&S, Request, Params) -> BoxFuture<Response> + 'static,
+  {
+    self.route(None, path, handler)
+  }
+
+  pub fn route<H>(&mut self, method: Option<Method>, path: &str, handler: H) -> &mut Self
+  where
+    H: Fn(&S, Request, Params) -> BoxFuture<Response> + 'static,
+  {
+    self.routes.push(Route {
+      method,
+      path: path.to_string(),
+      pattern: Pattern::parse(path),
+      handler: Rc::new(handler),
+    });
+    self
+  }
+
+  /// Add routes which paths start with `prefix`, group's middlewares wrap only them.
+  pub fn group<F>(&mut self, prefix: &str, build: F) -> &mut Self
+  where
+    F: FnOnce(&mut Router<S>),
+  {
+    let mut group = Router::new();
+    build(&mut group);
+
+    let Router {
+      routes,
+      middlewares,
+    } = group;
+    for route in routes {
+      let handler = middlewares
+        .iter()
+        .rev()
+        .fold(route.handler, |handler, it| wrap(it.clone(), handler));
+      let path = format!("{}{}", prefix, route.path);
+
+      self.routes.push(Route {
+        method: route.method,
+        pattern: Pattern::parse(&path),
+        path,
+        handler,
+      });
+    }
+
+    self
+  }
+
+  /// Methods and paths of routes, `None` is any method.
+  #[cfg(test)]
+  pub fn routes(&self) -> Vec<(Option<Method>, &str)> {
+    self
+      .routes
+      .iter()
+      .map(|it| (it.method.clone(), it.path.as_str()))
+      .collect()
+  }
+
+  /// Create an endpoint which handles requests with a given state.
+  pub fn into_endpoint(self, state: S) -> Endpoint {
+    let Router {
+      routes,
+      middlewares,
+    } = self;
+
+    let endpoint = Endpoint::new(move |req| dispatch(&routes, &state, req));
+    middlewares
+      .into_iter()
+      .rev()
+      .fold(endpoint, |endpoint, it| endpoint.wrap(it))
+  }
+}
+
+fn dispatch<S>(routes: &[Route<S>], state: &S, req: Request) -> BoxFuture<Response> {
+  let matched: Vec<_> = routes
+    .iter()
+    .filter_map(|route| route.pattern.matches(req.path()).map(|params| (route, params)))
+    .collect();
+
+  // routes of the most specific pattern only, `GET /todos/events` doesn't fall back to
+  // `PATCH /todos/{id}` for example
+  let specificity = matched
+    .iter()
+    .map(|it| it.0.pattern.specificity())
+    .max();
+  let matched: Vec<_> = matched
+    .into_iter()
+    .filter(|it| Some(it.0.pattern.specificity()) == specificity)
+    .collect();
+
+  if matched.is_empty() {
+    warn!("not found {} {}", req.method(), req.path());
+    return future::ok(Response::new().not_found()).into_boxed();
+  }
+
+  let method = req.method().clone();
+  let found = matched
+    .iter()
+    .find(|it| it.0.method.as_ref().map_or(true, |it| *it == method))
+    .or_else(|| match method {
+      Method::Head => matched
+        .iter()
+        .find(|it| it.0.method == Some(Method::Get)),
+      _ => None,
+    });
+
+  if let Some(&(route, _)) = found {
+    logger::set_route(&route.path);
+  }
+
+  match found {
+    Some(&(route, ref params)) if method == Method::Head => {
+      let resp = (route.handler)(state, req, params.clone());
+      resp
+        .map(|mut resp| {
+          // headers are kept as they are, `Content-Length` tells the size of a GET's body
+          resp.set_body(Body::empty());
+          resp
+        })
+        .into_boxed()
+    }
+    Some(&(route, ref params)) => (route.handler)(state, req, params.clone()),
+    None => {
+      let allow = allowed(matched.iter().map(|it| it.0));
+      let resp = if method == Method::Options {
+        Response::new().with_status(StatusCode::NoContent)
+      } else {
+        warn!("method not allowed {} {}", req.method(), req.path());
+        Response::new().with_status(StatusCode::MethodNotAllowed)
+      };
+
+      future::ok(resp.with_header(Allow(allow)).with_header(ContentLength(0))).into_boxed()
+    }
+  }
+}
+
+/// Methods of routes, `HEAD` and `OPTIONS` are answered for every path.
+fn allowed<'a, S: 'a, I>(routes: I) -> Vec<Method>
+where
+  I: Iterator<Item = &'a Route<S>>,
+{
+  let mut methods = Vec::new();
+  for method in routes.filter_map(|it| it.method.clone()) {
+    if method == Method::Get && !methods.contains(&Method::Head) {
+      methods.push(Method::Head);
+    }
+    if !methods.contains(&method) {
+      methods.push(method);
+    }
+  }
+  if !methods.contains(&Method::Options) {
+    methods.push(Method::Options);
+  }
+
+  methods
+}
+
+fn wrap<S: Clone + 'static>(middleware: Rc<Middleware>, handler: Handler<S>) -> Handler<S> {
+  Rc::new(move |state: &S, req, params| {
+    let handler = handler.clone();
+    let state = state.clone();
+    middleware.call(req, Endpoint::new(move |req| handler(&state, req, params.clone())))
+  })
+}
+
+fn split<'a>(path: &'a str) -> ::std::str::Split<'a, char> {
+  let path = if path.starts_with('/') {
+    &path[1..]
+  } else {
+    path
+  };
+  path.split('/')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::Stream;
+  use hyper::{Get, Head, Post};
+
+  #[test]
+  fn should_match_patterns() {
+    let pattern = Pattern::parse("/todos/{id}");
+    let params = pattern.matches("/todos/42").unwrap();
+    assert_that(&params.get("id")).is_equal_to(Some("42"));
+    assert_that(&params.id("id").ok()).is_equal_to(Some(42));
+    assert_that(&pattern.matches("/todos/")).is_none();
+    assert_that(&pattern.matches("/todos/42/foo")).is_none();
+    assert_that(&pattern.matches("/todo/42")).is_none();
+
+    let pattern = Pattern::parse("/dav/*");
+    assert_that(&pattern.matches("/dav")).is_some();
+    assert_that(&pattern.matches("/dav/")).is_some();
+    assert_that(&pattern.matches("/dav/todos/1.ics")).is_some();
+    assert_that(&pattern.matches("/davx")).is_none();
+
+    assert_that(&Pattern::parse("/").matches("/")).is_some();
+    assert_that(&Params::default().id("id")).is_err();
+  }
+
+  #[test]
+  fn should_route_to_most_specific_route() {
+    let mut router = Router::new();
+    router
+      .get("/todos/{id}", |state: &String, _, params| {
+        respond(format!("{} {}", state, params.get("id").unwrap()))
+      })
+      .get("/todos/events", |_, _, _| respond("events".to_string()))
+      .post("/todos/{id}", |_, _, _| respond("post".to_string()))
+      .any("/*", |_, _, _| respond("any".to_string()));
+    let endpoint = router.into_endpoint("todo".to_string());
+
+    assert_that(&call(&endpoint, Get, "/todos/1")).is_equal_to("todo 1".to_string());
+    assert_that(&call(&endpoint, Get, "/todos/events")).is_equal_to("events".to_string());
+    assert_that(&call(&endpoint, Post, "/todos/1")).is_equal_to("post".to_string());
+    assert_that(&call(&endpoint, Post, "/todos")).is_equal_to("any".to_string());
+  }
+
+  #[test]
+  fn should_route_groups_through_their_middlewares() {
+    let mut router = Router::new();
+    router
+      .wrap(|req: Request, next: Endpoint| -> BoxFuture<Response> {
+        next
+          .call(req)
+          .map(|resp| resp.with_header(ContentLength(0)))
+          .into_boxed()
+      })
+      .get("/health", |_: &(), _, _| respond("ok".to_string()))
+      .group("/todos", |group| {
+        group
+          .wrap(|req: Request, next: Endpoint| -> BoxFuture<Response> {
+            next
+              .call(req)
+              .map(|resp| resp.with_status(StatusCode::Accepted))
+              .into_boxed()
+          })
+          .get("", |_, _, _| respond("todos".to_string()))
+          .get("/{id}", |_, _, params| respond(params.get("id").unwrap().to_string()));
+      });
+    let endpoint = router.into_endpoint(());
+
+    let resp = endpoint
+      .call(Request::new(Get, "/todos/1".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::Accepted);
+    assert_that(&resp.headers().get::<ContentLength>()).is_some();
+
+    let resp = endpoint
+      .call(Request::new(Get, "/health".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::Ok);
+
+    assert_that(&call(&endpoint, Get, "/todos")).is_equal_to("todos".to_string());
+
+    let resp = endpoint
+      .call(Request::new(Get, "/foo".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::NotFound);
+  }
+
+  #[test]
+  fn should_answer_wrong_methods_options_and_head() {
+    let mut router = Router::new();
+    router
+      .get("/todos/{id}", |_: &(), _, _| respond("todo".to_string()))
+      .patch("/todos/{id}", |_, _, _| respond("patch".to_string()))
+      .post("/todos/create", |_, _, _| respond("create".to_string()));
+    let endpoint = router.into_endpoint(());
+
+    let resp = endpoint
+      .call(Request::new(Get, "/todos/create".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::MethodNotAllowed);
+    assert_that(&resp.headers().get::<Allow>())
+      .is_equal_to(Some(&Allow(vec![Method::Post, Method::Options])));
+
+    let resp = endpoint
+      .call(Request::new(Method::Options, "/todos/1".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::NoContent);
+    let allow = vec![Method::Head, Method::Get, Method::Patch, Method::Options];
+    assert_that(&resp.headers().get::<Allow>()).is_equal_to(Some(&Allow(allow)));
+
+    let resp = endpoint
+      .call(Request::new(Head, "/todos/1".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::Ok);
+    let body = resp.body().concat2().wait().unwrap();
+    assert_that(&body.is_empty()).is_true();
+
+    let resp = endpoint
+      .call(Request::new(Get, "/todos".parse().unwrap()))
+      .wait()
+      .unwrap();
+    assert_that(&resp.status()).is_equal_to(StatusCode::NotFound);
+  }
+
+  fn respond(body: String) -> BoxFuture<Response> {
+    future::ok(Response::new().with_body(body)).into_boxed()
+  }
+
+  fn call(endpoint: &Endpoint, method: Method, path: &str) -> String {
+    let resp = endpoint
+      .call(Request::new(method, path.parse().unwrap()))
+      .wait()
+      .unwrap();
+    let body = resp.body().concat2().wait().unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+  }
+}
@@ -0,0 +1,323 @@
+use futures::{future, stream, Future, IntoFuture, Stream};
+use hyper::{Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value as JsonValue};
+use std::error::Error as StdError;
+
+use result::Error;
+use db::{NewTodo, QueryTodos, TodosRepo, UpdateTodo};
+use common::{FuturesExt, RequestExt, ResponseExt};
+use validators::Validator;
+
+use super::server::error_status;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// a requested item doesn't exist
+const NOT_FOUND: i64 = -32001;
+/// an item was changed by another request
+const CONFLICT: i64 = -32002;
+
+/// How many calls a batch can have.
+const MAX_BATCH: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct FindTodo {
+  id: i64,
+}
+
+/// A call's error, `data` describes the error which caused it.
+#[derive(Debug)]
+struct RpcError {
+  code: i64,
+  message: &'static str,
+  data: Option<JsonValue>,
+}
+
+impl RpcError {
+  fn new(code: i64, message: &'static str) -> Self {
+    RpcError {
+      code,
+      message,
+      data: None,
+    }
+  }
+
+  fn to_json(&self) -> JsonValue {
+    let mut error = json!({"code": self.code, "message": self.message});
+    if let Some(ref data) = self.data {
+      error["data"] = data.clone();
+    }
+    error
+  }
+}
+
+impl From<Error> for RpcError {
+  fn from(err: Error) -> Self {
+    let (code, message) = match err {
+      Error::JsonParse(_) | Error::Validation(_) => (INVALID_PARAMS, "Invalid params"),
+      Error::RecordNotFound => (NOT_FOUND, "Not found"),
+      Error::PreconditionFailed => (CONFLICT, "Conflict"),
+      _ => (INTERNAL_ERROR, "Internal error"),
+    };
+
+    RpcError {
+      code,
+      message,
+      data: Some(json!({
+        "status": error_status(&err).as_u16(),
+        "error": err.to_string(),
+        "description": err.description(),
+      })),
+    }
+  }
+}
+
+/// JSON-RPC 2.0 controller with `todos.*` methods.
+///
+/// Batches run one call after another, notifications are executed but aren't answered.
+pub struct RpcController {
+  todos_repo: TodosRepo,
+}
+
+impl RpcController {
+  pub fn new(todos_repo: TodosRepo) -> Self {
+    RpcController { todos_repo }
+  }
+
+  pub fn call_rpc(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+
+    req
+      .json::<JsonValue>()
+      .then(move |body| -> BoxFuture<Option<JsonValue>> {
+        match body {
+          Ok(JsonValue::Array(calls)) => call_batch(repo, calls),
+          Ok(call) => call_one(repo, call),
+          Err(Error::JsonParse(_)) | Err(Error::BodyParse(_)) => {
+            let err = RpcError::new(PARSE_ERROR, "Parse error");
+            future::ok(Some(reply(JsonValue::Null, Err(err)))).into_boxed()
+          }
+          Err(err) => future::err(err).into_boxed(),
+        }
+      })
+      .map(|replies| match replies {
+        Some(replies) => Response::new().json(&replies),
+        // only notifications were sent
+        None => Response::new().with_status(StatusCode::NoContent),
+      })
+      .into_boxed()
+  }
+}
+
+fn call_batch(repo: TodosRepo, calls: Vec<JsonValue>) -> BoxFuture<Option<JsonValue>> {
+  if calls.is_empty() || calls.len() > MAX_BATCH {
+    let err = RpcError::new(INVALID_REQUEST, "Invalid Request");
+    return future::ok(Some(reply(JsonValue::Null, Err(err)))).into_boxed();
+  }
+
+  stream::iter_ok(calls)
+    .and_then(move |call| call_one(repo.clone(), call))
+    .filter_map(|it| it)
+    .collect()
+    .map(|replies| {
+      if replies.is_empty() {
+        None
+      } else {
+        Some(JsonValue::Array(replies))
+      }
+    })
+    .into_boxed()
+}
+
+/// Execute a call, the reply is `None` for a notification.
+fn call_one(repo: TodosRepo, call: JsonValue) -> BoxFuture<Option<JsonValue>> {
+  let (id, method, params) = match parse_call(call) {
+    Ok(call) => call,
+    Err((id, err)) => return future::ok(Some(reply(id, Err(err)))).into_boxed(),
+  };
+
+  dispatch(&repo, &method, params)
+    .then(move |result| {
+      if let Err(ref err) = result {
+        warn!("rpc {} failed with {}", method, err.code);
+      }
+
+      Ok(id.map(|id| reply(id, result)))
+    })
+    .into_boxed()
+}
+
+/// Split a call into its id, method and params, a notification doesn't have an id.
+fn parse_call(
+  call: JsonValue,
+) -> Result<(Option<JsonValue>, String, JsonValue), (JsonValue, RpcError)> {
+  let invalid = |id: JsonValue| (id, RpcError::new(INVALID_REQUEST, "Invalid Request"));
+
+  let mut call = match call {
+    JsonValue::Object(call) => call,
+    _ => return Err(invalid(JsonValue::Null)),
+  };
+
+  let id = call.remove("id");
+  match id {
+    None | Some(JsonValue::Null) | Some(JsonValue::Number(_)) | Some(JsonValue::String(_)) => {}
+    Some(_) => return Err(invalid(JsonValue::Null)),
+  }
+
+  let reply_id = id.clone().unwrap_or(JsonValue::Null);
+  if call.get("jsonrpc") != Some(&json!("2.0")) {
+    return Err(invalid(reply_id));
+  }
+
+  let method = match call.remove("method") {
+    Some(JsonValue::String(method)) => method,
+    _ => return Err(invalid(reply_id)),
+  };
+
+  let params = match call.remove("params") {
+    None => JsonValue::Object(Map::new()),
+    Some(params @ JsonValue::Object(_)) | Some(params @ JsonValue::Array(_)) => params,
+    Some(_) => return Err(invalid(reply_id)),
+  };
+
+  Ok((id, method, params))
+}
+
+fn dispatch(
+  repo: &TodosRepo,
+  method: &str,
+  params: JsonValue,
+) -> Box<Future<Item = JsonValue, Error = RpcError>> {
+  let repo = repo.clone();
+
+  let result: BoxFuture<JsonValue> = match method {
+    "todos.create" => parse::<NewTodo>(params)
+      .and_then(|it| it.validated())
+      .into_future()
+      .and_then(move |it| repo.insert(it))
+      .inspect(|it| info!("created {:?}", it))
+      .map(|it| json!(it))
+      .into_boxed(),
+    "todos.update" => parse::<UpdateTodo>(params)
+      .and_then(|it| it.validated())
+      .into_future()
+      .and_then(move |it| repo.update(it))
+      .inspect(|it| info!("updated {:?}", it))
+      .map(|it| json!(it))
+      .into_boxed(),
+    "todos.query" => parse::<QueryTodos>(params)
+      .and_then(|it| it.validated())
+      .into_future()
+      .and_then(move |it| repo.query(it))
+      .map(|it| json!(it))
+      .into_boxed(),
+    "todos.find" => parse::<FindTodo>(params)
+      .into_future()
+      .and_then(move |it| repo.find(it.id))
+      .map(|it| json!(it))
+      .into_boxed(),
+    _ => {
+      let err = RpcError::new(METHOD_NOT_FOUND, "Method not found");
+      return future::err(err).into_boxed();
+    }
+  };
+
+  result.map_err(RpcError::from).into_boxed()
+}
+
+/// Parse params given by name, or by position when a method has a single parameter.
+fn parse<T: DeserializeOwned>(params: JsonValue) -> Result<T, Error> {
+  let params = match params {
+    JsonValue::Array(mut params) => {
+      if params.len() != 1 {
+        return Err(Error::Validation(format!(
+          "expected 1 positional param, got {}",
+          params.len()
+        )));
+      }
+      match params.remove(0) {
+        JsonValue::Number(id) => json!({ "id": id }),
+        param => param,
+      }
+    }
+    params => params,
+  };
+
+  serde_json::from_value(params).map_err(Error::from)
+}
+
+fn reply(id: JsonValue, result: Result<JsonValue, RpcError>) -> JsonValue {
+  match result {
+    Ok(result) => json!({"jsonrpc": "2.0", "result": result, "id": id}),
+    Err(err) => json!({"jsonrpc": "2.0", "error": err.to_json(), "id": id}),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_parse_calls() {
+    let call =
+      parse_call(json!({"jsonrpc": "2.0", "method": "todos.find", "params": [1], "id": 1}));
+    assert_that(&call.ok()).is_equal_to(Some((
+      Some(json!(1)),
+      "todos.find".to_string(),
+      json!([1]),
+    )));
+
+    let notification = parse_call(json!({"jsonrpc": "2.0", "method": "todos.create"})).unwrap();
+    assert_that(&notification.0).is_none();
+    assert_that(&notification.2).is_equal_to(json!({}));
+
+    let codes = vec![
+      json!(1),
+      json!({"method": "todos.find", "id": 1}),
+      json!({"jsonrpc": "2.0", "method": 1, "id": 1}),
+      json!({"jsonrpc": "2.0", "method": "todos.find", "params": 1, "id": 1}),
+      json!({"jsonrpc": "2.0", "method": "todos.find", "id": [1]}),
+    ]
+    .into_iter()
+    .map(|it| parse_call(it).unwrap_err().1.code)
+    .collect::<Vec<_>>();
+    assert_that(&codes).is_equal_to(vec![INVALID_REQUEST; 5]);
+  }
+
+  #[test]
+  fn should_parse_params_by_name_and_position() {
+    assert_that(&parse::<FindTodo>(json!({"id": 1})).map(|it| it.id).ok()).is_equal_to(Some(1));
+    assert_that(&parse::<FindTodo>(json!([1])).map(|it| it.id).ok()).is_equal_to(Some(1));
+    assert_that(
+      &parse::<NewTodo>(json!([{"text": "foo"}]))
+        .map(|it| it.text)
+        .ok(),
+    )
+    .is_equal_to(Some("foo".to_string()));
+    assert_that(&parse::<FindTodo>(json!([1, 2]))).is_err();
+  }
+
+  #[test]
+  fn should_map_errors_to_codes() {
+    let codes = vec![
+      Error::Validation("foo".to_string()),
+      Error::RecordNotFound,
+      Error::PreconditionFailed,
+      Error::IdempotencyKeyReused,
+    ]
+    .into_iter()
+    .map(|it| RpcError::from(it).code)
+    .collect::<Vec<_>>();
+    assert_that(&codes).is_equal_to(vec![INVALID_PARAMS, NOT_FOUND, CONFLICT, INTERNAL_ERROR]);
+
+    let err = RpcError::from(Error::RecordNotFound).to_json();
+    assert_that(&err["data"]["status"]).is_equal_to(&json!(404));
+  }
+}
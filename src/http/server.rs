@@ -1,29 +1,72 @@
-use hyper::{Error as HyperError, Get, Post, Request, Response, StatusCode};
-use hyper::server::{Http, NewService, Service};
-use futures::{future, Future};
+use hyper::{Error as HyperError, Request, Response, StatusCode};
+use hyper::header::Location;
+use hyper::server::{Http, Service};
+use futures::{future, Async, Future, Stream};
+use futures_cpupool::CpuPool;
+use grpc::Server as GrpcServer;
+use std::cell::Cell;
 use std::io;
 use std::error::Error as StdError;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use tokio_core::net::{TcpListener, TcpStream};
+use tokio_core::reactor::{Core, Handle};
 
-use db::TodosRepo;
+use config::Config;
+use trace::{self, Span, SpanContext, SpanKind, TraceParent, Tracer};
+use metrics::Metrics;
+use db::{CalendarTokensRepo, ConnectionPool, IdempotencyKeysRepo, SyncRepo, TodosRepo,
+         WebhooksRepo};
 use result::Error;
 use common::{FuturesExt, ResponseExt};
 
+use super::access_log::AccessLog;
+use super::admin_auth::AdminAuth;
+use super::caldav_controller::CalDavController;
+use super::calendar_controller::CalendarController;
+use super::compression::Compression;
+use super::content_negotiation::negotiate_content;
+use super::event_bus::EventBus;
+use super::events_controller::EventsController;
+use super::graphql_controller::GraphQLController;
+use super::grpc::GrpcTodos;
+use super::idempotency::Idempotency;
+use super::metrics_controller::{MetricsController, RequestMetrics};
+use super::middleware::Endpoint;
+use super::openapi;
+use super::peer;
+use super::rate_limiter::RateLimiter;
+use super::router::{Params, Router};
+use super::rpc_controller::RpcController;
+use super::sync_controller::SyncController;
 use super::todos_controller::TodosController;
+use super::transfer_controller::TransferController;
+use super::webhooks_controller::WebhooksController;
+use super::websocket::WebSockets;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
 
 #[derive(Clone)]
 pub struct Server {
-  todos_repo: TodosRepo,
+  endpoint: Endpoint,
+  tracer: Tracer,
+  websockets: WebSockets,
+  grpc: GrpcTodos,
 }
 
-impl NewService for Server {
-  type Request = Request;
-  type Response = Response;
-  type Error = HyperError;
-  type Instance = Server;
-
-  fn new_service(&self) -> io::Result<Self::Instance> {
-    Ok(self.clone())
-  }
+/// Repositories and services which route handlers create controllers with.
+#[derive(Clone)]
+struct State {
+  cpu_pool: CpuPool,
+  conn_pool: ConnectionPool,
+  metrics: Metrics,
+  max_body_size: u64,
+  todos_repo: TodosRepo,
+  calendar_tokens_repo: CalendarTokensRepo,
+  sync_repo: SyncRepo,
+  webhooks_repo: WebhooksRepo,
+  event_bus: EventBus,
+  websockets: WebSockets,
 }
 
 impl Service for Server {
@@ -33,80 +76,400 @@ impl Service for Server {
   type Future = Box<Future<Item = Self::Response, Error = Self::Error>>;
 
   fn call(&self, req: Self::Request) -> Self::Future {
-    self.handle(req).then(handle_api_err).into_boxed()
+    // errors are responded by `handle_api_err`, only hyper's own errors can be left
+    self
+      .traced(req)
+      .map_err(|err| match err {
+        Error::HttpServer(err) => err,
+        err => HyperError::Io(io::Error::new(io::ErrorKind::Other, err.to_string())),
+      })
+      .into_boxed()
   }
 }
 
 impl Server {
-  pub fn new(todos_repo: TodosRepo) -> Self {
-    Server { todos_repo }
+  pub fn new(
+    cfg: &Config,
+    conn_pool: ConnectionPool,
+    cpu_pool: CpuPool,
+    metrics: Metrics,
+    tracer: Tracer,
+  ) -> Self {
+    let todos_repo = TodosRepo::new(conn_pool.clone(), cpu_pool.clone(), metrics.clone());
+    let event_bus = EventBus::new(conn_pool.clone());
+    // websocket and grpc writes take tokens of the same buckets as http ones
+    let rate_limiter = RateLimiter::new(cfg);
+    let websockets = WebSockets::new(
+      cfg,
+      todos_repo.clone(),
+      event_bus.clone(),
+      cpu_pool.clone(),
+      rate_limiter.clone(),
+    );
+    let grpc = GrpcTodos::new(
+      todos_repo.clone(),
+      event_bus.clone(),
+      cpu_pool.clone(),
+      rate_limiter.clone(),
+    );
+    let state = State {
+      todos_repo,
+      calendar_tokens_repo: CalendarTokensRepo::new(conn_pool.clone(), cpu_pool.clone()),
+      sync_repo: SyncRepo::new(conn_pool.clone(), cpu_pool.clone()),
+      webhooks_repo: WebhooksRepo::new(conn_pool.clone(), cpu_pool.clone()),
+      event_bus,
+      websockets: websockets.clone(),
+      cpu_pool: cpu_pool.clone(),
+      conn_pool: conn_pool.clone(),
+      metrics: metrics.clone(),
+      max_body_size: cfg.max_body_size,
+    };
+    let idempotency_keys_repo = IdempotencyKeysRepo::new(conn_pool, cpu_pool.clone());
+
+    Server {
+      endpoint: router(cfg, metrics, cpu_pool, rate_limiter, idempotency_keys_repo)
+        .into_endpoint(state),
+      tracer,
+      websockets,
+      grpc,
+    }
+  }
+
+  /// Handle a request in a server span, a sampled `traceparent` continues a client's trace.
+  fn traced(&self, req: Request) -> BoxFuture<Response> {
+    let parent = req
+      .headers()
+      .get::<TraceParent>()
+      .and_then(|it| SpanContext::parse(&it.0));
+    let sampled = parent.as_ref().map_or(true, |it| it.sampled);
+    if !self.tracer.is_enabled() || !sampled {
+      return self.endpoint.call(req);
+    }
+
+    let name = format!("HTTP {}", req.method());
+    let mut span = Span::start(&name, SpanKind::Server, parent.as_ref());
+    span.set_attribute("http.method", req.method());
+    span.set_attribute("http.target", req.path());
+
+    let tracer = self.tracer.clone();
+    let current = Some(trace::Current {
+      context: span.context.clone(),
+      tracer: tracer.clone(),
+    });
+    let resp = trace::scope(&current, || self.endpoint.call(req));
+
+    trace::Scoped::new(current, resp)
+      .then(move |result| {
+        let error = match result {
+          Ok(ref resp) => {
+            span.set_attribute("http.status_code", resp.status().as_u16());
+            if resp.status().is_server_error() {
+              Some(resp.status().to_string())
+            } else {
+              None
+            }
+          }
+          Err(ref err) => Some(err.variant().to_string()),
+        };
+        tracer.export(span.finish(error));
+        result
+      })
+      .into_boxed()
+  }
+
+  /// Serve `TodoService` on `grpc_port` next to the http api, it's served until the returned
+  /// server is dropped.
+  pub fn listen_grpc(&self, grpc_port: u16) -> GrpcServer {
+    self.grpc.listen(grpc_port)
   }
 
   pub fn listen(self, http_port: u16) {
+    let mut core = Core::new().expect("cannot create event loop");
+    let handle = core.handle();
+
     let addr = ([0, 0, 0, 0], http_port).into();
-    let listener = Http::new()
-      .bind(&addr, self)
-      .expect("cannot bind to address");
+    let listener = TcpListener::bind(&addr, &handle).expect("cannot bind to address");
     info!(
       "listen on http://{}",
       listener.local_addr().expect("cannot lookup local address")
     );
 
-    listener.run().expect("cannot handle requests");
+    let http = Http::new();
+    let connections = listener
+      .incoming()
+      .then(|result| match result {
+        Ok(it) => Ok(Some(it)),
+        Err(err) => {
+          warn!("cannot accept connection {}", err);
+          Ok(None)
+        }
+      })
+      .filter_map(|it| it)
+      .for_each(|(stream, peer)| {
+        handle.spawn(self.serve(&http, stream, peer, &handle));
+        Ok::<_, io::Error>(())
+      });
+
+    core.run(connections).expect("cannot handle requests");
   }
 
-  fn handle(&self, req: Request) -> Box<Future<Item = Response, Error = Error>> {
-    let todos_repo = self.todos_repo.clone();
+  /// Serve a connection's requests, it's handed over to a websocket session after an upgrade.
+  fn serve(
+    &self,
+    http: &Http,
+    stream: TcpStream,
+    peer: SocketAddr,
+    handle: &Handle,
+  ) -> Box<Future<Item = (), Error = ()>> {
+    let upgraded = Rc::new(Cell::new(false));
+    let service = Upgrading {
+      server: self.clone(),
+      upgraded: upgraded.clone(),
+    };
+    let mut conn = Some(http.serve_connection(stream, service));
 
-    match (req.method(), req.path()) {
-      (&Post, "/todos/create") => TodosController::new(todos_repo).call_create(req),
-      (&Post, "/todos/update") => TodosController::new(todos_repo).call_update(req),
-      (&Post, "/todos/query") => TodosController::new(todos_repo).call_query(req),
-      (&Get, "/health") => {
-        let body = json!({"ok": true});
-        future::ok(Response::new().json(&body)).into_boxed()
+    let served = future::poll_fn(move || {
+      let polled = {
+        let conn = conn.as_mut().expect("connection is polled after it's done");
+        peer::scope(Some(peer), || conn.poll_without_shutdown())?
+      };
+      match polled {
+        Async::Ready(()) => Ok(Async::Ready(conn.take().expect("connection is done"))),
+        Async::NotReady => Ok(Async::NotReady),
       }
-      _ => {
-        warn!("not found {} {}", req.method(), req.path());
-        future::ok(Response::new().not_found()).into_boxed()
-      }
-    }
+    });
+
+    let websockets = self.websockets.clone();
+    let handle = handle.clone();
+    served
+      .map(move |conn| {
+        if upgraded.get() {
+          let parts = conn.into_parts();
+          websockets.connect(parts.io, parts.read_buf, peer, &handle);
+        }
+      })
+      .map_err(move |err: HyperError| debug!("connection {} failed {}", peer, err))
+      .into_boxed()
+  }
+}
+
+/// Serves a connection's requests and tells if one of them switched protocols.
+struct Upgrading {
+  server: Server,
+  upgraded: Rc<Cell<bool>>,
+}
+
+impl Service for Upgrading {
+  type Request = Request;
+  type Response = Response;
+  type Error = HyperError;
+  type Future = Box<Future<Item = Self::Response, Error = Self::Error>>;
+
+  fn call(&self, req: Self::Request) -> Self::Future {
+    let upgraded = self.upgraded.clone();
+    self
+      .server
+      .call(req)
+      .inspect(move |resp| {
+        if resp.status() == StatusCode::SwitchingProtocols {
+          upgraded.set(true);
+        }
+      })
+      .into_boxed()
+  }
+}
+
+impl State {
+  fn todos(&self) -> TodosController {
+    TodosController::new(self.todos_repo.clone())
+  }
+
+  fn transfer(&self) -> TransferController {
+    TransferController::new(
+      self.todos_repo.clone(),
+      self.cpu_pool.clone(),
+      self.max_body_size,
+    )
+  }
+
+  fn caldav(&self) -> CalDavController {
+    CalDavController::new(
+      self.todos_repo.clone(),
+      self.calendar_tokens_repo.clone(),
+      self.max_body_size,
+    )
+  }
+
+  fn calendar(&self) -> CalendarController {
+    CalendarController::new(
+      self.todos_repo.clone(),
+      self.calendar_tokens_repo.clone(),
+      self.cpu_pool.clone(),
+    )
+  }
+
+  fn webhooks(&self) -> WebhooksController {
+    WebhooksController::new(self.webhooks_repo.clone(), self.cpu_pool.clone())
   }
+
+  fn metrics(&self) -> MetricsController {
+    MetricsController::new(self.metrics.clone(), self.conn_pool.clone())
+  }
+}
+
+/// Routes of the api, `openapi::spec` must document them.
+fn router(
+  cfg: &Config,
+  metrics: Metrics,
+  cpu_pool: CpuPool,
+  rate_limiter: RateLimiter,
+  idempotency_keys_repo: IdempotencyKeysRepo,
+) -> Router<State> {
+  let mut router: Router<State> = Router::new();
+  router
+    .wrap(AccessLog::new(cfg))
+    .wrap(RequestMetrics::new(metrics))
+    // a limited client's body isn't decompressed
+    .wrap(rate_limiter)
+    .wrap(Compression::new(cfg, cpu_pool))
+    .wrap(handle_api_err)
+    .group("", |api| {
+      // errors are responded inside the group, so they're encoded with an accepted media type
+      api
+        .wrap(negotiate_content)
+        .wrap(handle_api_err)
+        .get("/health", |_, _, _| {
+          let body = json!({"ok": true});
+          future::ok(Response::new().json(&body)).into_boxed()
+        })
+        .get("/openapi.json", |_, _, _| openapi::call_spec())
+        .group("", |group| {
+          group
+            .wrap(Idempotency::new(cfg, idempotency_keys_repo))
+            .post("/todos/create", |s, req, _| s.todos().call_create(req))
+            .post("/todos/update", |s, req, _| s.todos().call_update(req))
+            .post("/todos", |s, req, _| s.todos().call_post(req))
+            .patch("/todos/{id}", |s, req, params| {
+              with_id(&params, |id| s.todos().call_patch(id, req))
+            })
+            .post("/todos/import", |s, req, _| s.transfer().call_import(req))
+            .post("/sync", |s, req, _| {
+              SyncController::new(s.sync_repo.clone()).call_sync(req)
+            });
+        })
+        .post("/todos/query", |s, req, _| s.todos().call_query(req))
+        .get("/todos", |s, req, _| s.todos().call_list(req))
+        .get("/todos/{id}", |s, _, params| {
+          with_id(&params, |id| s.todos().call_find(id))
+        })
+        .delete("/todos/{id}", |s, _, params| {
+          with_id(&params, |id| s.todos().call_delete(id))
+        })
+        .post("/graphql", |s, req, _| {
+          GraphQLController::new(s.todos_repo.clone()).call_graphql(req)
+        })
+        .post("/rpc", |s, req, _| RpcController::new(s.todos_repo.clone()).call_rpc(req))
+        .group("/calendar/tokens", |group| {
+          group
+            .wrap(AdminAuth::new(cfg))
+            .post("", |s, req, _| s.calendar().call_create_token(req))
+            .post("/revoke", |s, req, _| s.calendar().call_revoke_token(req));
+        })
+        .group("/webhooks", |group| {
+          group
+            .wrap(AdminAuth::new(cfg))
+            .get("", |s, _, _| s.webhooks().call_list())
+            .post("/create", |s, req, _| s.webhooks().call_create(req))
+            .post("/delete", |s, req, _| s.webhooks().call_delete(req))
+            .get("/dead_letters", |s, _, _| s.webhooks().call_dead_letters())
+            .post("/dead_letters/retry", |s, req, _| s.webhooks().call_retry(req));
+        });
+    })
+    // routes below respond with their own media types
+    .get("/metrics", |s, _, _| s.metrics().call_metrics())
+    .get("/todos/ws", |s, req, _| s.websockets.call_upgrade(req))
+    .get("/todos/events", |s, req, _| {
+      EventsController::new(s.event_bus.clone(), s.cpu_pool.clone()).call_events(req)
+    })
+    .get("/todos/export", |s, req, _| s.transfer().call_export(req))
+    .get("/calendar.ics", |s, req, _| s.calendar().call_feed(req))
+    .any("/.well-known/caldav", |_, _, _| {
+      let resp = Response::new()
+        .with_status(StatusCode::MovedPermanently)
+        .with_header(Location::new("/dav/"));
+      future::ok(resp).into_boxed()
+    })
+    .any("/dav/*", |s, req, _| s.caldav().call(req));
+
+  router
 }
 
-fn handle_api_err(result: Result<Response, Error>) -> Result<Response, HyperError> {
-  let err = match result {
-    Err(err) => err,
-    Ok(ok) => return Ok(ok),
-  };
+/// Call a handler with an item's id from the path.
+fn with_id<F>(params: &Params, handler: F) -> BoxFuture<Response>
+where
+  F: FnOnce(i64) -> BoxFuture<Response>,
+{
+  match params.id("id") {
+    Ok(id) => handler(id),
+    Err(err) => future::err(err).into_boxed(),
+  }
+}
 
-  let mut resp = Response::new();
-  match err {
-    Error::JsonParse(_) => resp.set_status(StatusCode::BadRequest),
-    Error::RecordNotFound => resp.set_status(StatusCode::NotFound),
-    Error::Validation(_) => resp.set_status(StatusCode::PreconditionFailed),
-    _ => resp.set_status(StatusCode::InternalServerError),
-  };
+/// A middleware which responds to api errors with their status and description.
+fn handle_api_err(req: Request, next: Endpoint) -> BoxFuture<Response> {
+  next
+    .call(req)
+    .or_else(|err| Ok(error_response(&err)))
+    .into_boxed()
+}
 
+/// A response which an api error is reported with.
+pub fn error_response(err: &Error) -> Response {
   let body = json!({"error": err.to_string(), "description": err.description()});
 
-  Ok(resp.json(&body))
+  Response::new()
+    .with_status(error_status(err))
+    .json(&body)
+}
+
+/// A status which an api error is reported with.
+pub fn error_status(err: &Error) -> StatusCode {
+  match *err {
+    Error::JsonParse(_) | Error::CsvParse(_) | Error::BodyParse(_) => StatusCode::BadRequest,
+    Error::RecordNotFound => StatusCode::NotFound,
+    Error::Validation(_) | Error::PreconditionFailed => StatusCode::PreconditionFailed,
+    Error::IdempotencyKeyReused => StatusCode::UnprocessableEntity,
+    Error::IdempotencyKeyInProgress => StatusCode::Conflict,
+    Error::UnsupportedMediaType(_) => StatusCode::UnsupportedMediaType,
+    Error::NotAcceptable => StatusCode::NotAcceptable,
+    Error::PayloadTooLarge(_) => StatusCode::PayloadTooLarge,
+    Error::Unauthorized => StatusCode::Unauthorized,
+    Error::TooManyRequests(_) => StatusCode::TooManyRequests,
+    _ => StatusCode::InternalServerError,
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
   use spectral::prelude::*;
-  use hyper::{Body, Uri};
+  use hyper::{Body, Delete, Get, Method, Post, Uri};
+  use hyper::header::{Allow, Authorization, Basic, ContentLength, ContentType};
   use serde_json;
   use serde_json::Value as JsonValue;
   use std::str::FromStr;
   use futures::Stream;
+  use chrono::Utc;
+
+  const ADMIN_TOKEN: &str = "admin-secret";
 
   use db;
-  use config::Config;
+  use common::MediaType;
   use http::assertions::*;
 
+  /// Routes which aren't a JSON api, CalDAV clients discover them on their own.
+  const UNDOCUMENTED: &[&str] = &["/.well-known/caldav", "/dav/*"];
+
   #[test]
   fn should_be_healthy() {
     let svc = create_server();
@@ -158,6 +521,8 @@ mod tests {
       .is_equal_to(&JsonValue::Bool(true));
     assert_that(&todo.get("created_at")).is_some();
     assert_that(&todo.get("updated_at")).is_some();
+    assert_that(&todo["accepted"]).is_equal_to(&json!(["done"]));
+    assert_that(&todo["overridden"]).is_equal_to(&json!([]));
   }
 
   #[test]
@@ -175,6 +540,375 @@ mod tests {
     assert_that(&query.get("items")).is_some();
   }
 
+  #[test]
+  fn should_export_and_import_todos() {
+    let svc = create_server();
+
+    let resp = post(&svc, "/todos/create", json!({"text": "foo"}));
+    assert_that(&resp).is_ok().has_json();
+
+    let resp = get(&svc, "/todos/export?format=csv&text=foo");
+    assert_that(&resp).is_ok();
+    let csv = resp.body().concat2().wait().unwrap();
+    assert_that(&csv.starts_with(b"id,text,done,created_at,updated_at,due_at\n")).is_true();
+
+    let mut req: Request<Body> = Request::new(
+      Post,
+      Uri::from_str("/todos/import?format=csv&dry_run=true").unwrap(),
+    );
+    req.set_body(csv);
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp).is_ok().has_json();
+
+    let report = json(resp);
+    assert_that(&report["dry_run"]).is_equal_to(&JsonValue::Bool(true));
+    assert_that(&report["failed"]).is_equal_to(&json!(0));
+  }
+
+  #[test]
+  fn should_reject_too_large_bodies() {
+    let cfg = Config {
+      max_body_size: 16,
+      ..Config::default()
+    };
+    let svc = create_server_with(cfg);
+
+    let mut req: Request<Body> = Request::new(Post, Uri::from_str("/todos/import").unwrap());
+    req.set_body("{\"text\": \"buy milk\"}\n");
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp)
+      .has_status(StatusCode::PayloadTooLarge)
+      .has_json();
+
+    let resp = post_with_key(&svc, "/todos/create", "import", json!({"text": "buy milk"}));
+    assert_that(&resp)
+      .has_status(StatusCode::PayloadTooLarge)
+      .has_json();
+
+    let resp = admin_post(
+      &svc,
+      "/calendar/tokens",
+      json!({"owner": "alice", "scope": "read_write"}),
+    );
+    let token = json(resp)["token"].as_str().unwrap().to_string();
+    let vtodo = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:foo\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+    let resp = dav(&svc, "PUT", "/dav/todos/new.ics", &token, vec![], vtodo);
+    assert_that(&resp).has_status(StatusCode::PayloadTooLarge);
+  }
+
+  #[test]
+  fn should_serve_calendar_feed() {
+    let svc = create_server();
+
+    let resp = get(&svc, "/calendar.ics?token=unknown");
+    assert_that(&resp).has_status(StatusCode::NotFound);
+
+    let resp = post(&svc, "/calendar/tokens", json!({"owner": "alice"}));
+    assert_that(&resp).has_status(StatusCode::Unauthorized);
+
+    let resp = admin_post(&svc, "/calendar/tokens", json!({"owner": "alice"}));
+    assert_that(&resp).is_ok().has_json();
+    let token = json(resp)["token"].as_str().unwrap().to_string();
+
+    let resp = get(&svc, &format!("/calendar.ics?token={}", token));
+    assert_that(&resp).is_ok();
+    let ics = resp.body().concat2().wait().unwrap();
+    assert_that(&ics.starts_with(b"BEGIN:VCALENDAR\r\n")).is_true();
+    assert_that(&ics.ends_with(b"END:VCALENDAR\r\n")).is_true();
+
+    let resp = admin_post(&svc, "/calendar/tokens/revoke", json!({"token": token}));
+    assert_that(&resp).is_ok();
+
+    let resp = get(&svc, &format!("/calendar.ics?token={}", token));
+    assert_that(&resp).has_status(StatusCode::NotFound);
+  }
+
+  #[test]
+  fn should_sync_todos_with_caldav() {
+    let svc = create_server();
+
+    let resp = admin_post(&svc, "/calendar/tokens", json!({"owner": "alice"}));
+    let feed_token = json(resp)["token"].as_str().unwrap().to_string();
+    let resp = admin_post(
+      &svc,
+      "/calendar/tokens",
+      json!({"owner": "alice", "scope": "read_write"}),
+    );
+    let token = json(resp)["token"].as_str().unwrap().to_string();
+
+    let resp = dav(&svc, "PROPFIND", "/dav/todos/", "wrong", vec![], "");
+    assert_that(&resp).has_status(StatusCode::Unauthorized);
+
+    let vtodo = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:x\r\nSUMMARY:foo\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+    let resp = dav(&svc, "PROPFIND", "/dav/todos/", &feed_token, vec![], "");
+    assert_that(&resp).has_status(StatusCode::MultiStatus);
+    let resp = dav(&svc, "PUT", "/dav/todos/new.ics", &feed_token, vec![], vtodo);
+    assert_that(&resp).has_status(StatusCode::Forbidden);
+
+    let resp = dav(&svc, "PUT", "/dav/todos/new.ics", &token, vec![], vtodo);
+    assert_that(&resp).has_status(StatusCode::Created);
+    let location = resp.headers().get::<Location>().unwrap().to_string();
+
+    let resp = dav(&svc, "GET", &location, &token, vec![], "");
+    assert_that(&resp).is_ok();
+    let etag = resp.headers().get_raw("ETag").unwrap().one().unwrap().to_vec();
+    let etag = String::from_utf8(etag).unwrap();
+
+    let changed = vtodo.replace("foo", "bar");
+    let resp = dav(&svc, "PUT", &location, &token, vec![("If-Match", &etag)], &changed);
+    assert_that(&resp).has_status(StatusCode::NoContent);
+
+    let resp = dav(&svc, "PUT", &location, &token, vec![("If-Match", &etag)], vtodo);
+    assert_that(&resp).has_status(StatusCode::PreconditionFailed);
+
+    let resp = dav(&svc, "PROPFIND", "/dav/todos/", &token, vec![("Depth", "1")], "");
+    assert_that(&resp).has_status(StatusCode::MultiStatus);
+    let body = resp.body().concat2().wait().unwrap();
+    assert_that(&String::from_utf8_lossy(&body).contains(location.as_str())).is_true();
+
+    // a client's name is never taken as an id
+    let resp = dav(&svc, "PUT", "/dav/todos/9000000000.ics", &token, vec![], vtodo);
+    assert_that(&resp).has_status(StatusCode::Created);
+    let assigned = resp.headers().get::<Location>().unwrap().to_string();
+    assert_that(&assigned.as_str()).is_not_equal_to("/dav/todos/9000000000.ics");
+
+    let report = "<d:sync-collection xmlns:d=\"DAV:\"><d:sync-token/></d:sync-collection>";
+    let resp = dav(&svc, "REPORT", "/dav/todos/", &token, vec![], report);
+    assert_that(&resp).has_status(StatusCode::MultiStatus);
+    let body = resp.body().concat2().wait().unwrap();
+    let body = String::from_utf8_lossy(&body).into_owned();
+    let sync_token = body.split("<d:sync-token>").nth(1).unwrap();
+    let sync_token = sync_token.split('<').next().unwrap().to_string();
+
+    let resp = dav(&svc, "DELETE", &location, &feed_token, vec![], "");
+    assert_that(&resp).has_status(StatusCode::Forbidden);
+
+    let resp = dav(&svc, "DELETE", &location, &token, vec![], "");
+    assert_that(&resp).has_status(StatusCode::NoContent);
+
+    let resp = dav(&svc, "GET", &location, &token, vec![], "");
+    assert_that(&resp).has_status(StatusCode::NotFound);
+
+    let report = format!(
+      "<d:sync-collection xmlns:d=\"DAV:\"><d:sync-token>{}</d:sync-token></d:sync-collection>",
+      sync_token
+    );
+    let resp = dav(&svc, "REPORT", "/dav/todos/", &token, vec![], &report);
+    let body = resp.body().concat2().wait().unwrap();
+    let deleted = format!("<d:href>{}</d:href><d:status>HTTP/1.1 404", location);
+    assert_that(&String::from_utf8_lossy(&body).contains(deleted.as_str())).is_true();
+  }
+
+  #[test]
+  fn should_stream_todo_events() {
+    let svc = create_server();
+
+    let resp = get(&svc, "/todos/events");
+    assert_that(&resp).is_ok();
+    let mut chunks = resp.body().wait();
+    let first = chunks.next().unwrap().unwrap();
+    assert_that(&first.starts_with(b"retry: ")).is_true();
+
+    let resp = post(&svc, "/todos/create", json!({"text": "foo"}));
+    let id = json(resp)["id"].clone();
+
+    let event = chunks
+      .map(|it| String::from_utf8_lossy(&it.unwrap()).into_owned())
+      .find(|it| it.contains("event: todo.created"))
+      .unwrap();
+    assert_that(&event.contains(&format!("\"id\":{}", id))).is_true();
+  }
+
+  #[test]
+  fn should_upgrade_to_websocket() {
+    let svc = create_server();
+    let upgrade = |origin: Option<&str>| {
+      let mut req = Request::new(Get, Uri::from_str("/todos/ws").unwrap());
+      {
+        let headers = req.headers_mut();
+        headers.set_raw("Host", "localhost:3000");
+        headers.set_raw("Upgrade", "websocket");
+        headers.set_raw("Connection", "Upgrade");
+        headers.set_raw("Sec-WebSocket-Version", "13");
+        headers.set_raw("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==");
+        if let Some(origin) = origin {
+          headers.set_raw("Origin", origin);
+        }
+      }
+      svc.call(req).wait().unwrap()
+    };
+
+    let resp = upgrade(Some("http://localhost:3000"));
+    assert_that(&resp).has_status(StatusCode::SwitchingProtocols);
+    let accept = resp.headers().get_raw("Sec-WebSocket-Accept").unwrap();
+    assert_that(&accept.one()).is_equal_to(Some(&b"s3pPLMBiTxaQ9kYGzzhZRbK+xOo="[..]));
+
+    let resp = upgrade(Some("https://evil.example.com"));
+    assert_that(&resp).has_status(StatusCode::Forbidden);
+
+    let resp = get(&svc, "/todos/ws");
+    assert_that(&resp).has_status(StatusCode::UpgradeRequired);
+  }
+
+  #[test]
+  fn should_manage_webhooks() {
+    let svc = create_server();
+
+    let hook = json!({"url": "http://93.184.216.34/hook", "events": ["todo.completed"]});
+    let resp = post(&svc, "/webhooks/create", hook.clone());
+    assert_that(&resp).has_status(StatusCode::Unauthorized);
+    let resp = get(&svc, "/webhooks/dead_letters");
+    assert_that(&resp).has_status(StatusCode::Unauthorized);
+
+    let resp = admin_post(&svc, "/webhooks/create", json!({"url": "ftp://localhost/hook"}));
+    assert_that(&resp).has_status(StatusCode::PreconditionFailed);
+
+    for url in &["http://localhost:8080/hook", "http://169.254.169.254/latest/meta-data"] {
+      let resp = admin_post(&svc, "/webhooks/create", json!({ "url": url }));
+      assert_that(&resp).has_status(StatusCode::PreconditionFailed);
+    }
+
+    let resp = admin_post(&svc, "/webhooks/create", hook);
+    assert_that(&resp).is_ok().has_json();
+    let hook = json(resp);
+    assert_that(&hook["secret"].is_string()).is_true();
+
+    let resp = admin_get(&svc, "/webhooks");
+    assert_that(&resp).is_ok().has_json();
+    let hooks = json(resp);
+    let listed = hooks
+      .as_array()
+      .unwrap()
+      .iter()
+      .find(|it| it["id"] == hook["id"])
+      .unwrap();
+    assert_that(&listed.get("secret")).is_none();
+
+    let resp = admin_post(&svc, "/webhooks/delete", json!({"id": hook["id"]}));
+    assert_that(&resp).is_ok();
+
+    let resp = admin_get(&svc, "/webhooks/dead_letters");
+    assert_that(&resp).is_ok().has_json();
+  }
+
+  #[test]
+  fn should_sync_todos() {
+    let svc = create_server();
+
+    let resp = post(&svc, "/sync", json!({"token": "foo"}));
+    assert_that(&resp).has_status(StatusCode::PreconditionFailed);
+
+    let resp = post(&svc, "/sync", json!({"limit": 1000}));
+    assert_that(&resp).is_ok().has_json();
+    let token = json(resp)["token"].clone();
+
+    let changes = json!([{"client_id": "local-1", "text": "created offline"}]);
+    let resp = post(&svc, "/sync", json!({"token": token, "changes": changes}));
+    assert_that(&resp).is_ok().has_json();
+
+    let synced = json(resp);
+    assert_that(&synced["applied"][0]["client_id"].as_str()).is_equal_to(Some("local-1"));
+    assert_that(&synced["todos"][0]["text"].as_str()).is_equal_to(Some("created offline"));
+    assert_that(&synced["token"]).is_not_equal_to(&token);
+  }
+
+  #[test]
+  fn should_handle_todos_resources() {
+    let svc = create_server();
+
+    let resp = post(&svc, "/todos", json!({"text": "foo"}));
+    assert_that(&resp).has_status(StatusCode::Created);
+    let location = resp.headers().get::<Location>().unwrap().to_string();
+    let todo = json(resp);
+    assert_that(&location).is_equal_to(format!("/todos/{}", todo["id"]));
+
+    let resp = get(&svc, &location);
+    assert_that(&resp).is_ok().has_json();
+    assert_that(&json(resp)["text"]).is_equal_to(&json!("foo"));
+
+    let resp = get(&svc, "/todos?text=foo&limit=1");
+    assert_that(&resp).is_ok().has_json();
+    assert_that(&json(resp)["items"][0]["id"]).is_equal_to(&todo["id"]);
+
+    let resp = get(&svc, "/todos?limit=many");
+    assert_that(&resp).has_status(StatusCode::PreconditionFailed);
+
+    let resp = request(&svc, Method::Patch, &location, json!({"done": true}));
+    assert_that(&resp).is_ok().has_json();
+    assert_that(&json(resp)["done"]).is_equal_to(&json!(true));
+
+    let resp = request(&svc, Delete, &location, json!({}));
+    assert_that(&resp).has_status(StatusCode::NoContent);
+
+    let resp = get(&svc, &location);
+    assert_that(&resp).has_status(StatusCode::NotFound);
+  }
+
+  #[test]
+  fn should_handle_graphql() {
+    let svc = create_server();
+
+    let mutation = json!({
+      "query": "mutation($text: String!) { createTodo(text: $text) { id text } }",
+      "variables": {"text": "foo"},
+    });
+    let resp = post(&svc, "/graphql", mutation);
+    assert_that(&resp).is_ok().has_json();
+    let created = json(resp)["data"]["createTodo"].clone();
+    assert_that(&created["text"].as_str()).is_equal_to(Some("foo"));
+
+    let query = json!({
+      "query": "{ todos(first: 2) { edges { cursor node { id text } } pageInfo { endCursor } } }",
+    });
+    let resp = post(&svc, "/graphql", query);
+    assert_that(&resp).is_ok().has_json();
+    let todos = json(resp)["data"]["todos"].clone();
+    assert_that(&todos["edges"][0]["node"]["id"]).is_equal_to(&created["id"]);
+
+    let invalid = json!({"query": "mutation { createTodo(text: \"\") { id } }"});
+    let resp = post(&svc, "/graphql", invalid);
+    let body = json(resp);
+    assert_that(&body["data"]["createTodo"].is_null()).is_true();
+    assert_that(&body["errors"][0]["extensions"]["status"].as_u64()).is_equal_to(Some(412));
+
+    let expensive = "{ todos(first: 100) { nodes { id text done createdAt updatedAt dueAt } } }";
+    let resp = post(&svc, "/graphql", json!({ "query": expensive }));
+    let body = json(resp);
+    assert_that(&body.get("data")).is_none();
+    assert_that(&body["errors"][0]["message"].as_str().unwrap()).contains("complexity");
+  }
+
+  #[test]
+  fn should_handle_rpc_calls() {
+    let svc = create_server();
+
+    let call =
+      json!({"jsonrpc": "2.0", "method": "todos.create", "params": {"text": "foo"}, "id": 1});
+    let resp = post(&svc, "/rpc", call);
+    assert_that(&resp).is_ok().has_json();
+    let created = json(resp)["result"].clone();
+    assert_that(&created["text"].as_str()).is_equal_to(Some("foo"));
+
+    let batch = json!([
+      {"jsonrpc": "2.0", "method": "todos.find", "params": [created["id"]], "id": "find"},
+      {"jsonrpc": "2.0", "method": "todos.update", "params": {"id": created["id"], "done": true}},
+      {"jsonrpc": "2.0", "method": "todos.find", "params": {"id": 0}, "id": "missing"},
+      {"jsonrpc": "2.0", "method": "todos.delete", "id": "unknown"},
+    ]);
+    let resp = post(&svc, "/rpc", batch);
+    assert_that(&resp).is_ok().has_json();
+    let replies = json(resp);
+    assert_that(&replies.as_array().map(|it| it.len())).is_equal_to(Some(3));
+    assert_that(&replies[0]["result"]["id"]).is_equal_to(&created["id"]);
+    assert_that(&replies[1]["error"]["code"]).is_equal_to(&json!(-32001));
+    assert_that(&replies[1]["error"]["data"]["status"]).is_equal_to(&json!(404));
+    assert_that(&replies[2]["error"]["code"]).is_equal_to(&json!(-32601));
+
+    let notification = json!({"jsonrpc": "2.0", "method": "todos.query"});
+    let resp = post(&svc, "/rpc", notification);
+    assert_that(&resp).has_status(StatusCode::NoContent);
+  }
+
   #[test]
   fn should_handle_not_found_error() {
     let svc = create_server();
@@ -183,6 +917,185 @@ mod tests {
     assert_that(&resp).has_status(StatusCode::NotFound);
   }
 
+  #[test]
+  fn should_handle_method_not_allowed() {
+    let svc = create_server();
+
+    let resp = get(&svc, "/todos/create");
+    assert_that(&resp).has_status(StatusCode::MethodNotAllowed);
+    assert_that(&resp.headers().get::<Allow>())
+      .is_equal_to(Some(&Allow(vec![Post, Method::Options])));
+
+    let resp = request(&svc, Method::Options, "/todos", json!({}));
+    assert_that(&resp).has_status(StatusCode::NoContent);
+    let allow = vec![Post, Method::Head, Get, Method::Options];
+    assert_that(&resp.headers().get::<Allow>()).is_equal_to(Some(&Allow(allow)));
+
+    let resp = request(&svc, Method::Head, "/health", json!({}));
+    assert_that(&resp).is_ok();
+    assert_that(&resp.headers().get::<ContentLength>()).is_some();
+  }
+
+  #[test]
+  fn should_limit_requests_rate() {
+    let cfg = Config {
+      rate_limit_write: "1/60".parse().unwrap(),
+      ..Config::default()
+    };
+    let svc = create_server_with(cfg);
+
+    let resp = post(&svc, "/todos/create", json!({"text": "foo"}));
+    assert_that(&resp).is_ok();
+    assert_that(&resp.headers().get_raw("RateLimit-Remaining")).is_some();
+
+    let resp = post(&svc, "/todos/create", json!({"text": "foo"}));
+    assert_that(&resp).has_status(StatusCode::TooManyRequests);
+    assert_that(&resp.headers().get_raw("Retry-After")).is_some();
+  }
+
+  #[test]
+  fn should_expose_metrics() {
+    let svc = create_server();
+    get(&svc, "/todos/1");
+    get(&svc, "/missing");
+
+    let resp = get(&svc, "/metrics");
+    assert_that(&resp).is_ok();
+    let body = resp.body().concat2().wait().unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+
+    assert_that(&text.contains(
+      "http_requests_total{method=\"GET\",route=\"/todos/{id}\",status=\"404\"} 1",
+    )).is_true();
+    assert_that(&text.contains(
+      "http_requests_total{method=\"GET\",route=\"unmatched\",status=\"404\"} 1",
+    )).is_true();
+    assert_that(&text.contains(
+      "todos_repo_errors_total{operation=\"find\",error=\"RecordNotFound\"} 1",
+    )).is_true();
+    assert_that(&text.contains("db_pool_connections ")).is_true();
+    assert_that(&text.contains("cpu_pool_queue_depth 0")).is_true();
+  }
+
+  #[test]
+  fn should_trace_requests() {
+    let cfg = Config::default();
+    let cpu_pool = cfg.create_cpu_pool();
+    let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
+    let (tracer, spans) = Tracer::channel(100);
+    let svc = Server::new(&cfg, conn_pool, cpu_pool, Metrics::new(), tracer);
+
+    let mut req: Request<Body> = Request::new(Get, Uri::from_str("/todos/1").unwrap());
+    req.headers_mut().set(TraceParent(
+      "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string(),
+    ));
+    svc.call(req).wait().unwrap();
+
+    let spans: Vec<Span> = spans.try_iter().collect();
+    let names: Vec<&str> = spans.iter().map(|it| it.name.as_str()).collect();
+    assert_that(&names).contains("HTTP GET");
+    assert_that(&names).contains("TodosController.find");
+    assert_that(&names).contains("TodosRepo.find");
+    assert_that(&names).contains("db.query");
+    for span in &spans {
+      assert_that(&span.context.trace_id.as_str()).is_equal_to("0af7651916cd43dd8448eb211c80319c");
+    }
+
+    let server = spans.iter().find(|it| it.name == "HTTP GET").unwrap();
+    assert_that(&server.parent_id).is_equal_to(Some("b7ad6b7169203331".to_string()));
+  }
+
+  #[test]
+  fn should_give_requests_ids() {
+    let svc = create_server();
+
+    let resp = get(&svc, "/health");
+    let id = resp.headers().get_raw("X-Request-Id").map(|it| it.to_owned());
+    assert_that(&id).is_some();
+
+    let mut req: Request<Body> = Request::new(Get, Uri::from_str("/health").unwrap());
+    req.headers_mut().set_raw("X-Request-Id", "client-id-1");
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp.headers().get_raw("X-Request-Id").and_then(|it| it.one()))
+      .is_equal_to(Some(&b"client-id-1"[..]));
+
+    let mut req: Request<Body> = Request::new(Get, Uri::from_str("/health").unwrap());
+    req.headers_mut().set_raw("X-Request-Id", "bad id");
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp.headers().get_raw("X-Request-Id").and_then(|it| it.one()))
+      .is_not_equal_to(Some(&b"bad id"[..]));
+  }
+
+  #[test]
+  fn should_serve_openapi_spec() {
+    let svc = create_server();
+    let resp = get(&svc, "/openapi.json");
+    assert_that(&resp).is_ok().has_json();
+
+    let spec = json(resp);
+    assert_that(&spec["openapi"]).is_equal_to(&json!("3.0.3"));
+    assert_that(&spec["paths"]["/todos"]["post"])
+      .is_equal_to(&openapi::spec()["paths"]["/todos"]["post"]);
+  }
+
+  #[test]
+  fn should_document_every_route() {
+    let cfg = Config::default();
+    let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
+    let cpu_pool = cfg.create_cpu_pool();
+    let idempotency_keys_repo = IdempotencyKeysRepo::new(conn_pool, cpu_pool.clone());
+    let rate_limiter = RateLimiter::new(&cfg);
+    let router = router(&cfg, Metrics::new(), cpu_pool, rate_limiter, idempotency_keys_repo);
+
+    let mut routes: Vec<(Method, String)> = Vec::new();
+    for (method, path) in router.routes() {
+      match method {
+        Some(method) => routes.push((method, path.to_string())),
+        None => assert_that(&UNDOCUMENTED.contains(&path)).named(path).is_true(),
+      }
+    }
+    let documented = documented(&openapi::spec());
+
+    for route in &routes {
+      assert_that(&documented).named(&format!("{:?}", route)).contains(route);
+    }
+    for operation in &documented {
+      assert_that(&routes).named(&format!("{:?}", operation)).contains(operation);
+    }
+  }
+
+  #[test]
+  fn should_replay_idempotent_request() {
+    let svc = create_server();
+    let key = format!("create-{}", Utc::now().timestamp_nanos());
+
+    let resp = post_with_key(&svc, "/todos/create", &key, json!({"text": "foo"}));
+    assert_that(&resp).is_ok().has_json();
+    let first = json(resp);
+
+    let resp = post_with_key(&svc, "/todos/create", &key, json!({"text": "foo"}));
+    assert_that(&resp).is_ok().has_json();
+    assert_that(&resp.headers().get_raw("Idempotent-Replayed")).is_some();
+    let second = json(resp);
+
+    assert_that(&second.get("id")).is_equal_to(first.get("id"));
+
+    let resp = post_with_key(&svc, "/todos/create", &key, json!({"text": "bar"}));
+    assert_that(&resp)
+      .has_status(StatusCode::UnprocessableEntity)
+      .has_json();
+
+    let key = format!("post-{}", Utc::now().timestamp_nanos());
+    let resp = post_with_key(&svc, "/todos", &key, json!({"text": "foo"}));
+    assert_that(&resp).has_status(StatusCode::Created);
+    let location = resp.headers().get::<Location>().unwrap().to_string();
+
+    let resp = post_with_key(&svc, "/todos", &key, json!({"text": "foo"}));
+    assert_that(&resp).has_status(StatusCode::Created);
+    assert_that(&resp.headers().get::<Location>().map(|it| it.to_string()))
+      .is_equal_to(Some(location));
+  }
+
   #[test]
   fn should_handle_json_error() {
     let svc = create_server();
@@ -193,6 +1106,64 @@ mod tests {
       .has_json();
   }
 
+  #[test]
+  fn should_negotiate_msgpack_and_cbor() {
+    let svc = create_server();
+
+    let mut req: Request<Body> = Request::new(Post, Uri::from_str("/todos").unwrap());
+    req.headers_mut().set_raw("Content-Type", "application/msgpack");
+    req.headers_mut().set_raw("Accept", "application/cbor");
+    // {"text": "foo"}
+    req.set_body(vec![0x81, 0xa4, b't', b'e', b'x', b't', 0xa3, b'f', b'o', b'o']);
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp).has_status(StatusCode::Created);
+    assert_that(&resp.headers().get::<ContentType>().map(|it| it.to_string()))
+      .is_equal_to(Some("application/cbor".to_string()));
+
+    let chunk = resp.body().concat2().wait().unwrap();
+    let todo = MediaType::Cbor.decode(&chunk).unwrap();
+    assert_that(&todo["text"]).is_equal_to(&json!("foo"));
+
+    let mut req: Request<Body> = Request::new(Post, Uri::from_str("/todos").unwrap());
+    req.headers_mut().set_raw("Content-Type", "text/xml");
+    req.set_body("<todo/>");
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp)
+      .has_status(StatusCode::UnsupportedMediaType)
+      .has_json();
+
+    let mut req = Request::new(Get, Uri::from_str("/todos").unwrap());
+    req.headers_mut().set_raw("Accept", "text/html");
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp).has_status(StatusCode::NotAcceptable);
+
+    // a write isn't made when its response can't be encoded
+    let mut req: Request<Body> = Request::new(Post, Uri::from_str("/todos").unwrap());
+    req.headers_mut().set_raw("Accept", "text/html");
+    req.set_body(r#"{"text": "foo"}"#);
+    let resp = svc.call(req).wait().unwrap();
+    assert_that(&resp).has_status(StatusCode::NotAcceptable).has_json();
+  }
+
+  /// Methods and paths of documented operations.
+  fn documented(spec: &JsonValue) -> Vec<(Method, String)> {
+    let paths = match spec["paths"].as_object() {
+      Some(paths) => paths,
+      None => return Vec::new(),
+    };
+
+    let mut documented = Vec::new();
+    for (path, operations) in paths {
+      for method in operations.as_object().into_iter().flat_map(|it| it.keys()) {
+        if let Ok(method) = method.to_uppercase().parse() {
+          documented.push((method, path.clone()));
+        }
+      }
+    }
+
+    documented
+  }
+
   fn json(resp: Response) -> JsonValue {
     let chunk = resp.body().concat2().wait().unwrap();
     serde_json::from_slice(&chunk).unwrap()
@@ -206,17 +1177,80 @@ mod tests {
     svc.call(req).wait().unwrap()
   }
 
+  fn admin_post(svc: &Server, path: &str, body: JsonValue) -> Response {
+    let mut req: Request<Body> = Request::new(Post, Uri::from_str(path).unwrap());
+    req
+      .headers_mut()
+      .set_raw("Authorization", format!("Bearer {}", ADMIN_TOKEN));
+    req.set_body(serde_json::to_string(&body).unwrap());
+
+    svc.call(req).wait().unwrap()
+  }
+
+  fn admin_get(svc: &Server, path: &str) -> Response {
+    let mut req: Request<Body> = Request::new(Get, Uri::from_str(path).unwrap());
+    req
+      .headers_mut()
+      .set_raw("Authorization", format!("Bearer {}", ADMIN_TOKEN));
+
+    svc.call(req).wait().unwrap()
+  }
+
+  fn request(svc: &Server, method: Method, path: &str, body: JsonValue) -> Response {
+    let mut req: Request<Body> = Request::new(method, Uri::from_str(path).unwrap());
+    req.set_body(serde_json::to_string(&body).unwrap());
+
+    svc.call(req).wait().unwrap()
+  }
+
+  fn post_with_key(svc: &Server, path: &str, key: &str, body: JsonValue) -> Response {
+    let mut req: Request<Body> = Request::new(Post, Uri::from_str(path).unwrap());
+    let body = serde_json::to_string(&body).unwrap();
+    req.headers_mut().set_raw("Idempotency-Key", key.to_string());
+    req.set_body(body);
+
+    svc.call(req).wait().unwrap()
+  }
+
+  fn dav(
+    svc: &Server,
+    method: &str,
+    path: &str,
+    token: &str,
+    headers: Vec<(&'static str, &str)>,
+    body: &str,
+  ) -> Response {
+    let method = Method::from_str(method).unwrap();
+    let mut req: Request<Body> = Request::new(method, Uri::from_str(path).unwrap());
+    req.headers_mut().set(Authorization(Basic {
+      username: "alice".to_string(),
+      password: Some(token.to_string()),
+    }));
+    for (name, value) in headers {
+      req.headers_mut().set_raw(name, value.to_string());
+    }
+    req.set_body(body.to_string());
+
+    svc.call(req).wait().unwrap()
+  }
+
   fn get(svc: &Server, path: &str) -> Response {
     let req = Request::new(Get, Uri::from_str(path).unwrap());
     svc.call(req).wait().unwrap()
   }
 
   fn create_server() -> Server {
-    let cfg = Config::default();
+    create_server_with(Config::default())
+  }
+
+  fn create_server_with(cfg: Config) -> Server {
+    let cfg = Config {
+      admin_token: ADMIN_TOKEN.to_string(),
+      ..cfg
+    };
     let cpu_pool = cfg.create_cpu_pool();
     let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
-    let todos_repo = db::TodosRepo::new(conn_pool, cpu_pool);
 
-    Server::new(todos_repo)
+    Server::new(&cfg, conn_pool, cpu_pool, Metrics::new(), Tracer::disabled())
   }
 }
@@ -0,0 +1,38 @@
+use futures::Future;
+use hyper::{Request, Response};
+
+use result::Error;
+use db::{SyncRepo, SyncTodos};
+use common::{FuturesExt, RequestExt, ResponseExt};
+use validators::Validator;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+pub struct SyncController {
+  sync_repo: SyncRepo,
+}
+
+impl SyncController {
+  pub fn new(sync_repo: SyncRepo) -> Self {
+    SyncController { sync_repo }
+  }
+
+  /// Upload local changes and return changes after the request's token with a new token.
+  pub fn call_sync(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.sync_repo.clone();
+
+    req
+      .json::<SyncTodos>()
+      .and_then(|it| it.validated())
+      .and_then(move |it| repo.sync(it))
+      .inspect(|it| {
+        info!(
+          "synced {} changes, {} conflicts",
+          it.applied.len(),
+          it.conflicts.len()
+        )
+      })
+      .map(|it| Response::new().json(&it))
+      .into_boxed()
+  }
+}
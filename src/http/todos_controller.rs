@@ -1,13 +1,25 @@
-use hyper::{Request, Response};
-use futures::Future;
+use chrono::NaiveDateTime;
+use hyper::{Request, Response, StatusCode};
+use hyper::header::{ContentLength, Location};
+use futures::{Future, IntoFuture};
+use std::collections::HashMap;
 
 use result::Error;
 use db::{NewTodo, QueryTodos, TodosRepo, UpdateTodo};
 use common::{FuturesExt, RequestExt, ResponseExt};
 use validators::Validator;
+use trace;
 
 type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
 
+/// Changes of a todo item which id is in the path.
+#[derive(Debug, Clone, Deserialize)]
+struct TodoChanges {
+  text: Option<String>,
+  done: Option<bool>,
+  changed_at: Option<NaiveDateTime>,
+}
+
 pub struct TodosController {
   todos_repo: TodosRepo,
 }
@@ -20,35 +32,169 @@ impl TodosController {
   pub fn call_create(&self, req: Request) -> BoxFuture<Response> {
     let repo = self.todos_repo.clone();
 
-    req
-      .json::<NewTodo>()
-      .and_then(|it| it.validated())
-      .and_then(move |it| repo.insert(it))
-      .inspect(|it| info!("created {:?}", it))
-      .map(|it| Response::new().json(&it))
-      .into_boxed()
+    trace::in_span("TodosController.create", move || {
+      req
+        .json::<NewTodo>()
+        .and_then(|it| it.validated())
+        .and_then(move |it| repo.insert(it))
+        .inspect(|it| info!("created {:?}", it))
+        .map(|it| Response::new().json(&it))
+        .into_boxed()
+    })
   }
 
   pub fn call_query(&self, req: Request) -> BoxFuture<Response> {
     let repo = self.todos_repo.clone();
 
-    req
-      .json::<QueryTodos>()
-      .and_then(|it| it.validated())
-      .and_then(move |it| repo.query(it))
-      .map(|it| Response::new().json(&it))
-      .into_boxed()
+    trace::in_span("TodosController.query", move || {
+      req
+        .json::<QueryTodos>()
+        .and_then(|it| it.validated())
+        .and_then(move |it| repo.query(it))
+        .map(|it| Response::new().json(&it))
+        .into_boxed()
+    })
   }
 
   pub fn call_update(&self, req: Request) -> BoxFuture<Response> {
     let repo = self.todos_repo.clone();
 
-    req
-      .json::<UpdateTodo>()
-      .and_then(|it| it.validated())
-      .and_then(move |it| repo.update(it))
-      .inspect(|it| info!("updated {:?}", it))
-      .map(|it| Response::new().json(&it))
-      .into_boxed()
+    trace::in_span("TodosController.update", move || {
+      req
+        .json::<UpdateTodo>()
+        .and_then(|it| it.validated())
+        .and_then(move |it| repo.update(it))
+        .inspect(|it| info!("updated {:?}", it))
+        .map(|it| Response::new().json(&it))
+        .into_boxed()
+    })
+  }
+
+  /// `GET /todos` filtered by `next`, `limit` and `text` query parameters.
+  pub fn call_list(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+
+    trace::in_span("TodosController.list", move || {
+      query_params(&req.query_pairs())
+        .and_then(|it| it.validated())
+        .into_future()
+        .and_then(move |it| repo.query(it))
+        .map(|it| Response::new().json(&it))
+        .into_boxed()
+    })
+  }
+
+  /// `GET /todos/{id}`
+  pub fn call_find(&self, id: i64) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+
+    trace::in_span("TodosController.find", move || {
+      repo
+        .find(id)
+        .map(|it| Response::new().json(&it))
+        .into_boxed()
+    })
+  }
+
+  /// `POST /todos`, responds with `201 Created` and the item's location.
+  pub fn call_post(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+
+    trace::in_span("TodosController.post", move || {
+      req
+        .json::<NewTodo>()
+        .and_then(|it| it.validated())
+        .and_then(move |it| repo.insert(it))
+        .inspect(|it| info!("created {:?}", it))
+        .map(|it| {
+          Response::new()
+            .with_status(StatusCode::Created)
+            .with_header(Location::new(format!("/todos/{}", it.id)))
+            .json(&it)
+        })
+        .into_boxed()
+    })
+  }
+
+  /// `PATCH /todos/{id}`, missing fields are left as they are.
+  pub fn call_patch(&self, id: i64, req: Request) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+
+    trace::in_span("TodosController.patch", move || {
+      req
+        .json::<TodoChanges>()
+        .map(move |it| UpdateTodo {
+          id,
+          text: it.text,
+          done: it.done,
+          changed_at: it.changed_at,
+        })
+        .and_then(|it| it.validated())
+        .and_then(move |it| repo.update(it))
+        .inspect(|it| info!("updated {:?}", it))
+        .map(|it| Response::new().json(&it))
+        .into_boxed()
+    })
+  }
+
+  /// `DELETE /todos/{id}`
+  pub fn call_delete(&self, id: i64) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+
+    trace::in_span("TodosController.delete", move || {
+      repo
+        .delete(id, |_| Ok(()))
+        .inspect(move |_| info!("deleted {}", id))
+        .map(|_| {
+          Response::new()
+            .with_status(StatusCode::NoContent)
+            .with_header(ContentLength(0))
+        })
+        .into_boxed()
+    })
+  }
+}
+
+/// Parse `next`, `limit` and `text` query parameters.
+pub fn query_params(params: &HashMap<String, String>) -> Result<QueryTodos, Error> {
+  let next = match params.get("next") {
+    Some(next) => Some(next
+      .parse()
+      .map_err(|_| Error::Validation(format!("cannot parse next {:?}", next)))?),
+    None => None,
+  };
+  let limit = match params.get("limit") {
+    Some(limit) => Some(limit
+      .parse()
+      .map_err(|_| Error::Validation(format!("cannot parse limit {:?}", limit)))?),
+    None => None,
+  };
+
+  Ok(QueryTodos {
+    next,
+    limit,
+    text: params.get("text").cloned(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_parse_query_params() {
+    let params = vec![("next", "10"), ("limit", "5"), ("text", "foo")]
+      .into_iter()
+      .map(|(name, value)| (name.to_string(), value.to_string()))
+      .collect();
+    let query = query_params(&params).unwrap();
+    assert_that(&query.next).is_equal_to(Some(10));
+    assert_that(&query.limit).is_equal_to(Some(5));
+    assert_that(&query.text).is_equal_to(Some("foo".to_string()));
+
+    let mut params = HashMap::new();
+    params.insert("limit".to_string(), "many".to_string());
+    assert_that(&query_params(&params)).is_err();
   }
 }
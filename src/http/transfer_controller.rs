@@ -0,0 +1,119 @@
+use futures::{future, Future, Sink, Stream};
+use futures_cpupool::CpuPool;
+use hyper::{self, Body, Chunk, Request, Response};
+use hyper::header::ContentType;
+use std::collections::HashMap;
+use std::io;
+
+use result::Error;
+use db::TodosRepo;
+use common::{FuturesExt, RequestExt, ResponseExt};
+use transfer::{self, Format, ImportOptions};
+use validators::Validator;
+
+use super::todos_controller::query_params;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+pub struct TransferController {
+  todos_repo: TodosRepo,
+  cpu_pool: CpuPool,
+  /// largest body of an import, bytes
+  max_body_size: u64,
+}
+
+impl TransferController {
+  pub fn new(todos_repo: TodosRepo, cpu_pool: CpuPool, max_body_size: u64) -> Self {
+    TransferController {
+      todos_repo,
+      cpu_pool,
+      max_body_size,
+    }
+  }
+
+  /// Stream todo items filtered by `text` and `next` query parameters.
+  pub fn call_export(&self, req: Request) -> BoxFuture<Response> {
+    let params = req.query_pairs();
+
+    let query = format_param(&params)
+      .and_then(|format| Ok((format, query_params(&params)?.validated()?)));
+
+    let (format, query) = match query {
+      Ok(it) => it,
+      Err(err) => return future::err(err).into_boxed(),
+    };
+
+    let chunks = transfer::export(&self.todos_repo, query, format);
+    let mut resp = stream_response(&self.cpu_pool, chunks, format);
+
+    let disposition = format!("attachment; filename=\"todos.{}\"", format.extension());
+    resp
+      .headers_mut()
+      .set_raw("Content-Disposition", disposition);
+
+    future::ok(resp).into_boxed()
+  }
+
+  /// Import todo items from the request body, see `ImportOptions` for query parameters.
+  ///
+  /// A body larger than `MAX_BODY_SIZE` is rejected with `413 Payload Too Large`.
+  pub fn call_import(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.todos_repo.clone();
+    let params = req.query_pairs();
+
+    let options = format_param(&params).map(|format| ImportOptions {
+      format,
+      keep_ids: params.get("ids").map(String::as_str) == Some("keep"),
+      dry_run: params.get("dry_run").map(String::as_str) == Some("true"),
+    });
+
+    let options = match options {
+      Ok(it) => it,
+      Err(err) => return future::err(err).into_boxed(),
+    };
+
+    req
+      .bytes(self.max_body_size)
+      .and_then(move |bytes| transfer::import(&repo, &bytes, options))
+      .inspect(|it| info!("imported {} todo items, {} failed", it.imported, it.failed))
+      .map(|it| Response::new().json(&it))
+      .into_boxed()
+  }
+}
+
+/// Create a response which body is sent from a stream of encoded chunks.
+///
+/// The stream is driven by the cpu pool, an error in the middle aborts the response.
+pub fn stream_response(
+  cpu_pool: &CpuPool,
+  chunks: Box<Stream<Item = Vec<u8>, Error = Error> + Send>,
+  format: Format,
+) -> Response {
+  let (sender, body) = Body::pair();
+  let chunks = chunks.then(|result| {
+    Ok::<_, ()>(result.map(Chunk::from).map_err(|err| {
+      error!("export failed {}", err);
+      hyper::Error::from(io::Error::new(io::ErrorKind::Other, err.to_string()))
+    }))
+  });
+
+  cpu_pool
+    .spawn(sender.sink_map_err(|_| ()).send_all(chunks))
+    .forget();
+
+  let mime = format
+    .content_type()
+    .parse()
+    .expect("content type cannot be invalid");
+
+  Response::new()
+    .with_header(ContentType(mime))
+    .with_body(body)
+}
+
+fn format_param(params: &HashMap<String, String>) -> Result<Format, Error> {
+  params
+    .get("format")
+    .map(|it| it.parse())
+    .unwrap_or(Ok(Format::JsonLines))
+}
@@ -0,0 +1,107 @@
+use futures::Future;
+use futures_cpupool::CpuPool;
+use hyper::{Request, Response};
+use serde_json::Value as JsonValue;
+
+use result::Error;
+use db::{NewWebhook, Webhook, WebhooksRepo};
+use common::{FuturesExt, RequestExt, ResponseExt};
+use validators::Validator;
+use webhooks;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// Parameters which address a single webhook or delivery
+#[derive(Debug, Deserialize)]
+struct ById {
+  id: i64,
+}
+
+pub struct WebhooksController {
+  webhooks_repo: WebhooksRepo,
+  cpu_pool: CpuPool,
+}
+
+impl WebhooksController {
+  pub fn new(webhooks_repo: WebhooksRepo, cpu_pool: CpuPool) -> Self {
+    WebhooksController {
+      webhooks_repo,
+      cpu_pool,
+    }
+  }
+
+  /// Register a webhook, the secret to verify signatures is returned only here.
+  ///
+  /// The url's host is resolved on the cpu pool, hosts with non-public addresses are rejected.
+  pub fn call_create(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.webhooks_repo.clone();
+    let cpu_pool = self.cpu_pool.clone();
+
+    req
+      .json::<NewWebhook>()
+      .and_then(|it| it.validated())
+      .and_then(move |it| {
+        cpu_pool.spawn_fn(move || match webhooks::resolve(&it.url) {
+          Ok(_) => Ok(it),
+          Err(err) => Err(Error::Validation(format!("webhook's url is not allowed: {}", err))),
+        })
+      })
+      .and_then(move |it| repo.create(it))
+      .inspect(|it| info!("created webhook {} for {}", it.id, it.url))
+      .map(|it| Response::new().json(&webhook_json(&it, true)))
+      .into_boxed()
+  }
+
+  pub fn call_list(&self) -> BoxFuture<Response> {
+    self
+      .webhooks_repo
+      .list()
+      .map(|items| {
+        let items: Vec<_> = items.iter().map(|it| webhook_json(it, false)).collect();
+        Response::new().json(&items)
+      })
+      .into_boxed()
+  }
+
+  pub fn call_delete(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.webhooks_repo.clone();
+
+    req
+      .json::<ById>()
+      .and_then(move |it| repo.delete(it.id))
+      .map(|_| Response::new().json(&json!({"ok": true})))
+      .into_boxed()
+  }
+
+  pub fn call_dead_letters(&self) -> BoxFuture<Response> {
+    self
+      .webhooks_repo
+      .dead_letters()
+      .map(|items| Response::new().json(&items))
+      .into_boxed()
+  }
+
+  /// Schedule a dead letter's delivery again.
+  pub fn call_retry(&self, req: Request) -> BoxFuture<Response> {
+    let repo = self.webhooks_repo.clone();
+
+    req
+      .json::<ById>()
+      .and_then(move |it| repo.retry(it.id))
+      .map(|_| Response::new().json(&json!({"ok": true})))
+      .into_boxed()
+  }
+}
+
+fn webhook_json(hook: &Webhook, with_secret: bool) -> JsonValue {
+  let mut value = json!({
+    "id": hook.id,
+    "url": hook.url,
+    "events": hook.events(),
+    "created_at": hook.created_at,
+  });
+  if with_secret {
+    value["secret"] = json!(hook.secret);
+  }
+  value
+}
@@ -0,0 +1,183 @@
+use bytes::{BufMut, BytesMut};
+use std::io;
+use tokio_io::codec::{Decoder, Encoder};
+
+/// Larger messages close the connection.
+const MAX_PAYLOAD_LEN: usize = 1 << 20;
+
+pub const CONTINUATION: u8 = 0x0;
+pub const TEXT: u8 = 0x1;
+pub const BINARY: u8 = 0x2;
+pub const CLOSE: u8 = 0x8;
+pub const PING: u8 = 0x9;
+pub const PONG: u8 = 0xa;
+
+/// A websocket frame, see RFC 6455 section 5.2.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+  pub fin: bool,
+  pub opcode: u8,
+  pub payload: Vec<u8>,
+}
+
+impl Frame {
+  pub fn text(text: String) -> Self {
+    Frame {
+      fin: true,
+      opcode: TEXT,
+      payload: text.into_bytes(),
+    }
+  }
+
+  pub fn control(opcode: u8, payload: Vec<u8>) -> Self {
+    Frame {
+      fin: true,
+      opcode,
+      payload,
+    }
+  }
+
+  pub fn is_control(&self) -> bool {
+    self.opcode & 0x8 != 0
+  }
+}
+
+/// Decodes masked client frames and encodes unmasked server frames.
+pub struct FrameCodec;
+
+impl Decoder for FrameCodec {
+  type Item = Frame;
+  type Error = io::Error;
+
+  fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Frame>, io::Error> {
+    if buf.len() < 2 {
+      return Ok(None);
+    }
+
+    let (first, second) = (buf[0], buf[1]);
+    if first & 0x70 != 0 {
+      return Err(invalid("reserved bits must be zero"));
+    }
+    if second & 0x80 == 0 {
+      return Err(invalid("client frames must be masked"));
+    }
+
+    let (len, offset) = match second & 0x7f {
+      126 if buf.len() >= 4 => ((usize::from(buf[2]) << 8) | usize::from(buf[3]), 4),
+      127 if buf.len() >= 10 => {
+        let len = buf[2..10]
+          .iter()
+          .fold(0u64, |len, byte| (len << 8) | u64::from(*byte));
+        if len > MAX_PAYLOAD_LEN as u64 {
+          return Err(invalid("frame is too large"));
+        }
+        (len as usize, 10)
+      }
+      126 | 127 => return Ok(None),
+      len => (usize::from(len), 2),
+    };
+
+    if len > MAX_PAYLOAD_LEN {
+      return Err(invalid("frame is too large"));
+    }
+    if buf.len() < offset + 4 + len {
+      return Ok(None);
+    }
+
+    let frame = buf.split_to(offset + 4 + len);
+    let mask = [
+      frame[offset],
+      frame[offset + 1],
+      frame[offset + 2],
+      frame[offset + 3],
+    ];
+    let payload = frame[offset + 4..]
+      .iter()
+      .enumerate()
+      .map(|(i, byte)| byte ^ mask[i % 4])
+      .collect();
+
+    let frame = Frame {
+      fin: first & 0x80 != 0,
+      opcode: first & 0x0f,
+      payload,
+    };
+    if frame.is_control() && (!frame.fin || frame.payload.len() > 125) {
+      return Err(invalid("control frames cannot be fragmented or longer than 125 bytes"));
+    }
+
+    Ok(Some(frame))
+  }
+}
+
+impl Encoder for FrameCodec {
+  type Item = Frame;
+  type Error = io::Error;
+
+  fn encode(&mut self, frame: Frame, buf: &mut BytesMut) -> Result<(), io::Error> {
+    let len = frame.payload.len();
+    buf.reserve(len + 10);
+
+    let fin = if frame.fin { 0x80 } else { 0 };
+    buf.put_u8(fin | frame.opcode);
+
+    if len < 126 {
+      buf.put_u8(len as u8);
+    } else if len <= 0xffff {
+      buf.put_u8(126);
+      buf.put_u16_be(len as u16);
+    } else {
+      buf.put_u8(127);
+      buf.put_u64_be(len as u64);
+    }
+
+    buf.put_slice(&frame.payload);
+    Ok(())
+  }
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_decode_masked_frame() {
+    // RFC 6455 section 5.7, a masked "Hello"
+    let mut buf = BytesMut::from(
+      &[
+        0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58, 0x81,
+      ][..],
+    );
+
+    let frame = FrameCodec.decode(&mut buf).unwrap().unwrap();
+    assert_that(&frame).is_equal_to(Frame::text("Hello".to_string()));
+    assert_that(&buf.len()).is_equal_to(1);
+    assert_that(&FrameCodec.decode(&mut buf).unwrap()).is_none();
+  }
+
+  #[test]
+  fn should_reject_unmasked_frame() {
+    let mut buf = BytesMut::from(&[0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f][..]);
+    assert_that(&FrameCodec.decode(&mut buf)).is_err();
+  }
+
+  #[test]
+  fn should_encode_frames() {
+    let mut buf = BytesMut::new();
+    FrameCodec
+      .encode(Frame::text("Hello".to_string()), &mut buf)
+      .unwrap();
+    assert_that(&&buf[..]).is_equal_to(&b"\x81\x05Hello"[..]);
+
+    let mut buf = BytesMut::new();
+    FrameCodec
+      .encode(Frame::text("x".repeat(300)), &mut buf)
+      .unwrap();
+    assert_that(&&buf[..4]).is_equal_to(&[0x81, 126, 0x01, 0x2c][..]);
+  }
+}
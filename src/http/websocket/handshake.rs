@@ -0,0 +1,238 @@
+use base64;
+use hyper::{Request, StatusCode};
+
+/// Appended to a client's key, see RFC 6455 section 1.3.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// An upgrade request, only the fields the handshake needs.
+#[derive(Debug, Default, PartialEq)]
+pub struct Handshake {
+  pub method: String,
+  pub upgrade: Option<String>,
+  pub connection: Option<String>,
+  pub version: Option<String>,
+  pub key: Option<String>,
+  pub host: Option<String>,
+  pub origin: Option<String>,
+}
+
+/// A reply to an upgrade request.
+#[derive(Debug, PartialEq)]
+pub enum HandshakeReply {
+  Accept(String),
+  Reject(StatusCode, &'static str),
+}
+
+impl<'a> From<&'a Request> for Handshake {
+  fn from(req: &'a Request) -> Self {
+    let header = |name: &str| {
+      req
+        .headers()
+        .get_raw(name)
+        .and_then(|raw| raw.one())
+        .and_then(|it| String::from_utf8(it.to_vec()).ok())
+    };
+
+    Handshake {
+      method: req.method().to_string(),
+      upgrade: header("Upgrade"),
+      connection: header("Connection"),
+      version: header("Sec-WebSocket-Version"),
+      key: header("Sec-WebSocket-Key"),
+      host: header("Host"),
+      origin: header("Origin"),
+    }
+  }
+}
+
+impl Handshake {
+  /// Check the request is a websocket upgrade from an allowed origin.
+  ///
+  /// Requests without `Origin` don't come from browsers and are accepted. A page's origin must
+  /// be the server's own or one of `allowed_origins`.
+  pub fn reply(&self, allowed_origins: &[String]) -> HandshakeReply {
+    let has_token = |value: &Option<String>, token: &str| match *value {
+      Some(ref value) => value
+        .split(',')
+        .any(|it| it.trim().eq_ignore_ascii_case(token)),
+      None => false,
+    };
+
+    if !has_token(&self.upgrade, "websocket") {
+      return HandshakeReply::Reject(StatusCode::UpgradeRequired, "Upgrade Required");
+    }
+    if !self.is_allowed_origin(allowed_origins) {
+      return HandshakeReply::Reject(StatusCode::Forbidden, "Forbidden");
+    }
+
+    let valid = self.method == "GET" && has_token(&self.connection, "upgrade")
+      && self.version.as_ref().map(|it| it.trim()) == Some("13");
+
+    match self.key {
+      Some(ref key) if valid => HandshakeReply::Accept(accept_key(key.trim())),
+      _ => HandshakeReply::Reject(StatusCode::BadRequest, "Bad Request"),
+    }
+  }
+
+  fn is_allowed_origin(&self, allowed_origins: &[String]) -> bool {
+    let origin = match self.origin {
+      Some(ref origin) => origin.trim().trim_right_matches('/').to_lowercase(),
+      None => return true,
+    };
+    let own = self.host.as_ref().map_or(false, |host| {
+      let host = host.trim().to_lowercase();
+      origin == format!("http://{}", host) || origin == format!("https://{}", host)
+    });
+
+    own || allowed_origins.iter().any(|it| *it == origin)
+  }
+}
+
+/// `Sec-WebSocket-Accept` for a client's `Sec-WebSocket-Key`.
+pub fn accept_key(key: &str) -> String {
+  base64::encode(&sha1(format!("{}{}", key, ACCEPT_GUID).as_bytes()))
+}
+
+/// SHA-1 as defined in RFC 3174, only the handshake uses it.
+fn sha1(data: &[u8]) -> [u8; 20] {
+  let mut h: [u32; 5] = [
+    0x6745_2301,
+    0xEFCD_AB89,
+    0x98BA_DCFE,
+    0x1032_5476,
+    0xC3D2_E1F0,
+  ];
+
+  let mut msg = data.to_vec();
+  msg.push(0x80);
+  while msg.len() % 64 != 56 {
+    msg.push(0);
+  }
+  let bits = (data.len() as u64).wrapping_mul(8);
+  for i in (0..8).rev() {
+    msg.push((bits >> (i * 8)) as u8);
+  }
+
+  for chunk in msg.chunks(64) {
+    let mut w = [0u32; 80];
+    for (word, bytes) in w.iter_mut().zip(chunk.chunks(4)) {
+      *word = bytes
+        .iter()
+        .fold(0u32, |word, byte| (word << 8) | u32::from(*byte));
+    }
+    for i in 16..80 {
+      w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+    }
+
+    let (mut a, mut b, mut c, mut d, mut e) = (h[0], h[1], h[2], h[3], h[4]);
+    for (i, word) in w.iter().enumerate() {
+      let (f, k) = if i < 20 {
+        ((b & c) | (!b & d), 0x5A82_7999)
+      } else if i < 40 {
+        (b ^ c ^ d, 0x6ED9_EBA1)
+      } else if i < 60 {
+        ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC)
+      } else {
+        (b ^ c ^ d, 0xCA62_C1D6)
+      };
+      let temp = a.rotate_left(5)
+        .wrapping_add(f)
+        .wrapping_add(e)
+        .wrapping_add(k)
+        .wrapping_add(*word);
+      e = d;
+      d = c;
+      c = b.rotate_left(30);
+      b = a;
+      a = temp;
+    }
+
+    h[0] = h[0].wrapping_add(a);
+    h[1] = h[1].wrapping_add(b);
+    h[2] = h[2].wrapping_add(c);
+    h[3] = h[3].wrapping_add(d);
+    h[4] = h[4].wrapping_add(e);
+  }
+
+  let mut digest = [0u8; 20];
+  for (i, word) in h.iter().enumerate() {
+    for j in 0..4 {
+      digest[i * 4 + j] = (word >> (24 - j * 8)) as u8;
+    }
+  }
+  digest
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_hash_sha1() {
+    let hex = |bytes: [u8; 20]| {
+      bytes
+        .iter()
+        .map(|it| format!("{:02x}", it))
+        .collect::<String>()
+    };
+
+    assert_that(&hex(sha1(b"abc"))).is_equal_to("a9993e364706816aba3e25717850c26c9cd0d89d".to_string());
+    assert_that(&hex(sha1(b""))).is_equal_to("da39a3ee5e6b4b0d3255bfef95601890afd80709".to_string());
+  }
+
+  fn upgrade() -> Handshake {
+    Handshake {
+      method: "GET".to_string(),
+      upgrade: Some("websocket".to_string()),
+      connection: Some("keep-alive, Upgrade".to_string()),
+      version: Some("13".to_string()),
+      key: Some("dGhlIHNhbXBsZSBub25jZQ==".to_string()),
+      host: Some("localhost:3000".to_string()),
+      origin: None,
+    }
+  }
+
+  #[test]
+  fn should_accept_upgrade() {
+    assert_that(&upgrade().reply(&[])).is_equal_to(HandshakeReply::Accept(
+      "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_string(),
+    ));
+  }
+
+  #[test]
+  fn should_reject_plain_requests() {
+    let plain = Handshake {
+      upgrade: None,
+      ..upgrade()
+    };
+    assert_that(&plain.reply(&[]))
+      .is_equal_to(HandshakeReply::Reject(StatusCode::UpgradeRequired, "Upgrade Required"));
+
+    let no_key = Handshake {
+      key: None,
+      ..upgrade()
+    };
+    assert_that(&no_key.reply(&[]))
+      .is_equal_to(HandshakeReply::Reject(StatusCode::BadRequest, "Bad Request"));
+  }
+
+  #[test]
+  fn should_check_origin() {
+    let from = |origin: &str| Handshake {
+      origin: Some(origin.to_string()),
+      ..upgrade()
+    };
+    let allowed = vec!["https://app.example.com".to_string()];
+    let accepted = |reply: HandshakeReply| match reply {
+      HandshakeReply::Accept(_) => true,
+      HandshakeReply::Reject(..) => false,
+    };
+
+    assert_that(&accepted(from("http://localhost:3000").reply(&[]))).is_true();
+    assert_that(&accepted(from("https://app.example.com").reply(&allowed))).is_true();
+    assert_that(&accepted(from("https://evil.example.com").reply(&allowed))).is_false();
+    assert_that(&from("http://localhost:4000").reply(&[]))
+      .is_equal_to(HandshakeReply::Reject(StatusCode::Forbidden, "Forbidden"));
+  }
+}
@@ -0,0 +1,142 @@
+mod frame;
+mod handshake;
+mod session;
+
+use bytes::{Bytes, BytesMut};
+use futures::{future, Future, Stream};
+use futures::sync::mpsc;
+use futures_cpupool::CpuPool;
+use hyper::{Request, Response, StatusCode};
+use std::cell::Cell;
+use std::io;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Handle;
+use tokio_io::codec::{Framed, FramedParts};
+
+use config::Config;
+use db::TodosRepo;
+use result::Error;
+use common::{FuturesExt, ResponseExt};
+
+use super::event_bus::EventBus;
+use super::rate_limiter::{ip_key, RateLimiter};
+
+use self::frame::{Frame, FrameCodec};
+use self::handshake::{Handshake, HandshakeReply};
+use self::session::{Out, Session};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// How many frames wait for a client which doesn't read them, then its subscription is reset and
+/// a connection which doesn't read replies is closed.
+const OUT_BUFFER: usize = 100;
+
+/// Runs websocket sessions which create, update and query todos with JSON messages and subscribe
+/// to changes.
+///
+/// A client upgrades `GET /todos/ws` like any other request, the server hands its connection
+/// over to `connect` after the reply.
+#[derive(Clone)]
+pub struct WebSockets {
+  todos_repo: TodosRepo,
+  event_bus: EventBus,
+  cpu_pool: CpuPool,
+  rate_limiter: RateLimiter,
+  /// origins of pages besides the server's own which may connect
+  allowed_origins: Vec<String>,
+}
+
+impl WebSockets {
+  pub fn new(
+    cfg: &Config,
+    todos_repo: TodosRepo,
+    event_bus: EventBus,
+    cpu_pool: CpuPool,
+    rate_limiter: RateLimiter,
+  ) -> Self {
+    WebSockets {
+      todos_repo,
+      event_bus,
+      cpu_pool,
+      rate_limiter,
+      allowed_origins: cfg.ws_allowed_origins.clone(),
+    }
+  }
+
+  /// Reply to an upgrade request, `101 Switching Protocols` makes the server call `connect`.
+  ///
+  /// Browsers don't apply CORS to websockets, so a page of another origin is rejected with
+  /// `403 Forbidden` unless it's allowed, otherwise any site could act with a user's access.
+  pub fn call_upgrade(&self, req: Request) -> BoxFuture<Response> {
+    let mut resp = Response::new();
+    match Handshake::from(&req).reply(&self.allowed_origins) {
+      HandshakeReply::Accept(key) => {
+        resp.set_status(StatusCode::SwitchingProtocols);
+        let headers = resp.headers_mut();
+        headers.set_raw("Upgrade", "websocket");
+        headers.set_raw("Connection", "Upgrade");
+        headers.set_raw("Sec-WebSocket-Accept", key);
+      }
+      HandshakeReply::Reject(status, reason) => {
+        let body = json!({"error": reason, "description": "websocket upgrade is rejected"});
+        resp.set_status(status);
+        resp.headers_mut().set_raw("Sec-WebSocket-Version", "13");
+        resp = resp.json(&body);
+      }
+    }
+
+    future::ok(resp).into_boxed()
+  }
+
+  /// Start a session on an upgraded connection, `read_buf` has bytes read after the request.
+  pub fn connect(&self, stream: TcpStream, read_buf: Bytes, peer: SocketAddr, handle: &Handle) {
+    let parts = FramedParts {
+      inner: stream,
+      readbuf: BytesMut::from(read_buf),
+      writebuf: BytesMut::new(),
+    };
+    let (sink, frames) = Framed::from_parts(parts, FrameCodec).split();
+    let (sender, outgoing) = mpsc::channel::<Frame>(OUT_BUFFER);
+    let out = Out::new(sender);
+
+    let mut session = Session::new(
+      self.todos_repo.clone(),
+      self.event_bus.clone(),
+      self.cpu_pool.clone(),
+      handle.clone(),
+      out.clone(),
+      self.rate_limiter.clone(),
+      ip_key(Some(peer)),
+    );
+    let reading = frames
+      .take_while(|frame| Ok(frame.opcode != frame::CLOSE))
+      .for_each(move |frame| session.receive(frame))
+      .then(move |result| {
+        if let Err(err) = result {
+          info!("websocket {} closed {}", peer, err);
+        }
+        out.send(Frame::control(frame::CLOSE, Vec::new()));
+        Ok(())
+      });
+
+    // the writer stops after a close frame
+    let closed = Rc::new(Cell::new(false));
+    let writing = outgoing
+      .take_while(move |frame| {
+        let open = !closed.get();
+        if frame.opcode == frame::CLOSE {
+          closed.set(true);
+        }
+        Ok(open)
+      })
+      .map_err(|_| io::Error::new(io::ErrorKind::Other, "closed"))
+      .forward(sink)
+      .map(|_| ())
+      .map_err(move |err| info!("websocket {} closed {}", peer, err));
+
+    handle.spawn(reading);
+    handle.spawn(writing);
+  }
+}
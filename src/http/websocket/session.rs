@@ -0,0 +1,435 @@
+use futures::{future, Async, Future, IntoFuture, Stream};
+use futures::sync::mpsc::Sender;
+use futures_cpupool::CpuPool;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value as JsonValue};
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::io;
+use std::rc::Rc;
+use std::str;
+use tokio_core::reactor::Handle;
+
+use db::{NewTodo, QueryTodos, TodosRepo, UpdateTodo};
+use result::Error;
+use validators::Validator;
+
+use http::event_bus::{EventBus, Message};
+use http::rate_limiter::RateLimiter;
+use http::server::error_status;
+
+use super::frame::{self, Frame};
+
+/// Larger fragmented messages close the connection.
+const MAX_MESSAGE_LEN: usize = 1 << 20;
+/// How many of the connection's latest writes are kept to skip their events.
+const OWN_WRITES_SIZE: usize = 100;
+
+/// A client's request, `id` is echoed back in the reply.
+#[derive(Debug, Deserialize)]
+struct Command {
+  #[serde(default)]
+  id: JsonValue,
+  #[serde(rename = "type")]
+  kind: String,
+  #[serde(default)]
+  data: JsonValue,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Subscribe {
+  last_event_id: Option<i64>,
+}
+
+/// A connection's queue of outgoing frames.
+///
+/// Every clone of a channel sender may queue one frame past the buffer, so the session shares a
+/// single one to keep the queue bounded.
+#[derive(Clone)]
+pub struct Out(Rc<RefCell<Sender<Frame>>>);
+
+impl Out {
+  pub fn new(sender: Sender<Frame>) -> Self {
+    Out(Rc::new(RefCell::new(sender)))
+  }
+
+  /// Queue a frame, `false` when the connection is closed or a client doesn't read frames.
+  pub fn send(&self, frame: Frame) -> bool {
+    match self.0.borrow_mut().try_send(frame) {
+      Ok(()) => true,
+      Err(ref err) if err.is_full() => {
+        warn!("websocket client doesn't read frames");
+        false
+      }
+      Err(_) => false,
+    }
+  }
+
+  /// Queue a frame once there's room for it.
+  fn send_later(&self, frame: Frame) -> Box<Future<Item = (), Error = ()>> {
+    let sender = self.0.clone();
+    let mut frame = Some(frame);
+
+    Box::new(future::poll_fn(move || {
+      let mut sender = sender.borrow_mut();
+      match sender.poll_ready() {
+        Ok(Async::Ready(())) => {
+          let frame = frame.take().expect("frame is sent once");
+          sender.try_send(frame).map(Async::Ready).map_err(|_| ())
+        }
+        Ok(Async::NotReady) => Ok(Async::NotReady),
+        Err(_) => Err(()),
+      }
+    }))
+  }
+}
+
+/// A single websocket connection's state, frames it sends go to `out`.
+pub struct Session {
+  todos_repo: TodosRepo,
+  event_bus: EventBus,
+  cpu_pool: CpuPool,
+  handle: Handle,
+  out: Out,
+  /// a reply didn't fit into `out`, the connection is closed
+  overflowed: Rc<Cell<bool>>,
+  rate_limiter: RateLimiter,
+  /// the client's rate limit key
+  client: String,
+  /// a fragmented message's opcode and payload received so far
+  partial: Option<(u8, Vec<u8>)>,
+  /// increased on every subscribe and unsubscribe to stop the previous subscription
+  generation: Rc<Cell<u64>>,
+  /// todos returned by the latest writes, their events are not pushed back
+  own_writes: Rc<RefCell<VecDeque<JsonValue>>>,
+}
+
+impl Session {
+  pub fn new(
+    todos_repo: TodosRepo,
+    event_bus: EventBus,
+    cpu_pool: CpuPool,
+    handle: Handle,
+    out: Out,
+    rate_limiter: RateLimiter,
+    client: String,
+  ) -> Self {
+    Session {
+      todos_repo,
+      event_bus,
+      cpu_pool,
+      handle,
+      out,
+      overflowed: Rc::new(Cell::new(false)),
+      rate_limiter,
+      client,
+      partial: None,
+      generation: Rc::new(Cell::new(0)),
+      own_writes: Rc::new(RefCell::new(VecDeque::new())),
+    }
+  }
+
+  /// Handle a data or control frame except close.
+  pub fn receive(&mut self, frame: Frame) -> Result<(), io::Error> {
+    if self.overflowed.get() {
+      return Err(invalid("replies are not read"));
+    }
+
+    match frame.opcode {
+      frame::PING => self.send(Frame::control(frame::PONG, frame.payload)),
+      frame::PONG => {}
+      frame::TEXT | frame::BINARY if self.partial.is_none() => {
+        if frame.fin {
+          self.message(frame.opcode, frame.payload);
+        } else {
+          self.partial = Some((frame.opcode, frame.payload));
+        }
+      }
+      frame::CONTINUATION if self.partial.is_some() => {
+        let (opcode, mut payload) = self.partial.take().expect("partial message");
+        if payload.len() + frame.payload.len() > MAX_MESSAGE_LEN {
+          return Err(invalid("message is too large"));
+        }
+        payload.extend_from_slice(&frame.payload);
+        if frame.fin {
+          self.message(opcode, payload);
+        } else {
+          self.partial = Some((opcode, payload));
+        }
+      }
+      _ => return Err(invalid("unexpected frame")),
+    }
+
+    Ok(())
+  }
+
+  fn message(&mut self, opcode: u8, payload: Vec<u8>) {
+    let command = if opcode != frame::TEXT {
+      Err(Error::Validation("only text messages are supported".to_string()))
+    } else {
+      str::from_utf8(&payload)
+        .map_err(|err| Error::Validation(err.to_string()))
+        .and_then(|it| serde_json::from_str::<Command>(it).map_err(Error::from))
+    };
+
+    match command {
+      Ok(command) => self.command(command),
+      Err(err) => self.send(error_reply(JsonValue::Null, &err)),
+    }
+  }
+
+  fn command(&mut self, command: Command) {
+    let Command { id, kind, data } = command;
+    let repo = self.todos_repo.clone();
+    let own_writes = self.own_writes.clone();
+
+    match kind.as_str() {
+      "create" => self.reply(
+        id,
+        self
+          .rate_limiter
+          .limit_write(&self.client)
+          .and_then(|_| parse::<NewTodo>(data))
+          .and_then(|it| it.validated())
+          .into_future()
+          .and_then(move |it| repo.insert(it))
+          .inspect(|it| info!("created {:?}", it))
+          .and_then(move |it| remember(&own_writes, json!(it))),
+      ),
+      "update" => self.reply(
+        id,
+        self
+          .rate_limiter
+          .limit_write(&self.client)
+          .and_then(|_| parse::<UpdateTodo>(data))
+          .and_then(|it| it.validated())
+          .into_future()
+          .and_then(move |it| repo.update(it))
+          .inspect(|it| info!("updated {:?}", it))
+          .and_then(move |it| remember(&own_writes, json!(it.todo)).map(|_| json!(it))),
+      ),
+      "query" => self.reply(
+        id,
+        parse::<QueryTodos>(data)
+          .and_then(|it| it.validated())
+          .into_future()
+          .and_then(move |it| repo.query(it))
+          .map(|it| json!(it)),
+      ),
+      "subscribe" => {
+        let subscribed = match parse::<Option<Subscribe>>(data) {
+          Ok(it) => self.subscribe(it.unwrap_or_default()),
+          Err(err) => Box::new(Err(err).into_future()),
+        };
+        self.reply(id, subscribed.map(|_| json!({"subscribed": true})))
+      }
+      "unsubscribe" => {
+        self.generation.set(self.generation.get() + 1);
+        self.reply(id, Ok(json!({"subscribed": false})).into_future())
+      }
+      _ => {
+        let err = Error::Validation(format!("unknown message type {:?}", kind));
+        self.send(error_reply(id, &err))
+      }
+    }
+  }
+
+  /// Forward bus messages until the next subscribe or unsubscribe.
+  ///
+  /// The bus drops a subscription which falls behind and the subscription ends when a client
+  /// doesn't read pushes, then a `reset` is pushed and a client reloads items and subscribes
+  /// again. Missed events are loaded on the cpu pool.
+  fn subscribe(&self, subscribe: Subscribe) -> Box<Future<Item = (), Error = Error>> {
+    let generation = self.generation.get() + 1;
+    self.generation.set(generation);
+
+    let event_bus = self.event_bus.clone();
+    let receiver = self
+      .cpu_pool
+      .spawn_fn(move || event_bus.subscribe(subscribe.last_event_id));
+
+    let handle = self.handle.clone();
+    let current = self.generation.clone();
+    let own_writes = self.own_writes.clone();
+    let out = self.out.clone();
+    let (ended, ended_out) = (self.generation.clone(), self.out.clone());
+
+    let subscribed = receiver.map(move |receiver| {
+      let forward = receiver
+        .take_while(move |_| Ok(current.get() == generation))
+        .for_each(move |message| match push(message, &own_writes) {
+          Some(frame) => {
+            if out.send(frame) {
+              Ok(())
+            } else {
+              Err(())
+            }
+          }
+          None => Ok(()),
+        })
+        .then(move |_| -> Box<Future<Item = (), Error = ()>> {
+          if ended.get() != generation {
+            return Box::new(future::ok(()));
+          }
+          ended_out.send_later(push_reset())
+        });
+      handle.spawn(forward);
+    });
+
+    Box::new(subscribed)
+  }
+
+  /// Send a future's result as a reply correlated by `id`.
+  fn reply<F>(&self, id: JsonValue, result: F)
+  where
+    F: Future<Item = JsonValue, Error = Error> + 'static,
+  {
+    let out = self.out.clone();
+    let overflowed = self.overflowed.clone();
+
+    self.handle.spawn(result.then(move |result| {
+      let frame = match result {
+        Ok(data) => Frame::text(json!({"id": id, "type": "reply", "data": data}).to_string()),
+        Err(err) => error_reply(id, &err),
+      };
+      if !out.send(frame) {
+        overflowed.set(true);
+      }
+      Ok(())
+    }));
+  }
+
+  fn send(&self, frame: Frame) {
+    if !self.out.send(frame) {
+      self.overflowed.set(true);
+    }
+  }
+}
+
+impl Drop for Session {
+  fn drop(&mut self) {
+    // stops the subscription with the connection
+    self.generation.set(self.generation.get() + 1);
+  }
+}
+
+fn parse<T: DeserializeOwned>(data: JsonValue) -> Result<T, Error> {
+  serde_json::from_value(data).map_err(Error::from)
+}
+
+fn remember(own_writes: &RefCell<VecDeque<JsonValue>>, todo: JsonValue) -> Result<JsonValue, Error> {
+  let mut own_writes = own_writes.borrow_mut();
+  own_writes.push_back(todo.clone());
+  if own_writes.len() > OWN_WRITES_SIZE {
+    own_writes.pop_front();
+  }
+  Ok(todo)
+}
+
+/// A frame for a bus message, `None` for events of the connection's own writes.
+fn push(message: Message, own_writes: &RefCell<VecDeque<JsonValue>>) -> Option<Frame> {
+  match message {
+    Message::Event(event) => {
+      let data = serde_json::from_str::<JsonValue>(&event.payload).unwrap_or(JsonValue::Null);
+      if own_writes.borrow().contains(&data["todo"]) {
+        return None;
+      }
+
+      let push = json!({
+        "type": "event",
+        "event_id": event.id,
+        "event": event.event,
+        "data": data,
+      });
+      Some(Frame::text(push.to_string()))
+    }
+    Message::Reset => Some(push_reset()),
+    Message::Heartbeat => Some(Frame::control(frame::PING, Vec::new())),
+  }
+}
+
+/// Tell a client that events were missed, it should reload items.
+fn push_reset() -> Frame {
+  Frame::text(json!({"type": "reset"}).to_string())
+}
+
+fn error_reply(id: JsonValue, err: &Error) -> Frame {
+  let reply = json!({
+    "id": id,
+    "type": "error",
+    "error": {
+      "status": error_status(err).as_u16(),
+      "error": err.to_string(),
+      "description": err.description(),
+    },
+  });
+  Frame::text(reply.to_string())
+}
+
+fn invalid(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use chrono::Utc;
+  use futures::sync::mpsc;
+  use std::sync::Arc;
+
+  use db::StoredEvent;
+
+  #[test]
+  fn should_skip_own_writes() {
+    let own_writes = RefCell::new(VecDeque::new());
+    let todo = json!({"id": 1, "text": "foo", "done": false});
+    remember(&own_writes, todo.clone()).unwrap();
+
+    let event = |id, todo: JsonValue| {
+      Message::Event(Arc::new(StoredEvent {
+        id,
+        event: "todo.created".to_string(),
+        payload: json!({"event": "todo.created", "todo": todo}).to_string(),
+        created_at: Utc::now().naive_utc(),
+      }))
+    };
+
+    assert_that(&push(event(1, todo), &own_writes)).is_none();
+
+    let frame = push(event(2, json!({"id": 2})), &own_writes).unwrap();
+    let push = serde_json::from_slice::<JsonValue>(&frame.payload).unwrap();
+    assert_that(&push["type"].as_str()).is_equal_to(Some("event"));
+    assert_that(&push["event_id"].as_i64()).is_equal_to(Some(2));
+    assert_that(&push["data"]["todo"]["id"].as_i64()).is_equal_to(Some(2));
+  }
+
+  #[test]
+  fn should_bound_outgoing_frames() {
+    let (sender, outgoing) = mpsc::channel(1);
+    let out = Out::new(sender);
+
+    // the buffer and the sender's own slot
+    let queued = (0..10).take_while(|_| out.send(push_reset())).count();
+    assert_that(&queued).is_equal_to(2);
+
+    let mut frames = outgoing.wait();
+    frames.next();
+    frames.next();
+    out.send_later(push_reset()).wait().unwrap();
+    let frame = frames.next().unwrap().unwrap();
+    let push = serde_json::from_slice::<JsonValue>(&frame.payload).unwrap();
+    assert_that(&push["type"].as_str()).is_equal_to(Some("reset"));
+  }
+
+  #[test]
+  fn should_correlate_errors() {
+    let frame = error_reply(json!("42"), &Error::Validation("text is empty".to_string()));
+    let reply = serde_json::from_slice::<JsonValue>(&frame.payload).unwrap();
+
+    assert_that(&reply["id"].as_str()).is_equal_to(Some("42"));
+    assert_that(&reply["type"].as_str()).is_equal_to(Some("error"));
+    assert_that(&reply["error"]["status"].as_u64()).is_equal_to(Some(412));
+  }
+}
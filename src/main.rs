@@ -1,20 +1,35 @@
+extern crate base64;
+extern crate brotli;
+extern crate bytes;
 extern crate chrono;
 #[macro_use]
+extern crate clap;
+extern crate csv;
+#[macro_use]
 extern crate diesel;
 extern crate dotenv;
 extern crate env_logger;
+extern crate flate2;
 extern crate futures;
 extern crate futures_cpupool;
+extern crate grpc;
+#[macro_use]
 extern crate hyper;
 #[macro_use]
 extern crate log;
+extern crate protobuf;
 extern crate r2d2;
 extern crate r2d2_diesel;
+extern crate rand;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 #[macro_use]
 extern crate serde_json;
+extern crate sha2;
+extern crate tls_api;
+extern crate tokio_core;
+extern crate tokio_io;
 extern crate url;
 
 #[cfg(test)]
@@ -23,23 +38,20 @@ extern crate spectral;
 mod result;
 mod common;
 mod config;
+mod logger;
+mod metrics;
 mod db;
+mod trace;
 mod http;
 mod validators;
+mod transfer;
+mod webhooks;
+mod cli;
 
-use config::Config;
 use dotenv::dotenv;
 
 fn main() {
   dotenv().ok();
-  env_logger::init();
-
-  let cfg = Config::default();
-  info!("using {:?}", cfg);
-
-  let cpu_pool = cfg.create_cpu_pool();
-  let conn_pool = db::connection_pool(&cfg.database_url, cfg.pool_size);
-  let todos_repo = db::TodosRepo::new(conn_pool, cpu_pool);
 
-  http::Server::new(todos_repo).listen(cfg.http_port);
+  cli::run();
 }
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const HTTP_REQUESTS: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION: &str = "http_request_duration_seconds";
+pub const TODOS_REPO_DURATION: &str = "todos_repo_operation_duration_seconds";
+pub const TODOS_REPO_ERRORS: &str = "todos_repo_errors_total";
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+pub const DB_POOL_WAIT: &str = "db_pool_wait_seconds";
+pub const CPU_POOL_QUEUE_DEPTH: &str = "cpu_pool_queue_depth";
+
+/// Metrics which are exposed with their kind and help, in the order they are rendered.
+pub const METRICS: &[(&str, Kind, &str)] = &[
+  (
+    HTTP_REQUESTS,
+    Kind::Counter,
+    "HTTP requests by method, route and status",
+  ),
+  (
+    HTTP_REQUEST_DURATION,
+    Kind::Histogram,
+    "HTTP request latency until the response head by method, route and status",
+  ),
+  (
+    TODOS_REPO_DURATION,
+    Kind::Histogram,
+    "TodosRepo operation latency including the wait for a thread and a connection",
+  ),
+  (
+    TODOS_REPO_ERRORS,
+    Kind::Counter,
+    "TodosRepo operation errors by error variant",
+  ),
+  (
+    DB_POOL_CONNECTIONS,
+    Kind::Gauge,
+    "connections managed by the db pool",
+  ),
+  (
+    DB_POOL_IDLE_CONNECTIONS,
+    Kind::Gauge,
+    "idle connections of the db pool",
+  ),
+  (
+    DB_POOL_WAIT,
+    Kind::Histogram,
+    "time spent waiting for a db pool's connection",
+  ),
+  (
+    CPU_POOL_QUEUE_DEPTH,
+    Kind::Gauge,
+    "TodosRepo operations waiting for a cpu pool's thread",
+  ),
+];
+
+/// Upper bounds of histograms' buckets, seconds.
+const BUCKETS: &[f64] = &[
+  0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+  Counter,
+  Gauge,
+  Histogram,
+}
+
+type Key = (&'static str, Vec<(&'static str, String)>);
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+  /// counts per bucket, not cumulative
+  buckets: Vec<u64>,
+  sum: f64,
+  count: u64,
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+  values: BTreeMap<Key, f64>,
+  histograms: BTreeMap<Key, Histogram>,
+}
+
+/// Counters, gauges and histograms which are shared between threads, it's cheap to clone.
+///
+/// Labels must have a few possible values like routes' patterns, never raw paths or ids.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics(Arc<Mutex<Registry>>);
+
+impl Metrics {
+  pub fn new() -> Self {
+    Metrics::default()
+  }
+
+  pub fn inc(&self, name: &'static str, labels: &[(&'static str, &str)]) {
+    self.add(name, labels, 1.0)
+  }
+
+  pub fn add(&self, name: &'static str, labels: &[(&'static str, &str)], delta: f64) {
+    let mut registry = self.0.lock().expect("metrics lock is poisoned");
+    *registry.values.entry(key(name, labels)).or_insert(0.0) += delta;
+  }
+
+  pub fn set(&self, name: &'static str, labels: &[(&'static str, &str)], value: f64) {
+    let mut registry = self.0.lock().expect("metrics lock is poisoned");
+    registry.values.insert(key(name, labels), value);
+  }
+
+  pub fn observe(&self, name: &'static str, labels: &[(&'static str, &str)], value: Duration) {
+    let value = value.as_secs() as f64 + f64::from(value.subsec_nanos()) / 1e9;
+
+    let mut registry = self.0.lock().expect("metrics lock is poisoned");
+    let histogram = registry
+      .histograms
+      .entry(key(name, labels))
+      .or_insert_with(|| Histogram {
+        buckets: vec![0; BUCKETS.len()],
+        ..Histogram::default()
+      });
+    if let Some(i) = BUCKETS.iter().position(|it| value <= *it) {
+      histogram.buckets[i] += 1;
+    }
+    histogram.sum += value;
+    histogram.count += 1;
+  }
+
+  /// Render metrics in Prometheus text format.
+  pub fn render(&self) -> String {
+    let registry = self.0.lock().expect("metrics lock is poisoned");
+    let mut out = String::new();
+
+    for &(name, kind, help) in METRICS {
+      let kind_name = match kind {
+        Kind::Counter => "counter",
+        Kind::Gauge => "gauge",
+        Kind::Histogram => "histogram",
+      };
+      writeln!(out, "# HELP {} {}", name, help).unwrap();
+      writeln!(out, "# TYPE {} {}", name, kind_name).unwrap();
+
+      for (&(_, ref labels), value) in registry.values.iter().filter(|it| (it.0).0 == name) {
+        writeln!(out, "{}{} {}", name, format_labels(labels, None), value).unwrap();
+      }
+
+      for (&(_, ref labels), histogram) in registry.histograms.iter().filter(|it| (it.0).0 == name)
+      {
+        let mut count = 0;
+        for (bound, n) in BUCKETS.iter().zip(&histogram.buckets) {
+          count += n;
+          let labels = format_labels(labels, Some(&bound.to_string()));
+          writeln!(out, "{}_bucket{} {}", name, labels, count).unwrap();
+        }
+        let labels_inf = format_labels(labels, Some("+Inf"));
+        writeln!(out, "{}_bucket{} {}", name, labels_inf, histogram.count).unwrap();
+        let labels = format_labels(labels, None);
+        writeln!(out, "{}_sum{} {}", name, labels, histogram.sum).unwrap();
+        writeln!(out, "{}_count{} {}", name, labels, histogram.count).unwrap();
+      }
+    }
+
+    out
+  }
+}
+
+fn key(name: &'static str, labels: &[(&'static str, &str)]) -> Key {
+  let labels = labels
+    .iter()
+    .map(|&(name, value)| (name, value.to_string()))
+    .collect();
+  (name, labels)
+}
+
+fn format_labels(labels: &[(&'static str, String)], le: Option<&str>) -> String {
+  let mut pairs: Vec<String> = labels
+    .iter()
+    .map(|&(name, ref value)| format!("{}=\"{}\"", name, escape(value)))
+    .collect();
+  if let Some(le) = le {
+    pairs.push(format!("le=\"{}\"", le));
+  }
+
+  if pairs.is_empty() {
+    String::new()
+  } else {
+    format!("{{{}}}", pairs.join(","))
+  }
+}
+
+fn escape(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_render_metrics() {
+    let metrics = Metrics::new();
+    let labels = &[("route", "/todos/{id}"), ("status", "200")];
+    metrics.inc(HTTP_REQUESTS, labels);
+    metrics.inc(HTTP_REQUESTS, labels);
+    metrics.inc(TODOS_REPO_ERRORS, &[("error", "say \"hi\"")]);
+    metrics.set(DB_POOL_CONNECTIONS, &[], 10.0);
+    metrics.observe(DB_POOL_WAIT, &[], Duration::from_millis(3));
+    metrics.observe(DB_POOL_WAIT, &[], Duration::from_secs(60));
+
+    let text = metrics.render();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_that(&lines[0])
+      .is_equal_to("# HELP http_requests_total HTTP requests by method, route and status");
+    assert_that(&lines[1]).is_equal_to("# TYPE http_requests_total counter");
+    assert_that(&lines[2])
+      .is_equal_to("http_requests_total{route=\"/todos/{id}\",status=\"200\"} 2");
+    assert_that(&lines).contains("todos_repo_errors_total{error=\"say \\\"hi\\\"\"} 1");
+    assert_that(&lines).contains("db_pool_connections 10");
+    assert_that(&lines).contains("# TYPE db_pool_wait_seconds histogram");
+    assert_that(&lines).contains("db_pool_wait_seconds_bucket{le=\"0.001\"} 0");
+    assert_that(&lines).contains("db_pool_wait_seconds_bucket{le=\"0.005\"} 1");
+    assert_that(&lines).contains("db_pool_wait_seconds_bucket{le=\"10\"} 1");
+    assert_that(&lines).contains("db_pool_wait_seconds_bucket{le=\"+Inf\"} 2");
+    assert_that(&lines).contains("db_pool_wait_seconds_sum 60.003");
+    assert_that(&lines).contains("db_pool_wait_seconds_count 2");
+  }
+}
@@ -5,6 +5,7 @@ use std::fmt;
 use std::result::Result as StdResult;
 use serde_json::Error as SerdeJsonError;
 use hyper::Error as HyperError;
+use csv::Error as CsvError;
 
 #[derive(Debug)]
 pub enum Error {
@@ -16,15 +17,59 @@ pub enum Error {
   RecordNotFound,
   /// Indicates a json parsing error
   JsonParse(SerdeJsonError),
+  /// Indicates a csv parsing error
+  CsvParse(CsvError),
+  /// Indicates a msgpack, cbor or compressed body parsing error
+  BodyParse(String),
   /// Indicates http server error
   HttpServer(HyperError),
   /// Indicates invalid input data
   Validation(String),
+  /// Indicates that an idempotency key was reused for a different request
+  IdempotencyKeyReused,
+  /// Indicates that a request with the same idempotency key is still in progress
+  IdempotencyKeyInProgress,
+  /// Indicates that a resource was changed since a client has seen it
+  PreconditionFailed,
+  /// Indicates that a request's body is encoded with an unknown media type
+  UnsupportedMediaType(String),
+  /// Indicates that a response can't be encoded with any of accepted media types
+  NotAcceptable,
+  /// Indicates that a request's body is larger than a limit in bytes
+  PayloadTooLarge(u64),
+  /// Indicates that a request to an admin route has no valid token
+  Unauthorized,
+  /// Indicates that a client is over its rate limit, seconds until it may retry
+  TooManyRequests(u64),
 }
 
 #[allow(dead_code)]
 pub type Result<T> = StdResult<T, Error>;
 
+impl Error {
+  /// A variant's name, it labels error metrics.
+  pub fn variant(&self) -> &'static str {
+    match *self {
+      Error::MySql(_) => "MySql",
+      Error::MySqlConnection(_) => "MySqlConnection",
+      Error::RecordNotFound => "RecordNotFound",
+      Error::JsonParse(_) => "JsonParse",
+      Error::CsvParse(_) => "CsvParse",
+      Error::BodyParse(_) => "BodyParse",
+      Error::HttpServer(_) => "HttpServer",
+      Error::Validation(_) => "Validation",
+      Error::IdempotencyKeyReused => "IdempotencyKeyReused",
+      Error::IdempotencyKeyInProgress => "IdempotencyKeyInProgress",
+      Error::PreconditionFailed => "PreconditionFailed",
+      Error::UnsupportedMediaType(_) => "UnsupportedMediaType",
+      Error::NotAcceptable => "NotAcceptable",
+      Error::PayloadTooLarge(_) => "PayloadTooLarge",
+      Error::Unauthorized => "Unauthorized",
+      Error::TooManyRequests(_) => "TooManyRequests",
+    }
+  }
+}
+
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match *self {
@@ -32,8 +77,18 @@ impl fmt::Display for Error {
       Error::MySqlConnection(ref err) => write!(f, "Error::MySqlConnection {}", err),
       Error::RecordNotFound => f.write_str("Error::RecordNotFound"),
       Error::JsonParse(ref err) => write!(f, "Error::JsonParse {}", err),
+      Error::CsvParse(ref err) => write!(f, "Error::CsvParse {}", err),
+      Error::BodyParse(ref err) => write!(f, "Error::BodyParse {}", err),
       Error::HttpServer(ref err) => write!(f, "Error::HttpServer {}", err),
       Error::Validation(ref err) => write!(f, "Error::Validation {}", err),
+      Error::IdempotencyKeyReused => f.write_str("Error::IdempotencyKeyReused"),
+      Error::IdempotencyKeyInProgress => f.write_str("Error::IdempotencyKeyInProgress"),
+      Error::PreconditionFailed => f.write_str("Error::PreconditionFailed"),
+      Error::UnsupportedMediaType(ref err) => write!(f, "Error::UnsupportedMediaType {}", err),
+      Error::NotAcceptable => f.write_str("Error::NotAcceptable"),
+      Error::PayloadTooLarge(limit) => write!(f, "Error::PayloadTooLarge over {} bytes", limit),
+      Error::Unauthorized => f.write_str("Error::Unauthorized"),
+      Error::TooManyRequests(secs) => write!(f, "Error::TooManyRequests retry after {}s", secs),
     }
   }
 }
@@ -45,8 +100,18 @@ impl StdError for Error {
       Error::MySqlConnection(ref err) => err.description(),
       Error::RecordNotFound => "record not found in database",
       Error::JsonParse(ref err) => err.description(),
+      Error::CsvParse(ref err) => err.description(),
+      Error::BodyParse(_) => "request body parsing error",
       Error::HttpServer(ref err) => err.description(),
       Error::Validation(_) => "input data validation error",
+      Error::IdempotencyKeyReused => "idempotency key was used for a different request",
+      Error::IdempotencyKeyInProgress => "request with the same idempotency key is in progress",
+      Error::PreconditionFailed => "resource was changed by another request",
+      Error::UnsupportedMediaType(_) => "request body's media type isn't supported",
+      Error::NotAcceptable => "response can't be encoded with accepted media types",
+      Error::PayloadTooLarge(_) => "request body is too large",
+      Error::Unauthorized => "admin token is missing or invalid",
+      Error::TooManyRequests(_) => "too many requests, retry later",
     }
   }
 
@@ -55,6 +120,7 @@ impl StdError for Error {
       Error::MySql(ref err) => Some(err),
       Error::MySqlConnection(ref err) => Some(err),
       Error::JsonParse(ref err) => Some(err),
+      Error::CsvParse(ref err) => Some(err),
       Error::HttpServer(ref err) => Some(err),
       _ => None,
     }
@@ -82,6 +148,12 @@ impl From<SerdeJsonError> for Error {
   }
 }
 
+impl From<CsvError> for Error {
+  fn from(err: CsvError) -> Self {
+    Error::CsvParse(err)
+  }
+}
+
 impl From<HyperError> for Error {
   fn from(err: HyperError) -> Self {
     Error::HttpServer(err)
@@ -0,0 +1,247 @@
+use futures::Future;
+use hyper::{Client, Method, Request, Uri};
+use hyper::client::HttpConnector;
+use hyper::header::{ContentLength, ContentType};
+use serde_json::Value as JsonValue;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tokio_core::reactor::{Core, Timeout};
+
+use config::{Config, TraceExporter};
+
+use super::{Span, SpanKind};
+
+/// How many finished spans wait for the exporter, newer ones are dropped when it falls behind.
+const QUEUE_SIZE: usize = 10_000;
+/// How many spans are exported at once.
+const MAX_BATCH: usize = 512;
+/// How long spans are collected before they are exported.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends finished spans to an exporter, a disabled tracer doesn't start spans at all.
+#[derive(Clone)]
+pub struct Tracer(Option<SyncSender<Span>>);
+
+impl Tracer {
+  pub fn disabled() -> Self {
+    Tracer(None)
+  }
+
+  /// A tracer which sends spans to the receiver.
+  pub fn channel(capacity: usize) -> (Self, Receiver<Span>) {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    (Tracer(Some(sender)), receiver)
+  }
+
+  pub fn is_enabled(&self) -> bool {
+    self.0.is_some()
+  }
+
+  /// Queue a finished span without blocking.
+  pub fn export(&self, span: Span) {
+    if let Some(ref sender) = self.0 {
+      sender.try_send(span).ok();
+    }
+  }
+}
+
+/// Writes spans in OTLP/JSON to a file or POSTs them to a collector's `/v1/traces`.
+pub struct Exporter {
+  target: TraceExporter,
+  spans: Receiver<Span>,
+}
+
+impl Exporter {
+  /// Create a tracer and its exporter, there is no exporter when `TRACE_EXPORTER` is off.
+  pub fn new(cfg: &Config) -> (Tracer, Option<Exporter>) {
+    match cfg.trace_exporter {
+      TraceExporter::Off => (Tracer::disabled(), None),
+      ref target => {
+        let (tracer, spans) = Tracer::channel(QUEUE_SIZE);
+        let exporter = Exporter {
+          target: target.clone(),
+          spans,
+        };
+        (tracer, Some(exporter))
+      }
+    }
+  }
+
+  /// Run the exporter in a background thread.
+  pub fn spawn(self) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+      .name("trace-exporter".to_string())
+      .spawn(move || self.run())
+      .expect("cannot spawn trace exporter")
+  }
+
+  fn run(self) {
+    let mut core = Core::new().expect("cannot create trace exporter event loop");
+    let client = Client::new(&core.handle());
+
+    info!("trace exporter started for {}", self.target);
+
+    while let Some(batch) = self.next_batch() {
+      let body = encode(&batch).to_string();
+      let result = match self.target {
+        TraceExporter::Off => Ok(()),
+        TraceExporter::File(ref path) => OpenOptions::new()
+          .create(true)
+          .append(true)
+          .open(path)
+          .and_then(|mut file| writeln!(file, "{}", body))
+          .map_err(|err| err.to_string()),
+        TraceExporter::Otlp(ref url) => post(&mut core, &client, url, body),
+      };
+
+      if let Err(err) = result {
+        warn!("cannot export {} spans {}", batch.len(), err);
+      }
+    }
+  }
+
+  /// Wait for spans and collect them for a while, returns `None` when all tracers are dropped.
+  fn next_batch(&self) -> Option<Vec<Span>> {
+    let mut batch = vec![self.spans.recv().ok()?];
+    let deadline = Instant::now() + FLUSH_INTERVAL;
+
+    while batch.len() < MAX_BATCH {
+      let now = Instant::now();
+      if now >= deadline {
+        break;
+      }
+      match self.spans.recv_timeout(deadline - now) {
+        Ok(span) => batch.push(span),
+        Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+      }
+    }
+
+    Some(batch)
+  }
+}
+
+fn post(
+  core: &mut Core,
+  client: &Client<HttpConnector>,
+  url: &str,
+  body: String,
+) -> Result<(), String> {
+  let uri = url
+    .parse::<Uri>()
+    .map_err(|err| format!("invalid url {}", err))?;
+  let timeout = Timeout::new(EXPORT_TIMEOUT, &core.handle())
+    .map_err(|err| err.to_string())?
+    .then(|_| Err("timed out".to_string()));
+
+  let mut req = Request::new(Method::Post, uri);
+  req.headers_mut().set(ContentType::json());
+  req.headers_mut().set(ContentLength(body.len() as u64));
+  req.set_body(body);
+
+  let sent = client.request(req).then(|result| match result {
+    Ok(ref resp) if resp.status().is_success() => Ok(()),
+    Ok(resp) => Err(format!("unexpected status {}", resp.status())),
+    Err(err) => Err(err.to_string()),
+  });
+
+  core.run(
+    sent
+      .select(timeout)
+      .map(|(it, _)| it)
+      .map_err(|(err, _)| err),
+  )
+}
+
+/// Encode spans as an OTLP `ExportTraceServiceRequest` in its JSON mapping.
+pub fn encode(spans: &[Span]) -> JsonValue {
+  let spans: Vec<JsonValue> = spans.iter().map(encode_span).collect();
+
+  json!({
+    "resourceSpans": [{
+      "resource": {
+        "attributes": [attribute("service.name", "todo-demo")],
+      },
+      "scopeSpans": [{
+        "scope": {"name": "todo-demo", "version": env!("CARGO_PKG_VERSION")},
+        "spans": spans,
+      }],
+    }],
+  })
+}
+
+fn encode_span(span: &Span) -> JsonValue {
+  let nanos = |time: ::std::time::SystemTime| {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    (since.as_secs() * 1_000_000_000 + u64::from(since.subsec_nanos())).to_string()
+  };
+  let attributes: Vec<JsonValue> = span
+    .attributes
+    .iter()
+    .map(|&(key, ref value)| attribute(key, value))
+    .collect();
+  let status = match span.error {
+    Some(ref err) => json!({"code": 2, "message": err}),
+    None => json!({"code": 0}),
+  };
+
+  let mut encoded = json!({
+    "traceId": span.context.trace_id,
+    "spanId": span.context.span_id,
+    "name": span.name,
+    "kind": match span.kind {
+      SpanKind::Internal => 1,
+      SpanKind::Server => 2,
+    },
+    "startTimeUnixNano": nanos(span.start),
+    "endTimeUnixNano": nanos(span.end),
+    "attributes": attributes,
+    "status": status,
+  });
+  if let Some(ref parent_id) = span.parent_id {
+    encoded["parentSpanId"] = json!(parent_id);
+  }
+
+  encoded
+}
+
+fn attribute(key: &str, value: &str) -> JsonValue {
+  json!({"key": key, "value": {"stringValue": value}})
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  use trace::SpanContext;
+
+  #[test]
+  fn should_encode_spans_as_otlp_json() {
+    let parent = SpanContext::parse("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01");
+    let mut span = Span::start("TodosRepo.find", SpanKind::Internal, parent.as_ref());
+    span.set_attribute("db.operation", "find");
+    span.start = UNIX_EPOCH + Duration::new(1, 5);
+    span.end = UNIX_EPOCH + Duration::new(2, 0);
+    span.error = Some("RecordNotFound".to_string());
+    let span_id = span.context.span_id.clone();
+
+    let encoded = encode(&[span]);
+    let scope = &encoded["resourceSpans"][0]["scopeSpans"][0];
+    assert_that(&scope["scope"]["name"]).is_equal_to(&json!("todo-demo"));
+    assert_that(&scope["spans"][0]).is_equal_to(&json!({
+      "traceId": "0af7651916cd43dd8448eb211c80319c",
+      "spanId": span_id,
+      "parentSpanId": "b7ad6b7169203331",
+      "name": "TodosRepo.find",
+      "kind": 1,
+      "startTimeUnixNano": "1000000005",
+      "endTimeUnixNano": "2000000000",
+      "attributes": [{"key": "db.operation", "value": {"stringValue": "find"}}],
+      "status": {"code": 2, "message": "RecordNotFound"},
+    }));
+  }
+}
@@ -0,0 +1,322 @@
+mod exporter;
+
+use futures::{Future, Poll};
+use rand::{self, Rng};
+use std::cell::RefCell;
+use std::mem;
+use std::time::SystemTime;
+
+use result::Error;
+use common::{to_hex, FuturesExt};
+
+pub use self::exporter::{Exporter, Tracer};
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+header! { (TraceParent, "traceparent") => [String] }
+
+thread_local! {
+  static CURRENT: RefCell<Option<Current>> = RefCell::new(None);
+}
+
+/// Identifies a span of a trace, it's propagated with W3C `traceparent` headers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanContext {
+  /// 32 lowercase hex digits
+  pub trace_id: String,
+  /// 16 lowercase hex digits
+  pub span_id: String,
+  pub sampled: bool,
+}
+
+impl SpanContext {
+  /// A context of a new trace.
+  pub fn root() -> Self {
+    let trace_id: [u8; 16] = rand::thread_rng().gen();
+    SpanContext {
+      trace_id: to_hex(&trace_id),
+      span_id: new_span_id(),
+      sampled: true,
+    }
+  }
+
+  /// A context of a span in the same trace.
+  pub fn child(&self) -> Self {
+    SpanContext {
+      span_id: new_span_id(),
+      ..self.clone()
+    }
+  }
+
+  /// Parse a `traceparent` header like `00-<trace id>-<parent id>-<flags>`, invalid ones are
+  /// ignored and a new trace is started instead.
+  pub fn parse(traceparent: &str) -> Option<Self> {
+    let parts: Vec<&str> = traceparent.trim().split('-').collect();
+    if parts.len() < 4 {
+      return None;
+    }
+
+    let (version, trace_id, span_id, flags) = (parts[0], parts[1], parts[2], parts[3]);
+    // later versions may add fields, only `00` has exactly four
+    let known = (version == "00" && parts.len() == 4) || (version != "00" && version != "ff");
+    if !known
+      || !is_hex(version, 2)
+      || !is_hex(trace_id, 32)
+      || !is_hex(span_id, 16)
+      || !is_hex(flags, 2)
+    {
+      return None;
+    }
+    if trace_id.bytes().all(|it| it == b'0') || span_id.bytes().all(|it| it == b'0') {
+      return None;
+    }
+
+    let flags = u8::from_str_radix(flags, 16).ok()?;
+    Some(SpanContext {
+      trace_id: trace_id.to_string(),
+      span_id: span_id.to_string(),
+      sampled: flags & 1 == 1,
+    })
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+  /// handles a request of a remote client
+  Server,
+  Internal,
+}
+
+/// A timed operation of a trace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+  pub context: SpanContext,
+  pub parent_id: Option<String>,
+  pub name: String,
+  pub kind: SpanKind,
+  pub start: SystemTime,
+  pub end: SystemTime,
+  pub attributes: Vec<(&'static str, String)>,
+  /// a failed operation's error
+  pub error: Option<String>,
+}
+
+impl Span {
+  /// Start a span, it's a root of a new trace without a parent.
+  pub fn start(name: &str, kind: SpanKind, parent: Option<&SpanContext>) -> Self {
+    let now = SystemTime::now();
+    Span {
+      context: parent.map_or_else(SpanContext::root, |it| it.child()),
+      parent_id: parent.map(|it| it.span_id.clone()),
+      name: name.to_string(),
+      kind,
+      start: now,
+      end: now,
+      attributes: Vec::new(),
+      error: None,
+    }
+  }
+
+  pub fn set_attribute<V: ToString>(&mut self, key: &'static str, value: V) {
+    self.attributes.push((key, value.to_string()));
+  }
+
+  /// End the span now.
+  pub fn finish(mut self, error: Option<String>) -> Self {
+    self.end = SystemTime::now();
+    self.error = error;
+    self
+  }
+
+  /// End the span at a given time, an error is kept.
+  pub fn finish_at(mut self, end: SystemTime) -> Self {
+    self.end = end;
+    self
+  }
+}
+
+/// A span which is being handled with a tracer which its children are exported to.
+#[derive(Clone)]
+pub struct Current {
+  pub context: SpanContext,
+  pub tracer: Tracer,
+}
+
+impl Current {
+  /// Start a child span.
+  pub fn child(&self, name: &str) -> Span {
+    Span::start(name, SpanKind::Internal, Some(&self.context))
+  }
+}
+
+/// A current span of this thread, there is none when a request isn't traced.
+pub fn current() -> Option<Current> {
+  CURRENT.with(|it| it.borrow().clone())
+}
+
+/// Run `f` with a current span, a previous one is restored after it.
+pub fn scope<F, R>(current: &Option<Current>, f: F) -> R
+where
+  F: FnOnce() -> R,
+{
+  let previous = CURRENT.with(|it| mem::replace(&mut *it.borrow_mut(), current.clone()));
+  let result = f();
+  CURRENT.with(|it| *it.borrow_mut() = previous);
+  result
+}
+
+/// A future which is polled with a current span.
+pub struct Scoped<F> {
+  current: Option<Current>,
+  inner: F,
+}
+
+impl<F> Scoped<F> {
+  pub fn new(current: Option<Current>, inner: F) -> Self {
+    Scoped { current, inner }
+  }
+}
+
+impl<F: Future> Future for Scoped<F> {
+  type Item = F::Item;
+  type Error = F::Error;
+
+  fn poll(&mut self) -> Poll<F::Item, F::Error> {
+    let inner = &mut self.inner;
+    scope(&self.current, || inner.poll())
+  }
+}
+
+/// Trace a future which `f` creates with a child span of the current one.
+pub fn in_span<F, T>(name: &str, f: F) -> BoxFuture<T>
+where
+  F: FnOnce() -> BoxFuture<T>,
+  T: 'static,
+{
+  let parent = match current() {
+    Some(parent) => parent,
+    None => return f(),
+  };
+
+  let span = parent.child(name);
+  let current = Some(Current {
+    context: span.context.clone(),
+    tracer: parent.tracer.clone(),
+  });
+  let future = scope(&current, f);
+
+  Scoped::new(current, future)
+    .then(move |result| {
+      let error = result.as_ref().err().map(|it| it.variant().to_string());
+      parent.tracer.export(span.finish(error));
+      result
+    })
+    .into_boxed()
+}
+
+/// Trace a synchronous operation with a child span of the current one.
+pub fn in_span_sync<F, T>(name: &str, f: F) -> Result<T, Error>
+where
+  F: FnOnce() -> Result<T, Error>,
+{
+  let parent = match current() {
+    Some(parent) => parent,
+    None => return f(),
+  };
+
+  let span = parent.child(name);
+  let current = Some(Current {
+    context: span.context.clone(),
+    tracer: parent.tracer.clone(),
+  });
+  let result = scope(&current, f);
+
+  let error = result.as_ref().err().map(|it| it.variant().to_string());
+  parent.tracer.export(span.finish(error));
+  result
+}
+
+fn new_span_id() -> String {
+  let span_id: [u8; 8] = rand::thread_rng().gen();
+  to_hex(&span_id)
+}
+
+fn is_hex(s: &str, len: usize) -> bool {
+  s.len() == len
+    && s
+      .bytes()
+      .all(|it| (it >= b'0' && it <= b'9') || (it >= b'a' && it <= b'f'))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use futures::future;
+
+  #[test]
+  fn should_parse_traceparent() {
+    let header = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+    let context = SpanContext::parse(header).unwrap();
+    assert_that(&context.trace_id.as_str()).is_equal_to("0af7651916cd43dd8448eb211c80319c");
+    assert_that(&context.span_id.as_str()).is_equal_to("b7ad6b7169203331");
+    assert_that(&context.sampled).is_true();
+
+    let later = "01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00-what-ever";
+    assert_that(&SpanContext::parse(later).map(|it| it.sampled)).is_equal_to(Some(false));
+
+    assert_that(&SpanContext::parse(
+      "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-x",
+    ))
+    .is_none();
+    assert_that(&SpanContext::parse(
+      "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+    ))
+    .is_none();
+    assert_that(&SpanContext::parse(
+      "00-00000000000000000000000000000000-b7ad6b7169203331-01",
+    ))
+    .is_none();
+    assert_that(&SpanContext::parse(
+      "00-0AF7651916CD43DD8448EB211C80319C-b7ad6b7169203331-01",
+    ))
+    .is_none();
+    assert_that(&SpanContext::parse("foo")).is_none();
+  }
+
+  #[test]
+  fn should_trace_futures_with_child_spans() {
+    let (tracer, spans) = Tracer::channel(10);
+    let root = Span::start("root", SpanKind::Server, None);
+    let current = Some(Current {
+      context: root.context.clone(),
+      tracer,
+    });
+
+    let result = scope(&current, || {
+      in_span("outer", || {
+        future::lazy(|| in_span_sync("inner", || Err::<(), _>(Error::RecordNotFound))).into_boxed()
+      })
+    })
+    .wait();
+    assert_that(&result).is_err();
+    assert_that(&current_trace_id()).is_none();
+
+    let inner = spans.try_recv().unwrap();
+    let outer = spans.try_recv().unwrap();
+    assert_that(&inner.name.as_str()).is_equal_to("inner");
+    assert_that(&inner.parent_id).is_equal_to(Some(outer.context.span_id.clone()));
+    assert_that(&inner.error).is_equal_to(Some("RecordNotFound".to_string()));
+    assert_that(&outer.name.as_str()).is_equal_to("outer");
+    assert_that(&outer.parent_id).is_equal_to(Some(root.context.span_id.clone()));
+    assert_that(&outer.context.trace_id).is_equal_to(root.context.trace_id.clone());
+
+    let result = in_span("untraced", || future::ok::<_, Error>(1).into_boxed()).wait();
+    assert_that(&result.ok()).is_equal_to(Some(1));
+    assert_that(&spans.try_recv()).is_err();
+  }
+
+  fn current_trace_id() -> Option<String> {
+    current().map(|it| it.context.trace_id)
+  }
+}
@@ -0,0 +1,190 @@
+use csv;
+use serde_json;
+use std::str::FromStr;
+
+use db::{ImportTodo, Todo};
+use result::{Error, Result};
+
+use super::ical;
+
+/// Csv columns, the same order as `Todo`'s fields.
+const CSV_HEADER: &str = "id,text,done,created_at,updated_at,due_at\n";
+
+/// A format which todo items are imported from and exported to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  /// one json object per line
+  JsonLines,
+  /// comma separated values with a header row
+  Csv,
+  /// iCalendar with a `VTODO` per todo item
+  ICalendar,
+}
+
+impl Format {
+  pub fn content_type(&self) -> &'static str {
+    match *self {
+      Format::JsonLines => "application/x-ndjson",
+      Format::Csv => "text/csv; charset=utf-8",
+      Format::ICalendar => "text/calendar; charset=utf-8",
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match *self {
+      Format::JsonLines => "jsonl",
+      Format::Csv => "csv",
+      Format::ICalendar => "ics",
+    }
+  }
+
+  /// Bytes which go before encoded items.
+  pub fn header(&self) -> Vec<u8> {
+    match *self {
+      Format::JsonLines => Vec::new(),
+      Format::Csv => CSV_HEADER.as_bytes().to_vec(),
+      Format::ICalendar => ical::header().into_bytes(),
+    }
+  }
+
+  /// Bytes which go after encoded items.
+  pub fn footer(&self) -> Vec<u8> {
+    match *self {
+      Format::JsonLines | Format::Csv => Vec::new(),
+      Format::ICalendar => ical::footer().into_bytes(),
+    }
+  }
+
+  /// Encode todo items, without a header and a footer.
+  pub fn encode(&self, items: &[Todo]) -> Result<Vec<u8>> {
+    match *self {
+      Format::JsonLines => {
+        let mut out = Vec::new();
+        for item in items {
+          serde_json::to_writer(&mut out, item).map_err(Error::from)?;
+          out.push(b'\n');
+        }
+        Ok(out)
+      }
+      Format::Csv => {
+        let mut writer = csv::WriterBuilder::new()
+          .has_headers(false)
+          .from_writer(Vec::new());
+        for item in items {
+          writer.serialize(item).map_err(Error::from)?;
+        }
+        writer
+          .into_inner()
+          .map_err(|err| Error::from(csv::Error::from(err.into_error())))
+      }
+      Format::ICalendar => {
+        let mut out = String::new();
+        for item in items {
+          ical::encode(item, &mut out);
+        }
+        Ok(out.into_bytes())
+      }
+    }
+  }
+
+  /// Decode todo items, returns every row with its number, starting from one.
+  pub fn decode(&self, input: &[u8]) -> Vec<(usize, Result<ImportTodo>)> {
+    match *self {
+      Format::JsonLines => String::from_utf8_lossy(input)
+        .lines()
+        .enumerate()
+        .filter(|&(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| (n + 1, serde_json::from_str(line).map_err(Error::from)))
+        .collect(),
+      Format::Csv => csv::Reader::from_reader(input)
+        .deserialize()
+        .enumerate()
+        .map(|(n, row)| (n + 1, row.map_err(Error::from)))
+        .collect(),
+      Format::ICalendar => ical::decode(&String::from_utf8_lossy(input)),
+    }
+  }
+}
+
+impl FromStr for Format {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    match s {
+      "jsonl" | "ndjson" | "json" => Ok(Format::JsonLines),
+      "csv" => Ok(Format::Csv),
+      "ics" | "ical" => Ok(Format::ICalendar),
+      _ => Err(Error::Validation(format!(
+        "unknown format {:?}, expecting jsonl, csv or ics",
+        s
+      ))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::NaiveDate;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_encode_todos() {
+    let items = vec![create_todo(1, "foo"), create_todo(2, "bar, baz")];
+
+    let jsonl = Format::JsonLines.encode(&items).unwrap();
+    let jsonl = String::from_utf8(jsonl).unwrap();
+    assert_that(&jsonl.lines().count()).is_equal_to(2);
+
+    let mut csv = Format::Csv.header();
+    csv.extend(Format::Csv.encode(&items).unwrap());
+    let csv = String::from_utf8(csv).unwrap();
+    assert_that(&csv.as_str()).is_equal_to(
+      "id,text,done,created_at,updated_at,due_at\n\
+       1,foo,false,2018-02-17T12:45:39,2018-02-17T12:45:39,\n\
+       2,\"bar, baz\",false,2018-02-17T12:45:39,2018-02-17T12:45:39,\n",
+    );
+
+    let ics = Format::ICalendar.encode(&items).unwrap();
+    let ics = String::from_utf8(ics).unwrap();
+    assert_that(&ics.matches("BEGIN:VTODO").count()).is_equal_to(2);
+  }
+
+  #[test]
+  fn should_decode_todos() {
+    let rows = Format::JsonLines.decode(b"{\"text\":\"foo\"}\n\n{\"id\":2}\n{\"id\":3,\"text\":\"bar\"}");
+    assert_that(&rows.len()).is_equal_to(3);
+    assert_that(&rows[0].0).is_equal_to(1);
+    assert_that(&rows[0].1.is_ok()).is_true();
+    assert_that(&rows[1].0).is_equal_to(3);
+    assert_that(&rows[1].1.is_err()).is_true();
+    assert_that(&rows[2].1.as_ref().map(|it| it.id).ok()).is_equal_to(Some(Some(3)));
+
+    let rows = Format::Csv.decode(b"id,text,done,created_at,updated_at,due_at\n,foo,,,,\n2,bar,true,,,\nx,baz,,,,\n");
+    assert_that(&rows.len()).is_equal_to(3);
+    assert_that(&rows[0].1.as_ref().map(|it| it.id).ok()).is_equal_to(Some(None));
+    assert_that(&rows[1].1.as_ref().map(|it| it.done).ok()).is_equal_to(Some(Some(true)));
+    assert_that(&rows[2].1.is_err()).is_true();
+  }
+
+  #[test]
+  fn should_parse_format() {
+    assert_that(&"csv".parse::<Format>().ok()).is_equal_to(Some(Format::Csv));
+    assert_that(&"jsonl".parse::<Format>().ok()).is_equal_to(Some(Format::JsonLines));
+    assert_that(&"ics".parse::<Format>().ok()).is_equal_to(Some(Format::ICalendar));
+    assert_that(&"xml".parse::<Format>().is_err()).is_true();
+  }
+
+  fn create_todo(id: i64, text: &str) -> Todo {
+    let time = NaiveDate::from_ymd(2018, 2, 17).and_hms(12, 45, 39);
+    Todo {
+      id,
+      text: text.to_string(),
+      done: false,
+      created_at: time,
+      updated_at: time,
+      due_at: None,
+      change_seq: 0,
+    }
+  }
+}
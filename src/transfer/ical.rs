@@ -0,0 +1,309 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use db::{ImportTodo, Todo};
+use result::{Error, Result};
+
+/// A domain part of todo items' UIDs, UIDs look like `42@todo-demo`.
+const UID_DOMAIN: &str = "todo-demo";
+/// Lines longer than this many octets are folded.
+const MAX_LINE_LEN: usize = 75;
+/// A date time format in UTC.
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+pub fn header() -> String {
+  let mut out = String::new();
+  write_line(&mut out, "BEGIN", "VCALENDAR");
+  write_line(&mut out, "VERSION", "2.0");
+  write_line(&mut out, "PRODID", "-//todo-demo//todos//EN");
+  write_line(&mut out, "X-WR-CALNAME", "Todos");
+  out
+}
+
+pub fn footer() -> String {
+  let mut out = String::new();
+  write_line(&mut out, "END", "VCALENDAR");
+  out
+}
+
+/// Encode a todo item as a `VTODO` component.
+pub fn encode(todo: &Todo, out: &mut String) {
+  let status = if todo.done {
+    "COMPLETED"
+  } else {
+    "NEEDS-ACTION"
+  };
+
+  write_line(out, "BEGIN", "VTODO");
+  write_line(out, "UID", &uid(todo.id));
+  write_line(out, "DTSTAMP", &format_time(&todo.updated_at));
+  write_line(out, "SUMMARY", &escape(&todo.text));
+  write_line(out, "STATUS", status);
+  write_line(out, "CREATED", &format_time(&todo.created_at));
+  write_line(out, "LAST-MODIFIED", &format_time(&todo.updated_at));
+  if let Some(ref due_at) = todo.due_at {
+    write_line(out, "DUE", &format_time(due_at));
+  }
+  write_line(out, "END", "VTODO");
+}
+
+/// A calendar object with a single todo item.
+pub fn document(todo: &Todo) -> String {
+  let mut out = header();
+  encode(todo, &mut out);
+  out.push_str(&footer());
+  out
+}
+
+/// A todo item's UID.
+pub fn uid(id: i64) -> String {
+  format!("{}@{}", id, UID_DOMAIN)
+}
+
+/// Decode all `VTODO` components, other components are ignored.
+///
+/// Only UIDs which were created by `uid` give an id, others are imported as new items.
+pub fn decode(input: &str) -> Vec<(usize, Result<ImportTodo>)> {
+  let mut rows = Vec::new();
+  let mut current: Option<Result<ImportTodo>> = None;
+
+  for line in unfold(input) {
+    let (name, params, value) = match split_property(&line) {
+      Some(it) => it,
+      None => continue,
+    };
+
+    match (name.as_str(), value) {
+      ("BEGIN", "VTODO") => current = Some(Ok(empty_todo())),
+      ("END", "VTODO") => if let Some(todo) = current.take() {
+        let todo = todo.and_then(|it| {
+          if it.text.is_empty() {
+            Err(Error::Validation("VTODO without SUMMARY".to_string()))
+          } else {
+            Ok(it)
+          }
+        });
+        rows.push((rows.len() + 1, todo));
+      },
+      (name, value) => match current.take() {
+        Some(Ok(mut todo)) => {
+          let result = apply_property(&mut todo, name, &params, value);
+          current = Some(result.map(|_| todo));
+        }
+        other => current = other,
+      },
+    }
+  }
+
+  rows
+}
+
+fn empty_todo() -> ImportTodo {
+  ImportTodo {
+    id: None,
+    text: String::new(),
+    done: None,
+    created_at: None,
+    updated_at: None,
+    due_at: None,
+  }
+}
+
+fn apply_property(todo: &mut ImportTodo, name: &str, params: &str, value: &str) -> Result<()> {
+  match name {
+    "UID" => todo.id = parse_uid(value),
+    "SUMMARY" => todo.text = unescape(value),
+    "STATUS" => todo.done = Some(value.eq_ignore_ascii_case("COMPLETED")),
+    "CREATED" => todo.created_at = Some(parse_time(params, value)?),
+    "LAST-MODIFIED" => todo.updated_at = Some(parse_time(params, value)?),
+    "DUE" => todo.due_at = Some(parse_time(params, value)?),
+    _ => {}
+  }
+  Ok(())
+}
+
+fn parse_uid(value: &str) -> Option<i64> {
+  let mut parts = value.splitn(2, '@');
+  match (parts.next(), parts.next()) {
+    (Some(id), Some(UID_DOMAIN)) => id.parse().ok(),
+    _ => None,
+  }
+}
+
+/// Parse a date time or a date, times with `TZID` are treated as UTC.
+fn parse_time(params: &str, value: &str) -> Result<NaiveDateTime> {
+  let invalid = || Error::Validation(format!("invalid date time {:?}", value));
+
+  if params.contains("VALUE=DATE") && !params.contains("VALUE=DATE-TIME") {
+    return NaiveDate::parse_from_str(value, "%Y%m%d")
+      .map(|it| it.and_hms(0, 0, 0))
+      .map_err(|_| invalid());
+  }
+
+  NaiveDateTime::parse_from_str(value.trim_right_matches('Z'), "%Y%m%dT%H%M%S")
+    .map_err(|_| invalid())
+}
+
+fn format_time(time: &NaiveDateTime) -> String {
+  time.format(DATE_TIME_FORMAT).to_string()
+}
+
+/// Split a content line into its name, parameters and a value.
+fn split_property(line: &str) -> Option<(String, String, &str)> {
+  let colon = line.find(':')?;
+  let (head, value) = (&line[..colon], &line[colon + 1..]);
+  let mut head = head.splitn(2, ';');
+  let name = head.next()?.trim().to_uppercase();
+  let params = head.next().unwrap_or("").to_uppercase();
+
+  Some((name, params, value))
+}
+
+/// Join folded lines, continuation lines start with a space or a tab.
+fn unfold(input: &str) -> Vec<String> {
+  let mut lines: Vec<String> = Vec::new();
+
+  for line in input.lines() {
+    let line = line.trim_right_matches('\r');
+    if line.starts_with(' ') || line.starts_with('\t') {
+      if let Some(last) = lines.last_mut() {
+        last.push_str(&line[1..]);
+        continue;
+      }
+    }
+    lines.push(line.to_string());
+  }
+
+  lines
+}
+
+/// Write a content line folding it at `MAX_LINE_LEN` octets.
+fn write_line(out: &mut String, name: &str, value: &str) {
+  let line = format!("{}:{}", name, value);
+  let mut len = 0;
+
+  for ch in line.chars() {
+    if len + ch.len_utf8() > MAX_LINE_LEN {
+      out.push_str("\r\n ");
+      len = 1;
+    }
+    out.push(ch);
+    len += ch.len_utf8();
+  }
+
+  out.push_str("\r\n");
+}
+
+fn escape(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  for ch in value.chars() {
+    match ch {
+      '\\' => out.push_str("\\\\"),
+      ';' => out.push_str("\\;"),
+      ',' => out.push_str("\\,"),
+      '\n' => out.push_str("\\n"),
+      '\r' => {}
+      ch => out.push(ch),
+    }
+  }
+  out
+}
+
+fn unescape(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  let mut chars = value.chars();
+  while let Some(ch) = chars.next() {
+    if ch != '\\' {
+      out.push(ch);
+      continue;
+    }
+    match chars.next() {
+      Some('n') | Some('N') => out.push('\n'),
+      Some(ch) => out.push(ch),
+      None => {}
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use std::iter;
+
+  #[test]
+  fn should_encode_todo() {
+    let time = NaiveDate::from_ymd(2018, 2, 17).and_hms(12, 45, 39);
+    let todo = Todo {
+      id: 42,
+      text: "buy milk, eggs; bread".to_string(),
+      done: true,
+      created_at: time,
+      updated_at: time,
+      due_at: Some(time),
+      change_seq: 0,
+    };
+
+    let mut out = String::new();
+    encode(&todo, &mut out);
+
+    assert_that(&out.as_str()).is_equal_to(
+      "BEGIN:VTODO\r\n\
+       UID:42@todo-demo\r\n\
+       DTSTAMP:20180217T124539Z\r\n\
+       SUMMARY:buy milk\\, eggs\\; bread\r\n\
+       STATUS:COMPLETED\r\n\
+       CREATED:20180217T124539Z\r\n\
+       LAST-MODIFIED:20180217T124539Z\r\n\
+       DUE:20180217T124539Z\r\n\
+       END:VTODO\r\n",
+    );
+  }
+
+  #[test]
+  fn should_fold_long_lines() {
+    let mut out = String::new();
+    let text = iter::repeat("x").take(100).collect::<String>();
+    write_line(&mut out, "SUMMARY", &text);
+
+    let lines: Vec<_> = out.split("\r\n").collect();
+    assert_that(&lines.len()).is_equal_to(3);
+    assert_that(&lines[0].len()).is_equal_to(75);
+    assert_that(&unfold(&out)[0].as_str()).is_equal_to(format!("SUMMARY:{}", text).as_str());
+  }
+
+  #[test]
+  fn should_decode_todos() {
+    let input = "BEGIN:VCALENDAR\r\n\
+                 BEGIN:VTODO\r\n\
+                 UID:42@todo-demo\r\n\
+                 SUMMARY:buy milk\\, \r\n eggs\r\n\
+                 STATUS:COMPLETED\r\n\
+                 DUE;VALUE=DATE:20180301\r\n\
+                 END:VTODO\r\n\
+                 BEGIN:VTODO\r\n\
+                 UID:foreign-uid\r\n\
+                 SUMMARY:call mom\r\n\
+                 CREATED:20180217T124539Z\r\n\
+                 END:VTODO\r\n\
+                 BEGIN:VTODO\r\n\
+                 CREATED:2018\r\n\
+                 END:VTODO\r\n\
+                 END:VCALENDAR\r\n";
+
+    let rows = decode(input);
+    assert_that(&rows.len()).is_equal_to(3);
+
+    let first = rows[0].1.as_ref().unwrap();
+    assert_that(&first.id).is_equal_to(Some(42));
+    assert_that(&first.text.as_str()).is_equal_to("buy milk, eggs");
+    assert_that(&first.done).is_equal_to(Some(true));
+    assert_that(&first.due_at).is_equal_to(Some(NaiveDate::from_ymd(2018, 3, 1).and_hms(0, 0, 0)));
+
+    let second = rows[1].1.as_ref().unwrap();
+    assert_that(&second.id).is_none();
+    assert_that(&second.created_at).is_some();
+
+    assert_that(&rows[2].1.is_err()).is_true();
+  }
+}
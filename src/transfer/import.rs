@@ -0,0 +1,115 @@
+use futures::{future, stream, Future, Stream};
+
+use common::FuturesExt;
+use db::{ImportTodo, TodosRepo};
+use result::{Error, Result};
+use validators::Validator;
+
+use super::Format;
+
+/// How imported rows are handled.
+#[derive(Debug, Clone, Copy)]
+pub struct ImportOptions {
+  pub format: Format,
+  /// keep ids from the input, otherwise new ones are assigned
+  pub keep_ids: bool,
+  /// only validate rows, nothing is written
+  pub dry_run: bool,
+}
+
+/// An outcome of a single imported row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportRow {
+  /// a row number in the input, starting from one
+  pub row: usize,
+  /// an id from the input
+  pub source_id: Option<i64>,
+  /// an id of the created todo item, it's `None` for failed rows and dry runs
+  pub id: Option<i64>,
+  pub error: Option<String>,
+}
+
+/// A result of the import.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+  pub dry_run: bool,
+  pub imported: usize,
+  pub failed: usize,
+  pub rows: Vec<ImportRow>,
+}
+
+/// Validate and insert rows one by one, failed rows are reported and don't stop the import.
+pub fn import(
+  repo: &TodosRepo,
+  input: &[u8],
+  options: ImportOptions,
+) -> Box<Future<Item = ImportReport, Error = Error>> {
+  let repo = repo.clone();
+  let rows = options.format.decode(input);
+
+  let report = ImportReport {
+    dry_run: options.dry_run,
+    imported: 0,
+    failed: 0,
+    rows: Vec::with_capacity(rows.len()),
+  };
+
+  stream::iter_ok(rows)
+    .and_then(move |(row, decoded)| {
+      let source_id = decoded.as_ref().ok().and_then(|it| it.id);
+
+      import_row(&repo, decoded, options).then(move |result| {
+        Ok(match result {
+          Ok(id) => ImportRow {
+            row,
+            source_id,
+            id,
+            error: None,
+          },
+          Err(err) => ImportRow {
+            row,
+            source_id,
+            id: None,
+            error: Some(err.to_string()),
+          },
+        })
+      })
+    })
+    .fold(report, |mut report, row| {
+      if row.error.is_some() {
+        report.failed += 1;
+      } else {
+        report.imported += 1;
+      }
+      report.rows.push(row);
+      Ok::<_, Error>(report)
+    })
+    .into_boxed()
+}
+
+fn import_row(
+  repo: &TodosRepo,
+  decoded: Result<ImportTodo>,
+  options: ImportOptions,
+) -> Box<Future<Item = Option<i64>, Error = Error>> {
+  let todo = match decoded.and_then(|it| it.validated()) {
+    Ok(todo) => todo,
+    Err(err) => return future::err(err).into_boxed(),
+  };
+
+  match (options.dry_run, todo.id) {
+    (true, Some(id)) if options.keep_ids => repo
+      .find(id)
+      .then(move |found| match found {
+        Ok(_) => Err(Error::Validation(format!("todo's id {} already exists", id))),
+        Err(Error::RecordNotFound) => Ok(None),
+        Err(err) => Err(err),
+      })
+      .into_boxed(),
+    (true, _) => future::ok(None).into_boxed(),
+    (false, _) => repo
+      .import(todo, options.keep_ids)
+      .map(|it| Some(it.id))
+      .into_boxed(),
+  }
+}
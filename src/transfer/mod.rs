@@ -0,0 +1,25 @@
+mod format;
+pub mod ical;
+mod import;
+
+pub use self::format::Format;
+pub use self::import::{import, ImportOptions};
+
+use futures::{stream, Stream};
+
+use db::{QueryTodos, TodosRepo};
+use result::Error;
+
+/// Stream encoded todo items matching query's filters, surrounded by format's header and footer.
+pub fn export(
+  repo: &TodosRepo,
+  query: QueryTodos,
+  format: Format,
+) -> Box<Stream<Item = Vec<u8>, Error = Error> + Send> {
+  let chunks = stream::once(Ok(format.header()))
+    .chain(repo.scan(query).and_then(move |items| format.encode(&items)))
+    .chain(stream::once(Ok(format.footer())))
+    .filter(|chunk| !chunk.is_empty());
+
+  Box::new(chunks)
+}
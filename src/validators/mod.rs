@@ -1,7 +1,11 @@
+mod sync_validator;
 mod todos_validator;
+mod webhooks_validator;
 
 use result::Result;
 
+pub use self::todos_validator::MAX_TEXT_LEN;
+
 pub trait Validator<T> {
   fn validated(self) -> Result<T>;
 }
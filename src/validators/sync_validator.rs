@@ -0,0 +1,91 @@
+use db::{parse_token, NewTodo, SyncTodos, UpdateTodo};
+use result::{Error, Result};
+use super::Validator;
+
+/// How many changes a single sync can upload.
+const MAX_CHANGES: usize = 100;
+/// How many changes a single sync can download.
+const MAX_LIMIT: u32 = 1000;
+
+impl Validator<SyncTodos> for SyncTodos {
+  fn validated(self) -> Result<Self> {
+    if let Some(ref token) = self.token {
+      parse_token(token)?;
+    }
+
+    match self.limit {
+      Some(limit) if limit == 0 || limit > MAX_LIMIT => {
+        return Err(Error::Validation(format!(
+          "sync's limit must be between 1 and {}, got {}",
+          MAX_LIMIT, limit
+        )))
+      }
+      _ => {}
+    }
+
+    if self.changes.len() > MAX_CHANGES {
+      return Err(Error::Validation(format!(
+        "sync can upload at most {} changes, got {}",
+        MAX_CHANGES,
+        self.changes.len()
+      )));
+    }
+
+    for change in &self.changes {
+      match change.id {
+        Some(id) => {
+          UpdateTodo {
+            id,
+            text: change.text.clone(),
+            done: change.done,
+            changed_at: change.changed_at,
+          }.validated()?;
+        }
+        None if change.deleted => {
+          return Err(Error::Validation(
+            "a deleted todo's id is missing".to_string(),
+          ))
+        }
+        None => {
+          let text = change
+            .text
+            .clone()
+            .ok_or_else(|| Error::Validation("new todo's text is missing".to_string()))?;
+          NewTodo { text }.validated()?;
+        }
+      }
+    }
+
+    Ok(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+  use serde_json::{self, Value as JsonValue};
+
+  #[test]
+  fn should_validate_sync() {
+    let sync = |it: JsonValue| serde_json::from_value::<SyncTodos>(it).unwrap();
+
+    let subject = sync(json!({"token": "42", "limit": 100, "changes": [{"text": "text"}]}));
+    assert_that(&subject.validated()).is_ok();
+
+    let subject = sync(json!({"token": "foo"}));
+    assert_that(&subject.validated()).is_err();
+
+    let subject = sync(json!({"limit": 0}));
+    assert_that(&subject.validated()).is_err();
+
+    let subject = sync(json!({"changes": [{"client_id": "local-1"}]}));
+    assert_that(&subject.validated()).is_err();
+
+    let subject = sync(json!({"changes": [{"id": -1, "text": "text"}]}));
+    assert_that(&subject.validated()).is_err();
+
+    let subject = sync(json!({"changes": [{"deleted": true}]}));
+    assert_that(&subject.validated()).is_err();
+  }
+}
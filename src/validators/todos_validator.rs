@@ -1,7 +1,10 @@
-use db::{NewTodo, QueryTodos, UpdateTodo};
+use db::{ImportTodo, NewTodo, QueryTodos, UpdateTodo};
 use result::{Error, Result};
 use super::Validator;
 
+/// The longest todo's text, bytes.
+pub const MAX_TEXT_LEN: usize = 255;
+
 struct TodoText(Option<String>);
 struct TodoId(i64);
 
@@ -12,9 +15,10 @@ impl Validator<TodoText> for TodoText {
         return Err(Error::Validation("todo's text cannot be empty".to_string()));
       }
 
-      if text.len() > 255 {
+      if text.len() > MAX_TEXT_LEN {
         return Err(Error::Validation(format!(
-          "todo's text must be less then 255, got {}",
+          "todo's text must be less then {}, got {}",
+          MAX_TEXT_LEN,
           text.len()
         )));
       }
@@ -44,6 +48,16 @@ impl Validator<NewTodo> for NewTodo {
   }
 }
 
+impl Validator<ImportTodo> for ImportTodo {
+  fn validated(self) -> Result<Self> {
+    TodoText(Some(self.text.clone())).validated()?;
+    if let Some(id) = self.id {
+      TodoId(id).validated()?;
+    }
+    Ok(self)
+  }
+}
+
 impl Validator<UpdateTodo> for UpdateTodo {
   fn validated(self) -> Result<Self> {
     TodoText(self.text.clone()).validated()?;
@@ -83,12 +97,50 @@ mod tests {
     assert_that(&subject.validated()).is_err();
   }
 
+  #[test]
+  fn should_validate_import_todo() {
+    let subject = ImportTodo {
+      id: Some(1),
+      text: "text".to_string(),
+      done: None,
+      created_at: None,
+      updated_at: None,
+      due_at: None,
+    };
+    {
+      let subject = subject.clone();
+      assert_that(&subject.validated()).is_ok();
+    }
+
+    {
+      let subject = ImportTodo { id: None, ..subject.clone() };
+      assert_that(&subject.validated()).is_ok();
+    }
+
+    {
+      let subject = ImportTodo {
+        id: Some(0),
+        ..subject.clone()
+      };
+      assert_that(&subject.validated()).is_err();
+    }
+
+    {
+      let subject = ImportTodo {
+        text: "".to_string(),
+        ..subject
+      };
+      assert_that(&subject.validated()).is_err();
+    }
+  }
+
   #[test]
   fn should_validate_update_todo() {
     let subject = UpdateTodo {
       id: 1,
       text: Some("text".to_string()),
       done: Some(false),
+      changed_at: None,
     };
     {
       let subject = subject.clone();
@@ -0,0 +1,60 @@
+use url::Url;
+
+use db::{NewWebhook, TodoEvent};
+use result::{Error, Result};
+use super::Validator;
+
+impl Validator<NewWebhook> for NewWebhook {
+  fn validated(self) -> Result<Self> {
+    if self.url.len() > 2048 {
+      return Err(Error::Validation(format!(
+        "webhook's url must be less then 2048, got {}",
+        self.url.len()
+      )));
+    }
+
+    let url = Url::parse(&self.url)
+      .map_err(|err| Error::Validation(format!("webhook's url is invalid: {}", err)))?;
+    if url.scheme() != "http" {
+      return Err(Error::Validation(format!(
+        "webhook's url must be http, got {}",
+        url.scheme()
+      )));
+    }
+
+    if let Some(ref events) = self.events {
+      for event in events {
+        event.parse::<TodoEvent>()?;
+      }
+    }
+
+    Ok(self)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_validate_new_webhook() {
+    let subject = NewWebhook {
+      url: "http://localhost:8080/hook".to_string(),
+      events: Some(vec!["todo.completed".to_string()]),
+    };
+    assert_that(&subject.validated()).is_ok();
+
+    let subject = NewWebhook {
+      url: "ftp://localhost/hook".to_string(),
+      events: None,
+    };
+    assert_that(&subject.validated()).is_err();
+
+    let subject = NewWebhook {
+      url: "http://localhost/hook".to_string(),
+      events: Some(vec!["todo.foo".to_string()]),
+    };
+    assert_that(&subject.validated()).is_err();
+  }
+}
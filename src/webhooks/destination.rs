@@ -0,0 +1,123 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use url::{Host, Url};
+
+/// Resolve a webhook's url to an address which is safe to connect to.
+///
+/// Every address of a host must be public, otherwise a webhook could reach the server's own
+/// network, like a database or cloud metadata at `169.254.169.254`. A delivery connects to the
+/// returned address, so a host can't resolve to another one between the check and the request.
+pub fn resolve(url: &str) -> Result<SocketAddr, String> {
+  let url = Url::parse(url).map_err(|err| format!("invalid url {}", err))?;
+  let port = url.port_or_known_default().unwrap_or(80);
+
+  let addrs: Vec<SocketAddr> = match url.host() {
+    Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+    Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+    Some(Host::Domain(domain)) => (domain, port)
+      .to_socket_addrs()
+      .map_err(|_| format!("cannot resolve {}", domain))?
+      .collect(),
+    None => return Err("url has no host".to_string()),
+  };
+
+  if let Some(addr) = addrs.iter().find(|it| !is_public(&it.ip())) {
+    return Err(format!("{} is not a public address", addr.ip()));
+  }
+
+  addrs
+    .into_iter()
+    .next()
+    .ok_or_else(|| "host has no addresses".to_string())
+}
+
+/// Whether an address is reachable from the internet, i.e. isn't private, loopback or link-local.
+pub fn is_public(ip: &IpAddr) -> bool {
+  match *ip {
+    IpAddr::V4(ip) => is_public_v4(&ip),
+    IpAddr::V6(ip) => match ipv4_mapped(&ip) {
+      Some(ip) => is_public_v4(&ip),
+      None => is_public_v6(&ip),
+    },
+  }
+}
+
+fn is_public_v4(ip: &Ipv4Addr) -> bool {
+  let octets = ip.octets();
+  // 0.0.0.0/8 is "this network" and 100.64.0.0/10 is shared by carrier-grade NATs
+  let this_network = octets[0] == 0;
+  let shared = octets[0] == 100 && octets[1] & 0xc0 == 64;
+
+  !(ip.is_private()
+    || ip.is_loopback()
+    || ip.is_link_local()
+    || ip.is_broadcast()
+    || ip.is_multicast()
+    || ip.is_documentation()
+    || this_network
+    || shared)
+}
+
+fn is_public_v6(ip: &Ipv6Addr) -> bool {
+  let first = ip.segments()[0];
+  // fc00::/7 are unique local addresses and fe80::/10 are link-local ones
+  let unique_local = first & 0xfe00 == 0xfc00;
+  let link_local = first & 0xffc0 == 0xfe80;
+
+  !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+}
+
+/// An IPv4 address written as `::ffff:a.b.c.d`.
+fn ipv4_mapped(ip: &Ipv6Addr) -> Option<Ipv4Addr> {
+  let segments = ip.segments();
+  if segments[..5] == [0; 5] && segments[5] == 0xffff {
+    ip.to_ipv4()
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_reject_internal_addresses() {
+    for ip in &[
+      "127.0.0.1",
+      "10.1.2.3",
+      "172.16.0.1",
+      "192.168.1.1",
+      "169.254.169.254",
+      "0.0.0.0",
+      "100.64.0.1",
+      "::1",
+      "::",
+      "fd00::1",
+      "fe80::1",
+      "::ffff:127.0.0.1",
+    ] {
+      let ip: IpAddr = ip.parse().unwrap();
+      assert_that(&is_public(&ip))
+        .named(&ip.to_string())
+        .is_false();
+    }
+
+    for ip in &["93.184.216.34", "2606:2800:220:1::248"] {
+      let ip: IpAddr = ip.parse().unwrap();
+      assert_that(&is_public(&ip))
+        .named(&ip.to_string())
+        .is_true();
+    }
+  }
+
+  #[test]
+  fn should_resolve_public_destinations() {
+    let addr = resolve("http://93.184.216.34/hook").unwrap();
+    assert_that(&addr.to_string().as_str()).is_equal_to("93.184.216.34:80");
+
+    assert_that(&resolve("http://localhost:8080/hook")).is_err();
+    assert_that(&resolve("http://169.254.169.254/latest/meta-data")).is_err();
+    assert_that(&resolve("http://[::1]/hook")).is_err();
+  }
+}
@@ -0,0 +1,6 @@
+mod destination;
+mod signature;
+mod worker;
+
+pub use self::destination::resolve;
+pub use self::worker::Worker;
@@ -0,0 +1,58 @@
+use sha2::{Digest, Sha256};
+
+use common::to_hex;
+
+/// SHA-256's block size in bytes.
+const BLOCK_SIZE: usize = 64;
+
+/// HMAC-SHA256 of a message, see RFC 2104.
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+  let mut block = [0u8; BLOCK_SIZE];
+  if key.len() > BLOCK_SIZE {
+    let digest = Sha256::digest(key);
+    block[..digest.len()].copy_from_slice(&digest);
+  } else {
+    block[..key.len()].copy_from_slice(key);
+  }
+
+  let mut inner = Sha256::default();
+  inner.input(&block.iter().map(|it| it ^ 0x36).collect::<Vec<_>>());
+  inner.input(message);
+
+  let mut outer = Sha256::default();
+  outer.input(&block.iter().map(|it| it ^ 0x5c).collect::<Vec<_>>());
+  outer.input(&inner.result());
+
+  outer.result().to_vec()
+}
+
+/// A `Webhook-Signature` header's value, `t=<unix time>,v1=<hex hmac of "<unix time>.<body>">`.
+///
+/// The time is signed too, so receivers could reject replayed deliveries.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+  let message = format!("{}.{}", timestamp, body);
+  let mac = hmac_sha256(secret.as_bytes(), message.as_bytes());
+
+  format!("t={},v1={}", timestamp, to_hex(&mac))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_calculate_hmac() {
+    // RFC 4231, test case 2
+    let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+    assert_that(&to_hex(&mac).as_str())
+      .is_equal_to("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+  }
+
+  #[test]
+  fn should_sign_body() {
+    let signature = sign("secret", 1521882000, "{}");
+    assert_that(&signature.starts_with("t=1521882000,v1=")).is_true();
+    assert_that(&signature).is_not_equal_to(sign("secret", 1521882001, "{}"));
+  }
+}
@@ -0,0 +1,300 @@
+use chrono::{self, Utc};
+use futures::{future, Future};
+use futures_cpupool::CpuPool;
+use hyper::{Client, Method, Request, StatusCode, Uri};
+use hyper::client::HttpConnector;
+use hyper::header::{ContentLength, ContentType, Host};
+use std::cmp;
+use std::fmt;
+use std::net::SocketAddr;
+use std::thread;
+use std::time::Duration;
+use tokio_core::reactor::{Core, Handle, Timeout};
+
+use config::Config;
+use db::{ConnectionPool, PendingDelivery, WebhooksRepo};
+use result::Error;
+use common::FuturesExt;
+
+use super::destination::resolve;
+use super::signature::sign;
+
+type BoxFuture<T> = Box<Future<Item = T, Error = Error>>;
+
+/// How many events and deliveries are taken at once.
+const BATCH_SIZE: i64 = 100;
+/// How long to wait when there is nothing to deliver.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// A delay before the second attempt, it doubles for every next one.
+const BASE_BACKOFF_SECS: u64 = 10;
+const MAX_BACKOFF_SECS: u64 = 3600;
+/// How long delivered events are kept.
+const EVENT_RETENTION_HOURS: i64 = 24;
+
+header! { (WebhookEvent, "Webhook-Event") => [String] }
+header! { (WebhookDelivery, "Webhook-Delivery") => [i64] }
+header! { (WebhookSignature, "Webhook-Signature") => [String] }
+
+/// Moves outbox events to webhooks' deliveries and POSTs them.
+///
+/// Deliveries are at least once, failed ones are retried with an exponential backoff and go to
+/// dead letters after `WEBHOOK_MAX_ATTEMPTS`. Only `http` urls with public addresses are
+/// supported, delivered events are pruned after `EVENT_RETENTION_HOURS`.
+pub struct Worker {
+  repo: WebhooksRepo,
+  cpu_pool: CpuPool,
+  max_attempts: u32,
+  timeout: Duration,
+}
+
+impl Worker {
+  pub fn new(cfg: &Config, conn_pool: ConnectionPool, cpu_pool: CpuPool) -> Self {
+    Worker {
+      repo: WebhooksRepo::new(conn_pool, cpu_pool.clone()),
+      cpu_pool,
+      max_attempts: cfg.webhook_max_attempts,
+      timeout: cfg.webhook_timeout,
+    }
+  }
+
+  /// Run the worker in a background thread.
+  pub fn spawn(self) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+      .name("webhooks".to_string())
+      .spawn(move || self.run())
+      .expect("cannot spawn webhooks worker")
+  }
+
+  fn run(self) {
+    let mut core = Core::new().expect("cannot create webhooks event loop");
+    let handle = core.handle();
+    let client = Client::new(&handle);
+
+    info!("webhooks worker started");
+
+    loop {
+      match core.run(self.tick(&client, &handle)) {
+        Ok(0) => thread::sleep(POLL_INTERVAL),
+        Ok(_) => {}
+        Err(err) => {
+          error!("webhooks worker failed {}", err);
+          thread::sleep(POLL_INTERVAL);
+        }
+      }
+    }
+  }
+
+  /// Dispatch new events, prune delivered ones and send due deliveries, returns how many were
+  /// processed.
+  fn tick(&self, client: &Client<HttpConnector>, handle: &Handle) -> BoxFuture<usize> {
+    let repo = self.repo.clone();
+    let client = client.clone();
+    let handle = handle.clone();
+    let cpu_pool = self.cpu_pool.clone();
+    let max_attempts = self.max_attempts;
+    let timeout = self.timeout;
+    let lease = chrono::Duration::from_std(timeout * 2).expect("webhook timeout is out of range");
+    let retention = chrono::Duration::hours(EVENT_RETENTION_HOURS);
+
+    let prepared = self.repo.dispatch(BATCH_SIZE).join(self.repo.prune(retention, BATCH_SIZE));
+
+    prepared
+      .and_then(move |(dispatched, pruned)| {
+        repo.claim(BATCH_SIZE, lease).and_then(move |deliveries| {
+          let count = dispatched + pruned + deliveries.len();
+          let sent = deliveries.into_iter().map(move |delivery| {
+            deliver(&repo, &client, &handle, &cpu_pool, delivery, max_attempts, timeout)
+          });
+
+          future::join_all(sent).map(move |_| count)
+        })
+      })
+      .into_boxed()
+  }
+}
+
+/// Why a delivery failed, details are only logged since dead letters are shown to clients.
+#[derive(Debug)]
+enum Failure {
+  InvalidUrl(String),
+  /// the url's host isn't public anymore
+  Forbidden(String),
+  Connection(String),
+  Status(StatusCode),
+  TimedOut,
+}
+
+impl Failure {
+  /// A summary which is stored with a delivery, it tells nothing about the receiver's network.
+  fn summary(&self) -> String {
+    match *self {
+      Failure::InvalidUrl(_) => "invalid url".to_string(),
+      Failure::Forbidden(_) => "destination is not allowed".to_string(),
+      Failure::Connection(_) => "connection failed".to_string(),
+      Failure::Status(status) => format!("unexpected status {}", u16::from(status)),
+      Failure::TimedOut => "timed out".to_string(),
+    }
+  }
+}
+
+impl fmt::Display for Failure {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Failure::InvalidUrl(ref err)
+      | Failure::Forbidden(ref err)
+      | Failure::Connection(ref err) => write!(f, "{}: {}", self.summary(), err),
+      _ => write!(f, "{}", self.summary()),
+    }
+  }
+}
+
+/// Send a delivery and record its result.
+fn deliver(
+  repo: &WebhooksRepo,
+  client: &Client<HttpConnector>,
+  handle: &Handle,
+  cpu_pool: &CpuPool,
+  delivery: PendingDelivery,
+  max_attempts: u32,
+  timeout: Duration,
+) -> BoxFuture<()> {
+  let repo = repo.clone();
+  let id = delivery.id;
+  let attempts = delivery.attempts as u32 + 1;
+
+  send(client, handle, cpu_pool, delivery, timeout)
+    .then(move |result| match result {
+      Ok(()) => repo.succeed(id),
+      Err(err) => {
+        warn!("webhook delivery {} attempt {} failed {}", id, attempts, err);
+        let next_attempt_at = if attempts < max_attempts {
+          let delay = chrono::Duration::from_std(backoff(attempts)).expect("backoff is in range");
+          Some(Utc::now().naive_utc() + delay)
+        } else {
+          None
+        };
+        repo.fail(id, err.summary(), next_attempt_at)
+      }
+    })
+    .into_boxed()
+}
+
+/// POST a signed payload, any response except `2xx` is a failure.
+///
+/// The host is resolved on the cpu pool and checked again, since its addresses could have changed
+/// since the registration, then the request goes to the checked address.
+fn send(
+  client: &Client<HttpConnector>,
+  handle: &Handle,
+  cpu_pool: &CpuPool,
+  delivery: PendingDelivery,
+  timeout: Duration,
+) -> Box<Future<Item = (), Error = Failure>> {
+  let timeout = match Timeout::new(timeout, handle) {
+    Ok(timeout) => timeout.then(|_| Err(Failure::TimedOut)),
+    Err(err) => return Box::new(future::err(Failure::Connection(err.to_string()))),
+  };
+
+  let client = client.clone();
+  let url = delivery.url.clone();
+  let sent = cpu_pool
+    .spawn_fn(move || resolve(&url).map_err(Failure::Forbidden))
+    .and_then(move |addr| request(delivery, addr))
+    .and_then(move |req| {
+      client.request(req).then(|result| match result {
+        Ok(ref resp) if resp.status().is_success() => Ok(()),
+        Ok(resp) => Err(Failure::Status(resp.status())),
+        Err(err) => Err(Failure::Connection(err.to_string())),
+      })
+    });
+
+  Box::new(
+    sent
+      .select(timeout)
+      .map(|(it, _)| it)
+      .map_err(|(err, _)| err),
+  )
+}
+
+/// Build a signed request to a resolved address with the url's host in `Host`.
+fn request(delivery: PendingDelivery, addr: SocketAddr) -> Result<Request, Failure> {
+  let invalid = |err: &fmt::Display| Failure::InvalidUrl(err.to_string());
+  let url = delivery.url.parse::<Uri>().map_err(|err| invalid(&err))?;
+  let host = url.host().ok_or_else(|| invalid(&"url has no host"))?;
+
+  let path = match url.query() {
+    Some(query) => format!("{}?{}", url.path(), query),
+    None => url.path().to_string(),
+  };
+  let uri = format!("http://{}{}", addr, path)
+    .parse::<Uri>()
+    .map_err(|err| invalid(&err))?;
+
+  let signature = sign(
+    &delivery.secret,
+    Utc::now().timestamp(),
+    &delivery.payload,
+  );
+  let len = delivery.payload.len();
+
+  let mut req = Request::new(Method::Post, uri);
+  {
+    let headers = req.headers_mut();
+    headers.set(Host::new(host.to_string(), url.port()));
+    headers.set(ContentType::json());
+    headers.set(ContentLength(len as u64));
+    headers.set(WebhookEvent(delivery.event));
+    headers.set(WebhookDelivery(delivery.id));
+    headers.set(WebhookSignature(signature));
+  }
+  req.set_body(delivery.payload);
+
+  Ok(req)
+}
+
+/// A delay before the next attempt after `attempts` failed ones.
+pub fn backoff(attempts: u32) -> Duration {
+  let exp = cmp::min(attempts.saturating_sub(1), 16);
+  let secs = BASE_BACKOFF_SECS.saturating_mul(1 << exp);
+
+  Duration::from_secs(cmp::min(secs, MAX_BACKOFF_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use spectral::prelude::*;
+
+  #[test]
+  fn should_back_off_exponentially() {
+    assert_that(&backoff(1)).is_equal_to(Duration::from_secs(10));
+    assert_that(&backoff(2)).is_equal_to(Duration::from_secs(20));
+    assert_that(&backoff(4)).is_equal_to(Duration::from_secs(80));
+    assert_that(&backoff(20)).is_equal_to(Duration::from_secs(MAX_BACKOFF_SECS));
+  }
+
+  #[test]
+  fn should_send_to_resolved_address() {
+    let delivery = PendingDelivery {
+      id: 1,
+      url: "http://hooks.example.com:8080/todos?v=1".to_string(),
+      secret: "secret".to_string(),
+      event: "todo.created".to_string(),
+      payload: "{}".to_string(),
+      attempts: 0,
+    };
+    let addr = "93.184.216.34:8080".parse().unwrap();
+
+    let req = request(delivery, addr).unwrap();
+    assert_that(&req.uri().to_string().as_str()).is_equal_to("http://93.184.216.34:8080/todos?v=1");
+    let host = req.headers().get::<Host>().unwrap();
+    assert_that(&host.to_string().as_str()).is_equal_to("hooks.example.com:8080");
+  }
+
+  #[test]
+  fn should_not_store_upstream_errors() {
+    let failure = Failure::Connection("Connection refused (os error 111)".to_string());
+    assert_that(&failure.summary().as_str()).is_equal_to("connection failed");
+    assert_that(&failure.to_string().contains("refused")).is_true();
+  }
+}